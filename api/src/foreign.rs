@@ -26,17 +26,20 @@ use crate::handlers::transactions_api::TxHashSetHandler;
 use crate::handlers::version_api::VersionHandler;
 use crate::pool::{self, BlockChain, PoolAdapter, PoolEntry};
 use crate::types::{
-	BlockHeaderPrintable, BlockPrintable, LocatedTxKernel, OutputListing, OutputPrintable, Tip,
-	Version,
+	BlockHeaderPrintable, BlockPrintable, KernelInclusionProof, LocatedTxKernel, OutputListing,
+	OutputMerkleProof, OutputMerkleProofs, OutputPrintable, Tip, TxPoolVerdict, Version,
 };
 use crate::util::RwLock;
 use crate::{rest::*, BlockListing};
-use crate::{Libp2pMessages, Libp2pPeers};
+use crate::{Libp2pMessages, Libp2pPeers, Libp2pStoredMessages};
 use chrono::Utc;
 use mwc_p2p::libp2p_connection;
 use mwc_util::secp::Secp256k1;
 use std::sync::Weak;
 
+/// Max number of the stored libp2p messages that can be read in a single call
+const MAX_STORED_MESSAGES: u64 = 1000;
+
 /// Main interface into all node API functions.
 /// Node APIs are split into two separate blocks of functionality
 /// called the ['Owner'](struct.Owner.html) and ['Foreign'](struct.Foreign.html) APIs
@@ -238,6 +241,139 @@ where
 		kernel_handler.get_kernel_v2(excess, min_height, max_height)
 	}
 
+	/// Builds the kernel inclusion proof against the block header. The kernel is located the same
+	/// way as in [`get_kernel`](struct.Foreign.html#method.get_kernel). The proof can be checked
+	/// without the node with `KernelMerkleProof::verify`, it is intended for the payment proofs
+	/// and atomic swaps.
+	///
+	/// # Arguments
+	/// * `excess` - kernel excess to look for.
+	/// * `min_height` - minimum height to stop the lookup.
+	/// * `max_height` - maximum height to start the lookup, capped by the `header_hash` height.
+	/// * `header_hash` - hash of the block header on the current chain the proof is built
+	///   against. Kernels above that header are not found. Default: chain head.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`KernelInclusionProof`](types/struct.KernelInclusionProof.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_kernel_merkle_proof(
+		&self,
+		excess: String,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+		header_hash: Option<Hash>,
+	) -> Result<KernelInclusionProof, Error> {
+		let kernel_handler = KernelHandler {
+			chain: self.chain.clone(),
+		};
+		kernel_handler.get_kernel_merkle_proof(excess, min_height, max_height, header_hash)
+	}
+
+	/// Returns [`LocatedTxKernel`](types/struct.LocatedTxKernel.html) for every kernel excess in the list.
+	/// The kernel MMR is traversed once for all excesses, the same way as in
+	/// [`get_kernel`](struct.Foreign.html#method.get_kernel).
+	/// Max number of excesses is limited by BATCH_REQUEST_LIMIT.
+	///
+	/// # Arguments
+	/// * `excesses` - kernel excesses to look for.
+	/// * `min_height` - minimum height to stop the lookup.
+	/// * `max_height` - maximum height to start the lookup.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of optional [`LocatedTxKernel`](types/struct.LocatedTxKernel.html) in the order of `excesses`, None if kernel is not found
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_kernels(
+		&self,
+		excesses: Vec<String>,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Vec<Option<LocatedTxKernel>>, Error> {
+		let kernel_handler = KernelHandler {
+			chain: self.chain.clone(),
+		};
+		kernel_handler.get_kernels_v2(excesses, min_height, max_height)
+	}
+
+	/// Gets block headers for the height range and for the list of block hashes.
+	/// Headers from the range go first, then headers for the hashes. Unknown hashes are skipped.
+	/// Total number of headers is limited by BATCH_REQUEST_LIMIT.
+	///
+	/// # Arguments
+	/// * `start_height` - first height of the range.
+	/// * `end_height` - last height of the range. Default: chain head.
+	/// * `hashes` - block hashes.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`BlockHeaderPrintable`](types/struct.BlockHeaderPrintable.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_headers(
+		&self,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+		hashes: Option<Vec<Hash>>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error> {
+		let header_handler = HeaderHandler {
+			chain: self.chain.clone(),
+		};
+		header_handler.get_headers(start_height, end_height, hashes)
+	}
+
+	/// Builds the merkle proof for the unspent output against the block header. The proof can be
+	/// checked without the node with
+	/// [`verify_merkle_proof`](../mwc_core/core/merkle_proof/fn.verify_merkle_proof.html).
+	///
+	/// # Arguments
+	/// * `commit` - unspent output commitment.
+	/// * `header_hash` - hash of the block header on the current chain the proof is built
+	///   against. The output must be already included at that header. The header can be at most
+	///   60 blocks below the chain head. Default: chain head.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * An [`OutputMerkleProof`](types/struct.OutputMerkleProof.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_merkle_proof(
+		&self,
+		commit: String,
+		header_hash: Option<Hash>,
+	) -> Result<OutputMerkleProof, Error> {
+		let output_handler = OutputHandler {
+			chain: self.chain.clone(),
+		};
+		output_handler.get_merkle_proof(commit, header_hash)
+	}
+
+	/// Builds merkle proofs for the unspent outputs. All proofs are valid against the same
+	/// header, the chain head at the time of the request.
+	/// Max number of commitments is limited by BATCH_REQUEST_LIMIT.
+	///
+	/// # Arguments
+	/// * `commits` - unspent output commitments.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * An [`OutputMerkleProofs`](types/struct.OutputMerkleProofs.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_merkle_proofs(&self, commits: Vec<String>) -> Result<OutputMerkleProofs, Error> {
+		let output_handler = OutputHandler {
+			chain: self.chain.clone(),
+		};
+		output_handler.get_merkle_proofs(commits)
+	}
+
 	/// Retrieves details about specifics outputs. Supports retrieval of multiple outputs in a single request.
 	/// Support retrieval by both commitment string and block height.
 	///
@@ -400,6 +536,31 @@ where
 		})
 	}
 
+	/// Run all the checks of [`push_transaction`](#method.push_transaction) without adding
+	/// the transaction to the pool and without broadcasting it. The pool state is validated
+	/// against the current pool, the pool is not modified.
+	///
+	/// # Arguments
+	/// * `tx` - the Mwc transaction to check.
+	/// * `fluff` - boolean to check against the txpool instead of the Dandelion stempool.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`TxPoolVerdict`](types/struct.TxPoolVerdict.html) with the result of every check
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn test_accept_transaction(
+		&self,
+		tx: Transaction,
+		fluff: Option<bool>,
+		secp: &Secp256k1,
+	) -> Result<TxPoolVerdict, Error> {
+		let pool_handler = PoolHandler {
+			tx_pool: self.tx_pool.clone(),
+		};
+		pool_handler.test_accept_transaction(tx, fluff, secp)
+	}
+
 	pub fn get_libp2p_peers(&self) -> Result<Libp2pPeers, Error> {
 		let libp2p_peers: Vec<String> = libp2p_connection::get_libp2p_connections()
 			.iter()
@@ -434,4 +595,67 @@ where
 				.collect(),
 		})
 	}
+
+	/// Get the received libp2p messages filtered by topic and receive time.
+	///
+	/// # Arguments
+	/// * `topic` - topic of the messages, all topics if None.
+	/// * `from_time` - unix timestamp, only messages received after that time are returned.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`Libp2pMessages`](types/struct.Libp2pMessages.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn get_libp2p_topic_messages(
+		&self,
+		topic: Option<String>,
+		from_time: Option<i64>,
+	) -> Result<Libp2pMessages, Error> {
+		let from_time = from_time.unwrap_or(0);
+		Ok(Libp2pMessages {
+			current_time: Utc::now().timestamp(),
+			libp2p_messages: libp2p_connection::get_received_messages(false)
+				.into_iter()
+				.filter(|m| m.timestamp > from_time)
+				.filter(|m| topic.as_ref().map(|t| *t == m.topic).unwrap_or(true))
+				.collect(),
+		})
+	}
+
+	/// Read the received libp2p messages from the node persistent store. Every consumer
+	/// keeps its own cursor (sequence number of the last read message), reading doesn't
+	/// delete the messages.
+	///
+	/// # Arguments
+	/// * `topic` - topic of the messages, all topics if None.
+	/// * `after_seq` - cursor, only messages with greater sequence number are returned. 0 if None.
+	/// * `limit` - max number of the messages to return, limited to 1000.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`Libp2pStoredMessages`](types/struct.Libp2pStoredMessages.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+	pub fn get_libp2p_stored_messages(
+		&self,
+		topic: Option<String>,
+		after_seq: Option<u64>,
+		limit: Option<u64>,
+	) -> Result<Libp2pStoredMessages, Error> {
+		let limit = limit
+			.unwrap_or(MAX_STORED_MESSAGES)
+			.min(MAX_STORED_MESSAGES);
+		let (last_seq, messages) = libp2p_connection::get_stored_messages(
+			topic.as_ref().map(|t| t.as_str()),
+			after_seq.unwrap_or(0),
+			limit as usize,
+		)
+		.map_err(|e| Error::P2pError(format!("Unable to read libp2p messages, {}", e)))?;
+		Ok(Libp2pStoredMessages {
+			current_time: Utc::now().timestamp(),
+			last_seq,
+			messages,
+		})
+	}
 }
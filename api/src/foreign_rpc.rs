@@ -23,10 +23,11 @@ use crate::pool::PoolEntry;
 use crate::pool::{BlockChain, PoolAdapter};
 use crate::rest::Error;
 use crate::types::{
-	BlockHeaderPrintable, BlockListing, BlockPrintable, LocatedTxKernel, OutputListing,
-	OutputPrintable, Tip, Version,
+	BlockHeaderPrintable, BlockListing, BlockPrintable, KernelInclusionProof, LocatedTxKernel,
+	OutputListing, OutputMerkleProof, OutputMerkleProofs, OutputPrintable, Tip, TxPoolVerdict,
+	Version,
 };
-use crate::{util, Libp2pMessages, Libp2pPeers};
+use crate::{util, Libp2pMessages, Libp2pPeers, Libp2pStoredMessages};
 
 /// Public definition used to generate Node jsonrpc api.
 /// * When running `mwc` with defaults, the V2 api is available at
@@ -725,6 +726,95 @@ pub trait ForeignRpc: Sync + Send {
 		max_height: Option<u64>,
 	) -> Result<LocatedTxKernel, Error>;
 
+	/**
+	Networked version of [Foreign::get_kernel_merkle_proof](struct.Foreign.html#method.get_kernel_merkle_proof).
+
+	// No example because the proof depends on the header and the current chain state.
+	 */
+	fn get_kernel_merkle_proof(
+		&self,
+		excess: String,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+		header_hash: Option<String>,
+	) -> Result<KernelInclusionProof, Error>;
+
+	/**
+	Networked version of [Foreign::get_kernels](struct.Foreign.html#method.get_kernels).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_foreign_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_kernels",
+		"params": [["09c868a2fed619580f296e91d2819b6b3ae61ab734bf3d9c3eafa6d9700f00361b"], null, null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+			{
+				"height": 374557,
+				"mmr_index": 2211662,
+				"tx_kernel": {
+					"excess": "09c868a2fed619580f296e91d2819b6b3ae61ab734bf3d9c3eafa6d9700f00361b",
+					"excess_sig": "1720ec1b94aa5d6ba4d567f7446314f9a6d064eea69c5675cc5659f65f290d80b0e9e3a48d818cadba0a4e894bbc6eb6754b56f53813e2ee0b1447969894ca4a",
+					"features": "Coinbase"
+				}
+			}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_kernels(
+		&self,
+		excesses: Vec<String>,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Vec<Option<LocatedTxKernel>>, Error>;
+
+	/**
+	Networked version of [Foreign::get_headers](struct.Foreign.html#method.get_headers).
+
+	// No example because result is the list of the headers from the
+	// [get_header](#tymethod.get_header) example.
+	 */
+	fn get_headers(
+		&self,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+		hashes: Option<Vec<String>>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error>;
+
+	/**
+	Networked version of [Foreign::get_merkle_proof](struct.Foreign.html#method.get_merkle_proof).
+
+	// No example because the proof depends on the header and the current chain state.
+	 */
+	fn get_merkle_proof(
+		&self,
+		commit: String,
+		header_hash: Option<String>,
+	) -> Result<OutputMerkleProof, Error>;
+
+	/**
+	Networked version of [Foreign::get_merkle_proofs](struct.Foreign.html#method.get_merkle_proofs).
+
+	// No example because proofs depend on the current chain head.
+	 */
+	fn get_merkle_proofs(&self, commits: Vec<String>) -> Result<OutputMerkleProofs, Error>;
+
 	/**
 	Networked version of [Foreign::get_outputs](struct.Foreign.html#method.get_outputs).
 
@@ -1108,6 +1198,18 @@ pub trait ForeignRpc: Sync + Send {
 	 */
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error>;
 
+	/**
+	Networked version of [Foreign::test_accept_transaction](struct.Foreign.html#method.test_accept_transaction).
+
+	// No example because the verdict depends on the current chain and pool state,
+	// the transaction format is the same as for [push_transaction](#tymethod.push_transaction).
+	 */
+	fn test_accept_transaction(
+		&self,
+		tx: Transaction,
+		fluff: Option<bool>,
+	) -> Result<TxPoolVerdict, Error>;
+
 	/**
 	Networked version of [Owner::get_libp2p_peers](struct.Owner.html#method.get_libp2p_peers).
 
@@ -1147,6 +1249,29 @@ pub trait ForeignRpc: Sync + Send {
 		// No example because if current time dynamic nature.
 	*/
 	fn get_libp2p_messages(&self) -> Result<Libp2pMessages, Error>;
+
+	/**
+		Networked version of [Foreign::get_libp2p_topic_messages](struct.Foreign.html#method.get_libp2p_topic_messages).
+
+		// No example because if current time dynamic nature.
+	*/
+	fn get_libp2p_topic_messages(
+		&self,
+		topic: Option<String>,
+		from_time: Option<i64>,
+	) -> Result<Libp2pMessages, Error>;
+
+	/**
+		Networked version of [Foreign::get_libp2p_stored_messages](struct.Foreign.html#method.get_libp2p_stored_messages).
+
+		// No example because the stored messages depend on the libp2p network.
+	*/
+	fn get_libp2p_stored_messages(
+		&self,
+		topic: Option<String>,
+		after_seq: Option<u64>,
+		limit: Option<u64>,
+	) -> Result<Libp2pStoredMessages, Error>;
 }
 
 impl<B, P> ForeignRpc for Foreign<B, P>
@@ -1229,6 +1354,71 @@ where
 		Foreign::get_kernel(self, excess, min_height, max_height)
 	}
 
+	fn get_kernel_merkle_proof(
+		&self,
+		excess: String,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+		header_hash: Option<String>,
+	) -> Result<KernelInclusionProof, Error> {
+		let mut parsed_hash: Option<Hash> = None;
+		if let Some(hash) = header_hash {
+			let vec = util::from_hex(&hash)
+				.map_err(|e| Error::Argument(format!("invalid block hash: {}", e)))?;
+			parsed_hash = Some(Hash::from_vec(&vec));
+		}
+		Foreign::get_kernel_merkle_proof(self, excess, min_height, max_height, parsed_hash)
+	}
+
+	fn get_kernels(
+		&self,
+		excesses: Vec<String>,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Vec<Option<LocatedTxKernel>>, Error> {
+		Foreign::get_kernels(self, excesses, min_height, max_height)
+	}
+
+	fn get_headers(
+		&self,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+		hashes: Option<Vec<String>>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error> {
+		let parsed_hashes = match hashes {
+			Some(hashes) => Some(
+				hashes
+					.iter()
+					.map(|hash| {
+						util::from_hex(hash)
+							.map(|vec| Hash::from_vec(&vec))
+							.map_err(|e| Error::Argument(format!("invalid block hash: {}", e)))
+					})
+					.collect::<Result<Vec<_>, _>>()?,
+			),
+			None => None,
+		};
+		Foreign::get_headers(self, start_height, end_height, parsed_hashes)
+	}
+
+	fn get_merkle_proof(
+		&self,
+		commit: String,
+		header_hash: Option<String>,
+	) -> Result<OutputMerkleProof, Error> {
+		let mut parsed_hash: Option<Hash> = None;
+		if let Some(hash) = header_hash {
+			let vec = util::from_hex(&hash)
+				.map_err(|e| Error::Argument(format!("invalid block hash: {}", e)))?;
+			parsed_hash = Some(Hash::from_vec(&vec));
+		}
+		Foreign::get_merkle_proof(self, commit, parsed_hash)
+	}
+
+	fn get_merkle_proofs(&self, commits: Vec<String>) -> Result<OutputMerkleProofs, Error> {
+		Foreign::get_merkle_proofs(self, commits)
+	}
+
 	fn get_outputs(
 		&self,
 		commits: Option<Vec<String>>,
@@ -1279,12 +1469,34 @@ where
 	fn push_transaction(&self, tx: Transaction, fluff: Option<bool>) -> Result<(), Error> {
 		Foreign::push_transaction(self, tx, fluff, w(&self.chain)?.secp())
 	}
+	fn test_accept_transaction(
+		&self,
+		tx: Transaction,
+		fluff: Option<bool>,
+	) -> Result<TxPoolVerdict, Error> {
+		Foreign::test_accept_transaction(self, tx, fluff, w(&self.chain)?.secp())
+	}
 	fn get_libp2p_peers(&self) -> Result<Libp2pPeers, Error> {
 		Foreign::get_libp2p_peers(self)
 	}
 	fn get_libp2p_messages(&self) -> Result<Libp2pMessages, Error> {
 		Foreign::get_libp2p_messages(self)
 	}
+	fn get_libp2p_topic_messages(
+		&self,
+		topic: Option<String>,
+		from_time: Option<i64>,
+	) -> Result<Libp2pMessages, Error> {
+		Foreign::get_libp2p_topic_messages(self, topic, from_time)
+	}
+	fn get_libp2p_stored_messages(
+		&self,
+		topic: Option<String>,
+		after_seq: Option<u64>,
+		limit: Option<u64>,
+	) -> Result<Libp2pStoredMessages, Error> {
+		Foreign::get_libp2p_stored_messages(self, topic, after_seq, limit)
+	}
 }
 
 #[doc(hidden)]
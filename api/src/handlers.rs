@@ -17,6 +17,7 @@ pub mod blocks_api;
 pub mod chain_api;
 pub mod peers_api;
 pub mod pool_api;
+pub mod scan_api;
 pub mod server_api;
 pub mod transactions_api;
 pub mod utils;
@@ -47,7 +48,7 @@ use crate::core::global;
 use crate::core::stratum;
 use crate::foreign::Foreign;
 use crate::foreign_rpc::ForeignRpc;
-use crate::owner::Owner;
+use crate::owner::{self, BlockGenerator, OnionClientAuth, Owner, PoolDryRun, WatchList};
 use crate::owner_rpc::OwnerRpc;
 use crate::p2p;
 use crate::pool;
@@ -82,6 +83,9 @@ pub fn node_apis<B, P>(
 	tls_config: Option<TLSConfig>,
 	allow_to_stop: bool,
 	stratum_ip_pool: Arc<stratum::connections::StratumIpPool>,
+	block_generator: Option<Arc<dyn BlockGenerator>>,
+	watch_list: Option<Arc<dyn WatchList>>,
+	onion_client_auth: Option<Arc<dyn OnionClientAuth>>,
 	api_chan: &'static mut (oneshot::Sender<()>, oneshot::Receiver<()>),
 	stop_state: Arc<StopState>,
 ) -> Result<(), Error>
@@ -121,10 +125,15 @@ where
 		router.add_middleware(basic_auth_middleware);
 	}
 
+	let pool_dry_run: Weak<dyn PoolDryRun> = Arc::downgrade(&tx_pool);
 	let api_handler = OwnerAPIHandlerV2::new(
 		Arc::downgrade(&chain),
 		Arc::downgrade(&peers),
 		Arc::downgrade(&sync_state),
+		pool_dry_run,
+		block_generator,
+		watch_list,
+		onion_client_auth,
 	);
 	router.add_route("/v2/owner", Arc::new(api_handler))?;
 
@@ -191,15 +200,56 @@ pub struct OwnerAPIHandlerV2 {
 	pub chain: Weak<Chain>,
 	pub peers: Weak<p2p::Peers>,
 	pub sync_state: Weak<SyncState>,
+	pub tx_pool: Weak<dyn PoolDryRun>,
+	pub block_generator: Option<Arc<dyn BlockGenerator>>,
+	pub watch_list: Option<Arc<dyn WatchList>>,
+	pub onion_client_auth: Option<Arc<dyn OnionClientAuth>>,
 }
 
 impl OwnerAPIHandlerV2 {
 	/// Create a new owner API handler for GET methods
-	pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+	pub fn new(
+		chain: Weak<Chain>,
+		peers: Weak<p2p::Peers>,
+		sync_state: Weak<SyncState>,
+		tx_pool: Weak<dyn PoolDryRun>,
+		block_generator: Option<Arc<dyn BlockGenerator>>,
+		watch_list: Option<Arc<dyn WatchList>>,
+		onion_client_auth: Option<Arc<dyn OnionClientAuth>>,
+	) -> Self {
 		OwnerAPIHandlerV2 {
 			chain,
 			peers,
 			sync_state,
+			tx_pool,
+			block_generator,
+			watch_list,
+			onion_client_auth,
+		}
+	}
+}
+
+/// Watch list events long poll. The wait is done here asynchronously, then the request
+/// timeout is reset, so the owner API call doesn't block the worker thread.
+async fn wait_watch_events_request(watch_list: &dyn WatchList, request: &mut serde_json::Value) {
+	if request.get("method").and_then(|m| m.as_str()) != Some("get_watch_events") {
+		return;
+	}
+	let (after_seq, timeout_sec) = match request.get_mut("params") {
+		Some(serde_json::Value::Array(p)) => (p.get(0).and_then(|v| v.as_u64()), p.get_mut(1)),
+		Some(serde_json::Value::Object(p)) => (
+			p.get("after_seq").and_then(|v| v.as_u64()),
+			p.get_mut("timeout_sec"),
+		),
+		_ => return,
+	};
+	if let (Some(after_seq), Some(timeout_sec)) = (after_seq, timeout_sec) {
+		match timeout_sec.as_u64() {
+			Some(timeout) if timeout > 0 => {
+				owner::wait_watch_events(watch_list, after_seq, timeout).await;
+				*timeout_sec = serde_json::Value::from(0);
+			}
+			_ => {}
 		}
 	}
 }
@@ -210,13 +260,24 @@ impl crate::router::Handler for OwnerAPIHandlerV2 {
 			self.chain.clone(),
 			self.peers.clone(),
 			self.sync_state.clone(),
+			self.tx_pool.clone(),
+			self.block_generator.clone(),
+			self.watch_list.clone(),
+			self.onion_client_auth.clone(),
 		);
 
+		let watch_list = self.watch_list.clone();
+
 		Box::pin(async move {
 			match parse_body(req).await {
-				Ok(val) => {
+				Ok(mut val) => {
+					if let Some(watch_list) = &watch_list {
+						wait_watch_events_request(watch_list.as_ref(), &mut val).await;
+					}
 					let owner_api = &api as &dyn OwnerRpc;
-					let res = match owner_api.handle_request(val) {
+					// Some owner calls are long (chain validation), they should not block
+					// other requests of this worker
+					let res = match tokio::task::block_in_place(|| owner_api.handle_request(val)) {
 						MaybeReply::Reply(r) => r,
 						MaybeReply::DontReply => {
 							// Since it's http, we need to return something. We return [] because jsonrpc
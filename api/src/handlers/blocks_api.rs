@@ -12,6 +12,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use super::chain_api::check_batch_size;
 use super::utils::{get_output, get_output_v2, w};
 use crate::chain;
 use crate::core::core::hash::Hash;
@@ -90,6 +91,46 @@ impl HeaderHandler {
 		Ok(BlockHeaderPrintable::from_header(&header))
 	}
 
+	/// Headers for the height range (both ends are included) and for the list of hashes.
+	/// Headers in the range are read under one chain read lock, unknown hashes are skipped.
+	pub fn get_headers(
+		&self,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+		hashes: Option<Vec<Hash>>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error> {
+		let chain = w(&self.chain)?;
+		let mut headers = vec![];
+		if let Some(start_height) = start_height {
+			let head_height = chain
+				.head()
+				.map_err(|e| Error::from_chain("Unable to get a chain head", e))?
+				.height;
+			let end_height = end_height.unwrap_or(head_height).min(head_height);
+			if start_height <= end_height {
+				check_batch_size("headers", (end_height - start_height + 1) as usize)?;
+				let range = chain
+					.get_headers_by_height(start_height, end_height)
+					.map_err(|e| {
+						Error::NotFound(format!(
+							"Headers for heights {}-{}, {}",
+							start_height, end_height, e
+						))
+					})?;
+				headers.extend(range.iter().map(BlockHeaderPrintable::from_header));
+			}
+		}
+		if let Some(hashes) = hashes {
+			check_batch_size("headers", headers.len() + hashes.len())?;
+			for hash in hashes {
+				if let Ok(header) = chain.get_block_header(&hash) {
+					headers.push(BlockHeaderPrintable::from_header(&header));
+				}
+			}
+		}
+		Ok(headers)
+	}
+
 	// Try to get hash from height, hash or output commit
 	pub fn parse_inputs(
 		&self,
@@ -165,9 +206,8 @@ impl BlockHandler {
 		let block = chain
 			.get_block(h)
 			.map_err(|e| Error::NotFound(format!("Block for hash {}, {}", h, e)))?;
-		BlockPrintable::from_block(&block, &chain, include_proof, include_merkle_proof).map_err(
-			|e| Error::Internal(format!("chain error, broken block for hash {}. {}", h, e)),
-		)
+		BlockPrintable::from_block(&block, &chain, include_proof, include_merkle_proof)
+			.map_err(|e| Error::from_chain(&format!("chain error, broken block for hash {}", h), e))
 	}
 
 	pub fn get_blocks(
@@ -249,10 +289,10 @@ impl BlockHandler {
 			.get_block(h)
 			.map_err(|e| Error::NotFound(format!("Block for hash {}, {}", h, e)))?;
 		CompactBlockPrintable::from_compact_block(&block.into(), &chain).map_err(|e| {
-			Error::Internal(format!(
-				"chain error, broken compact block for hash {}, {}",
-				h, e
-			))
+			Error::from_chain(
+				&format!("chain error, broken compact block for hash {}", h),
+				e,
+			)
 		})
 	}
 
@@ -13,18 +13,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::utils::{get_output, get_output_v2, w};
+use super::utils::{get_output, get_output_v2, parse_commitment, w};
 use crate::chain;
 use crate::core::core::hash::{Hash, Hashed};
 use crate::rest::*;
 use crate::router::{Handler, ResponseFuture};
 use crate::types::*;
-use crate::util;
 use crate::util::secp::pedersen::Commitment;
+use crate::util::{self, ToHex};
 use crate::web::*;
 use hyper::{Body, Request, StatusCode};
 use std::sync::Weak;
 
+/// Max number of items (outputs, kernels, headers, proofs) in a single batch request
+pub const BATCH_REQUEST_LIMIT: usize = 1000;
+
 /// Chain handler. Get the head details.
 /// GET /v1/chain
 pub struct ChainHandler {
@@ -35,7 +38,7 @@ impl ChainHandler {
 	pub fn get_tip(&self) -> Result<Tip, Error> {
 		let head = w(&self.chain)?
 			.head()
-			.map_err(|e| Error::Internal(format!("can't get head: {}", e)))?;
+			.map_err(|e| Error::from_chain("can't get head", e))?;
 		Ok(Tip::from_tip(head))
 	}
 }
@@ -55,10 +58,10 @@ pub struct ChainValidationHandler {
 impl ChainValidationHandler {
 	pub fn validate_chain(&self, fast_validation: bool) -> Result<(), Error> {
 		w(&self.chain)?.validate(fast_validation).map_err(|e| {
-			Error::Internal(format!(
-				"chain fast validation ({}) error: {}",
-				fast_validation, e
-			))
+			Error::from_chain(
+				&format!("chain fast validation ({}) error", fast_validation),
+				e,
+			)
 		})
 	}
 }
@@ -109,7 +112,7 @@ impl ChainCompactHandler {
 	pub fn compact_chain(&self) -> Result<(), Error> {
 		w(&self.chain)?
 			.compact()
-			.map_err(|e| Error::Internal(format!("compact chain error {}", e)))
+			.map_err(|e| Error::from_chain("compact chain error", e))
 	}
 }
 
@@ -144,6 +147,7 @@ impl OutputHandler {
 	) -> Result<Vec<OutputPrintable>, Error> {
 		let mut outputs: Vec<OutputPrintable> = vec![];
 		if let Some(commits) = commits {
+			check_batch_size("commits", commits.len())?;
 			// First check the commits length
 			for commit in &commits {
 				if commit.len() != 66 {
@@ -189,6 +193,54 @@ impl OutputHandler {
 		Ok(outputs)
 	}
 
+	/// Merkle proofs for the unspent outputs, all built against the same chain head.
+	pub fn get_merkle_proofs(&self, commits: Vec<String>) -> Result<OutputMerkleProofs, Error> {
+		check_batch_size("commits", commits.len())?;
+		let commitments = commits
+			.iter()
+			.map(|c| parse_commitment(c))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let (header, proofs) = w(&self.chain)?
+			.get_merkle_proofs_for_pos(&commitments)
+			.map_err(|e| Error::from_chain("Unable to build merkle proofs", e))?;
+		Ok(OutputMerkleProofs {
+			header_hash: header.hash().to_hex(),
+			height: header.height,
+			proofs: proofs.into_iter().map(|p| p.map(|p| p.to_hex())).collect(),
+		})
+	}
+
+	/// Merkle proof for the unspent output against the header `header_hash` (chain head
+	/// if None). The header must be on the current chain.
+	pub fn get_merkle_proof(
+		&self,
+		commit: String,
+		header_hash: Option<Hash>,
+	) -> Result<OutputMerkleProof, Error> {
+		let commitment = parse_commitment(&commit)?;
+		let (header, output, pos0, proof) = w(&self.chain)?
+			.get_merkle_proof_at_header(commitment, header_hash)
+			.map_err(|e| match e {
+				chain::Error::OutputNotFound(_)
+				| chain::Error::StoreErr(mwc_store::Error::NotFoundErr(_), _) => {
+					Error::NotFound(format!("Output {}, {}", commit, e))
+				}
+				_ => Error::from_chain(&format!("Unable to build merkle proof for {}", commit), e),
+			})?;
+		Ok(OutputMerkleProof {
+			commit,
+			output_type: if output.is_coinbase() {
+				OutputType::Coinbase
+			} else {
+				OutputType::Transaction
+			},
+			mmr_index: pos0 + 1,
+			merkle_proof: proof.to_hex(),
+			header: BlockHeaderPrintable::from_header(&header),
+		})
+	}
+
 	// allows traversal of utxo set
 	pub fn get_unspent_outputs(
 		&self,
@@ -226,7 +278,7 @@ impl OutputHandler {
 					)
 				})
 				.collect::<Result<Vec<_>, _>>()
-				.map_err(|e| Error::Internal(format!("chain error, {}", e)))?,
+				.map_err(|e| Error::from_chain("chain error", e))?,
 		};
 		Ok(out)
 	}
@@ -286,7 +338,7 @@ impl OutputHandler {
 				OutputPrintable::from_output(output, &chain, Some(&header), include_proof, true)
 			})
 			.collect::<Result<Vec<_>, _>>()
-			.map_err(|e| Error::Internal(format!("chain read outputs from block error, {}", e)))?;
+			.map_err(|e| Error::from_chain("chain read outputs from block error", e))?;
 
 		Ok(BlockOutputs {
 			header: BlockHeaderDifficultyInfo::from_header(&header),
@@ -330,7 +382,7 @@ impl OutputHandler {
 				)
 			})
 			.collect::<Result<Vec<_>, _>>()
-			.map_err(|e| Error::Internal(format!("chain read outputs from block error, {}", e)))?;
+			.map_err(|e| Error::from_chain("chain read outputs from block error", e))?;
 
 		Ok(outputs)
 	}
@@ -470,7 +522,7 @@ impl KernelHandler {
 				// Default is current head
 				let head_height = chain
 					.head()
-					.map_err(|e| Error::Internal(format!("Unable to get a chain head, {}", e)))?
+					.map_err(|e| Error::from_chain("Unable to get a chain head", e))?
 					.height;
 				max_height = if h >= head_height { None } else { Some(h) };
 			}
@@ -479,10 +531,10 @@ impl KernelHandler {
 		let kernel = chain
 			.get_kernel_height(&excess, min_height, max_height)
 			.map_err(|e| {
-				Error::Internal(format!(
-					"Unable to get a height for the excess {}, {}",
-					excess_s, e
-				))
+				Error::from_chain(
+					&format!("Unable to get a height for the excess {}", excess_s),
+					e,
+				)
 			})?
 			.map(|(tx_kernel, height, mmr_index)| LocatedTxKernel {
 				tx_kernel,
@@ -498,25 +550,16 @@ impl KernelHandler {
 		min_height: Option<u64>,
 		max_height: Option<u64>,
 	) -> Result<LocatedTxKernel, Error> {
-		let excess = util::from_hex(&excess_s)
-			.map_err(|e| Error::RequestError(format!("invalid excess hex {}, {}", excess_s, e)))?;
-		if excess.len() != 33 {
-			return Err(Error::RequestError(format!(
-				"invalid excess {}, get length {}, expected 33",
-				excess_s,
-				excess.len()
-			)));
-		}
-		let excess = Commitment::from_vec(excess);
+		let excess = parse_commitment(&excess_s)?;
 
 		let chain = w(&self.chain)?;
 		let kernel = chain
 			.get_kernel_height(&excess, min_height, max_height)
 			.map_err(|e| {
-				Error::Internal(format!(
-					"Unable to get a height for excess {}, {}",
-					excess_s, e
-				))
+				Error::from_chain(
+					&format!("Unable to get a height for excess {}", excess_s),
+					e,
+				)
 			})?
 			.map(|(tx_kernel, height, mmr_index)| LocatedTxKernel {
 				tx_kernel,
@@ -525,6 +568,65 @@ impl KernelHandler {
 			});
 		kernel.ok_or_else(|| Error::NotFound(format!("kernel value for excess {}", excess_s)))
 	}
+
+	/// Inclusion proof for the kernel against the header `header_hash` (chain head if None).
+	pub fn get_kernel_merkle_proof(
+		&self,
+		excess_s: String,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+		header_hash: Option<Hash>,
+	) -> Result<KernelInclusionProof, Error> {
+		let excess = parse_commitment(&excess_s)?;
+		let (height, header, proof) = w(&self.chain)?
+			.get_kernel_merkle_proof(&excess, header_hash, min_height, max_height)
+			.map_err(|e| {
+				Error::from_chain(
+					&format!(
+						"Unable to build kernel merkle proof for excess {}",
+						excess_s
+					),
+					e,
+				)
+			})?
+			.ok_or_else(|| Error::NotFound(format!("kernel value for excess {}", excess_s)))?;
+		Ok(KernelInclusionProof {
+			height,
+			proof_hex: proof.to_hex(),
+			proof,
+			header: BlockHeaderPrintable::from_header(&header),
+		})
+	}
+
+	/// Kernels for the list of excesses, the kernel MMR is scanned once for all of them.
+	/// Result is in the same order as `excesses`, None for kernels that are not found.
+	pub fn get_kernels_v2(
+		&self,
+		excesses: Vec<String>,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Vec<Option<LocatedTxKernel>>, Error> {
+		check_batch_size("excesses", excesses.len())?;
+		let excesses = excesses
+			.iter()
+			.map(|e| parse_commitment(e))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let chain = w(&self.chain)?;
+		let kernels = chain
+			.get_kernels_height(&excesses, min_height, max_height)
+			.map_err(|e| Error::from_chain("Unable to get kernels", e))?
+			.into_iter()
+			.map(|k| {
+				k.map(|(tx_kernel, height, mmr_index)| LocatedTxKernel {
+					tx_kernel,
+					height,
+					mmr_index,
+				})
+			})
+			.collect();
+		Ok(kernels)
+	}
 }
 
 impl Handler for KernelHandler {
@@ -532,3 +634,14 @@ impl Handler for KernelHandler {
 		result_to_response(self.get_kernel(req))
 	}
 }
+
+/// Check that the batch request is not too large
+pub fn check_batch_size(name: &str, size: usize) -> Result<(), Error> {
+	if size > BATCH_REQUEST_LIMIT {
+		return Err(Error::RequestError(format!(
+			"Too many {} requested: {}, max {} is allowed",
+			name, size, BATCH_REQUEST_LIMIT
+		)));
+	}
+	Ok(())
+}
@@ -56,7 +56,7 @@ impl PeersConnectedHandler {
 			let peer_addr_str = match peer.addr {
 				// for tor we just return this because older wallets
 				// can't process this.
-				PeerAddr::Onion(_) => format!("127.0.0.1:{}", 3414),
+				PeerAddr::Onion(_) | PeerAddr::I2p(_) => format!("127.0.0.1:{}", 3414),
 				PeerAddr::Ip(ip) => format!("{}:{}", ip.ip(), ip.port()),
 			};
 
@@ -97,7 +97,7 @@ impl Handler for PeersConnectedHandler {
 			let peer_addr_str = match peer.addr {
 				// for tor we just return this because older wallets
 				// can't process this.
-				PeerAddr::Onion(_) => format!("127.0.0.1:{}", 3414),
+				PeerAddr::Onion(_) | PeerAddr::I2p(_) => format!("127.0.0.1:{}", 3414),
 				PeerAddr::Ip(ip) => format!("{}:{}", ip.ip(), ip.port()),
 			};
 
@@ -184,6 +184,8 @@ impl Handler for PeerHandler {
 			peer_addr = PeerAddr::from_ip(ip_addr);
 		} else if let Ok(addr) = command.parse() {
 			peer_addr = PeerAddr::Ip(addr);
+		} else if p2p::i2p::is_i2p_address(command) {
+			peer_addr = PeerAddr::I2p(command.to_string());
 		} else if let Ok(onion) = command.parse() {
 			peer_addr = PeerAddr::Onion(onion);
 		} else {
@@ -216,6 +218,8 @@ impl Handler for PeerHandler {
 					PeerAddr::from_ip(ip_addr)
 				} else if let Ok(addr) = a.parse() {
 					PeerAddr::Ip(addr)
+				} else if p2p::i2p::is_i2p_address(a) {
+					PeerAddr::I2p(a.to_string())
 				} else if let Ok(addr) = a.parse() {
 					PeerAddr::Onion(addr)
 				} else {
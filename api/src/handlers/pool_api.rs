@@ -17,12 +17,13 @@ use super::utils::w;
 use crate::core::core::hash::Hashed;
 use crate::core::core::Transaction;
 use crate::core::ser::{self, DeserializationMode, ProtocolVersion};
+use crate::owner::PoolDryRun;
 use crate::pool::{self, BlockChain, PoolAdapter, PoolEntry};
 use crate::rest::*;
 use crate::router::{Handler, ResponseFuture};
 use crate::types::*;
-use crate::util;
 use crate::util::RwLock;
+use crate::util::{self, ToHex};
 use crate::web::*;
 use hyper::{Body, Request, StatusCode};
 use mwc_util::secp::{ContextFlag, Secp256k1};
@@ -105,16 +106,52 @@ where
 		let header = tx_pool
 			.blockchain
 			.chain_head()
-			.map_err(|e| Error::Internal(format!("Failed to get chain head, {}", e)))?;
+			.map_err(|e| Error::from_pool("Failed to get chain head", e))?;
 		tx_pool
 			.add_to_pool(source, tx, !fluff.unwrap_or(false), &header, secp)
-			.map_err(|e| Error::Internal(format!("Failed to update pool, {}", e)))?;
+			.map_err(Error::from)?;
 
 		info!("transaction {} was added to the pool", tx_hash);
 
 		Ok(())
 	}
+
+	pub fn test_accept_transaction(
+		&self,
+		tx: Transaction,
+		fluff: Option<bool>,
+		secp: &Secp256k1,
+	) -> Result<TxPoolVerdict, Error> {
+		let pool_arc = w(&self.tx_pool)?;
+		let tx_pool = pool_arc.read();
+		let header = tx_pool
+			.blockchain
+			.chain_head()
+			.map_err(|e| Error::from_pool("Failed to get chain head", e))?;
+		let checks = tx_pool.test_accept(&tx, !fluff.unwrap_or(false), &header, secp);
+		Ok(TxPoolVerdict::new(tx.hash().to_hex(), checks))
+	}
 }
+impl<B, P> PoolDryRun for RwLock<pool::TransactionPool<B, P>>
+where
+	B: BlockChain,
+	P: PoolAdapter,
+{
+	fn dry_run_transaction(
+		&self,
+		tx: Transaction,
+		secp: &Secp256k1,
+	) -> Result<TxPoolVerdict, Error> {
+		let tx_pool = self.read();
+		let header = tx_pool
+			.blockchain
+			.chain_head()
+			.map_err(|e| Error::from_pool("Failed to get chain head", e))?;
+		let checks = tx_pool.test_accept(&tx, false, &header, secp);
+		Ok(TxPoolVerdict::new(tx.hash().to_hex(), checks))
+	}
+}
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 struct TxWrapper {
@@ -178,10 +215,10 @@ where
 	let header = tx_pool
 		.blockchain
 		.chain_head()
-		.map_err(|e| Error::Internal(format!("Failed to get chain head: {}", e)))?;
+		.map_err(|e| Error::from_pool("Failed to get chain head", e))?;
 	tx_pool
 		.add_to_pool(source, tx, !fluff, &header, secp)
-		.map_err(|e| Error::Internal(format!("Failed to update pool: {}", e)))?;
+		.map_err(Error::from)?;
 	Ok(())
 }
 
@@ -0,0 +1,294 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! UTXO set scanning with the wallet view key. Scan is running as a background
+//! job, the caller is polling for the job status and found outputs.
+
+use super::utils::w;
+use crate::chain;
+use crate::core::libtx::proof::{self, RewindHashBuilder};
+use crate::keychain::SwitchCommitmentType;
+use crate::rest::*;
+use crate::types::*;
+use crate::util::secp::key::PublicKey;
+use crate::util::secp::{ContextFlag, Secp256k1};
+use crate::util::{self, Mutex, ToHex};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread;
+
+/// Number of outputs that are read from the chain at once
+const SCAN_BATCH_SIZE: u64 = 1000;
+/// Max number of the scans that can run at the same time
+const MAX_RUNNING_SCANS: usize = 2;
+/// Number of the finished scan jobs that the node keeps for the status requests
+const MAX_FINISHED_SCANS: usize = 10;
+
+struct ScanJob {
+	status: Mutex<ViewKeyScanStatus>,
+	cancelled: AtomicBool,
+}
+
+#[derive(Default)]
+struct ScanJobs {
+	next_job_id: u64,
+	jobs: HashMap<u64, Arc<ScanJob>>,
+}
+
+impl ScanJobs {
+	fn running_count(&self) -> usize {
+		self.jobs
+			.values()
+			.filter(|j| j.status.lock().state == ViewKeyScanState::Running)
+			.count()
+	}
+
+	// Removing the oldest finished jobs, running jobs are kept
+	fn cleanup(&mut self) {
+		let mut finished: Vec<u64> = self
+			.jobs
+			.iter()
+			.filter(|(_, j)| j.status.lock().state != ViewKeyScanState::Running)
+			.map(|(id, _)| *id)
+			.collect();
+		if finished.len() > MAX_FINISHED_SCANS {
+			finished.sort();
+			for id in &finished[..finished.len() - MAX_FINISHED_SCANS] {
+				self.jobs.remove(id);
+			}
+		}
+	}
+}
+
+lazy_static! {
+	static ref SCAN_JOBS: Mutex<ScanJobs> = Mutex::new(ScanJobs::default());
+}
+
+/// View key UTXO scan handler.
+pub struct ViewKeyScanHandler {
+	pub chain: Weak<chain::Chain>,
+}
+
+impl ViewKeyScanHandler {
+	/// Start a new scan job, returns the job id.
+	pub fn start_scan(
+		&self,
+		rewind_hash: Option<String>,
+		public_root_key: Option<String>,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+	) -> Result<u64, Error> {
+		let secp = Secp256k1::with_caps(ContextFlag::Commit);
+		let builder = Self::build_rewinder(&secp, rewind_hash, public_root_key)?;
+
+		let chain = w(&self.chain)?;
+		let start_height = start_height.unwrap_or(0);
+		if let Some(end_height) = end_height {
+			if end_height < start_height {
+				return Err(Error::Argument(format!(
+					"Invalid height range {}-{}",
+					start_height, end_height
+				)));
+			}
+		}
+		let (start_index, highest_index) = chain
+			.block_height_range_to_pmmr_indices(start_height, end_height)
+			.map_err(|e| {
+				Error::Argument(format!(
+					"Invalid height range {}-{:?}, {}",
+					start_height, end_height, e
+				))
+			})?;
+
+		let mut jobs = SCAN_JOBS.lock();
+		if jobs.running_count() >= MAX_RUNNING_SCANS {
+			return Err(Error::RequestError(format!(
+				"Too many running scans, max {} is allowed",
+				MAX_RUNNING_SCANS
+			)));
+		}
+		jobs.cleanup();
+		jobs.next_job_id += 1;
+		let job_id = jobs.next_job_id;
+		let job = Arc::new(ScanJob {
+			status: Mutex::new(ViewKeyScanStatus {
+				job_id,
+				state: ViewKeyScanState::Running,
+				start_index,
+				last_scanned_index: start_index.saturating_sub(1),
+				highest_index,
+				outputs: vec![],
+			}),
+			cancelled: AtomicBool::new(false),
+		});
+		jobs.jobs.insert(job_id, job.clone());
+
+		let chain = self.chain.clone();
+		thread::Builder::new()
+			.name(format!("view_key_scan_{}", job_id))
+			.spawn(move || {
+				let res = Self::scan(&chain, &secp, &builder, &job);
+				let mut status = job.status.lock();
+				status.state = match res {
+					Ok(_) => {
+						if job.cancelled.load(Ordering::Relaxed) {
+							ViewKeyScanState::Cancelled
+						} else {
+							ViewKeyScanState::Finished
+						}
+					}
+					Err(e) => {
+						warn!("View key scan {} is failed, {}", status.job_id, e);
+						ViewKeyScanState::Failed(e.to_string())
+					}
+				};
+				info!(
+					"View key scan {} is done, state {:?}, found {} outputs",
+					status.job_id,
+					status.state,
+					status.outputs.len()
+				);
+			})
+			.map_err(|e| Error::Internal(format!("Unable to start scan thread, {}", e)))?;
+
+		Ok(job_id)
+	}
+
+	/// Status and found outputs of the scan job
+	pub fn get_scan_status(&self, job_id: u64) -> Result<ViewKeyScanStatus, Error> {
+		let jobs = SCAN_JOBS.lock();
+		let job = jobs
+			.jobs
+			.get(&job_id)
+			.ok_or(Error::NotFound(format!("View key scan job {}", job_id)))?;
+		let status = job.status.lock().clone();
+		Ok(status)
+	}
+
+	/// Cancel the running scan job. Outputs that are already found stay available.
+	pub fn cancel_scan(&self, job_id: u64) -> Result<(), Error> {
+		let jobs = SCAN_JOBS.lock();
+		let job = jobs
+			.jobs
+			.get(&job_id)
+			.ok_or(Error::NotFound(format!("View key scan job {}", job_id)))?;
+		job.cancelled.store(true, Ordering::Relaxed);
+		Ok(())
+	}
+
+	fn build_rewinder(
+		secp: &Secp256k1,
+		rewind_hash: Option<String>,
+		public_root_key: Option<String>,
+	) -> Result<RewindHashBuilder, Error> {
+		let rewind_hash = match rewind_hash {
+			Some(hash) => {
+				let hash = util::from_hex(&hash)
+					.map_err(|e| Error::Argument(format!("Invalid rewind hash {}, {}", hash, e)))?;
+				if hash.len() != 32 {
+					return Err(Error::Argument(
+						"Invalid rewind hash, expected 32 bytes".into(),
+					));
+				}
+				Some(hash)
+			}
+			None => None,
+		};
+
+		match public_root_key {
+			Some(key) => {
+				let bytes = util::from_hex(&key).map_err(|e| {
+					Error::Argument(format!("Invalid public root key {}, {}", key, e))
+				})?;
+				let key = PublicKey::from_slice(secp, &bytes)
+					.map_err(|e| Error::Argument(format!("Invalid public root key, {}", e)))?;
+				let builder = RewindHashBuilder::from_public_root_key(secp, key);
+				if let Some(hash) = rewind_hash {
+					if &hash != builder.rewind_hash() {
+						return Err(Error::Argument(
+							"Rewind hash doesn't match the public root key".into(),
+						));
+					}
+				}
+				Ok(builder)
+			}
+			None => rewind_hash
+				.map(RewindHashBuilder::new)
+				.ok_or(Error::Argument(
+					"Either rewind hash or public root key is required".into(),
+				)),
+		}
+	}
+
+	fn scan(
+		chain: &Weak<chain::Chain>,
+		secp: &Secp256k1,
+		builder: &RewindHashBuilder,
+		job: &ScanJob,
+	) -> Result<(), Error> {
+		let (mut next_index, highest_index) = {
+			let status = job.status.lock();
+			(status.start_index, status.highest_index)
+		};
+
+		while next_index <= highest_index {
+			if job.cancelled.load(Ordering::Relaxed) {
+				return Ok(());
+			}
+			// Chain is upgraded for every batch, so the scan doesn't hold the node on shutdown
+			let chain = w(chain)?;
+			let (last_index, _, outputs) = chain.unspent_outputs_by_pmmr_index(
+				next_index,
+				SCAN_BATCH_SIZE,
+				Some(highest_index),
+			)?;
+
+			let mut found = vec![];
+			for output in outputs {
+				let commit = output.commitment();
+				let rewind =
+					proof::rewind(secp, builder, commit, None, output.proof).map_err(|e| {
+						Error::Internal(format!("Unable to rewind {:?}, {}", commit, e))
+					})?;
+				if let Some((value, key_id, switch)) = rewind {
+					let pos = match chain.get_unspent(commit)? {
+						Some((_, pos)) => pos,
+						None => continue, // was spent while we were scanning
+					};
+					found.push(ViewKeyOutput {
+						commit: commit.0.to_hex(),
+						value,
+						key_id: key_id.to_bytes().to_hex(),
+						path: key_id.to_bip_32_string(),
+						switch_commitment: switch != SwitchCommitmentType::None,
+						is_coinbase: output.is_coinbase(),
+						mmr_index: pos.pos,
+						height: pos.height,
+					});
+				}
+			}
+
+			let mut status = job.status.lock();
+			status.last_scanned_index = last_index;
+			status.outputs.extend(found);
+			if last_index < next_index {
+				// Output MMR is shorter than expected, nothing left to scan
+				break;
+			}
+			next_index = last_index + 1;
+		}
+		Ok(())
+	}
+}
@@ -54,7 +54,7 @@ impl StatusHandler {
 	pub fn get_status(&self) -> Result<Status, Error> {
 		let head = w(&self.chain)?
 			.head()
-			.map_err(|e| Error::Internal(format!("Unable to get chain tip, {}", e)))?;
+			.map_err(|e| Error::from_chain("Unable to get chain tip", e))?;
 		let sync_status = w(&self.sync_state)?.status();
 		let (api_sync_status, api_sync_info) = sync_status_to_api(sync_status);
 		Ok(Status::from_tip_and_peers(
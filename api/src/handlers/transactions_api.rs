@@ -49,7 +49,7 @@ impl TxHashSetHandler {
 	fn get_roots(&self) -> Result<TxHashSet, Error> {
 		let chain = w(&self.chain)?;
 		TxHashSet::from_head(&chain)
-			.map_err(|e| Error::Internal(format!("failed to read roots from txhashset: {}", e)))
+			.map_err(|e| Error::from_chain("failed to read roots from txhashset", e))
 	}
 
 	// gets last n outputs inserted in to the tree
@@ -98,7 +98,7 @@ impl TxHashSetHandler {
 				.iter()
 				.map(|x| OutputPrintable::from_output(x, &chain, None, true, true))
 				.collect::<Result<Vec<_>, _>>()
-				.map_err(|e| Error::Internal(format!("chain error: {}", e)))?,
+				.map_err(|e| Error::from_chain("chain error", e))?,
 		};
 		Ok(out)
 	}
@@ -25,11 +25,25 @@ use std::sync::{Arc, Weak};
 // All handlers use `Weak` references instead of `Arc` to avoid cycles that
 // can never be destroyed. These 2 functions are simple helpers to reduce the
 // boilerplate of dealing with `Weak`.
-pub fn w<T>(weak: &Weak<T>) -> Result<Arc<T>, Error> {
+pub fn w<T: ?Sized>(weak: &Weak<T>) -> Result<Arc<T>, Error> {
 	weak.upgrade()
 		.ok_or_else(|| Error::Internal("failed to upgrade weak reference".to_owned()))
 }
 
+/// Parse the hex encoded commitment, an output commitment or a kernel excess
+pub fn parse_commitment(commit: &str) -> Result<Commitment, Error> {
+	let c = util::from_hex(commit)
+		.map_err(|e| Error::RequestError(format!("invalid commitment hex {}, {}", commit, e)))?;
+	if c.len() != 33 {
+		return Err(Error::RequestError(format!(
+			"invalid commitment {}, get length {}, expected 33",
+			commit,
+			c.len()
+		)));
+	}
+	Ok(Commitment::from_vec(c))
+}
+
 /// Internal function to retrieves an output by a given commitment
 fn get_unspent(
 	chain: &Arc<chain::Chain>,
@@ -34,7 +34,7 @@ impl VersionHandler {
 	pub fn get_version(&self) -> Result<Version, Error> {
 		let head = w(&self.chain)?
 			.head_header()
-			.map_err(|e| Error::Internal(format!("can't get head: {}", e)))?;
+			.map_err(|e| Error::from_chain("can't get head", e))?;
 
 		Ok(Version {
 			node_version: CRATE_VERSION.to_owned(),
@@ -15,6 +15,7 @@
 
 use mwc_chain as chain;
 use mwc_core as core;
+use mwc_keychain as keychain;
 use mwc_p2p as p2p;
 use mwc_pool as pool;
 
@@ -51,10 +52,10 @@ pub use crate::auth::{
 pub use crate::foreign::Foreign;
 pub use crate::foreign_rpc::ForeignRpc;
 pub use crate::handlers::node_apis;
-pub use crate::owner::Owner;
 pub use crate::owner::{
 	get_server_onion_address, reset_server_onion_address, set_server_onion_address,
 };
+pub use crate::owner::{BlockGenerator, OnionClientAuth, Owner, PoolDryRun, WatchList};
 pub use crate::owner_rpc::OwnerRpc;
 pub use crate::rest::*;
 pub use crate::router::*;
@@ -17,16 +17,32 @@
 
 use crate::chain::{Chain, SyncState};
 use crate::core::core::hash::Hash;
+use crate::core::core::Transaction;
+use crate::core::global;
 use crate::handlers::chain_api::{ChainCompactHandler, ChainResetHandler, ChainValidationHandler};
 use crate::handlers::peers_api::{PeerHandler, PeersConnectedHandler};
+use crate::handlers::scan_api::ViewKeyScanHandler;
 use crate::handlers::server_api::StatusHandler;
+use crate::handlers::utils::w;
 use crate::p2p::{self, PeerData};
 use crate::rest::*;
-use crate::types::Status;
+use crate::types::{
+	BlockHeaderPrintable, Libp2pTopic, OnionClientKey, Status, TxPoolVerdict, ViewKeyScanStatus,
+	WatchEvents, WatchItem,
+};
+use crate::util;
+use mwc_p2p::libp2p_connection;
+use mwc_p2p::libp2p_rate_limit::{self, GossipRateLimitStats};
 use mwc_p2p::types::PeerInfoDisplayLegacy;
+use mwc_util::secp::Secp256k1;
 use mwc_util::Mutex;
 use std::net::SocketAddr;
-use std::sync::Weak;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Max time of the watch list events long poll
+const MAX_WATCH_EVENTS_TIMEOUT_SEC: u64 = 60;
 
 lazy_static! {
 	static ref SERVER_ONION_ADDRESS: Mutex<Option<String>> = Mutex::new(None);
@@ -45,6 +61,78 @@ pub fn reset_server_onion_address() {
 	SERVER_ONION_ADDRESS.lock().take();
 }
 
+/// Blocks generation on demand for the testing networks. Implemented by the server
+/// because block building needs the transaction pool and the wallet communication.
+pub trait BlockGenerator: Send + Sync {
+	/// Build, mine and process `count` blocks on top of the current head.
+	/// The coinbase is requested from the wallet listener at `coinbase_destination`,
+	/// if it is None, the reward is burnt. Returns hashes of the new blocks.
+	fn generate_blocks(
+		&self,
+		count: u64,
+		coinbase_destination: Option<String>,
+	) -> Result<Vec<Hash>, String>;
+}
+
+/// Watch list of the outputs and kernels. Implemented by the server because the
+/// events are produced by the chain hooks.
+pub trait WatchList: Send + Sync {
+	/// Add items to the watch list. Existing items are updated with the new label.
+	fn add_items(&self, items: Vec<WatchItem>) -> Result<(), String>;
+	/// Remove items from the watch list. Label of the items is ignored.
+	fn remove_items(&self, items: Vec<WatchItem>) -> Result<(), String>;
+	/// All items of the watch list
+	fn get_items(&self) -> Result<Vec<WatchItem>, String>;
+	/// Events with the sequence number after `after_seq`. If there are no such events,
+	/// waits up to `timeout` for the new ones.
+	fn get_events(&self, after_seq: u64, timeout: Duration) -> Result<WatchEvents, String>;
+	/// Sequence number of the last event, updated with every new event
+	fn subscribe(&self) -> watch::Receiver<u64>;
+}
+
+/// Waits asynchronously up to `timeout_sec` seconds for the watch list events after
+/// `after_seq`. Used by the API handler, so the long poll doesn't hold a worker thread.
+pub(crate) async fn wait_watch_events(
+	watch_list: &dyn WatchList,
+	after_seq: u64,
+	timeout_sec: u64,
+) {
+	let timeout = Duration::from_secs(timeout_sec.min(MAX_WATCH_EVENTS_TIMEOUT_SEC));
+	let mut last_seq = watch_list.subscribe();
+	let wait = async move {
+		// The first recv returns the current value
+		while let Some(seq) = last_seq.recv().await {
+			if seq > after_seq {
+				break;
+			}
+		}
+	};
+	let _ = tokio::time::timeout(timeout, wait).await;
+}
+
+/// Transaction pool checks without adding the transaction to the pool. The owner API
+/// is not generic over the pool types, so the pool is accessed through this trait.
+pub trait PoolDryRun: Send + Sync {
+	/// Run the pool checks of the fluffed transaction against the current chain head.
+	fn dry_run_transaction(
+		&self,
+		tx: Transaction,
+		secp: &Secp256k1,
+	) -> Result<TxPoolVerdict, Error>;
+}
+
+/// Client authorization of the node onion service. Implemented by the server because
+/// the client keys are stored at the tor onion service directory.
+pub trait OnionClientAuth: Send + Sync {
+	/// Generate a new client key pair and authorize the public key. The private key
+	/// is returned and is not stored by the node.
+	fn add_client(&self, name: String) -> Result<OnionClientKey, String>;
+	/// Remove the client key. Returns false if there is no such client.
+	fn revoke_client(&self, name: &str) -> Result<bool, String>;
+	/// All authorized clients, including the keys from the config
+	fn get_clients(&self) -> Result<Vec<OnionClientKey>, String>;
+}
+
 /// Main interface into all node API functions.
 /// Node APIs are split into two seperate blocks of functionality
 /// called the ['Owner'](struct.Owner.html) and ['Foreign'](struct.Foreign.html) APIs
@@ -56,6 +144,10 @@ pub struct Owner {
 	pub chain: Weak<Chain>,
 	pub peers: Weak<p2p::Peers>,
 	pub sync_state: Weak<SyncState>,
+	pub tx_pool: Weak<dyn PoolDryRun>,
+	pub block_generator: Option<Arc<dyn BlockGenerator>>,
+	pub watch_list: Option<Arc<dyn WatchList>>,
+	pub onion_client_auth: Option<Arc<dyn OnionClientAuth>>,
 }
 
 impl Owner {
@@ -67,16 +159,31 @@ impl Owner {
 	/// * `tx_pool` - A non-owning reference of the transaction pool.
 	/// * `peers` - A non-owning reference of the peers.
 	/// * `sync_state` - A non-owning reference of the `sync_state`.
+	/// * `block_generator` - Blocks generator, available for the testing networks only.
+	/// * `watch_list` - Watch list of the outputs and kernels.
+	/// * `onion_client_auth` - Client authorization of the onion service, available if the node runs tor.
 	///
 	/// # Returns
 	/// * An instance of the Node holding references to the current chain, transaction pool, peers and sync_state.
 	///
 
-	pub fn new(chain: Weak<Chain>, peers: Weak<p2p::Peers>, sync_state: Weak<SyncState>) -> Self {
+	pub fn new(
+		chain: Weak<Chain>,
+		peers: Weak<p2p::Peers>,
+		sync_state: Weak<SyncState>,
+		tx_pool: Weak<dyn PoolDryRun>,
+		block_generator: Option<Arc<dyn BlockGenerator>>,
+		watch_list: Option<Arc<dyn WatchList>>,
+		onion_client_auth: Option<Arc<dyn OnionClientAuth>>,
+	) -> Self {
 		Owner {
 			chain,
 			peers,
 			sync_state,
+			tx_pool,
+			block_generator,
+			watch_list,
+			onion_client_auth,
 		}
 	}
 
@@ -220,4 +327,413 @@ impl Owner {
 		};
 		peer_handler.unban_peer(addr)
 	}
+
+	/// Generates blocks immediately. Available for the testing networks only.
+	///
+	/// # Arguments
+	/// * `count` - number of blocks to generate.
+	/// * `coinbase_destination` - wallet listener url to request the coinbase from. If None, the reward is burnt.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`BlockHeaderPrintable`](types/struct.BlockHeaderPrintable.html) for the generated blocks
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn generate_blocks(
+		&self,
+		count: u64,
+		coinbase_destination: Option<String>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error> {
+		Self::check_testing_network()?;
+		let generator = self.block_generator.as_ref().ok_or(Error::RequestError(
+			"Blocks generation is not available".into(),
+		))?;
+		let hashes = generator
+			.generate_blocks(count, coinbase_destination)
+			.map_err(|e| Error::RequestError(format!("Unable to generate blocks, {}", e)))?;
+		let chain = w(&self.chain)?;
+		let mut headers = Vec::with_capacity(hashes.len());
+		for hash in hashes {
+			let header = chain.get_block_header(&hash)?;
+			headers.push(BlockHeaderPrintable::from_header(&header));
+		}
+		Ok(headers)
+	}
+
+	/// Sets the mock time for the timestamps of the new blocks. Available for the testing networks only.
+	/// Mock time in the future is limited by the future time limit of the block validation.
+	///
+	/// # Arguments
+	/// * `timestamp` - unix time in seconds, or None to use the system clock again.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the mock time was set
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), Error> {
+		Self::check_testing_network()?;
+		if let Some(ts) = timestamp {
+			if ts <= 0 {
+				return Err(Error::Argument(format!("Invalid mock time {}", ts)));
+			}
+		}
+		global::set_mock_time(timestamp);
+		Ok(())
+	}
+
+	/// Sets the difficulty for the blocks from [`generate_blocks`](#method.generate_blocks).
+	/// Available for the testing networks only. The consensus difficulty is not affected, the
+	/// generated blocks skip the difficulty validation on this node. The peers don't accept such
+	/// blocks, so they are not broadcasted.
+	///
+	/// # Arguments
+	/// * `difficulty` - difficulty of the next blocks, or None to use the consensus adjustment again.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the difficulty was set
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn set_difficulty(&self, difficulty: Option<u64>) -> Result<(), Error> {
+		Self::check_testing_network()?;
+		if difficulty == Some(0) {
+			return Err(Error::Argument("Difficulty can't be zero".into()));
+		}
+		global::set_mock_difficulty(difficulty);
+		Ok(())
+	}
+
+	/// Starts a background scan of the UTXO set for the outputs that belong to the view key.
+	/// The view key is defined by the rewind hash or the public root key of the wallet,
+	/// if both are provided, they must match.
+	///
+	/// # Arguments
+	/// * `rewind_hash` - hex encoded rewind hash of the wallet view key.
+	/// * `public_root_key` - hex encoded compressed public root key of the wallet.
+	/// * `start_height` - first block height to scan, 0 if None.
+	/// * `end_height` - last block height to scan, the chain head if None.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * The scan job id, use it for [`get_view_key_scan`](struct.Owner.html#method.get_view_key_scan)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn start_view_key_scan(
+		&self,
+		rewind_hash: Option<String>,
+		public_root_key: Option<String>,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+	) -> Result<u64, Error> {
+		let scan_handler = ViewKeyScanHandler {
+			chain: self.chain.clone(),
+		};
+		scan_handler.start_scan(rewind_hash, public_root_key, start_height, end_height)
+	}
+
+	/// Returns the progress and the found outputs of the view key scan.
+	///
+	/// # Arguments
+	/// * `job_id` - scan job id.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`ViewKeyScanStatus`](types/struct.ViewKeyScanStatus.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_view_key_scan(&self, job_id: u64) -> Result<ViewKeyScanStatus, Error> {
+		let scan_handler = ViewKeyScanHandler {
+			chain: self.chain.clone(),
+		};
+		scan_handler.get_scan_status(job_id)
+	}
+
+	/// Cancels the running view key scan. Outputs found so far are still available.
+	///
+	/// # Arguments
+	/// * `job_id` - scan job id.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the scan was cancelled
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn cancel_view_key_scan(&self, job_id: u64) -> Result<(), Error> {
+		let scan_handler = ViewKeyScanHandler {
+			chain: self.chain.clone(),
+		};
+		scan_handler.cancel_scan(job_id)
+	}
+
+	/// Adds outputs and kernels to the watch list. The node reports confirmation, spend and
+	/// reorg events for them, see [`get_watch_events`](struct.Owner.html#method.get_watch_events).
+	/// The watch list is stored by the node and survives the restart.
+	///
+	/// # Arguments
+	/// * `items` - output commitments or kernel excesses to watch.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the items were added
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn add_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error> {
+		self.watch_list()?
+			.add_items(items)
+			.map_err(|e| Error::Argument(format!("Unable to add watch items, {}", e)))
+	}
+
+	/// Removes outputs and kernels from the watch list.
+	///
+	/// # Arguments
+	/// * `items` - output commitments or kernel excesses to remove.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the items were removed
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn remove_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error> {
+		self.watch_list()?
+			.remove_items(items)
+			.map_err(|e| Error::Argument(format!("Unable to remove watch items, {}", e)))
+	}
+
+	/// Returns all items of the watch list.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`WatchItem`](types/struct.WatchItem.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_watch_items(&self) -> Result<Vec<WatchItem>, Error> {
+		self.watch_list()?
+			.get_items()
+			.map_err(|e| Error::Internal(format!("Unable to read watch items, {}", e)))
+	}
+
+	/// Long poll for the watch list events. Returns events that are newer than `after_seq`,
+	/// if there are none, waits up to `timeout_sec` seconds for the new events.
+	///
+	/// # Arguments
+	/// * `after_seq` - sequence number of the last processed event, 0 to get all events.
+	/// * `timeout_sec` - wait time in seconds, 0 if None, limited to 60 seconds.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`WatchEvents`](types/struct.WatchEvents.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_watch_events(
+		&self,
+		after_seq: u64,
+		timeout_sec: Option<u64>,
+	) -> Result<WatchEvents, Error> {
+		let timeout = timeout_sec.unwrap_or(0).min(MAX_WATCH_EVENTS_TIMEOUT_SEC);
+		self.watch_list()?
+			.get_events(after_seq, Duration::from_secs(timeout))
+			.map_err(|e| Error::Internal(format!("Unable to read watch events, {}", e)))
+	}
+
+	/// Runs the transaction pool checks of the transaction without adding it to the pool
+	/// and without broadcasting it. The checks are the same as for the fluffed transaction in
+	/// [`test_accept_transaction`](struct.Foreign.html#method.test_accept_transaction).
+	///
+	/// # Arguments
+	/// * `tx` - the Mwc transaction to check.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`TxPoolVerdict`](types/struct.TxPoolVerdict.html) with the result of every check
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn dry_run_transaction(&self, tx: Transaction) -> Result<TxPoolVerdict, Error> {
+		let chain = w(&self.chain)?;
+		w(&self.tx_pool)?.dry_run_transaction(tx, chain.secp())
+	}
+
+	/// Get the libp2p messaging topics that this node is listening on.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`Libp2pTopic`](types/struct.Libp2pTopic.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_libp2p_topics(&self) -> Result<Vec<Libp2pTopic>, Error> {
+		let mut topics: Vec<Libp2pTopic> = libp2p_connection::get_topics()
+			.into_iter()
+			.map(|(topic, _, min_fee)| Libp2pTopic { topic, min_fee })
+			.collect();
+		topics.sort_by(|t1, t2| t1.topic.cmp(&t2.topic));
+		Ok(topics)
+	}
+
+	/// Start listening on the libp2p messaging topic. If the node is already listening on
+	/// the topic, the minimal integrity fee is updated.
+	///
+	/// # Arguments
+	/// * `topic` - the topic name.
+	/// * `min_fee` - minimal integrity fee that the received messages should pay.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `true` if the topic is new, `false` if the topic fee was updated
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn add_libp2p_topic(&self, topic: String, min_fee: u64) -> Result<bool, Error> {
+		if topic.is_empty() {
+			return Err(Error::Argument("Empty libp2p topic".into()));
+		}
+		Ok(libp2p_connection::add_topic(&topic, min_fee))
+	}
+
+	/// Stop listening on the libp2p messaging topic.
+	///
+	/// # Arguments
+	/// * `topic` - the topic name.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `true` if the topic was removed, `false` if the node wasn't listening on it
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn remove_libp2p_topic(&self, topic: String) -> Result<bool, Error> {
+		Ok(libp2p_connection::remove_topic(&topic))
+	}
+
+	/// Publish the integrity message on the libp2p topic. The message is expected to be built
+	/// by the wallet (see `build_integrity_message`), the node doesn't validate the integrity
+	/// fee and the signature.
+	///
+	/// # Arguments
+	/// * `topic` - the topic name.
+	/// * `integrity_message` - the integrity message, hex encoded.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * Id of the published message
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn publish_libp2p_message(
+		&self,
+		topic: String,
+		integrity_message: String,
+	) -> Result<String, Error> {
+		if topic.is_empty() {
+			return Err(Error::Argument("Empty libp2p topic".into()));
+		}
+		let integrity_message = util::from_hex(&integrity_message).map_err(|e| {
+			Error::Argument(format!("Invalid hex for the integrity message, {}", e))
+		})?;
+		if !libp2p_connection::get_libp2p_running() {
+			return Err(Error::P2pError("libp2p node is not running".into()));
+		}
+		libp2p_connection::publish_topic_message(&topic, integrity_message).ok_or(Error::P2pError(
+			format!("Unable to publish libp2p message on the topic {}", topic),
+		))
+	}
+
+	/// Get the counters of the libp2p messages that were dropped by the rate limits.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`GossipRateLimitStats`](../mwc_p2p/libp2p_rate_limit/struct.GossipRateLimitStats.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_libp2p_rate_limit_stats(&self) -> Result<GossipRateLimitStats, Error> {
+		Ok(libp2p_rate_limit::get_rate_limit_stats())
+	}
+
+	/// Generate a new client key pair for the onion service client authorization. The public
+	/// key is stored by the node, the private key is returned and is not stored, pass it to the
+	/// client tor (`ClientOnionAuthDir`). The change is applied when the onion service is
+	/// published next time, i.e. at the node restart.
+	///
+	/// # Arguments
+	/// * `name` - name of the client, letters, digits, '-' and '_'.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`OnionClientKey`](types/struct.OnionClientKey.html) with the client private key
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn add_onion_client(&self, name: String) -> Result<OnionClientKey, Error> {
+		self.onion_client_auth()?
+			.add_client(name)
+			.map_err(|e| Error::Argument(format!("Unable to add onion client, {}", e)))
+	}
+
+	/// Revoke the onion service client key. The keys from the config can't be revoked with
+	/// this method. The change is applied when the onion service is published next time,
+	/// i.e. at the node restart.
+	///
+	/// # Arguments
+	/// * `name` - name of the client.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `true` if the client was revoked, `false` if there is no such client
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn revoke_onion_client(&self, name: String) -> Result<bool, Error> {
+		self.onion_client_auth()?
+			.revoke_client(&name)
+			.map_err(|e| Error::Argument(format!("Unable to revoke onion client, {}", e)))
+	}
+
+	/// Returns the clients that are authorized to connect to the node onion service.
+	/// If no clients are authorized, the onion service is open for everybody.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A vector of [`OnionClientKey`](types/struct.OnionClientKey.html)
+	/// * or [`Error`](struct.Error.html) if an error is encountered.
+	///
+
+	pub fn get_onion_clients(&self) -> Result<Vec<OnionClientKey>, Error> {
+		self.onion_client_auth()?
+			.get_clients()
+			.map_err(|e| Error::Internal(format!("Unable to read onion clients, {}", e)))
+	}
+
+	fn watch_list(&self) -> Result<&Arc<dyn WatchList>, Error> {
+		self.watch_list
+			.as_ref()
+			.ok_or(Error::RequestError("Watch list is not available".into()))
+	}
+
+	fn onion_client_auth(&self) -> Result<&Arc<dyn OnionClientAuth>, Error> {
+		self.onion_client_auth.as_ref().ok_or(Error::RequestError(
+			"Onion service client authorization is not available, tor is not managed by the node"
+				.into(),
+		))
+	}
+
+	fn check_testing_network() -> Result<(), Error> {
+		if global::is_production_mode() {
+			return Err(Error::RequestError(
+				"This method is available for the testing networks only".into(),
+			));
+		}
+		Ok(())
+	}
 }
@@ -15,10 +15,15 @@
 
 //! JSON-RPC Stub generation for the Owner API
 
+use crate::core::core::Transaction;
 use crate::owner::Owner;
 use crate::p2p::PeerData;
 use crate::rest::Error;
-use crate::types::Status;
+use crate::types::{
+	BlockHeaderPrintable, Libp2pTopic, OnionClientKey, Status, TxPoolVerdict, ViewKeyScanStatus,
+	WatchEvents, WatchItem,
+};
+use mwc_p2p::libp2p_rate_limit::GossipRateLimitStats;
 use mwc_p2p::types::PeerInfoDisplayLegacy;
 use std::net::SocketAddr;
 
@@ -357,6 +362,500 @@ pub trait OwnerRpc: Sync + Send {
 	```
 	 */
 	fn unban_peer(&self, peer_addr: SocketAddr) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::generate_blocks](struct.Owner.html#method.generate_blocks).
+
+	The result is the list of the new block headers, in the same format as the result of
+	[ForeignRpc::get_header](trait.ForeignRpc.html#tymethod.get_header). The block hashes and
+	the roots depend on the coinbase, so there is no fixed example of the response.
+
+	# Json rpc example
+
+	```json
+	{
+		"jsonrpc": "2.0",
+		"method": "generate_blocks",
+		"params": [1, "http://127.0.0.1:13415"],
+		"id": 1
+	}
+	```
+	 */
+	fn generate_blocks(
+		&self,
+		count: u64,
+		coinbase_destination: Option<String>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error>;
+
+	/**
+	Networked version of [Owner::set_mock_time](struct.Owner.html#method.set_mock_time).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_mock_time",
+		"params": [1727075653],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::set_difficulty](struct.Owner.html#method.set_difficulty).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_difficulty",
+		"params": [1],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn set_difficulty(&self, difficulty: Option<u64>) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::start_view_key_scan](struct.Owner.html#method.start_view_key_scan).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "start_view_key_scan",
+		"params": ["c5ba3ffc8a2a81b4ac1cea2d5ab4bd3d2f5edc6e1e3d0a8a63b4e5e9e3a3c3d1", null, 0, null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": 1
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn start_view_key_scan(
+		&self,
+		rewind_hash: Option<String>,
+		public_root_key: Option<String>,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+	) -> Result<u64, Error>;
+
+	/**
+	Networked version of [Owner::get_view_key_scan](struct.Owner.html#method.get_view_key_scan).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_view_key_scan",
+		"params": [1],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"job_id": 1,
+				"state": "Finished",
+				"start_index": 0,
+				"last_scanned_index": 12,
+				"highest_index": 12,
+				"outputs": [
+					{
+						"commit": "08a3f8a3d9c5b1c0e8a0e6c1e1e6f0a5b5c3d7f1b4f6b9f1f4e1b8c3d0b1a2c3d4",
+						"value": 2380952380,
+						"key_id": "0300000000000000000000000100000000",
+						"path": "m/0/0/1",
+						"switch_commitment": true,
+						"is_coinbase": true,
+						"mmr_index": 8,
+						"height": 5
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_view_key_scan(&self, job_id: u64) -> Result<ViewKeyScanStatus, Error>;
+
+	/**
+	Networked version of [Owner::cancel_view_key_scan](struct.Owner.html#method.cancel_view_key_scan).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "cancel_view_key_scan",
+		"params": [1],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn cancel_view_key_scan(&self, job_id: u64) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::add_watch_items](struct.Owner.html#method.add_watch_items).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "add_watch_items",
+		"params": [[
+			{
+				"item_type": "Output",
+				"commit": "08a3f8a3d9c5b1c0e8a0e6c1e1e6f0a5b5c3d7f1b4f6b9f1f4e1b8c3d0b1a2c3d4",
+				"label": "deposit 1"
+			}
+		]],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn add_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::remove_watch_items](struct.Owner.html#method.remove_watch_items).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "remove_watch_items",
+		"params": [[
+			{
+				"item_type": "Output",
+				"commit": "08a3f8a3d9c5b1c0e8a0e6c1e1e6f0a5b5c3d7f1b4f6b9f1f4e1b8c3d0b1a2c3d4",
+				"label": null
+			}
+		]],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn remove_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error>;
+
+	/**
+	Networked version of [Owner::get_watch_items](struct.Owner.html#method.get_watch_items).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_watch_items",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"item_type": "Output",
+					"commit": "08a3f8a3d9c5b1c0e8a0e6c1e1e6f0a5b5c3d7f1b4f6b9f1f4e1b8c3d0b1a2c3d4",
+					"label": "deposit 1"
+				}
+			]
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_watch_items(&self) -> Result<Vec<WatchItem>, Error>;
+
+	/**
+	Networked version of [Owner::get_watch_events](struct.Owner.html#method.get_watch_events).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_watch_events",
+		"params": [0, 30],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"last_seq": 1,
+				"events": [
+					{
+						"seq": 1,
+						"event_type": "Confirmed",
+						"item_type": "Output",
+						"commit": "08a3f8a3d9c5b1c0e8a0e6c1e1e6f0a5b5c3d7f1b4f6b9f1f4e1b8c3d0b1a2c3d4",
+						"label": "deposit 1",
+						"height": 1021,
+						"block_hash": "0000fa8a1c4bc8d0d7d3e5a3c0e0fb0f44d1e0e85a8e8b2f0ed7e1a4d8bd6c4c"
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_watch_events(
+		&self,
+		after_seq: u64,
+		timeout_sec: Option<u64>,
+	) -> Result<WatchEvents, Error>;
+
+	/**
+	Networked version of [Owner::dry_run_transaction](struct.Owner.html#method.dry_run_transaction).
+
+	// No example because the transaction must be valid for the node chain state,
+	// see the push_transaction foreign API method for the transaction format.
+	 */
+	fn dry_run_transaction(&self, tx: Transaction) -> Result<TxPoolVerdict, Error>;
+
+	/**
+	Networked version of [Owner::get_libp2p_topics](struct.Owner.html#method.get_libp2p_topics).
+
+	// No example because the topics depend on the node configuration.
+	 */
+	fn get_libp2p_topics(&self) -> Result<Vec<Libp2pTopic>, Error>;
+
+	/**
+	Networked version of [Owner::add_libp2p_topic](struct.Owner.html#method.add_libp2p_topic).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "add_libp2p_topic",
+		"params": ["TestTopic", 10000000],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": true
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn add_libp2p_topic(&self, topic: String, min_fee: u64) -> Result<bool, Error>;
+
+	/**
+	Networked version of [Owner::remove_libp2p_topic](struct.Owner.html#method.remove_libp2p_topic).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "remove_libp2p_topic",
+		"params": ["UnknownTopic"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": false
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn remove_libp2p_topic(&self, topic: String) -> Result<bool, Error>;
+
+	/**
+	Networked version of [Owner::publish_libp2p_message](struct.Owner.html#method.publish_libp2p_message).
+
+	// No example because the integrity message must be built by the wallet and the libp2p node must be running.
+	 */
+	fn publish_libp2p_message(
+		&self,
+		topic: String,
+		integrity_message: String,
+	) -> Result<String, Error>;
+
+	/**
+	Networked version of [Owner::get_libp2p_rate_limit_stats](struct.Owner.html#method.get_libp2p_rate_limit_stats).
+
+	# Json rpc example
+
+	```
+	# mwc_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_libp2p_rate_limit_stats",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"banned_peers": 0,
+				"dropped_by_peer": 0,
+				"dropped_by_topic": 0
+			}
+		}
+	}
+	# "#
+	# );
+	```
+	 */
+	fn get_libp2p_rate_limit_stats(&self) -> Result<GossipRateLimitStats, Error>;
+
+	/**
+	Networked version of [Owner::add_onion_client](struct.Owner.html#method.add_onion_client).
+
+	// No example because the client keys are random and the node must run the tor onion service.
+	 */
+	fn add_onion_client(&self, name: String) -> Result<OnionClientKey, Error>;
+
+	/**
+	Networked version of [Owner::revoke_onion_client](struct.Owner.html#method.revoke_onion_client).
+
+	// No example because the node must run the tor onion service.
+	 */
+	fn revoke_onion_client(&self, name: String) -> Result<bool, Error>;
+
+	/**
+	Networked version of [Owner::get_onion_clients](struct.Owner.html#method.get_onion_clients).
+
+	// No example because the node must run the tor onion service.
+	 */
+	fn get_onion_clients(&self) -> Result<Vec<OnionClientKey>, Error>;
 }
 
 impl OwnerRpc for Owner {
@@ -395,6 +894,100 @@ impl OwnerRpc for Owner {
 	fn unban_peer(&self, addr: SocketAddr) -> Result<(), Error> {
 		Owner::unban_peer(self, addr)
 	}
+
+	fn generate_blocks(
+		&self,
+		count: u64,
+		coinbase_destination: Option<String>,
+	) -> Result<Vec<BlockHeaderPrintable>, Error> {
+		Owner::generate_blocks(self, count, coinbase_destination)
+	}
+
+	fn set_mock_time(&self, timestamp: Option<i64>) -> Result<(), Error> {
+		Owner::set_mock_time(self, timestamp)
+	}
+
+	fn set_difficulty(&self, difficulty: Option<u64>) -> Result<(), Error> {
+		Owner::set_difficulty(self, difficulty)
+	}
+
+	fn start_view_key_scan(
+		&self,
+		rewind_hash: Option<String>,
+		public_root_key: Option<String>,
+		start_height: Option<u64>,
+		end_height: Option<u64>,
+	) -> Result<u64, Error> {
+		Owner::start_view_key_scan(self, rewind_hash, public_root_key, start_height, end_height)
+	}
+
+	fn get_view_key_scan(&self, job_id: u64) -> Result<ViewKeyScanStatus, Error> {
+		Owner::get_view_key_scan(self, job_id)
+	}
+
+	fn cancel_view_key_scan(&self, job_id: u64) -> Result<(), Error> {
+		Owner::cancel_view_key_scan(self, job_id)
+	}
+
+	fn add_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error> {
+		Owner::add_watch_items(self, items)
+	}
+
+	fn remove_watch_items(&self, items: Vec<WatchItem>) -> Result<(), Error> {
+		Owner::remove_watch_items(self, items)
+	}
+
+	fn get_watch_items(&self) -> Result<Vec<WatchItem>, Error> {
+		Owner::get_watch_items(self)
+	}
+
+	fn get_watch_events(
+		&self,
+		after_seq: u64,
+		timeout_sec: Option<u64>,
+	) -> Result<WatchEvents, Error> {
+		Owner::get_watch_events(self, after_seq, timeout_sec)
+	}
+
+	fn dry_run_transaction(&self, tx: Transaction) -> Result<TxPoolVerdict, Error> {
+		Owner::dry_run_transaction(self, tx)
+	}
+
+	fn get_libp2p_topics(&self) -> Result<Vec<Libp2pTopic>, Error> {
+		Owner::get_libp2p_topics(self)
+	}
+
+	fn add_libp2p_topic(&self, topic: String, min_fee: u64) -> Result<bool, Error> {
+		Owner::add_libp2p_topic(self, topic, min_fee)
+	}
+
+	fn remove_libp2p_topic(&self, topic: String) -> Result<bool, Error> {
+		Owner::remove_libp2p_topic(self, topic)
+	}
+
+	fn publish_libp2p_message(
+		&self,
+		topic: String,
+		integrity_message: String,
+	) -> Result<String, Error> {
+		Owner::publish_libp2p_message(self, topic, integrity_message)
+	}
+
+	fn get_libp2p_rate_limit_stats(&self) -> Result<GossipRateLimitStats, Error> {
+		Owner::get_libp2p_rate_limit_stats(self)
+	}
+
+	fn add_onion_client(&self, name: String) -> Result<OnionClientKey, Error> {
+		Owner::add_onion_client(self, name)
+	}
+
+	fn revoke_onion_client(&self, name: String) -> Result<bool, Error> {
+		Owner::revoke_onion_client(self, name)
+	}
+
+	fn get_onion_clients(&self) -> Result<Vec<OnionClientKey>, Error> {
+		Owner::get_onion_clients(self)
+	}
 }
 
 #[doc(hidden)]
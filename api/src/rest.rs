@@ -19,7 +19,10 @@
 //! To use it, just have your service(s) implement the ApiEndpoint trait and
 //! register them on a ApiServer.
 
+use crate::chain;
+use crate::pool::PoolError;
 use crate::router::{Handler, HandlerObj, ResponseFuture, Router, RouterError};
+use crate::util::ToHex;
 use crate::web::response;
 use futures::channel::oneshot;
 use futures::TryStreamExt;
@@ -38,8 +41,65 @@ use tokio::runtime::Runtime;
 use tokio::stream::StreamExt;
 use tokio_rustls::TlsAcceptor;
 
+/// Stable numeric codes of the API errors, reported by the foreign, owner and stratum
+/// JSON-RPC interfaces together with the message. Existing codes must never change.
+pub mod error_codes {
+	pub const INTERNAL: i32 = 1000;
+	pub const ARGUMENT: i32 = 1001;
+	pub const NOT_FOUND: i32 = 1002;
+	pub const REQUEST: i32 = 1003;
+	pub const RESPONSE: i32 = 1004;
+	pub const ROUTER: i32 = 1005;
+	pub const P2P: i32 = 1006;
+
+	pub const POOL_OTHER: i32 = 2000;
+	pub const POOL_INVALID_TX: i32 = 2001;
+	pub const POOL_INVALID_BLOCK: i32 = 2002;
+	pub const POOL_KEYCHAIN: i32 = 2003;
+	pub const POOL_COMMITTED: i32 = 2004;
+	pub const POOL_IMMATURE_TRANSACTION: i32 = 2005;
+	pub const POOL_IMMATURE_COINBASE: i32 = 2006;
+	pub const POOL_DANDELION: i32 = 2007;
+	pub const POOL_OVER_CAPACITY: i32 = 2008;
+	pub const POOL_LOW_FEE_TRANSACTION: i32 = 2009;
+	pub const POOL_DUPLICATE_COMMITMENT: i32 = 2010;
+	pub const POOL_DUPLICATE_KERNEL_OR_SPENT: i32 = 2011;
+	pub const POOL_DUPLICATE_TX: i32 = 2012;
+	pub const POOL_NRD_KERNEL_PRE_HF3: i32 = 2013;
+	pub const POOL_NRD_KERNEL_NOT_ENABLED: i32 = 2014;
+	pub const POOL_NRD_KERNEL_RELATIVE_HEIGHT: i32 = 2015;
+
+	pub const CHAIN_OTHER: i32 = 3000;
+	pub const CHAIN_ALREADY_SPENT: i32 = 3001;
+	pub const CHAIN_DUPLICATE_COMMITMENT: i32 = 3002;
+	pub const CHAIN_IMMATURE_COINBASE: i32 = 3003;
+	pub const CHAIN_OUTPUT_NOT_FOUND: i32 = 3004;
+	pub const CHAIN_KERNEL_NOT_FOUND: i32 = 3005;
+	pub const CHAIN_TX_LOCK_HEIGHT: i32 = 3006;
+	pub const CHAIN_NRD_RELATIVE_HEIGHT: i32 = 3007;
+	pub const CHAIN_IN_SYNCING: i32 = 3008;
+	pub const CHAIN_STOPPED: i32 = 3009;
+}
+
+/// Structured details of the error, so clients don't need to parse the message.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ErrorData {
+	/// Transaction fee is lower than the fee required for its weight
+	LowFeeTransaction { fee: u64, required_fee: u64 },
+	/// Output with this commitment already exists
+	DuplicateCommitment { commit: String },
+	/// Output with this commitment is already spent
+	AlreadySpent { commit: String },
+	/// Coinbase output can't be spent before this height
+	ImmatureCoinbase { mature_at: u64 },
+}
+
 /// Errors that can be returned by an ApiEndpoint implementation.
-#[derive(Clone, Eq, PartialEq, Debug, thiserror::Error, Serialize, Deserialize)]
+///
+/// Serialized as `{"code": <i32>, "message": <String>, "data": <ErrorData or null>}`, see
+/// [`error_codes`](error_codes/index.html). The legacy serialization `{"<Variant>": <String>}`
+/// is still accepted by the deserialization.
+#[derive(Clone, Eq, PartialEq, Debug, thiserror::Error)]
 pub enum Error {
 	#[error("API Internal error: {0}")]
 	Internal(String),
@@ -58,11 +118,260 @@ pub enum Error {
 	},
 	#[error("API P2P error: {0}")]
 	P2pError(String),
+	#[error("API Pool error: {message}")]
+	Pool {
+		code: i32,
+		message: String,
+		data: Option<ErrorData>,
+	},
+	#[error("API Chain error: {message}")]
+	Chain {
+		code: i32,
+		message: String,
+		data: Option<ErrorData>,
+	},
+}
+
+impl Error {
+	/// Stable numeric code of the error
+	pub fn code(&self) -> i32 {
+		match self {
+			Error::Internal(_) => error_codes::INTERNAL,
+			Error::Argument(_) => error_codes::ARGUMENT,
+			Error::NotFound(_) => error_codes::NOT_FOUND,
+			Error::RequestError(_) => error_codes::REQUEST,
+			Error::ResponseError(_) => error_codes::RESPONSE,
+			Error::Router { .. } => error_codes::ROUTER,
+			Error::P2pError(_) => error_codes::P2P,
+			Error::Pool { code, .. } | Error::Chain { code, .. } => *code,
+		}
+	}
+
+	/// Human readable message of the error, without the error kind prefix
+	pub fn message(&self) -> String {
+		match self {
+			Error::Internal(msg)
+			| Error::Argument(msg)
+			| Error::NotFound(msg)
+			| Error::RequestError(msg)
+			| Error::ResponseError(msg)
+			| Error::P2pError(msg) => msg.clone(),
+			Error::Router { source } => source.to_string(),
+			Error::Pool { message, .. } | Error::Chain { message, .. } => message.clone(),
+		}
+	}
+
+	/// Structured details of the error, if any
+	pub fn data(&self) -> Option<&ErrorData> {
+		match self {
+			Error::Pool { data, .. } | Error::Chain { data, .. } => data.as_ref(),
+			_ => None,
+		}
+	}
+
+	/// Chain error with the description of the failed operation. Code and data
+	/// of the chain error are kept.
+	pub fn from_chain(context: &str, error: chain::Error) -> Error {
+		Error::from(error).with_context(context)
+	}
+
+	/// Pool error with the description of the failed operation. Code and data
+	/// of the pool error are kept.
+	pub fn from_pool(context: &str, error: PoolError) -> Error {
+		Error::from(error).with_context(context)
+	}
+
+	fn with_context(self, context: &str) -> Error {
+		match self {
+			Error::Pool {
+				code,
+				message,
+				data,
+			} => Error::Pool {
+				code,
+				message: format!("{}, {}", context, message),
+				data,
+			},
+			Error::Chain {
+				code,
+				message,
+				data,
+			} => Error::Chain {
+				code,
+				message: format!("{}, {}", context, message),
+				data,
+			},
+			error => error,
+		}
+	}
+}
+
+impl From<chain::Error> for Error {
+	fn from(error: chain::Error) -> Error {
+		let (code, data) = match &error {
+			chain::Error::AlreadySpent(commit) => (
+				error_codes::CHAIN_ALREADY_SPENT,
+				Some(ErrorData::AlreadySpent {
+					commit: commit.to_hex(),
+				}),
+			),
+			chain::Error::DuplicateCommitment(commit) => (
+				error_codes::CHAIN_DUPLICATE_COMMITMENT,
+				Some(ErrorData::DuplicateCommitment {
+					commit: commit.to_hex(),
+				}),
+			),
+			chain::Error::ImmatureCoinbase(mature_at) => (
+				error_codes::CHAIN_IMMATURE_COINBASE,
+				Some(ErrorData::ImmatureCoinbase {
+					mature_at: *mature_at,
+				}),
+			),
+			chain::Error::OutputNotFound(_) => (error_codes::CHAIN_OUTPUT_NOT_FOUND, None),
+			chain::Error::TxKernelNotFound => (error_codes::CHAIN_KERNEL_NOT_FOUND, None),
+			chain::Error::TxLockHeight => (error_codes::CHAIN_TX_LOCK_HEIGHT, None),
+			chain::Error::NRDRelativeHeight => (error_codes::CHAIN_NRD_RELATIVE_HEIGHT, None),
+			chain::Error::ChainInSyncing(_) => (error_codes::CHAIN_IN_SYNCING, None),
+			chain::Error::Stopped => (error_codes::CHAIN_STOPPED, None),
+			_ => (error_codes::CHAIN_OTHER, None),
+		};
+		Error::Chain {
+			code,
+			message: error.to_string(),
+			data,
+		}
+	}
+}
+
+impl From<PoolError> for Error {
+	fn from(error: PoolError) -> Error {
+		let (code, data) = match &error {
+			PoolError::InvalidTx(_) => (error_codes::POOL_INVALID_TX, None),
+			PoolError::InvalidBlock(_) => (error_codes::POOL_INVALID_BLOCK, None),
+			PoolError::Keychain(_) => (error_codes::POOL_KEYCHAIN, None),
+			PoolError::Committed(_) => (error_codes::POOL_COMMITTED, None),
+			PoolError::ImmatureTransaction => (error_codes::POOL_IMMATURE_TRANSACTION, None),
+			PoolError::ImmatureCoinbase { mature_at } => (
+				error_codes::POOL_IMMATURE_COINBASE,
+				Some(ErrorData::ImmatureCoinbase {
+					mature_at: *mature_at,
+				}),
+			),
+			PoolError::DandelionError => (error_codes::POOL_DANDELION, None),
+			PoolError::OverCapacity => (error_codes::POOL_OVER_CAPACITY, None),
+			PoolError::LowFeeTransaction { fee, required_fee } => (
+				error_codes::POOL_LOW_FEE_TRANSACTION,
+				Some(ErrorData::LowFeeTransaction {
+					fee: *fee,
+					required_fee: *required_fee,
+				}),
+			),
+			PoolError::DuplicateCommitment(commit) => (
+				error_codes::POOL_DUPLICATE_COMMITMENT,
+				Some(ErrorData::DuplicateCommitment {
+					commit: commit.to_hex(),
+				}),
+			),
+			PoolError::DuplicateKernelOrDuplicateSpent(_) => {
+				(error_codes::POOL_DUPLICATE_KERNEL_OR_SPENT, None)
+			}
+			PoolError::DuplicateTx => (error_codes::POOL_DUPLICATE_TX, None),
+			PoolError::NRDKernelPreHF3 => (error_codes::POOL_NRD_KERNEL_PRE_HF3, None),
+			PoolError::NRDKernelNotEnabled => (error_codes::POOL_NRD_KERNEL_NOT_ENABLED, None),
+			PoolError::NRDKernelRelativeHeight => {
+				(error_codes::POOL_NRD_KERNEL_RELATIVE_HEIGHT, None)
+			}
+			PoolError::Other(_) => (error_codes::POOL_OTHER, None),
+		};
+		Error::Pool {
+			code,
+			message: error.to_string(),
+			data,
+		}
+	}
+}
+
+// Wire format of the error
+#[derive(Serialize, Deserialize)]
+struct ErrorRepr {
+	code: i32,
+	message: String,
+	data: Option<ErrorData>,
+}
+
+// Serialization of the error before the error codes were introduced
+#[derive(Deserialize)]
+enum LegacyError {
+	Internal(String),
+	Argument(String),
+	NotFound(String),
+	RequestError(String),
+	ResponseError(String),
+	Router { source: RouterError },
+	P2pError(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorFormat {
+	Repr(ErrorRepr),
+	Legacy(LegacyError),
+}
+
+impl serde::Serialize for Error {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: serde::Serializer,
+	{
+		ErrorRepr {
+			code: self.code(),
+			message: self.message(),
+			data: self.data().cloned(),
+		}
+		.serialize(serializer)
+	}
 }
 
-impl From<crate::chain::Error> for Error {
-	fn from(error: crate::chain::Error) -> Error {
-		Error::Internal(error.to_string())
+impl<'de> serde::Deserialize<'de> for Error {
+	fn deserialize<D>(deserializer: D) -> Result<Error, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		let error = match ErrorFormat::deserialize(deserializer)? {
+			ErrorFormat::Repr(ErrorRepr {
+				code,
+				message,
+				data,
+			}) => match code {
+				error_codes::ARGUMENT => Error::Argument(message),
+				error_codes::NOT_FOUND => Error::NotFound(message),
+				error_codes::REQUEST => Error::RequestError(message),
+				error_codes::RESPONSE => Error::ResponseError(message),
+				error_codes::P2P => Error::P2pError(message),
+				2000..=2999 => Error::Pool {
+					code,
+					message,
+					data,
+				},
+				3000..=3999 => Error::Chain {
+					code,
+					message,
+					data,
+				},
+				// Router error details can't be restored
+				_ => Error::Internal(message),
+			},
+			ErrorFormat::Legacy(error) => match error {
+				LegacyError::Internal(msg) => Error::Internal(msg),
+				LegacyError::Argument(msg) => Error::Argument(msg),
+				LegacyError::NotFound(msg) => Error::NotFound(msg),
+				LegacyError::RequestError(msg) => Error::RequestError(msg),
+				LegacyError::ResponseError(msg) => Error::ResponseError(msg),
+				LegacyError::Router { source } => Error::Router { source },
+				LegacyError::P2pError(msg) => Error::P2pError(msg),
+			},
+		};
+		Ok(error)
 	}
 }
 
@@ -15,13 +15,15 @@
 
 use crate::chain;
 use crate::core::core::hash::Hashed;
-use crate::core::core::merkle_proof::MerkleProof;
+use crate::core::core::merkle_proof::{KernelMerkleProof, MerkleProof};
 use crate::core::core::{FeeFields, KernelFeatures, TxKernel};
 use crate::core::{core, ser};
 use crate::p2p;
+use crate::pool::{PoolCheck, PoolError};
+use crate::rest;
 use crate::util::secp::pedersen;
 use crate::util::{self, ToHex};
-use mwc_p2p::libp2p_connection;
+use mwc_p2p::{libp2p_connection, libp2p_message_store};
 use serde;
 use serde::de::MapAccess;
 use serde::ser::SerializeStruct;
@@ -772,6 +774,46 @@ pub struct LocatedTxKernel {
 	pub mmr_index: u64,
 }
 
+/// Kernel inclusion proof against the block header
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KernelInclusionProof {
+	/// Height of the block with the kernel
+	pub height: u64,
+	/// Kernel, its MMR index and the merkle proof
+	pub proof: KernelMerkleProof,
+	/// Hex encoded `proof`, ready to be attached to the payment proof or swap message
+	pub proof_hex: String,
+	/// Header the proof is valid against, the proof root is its kernel_root
+	pub header: BlockHeaderPrintable,
+}
+
+/// Merkle proof of the output against the block header
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutputMerkleProof {
+	/// Output commitment
+	pub commit: String,
+	/// Output type, needed to compute the output MMR element hash
+	pub output_type: OutputType,
+	/// Output MMR index (1-based)
+	pub mmr_index: u64,
+	/// Hex encoded merkle proof
+	pub merkle_proof: String,
+	/// Header the proof is valid against, the proof root is its output_root
+	pub header: BlockHeaderPrintable,
+}
+
+/// Merkle proofs for the batch of outputs
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutputMerkleProofs {
+	/// Hash of the header the proofs are valid against
+	pub header_hash: String,
+	/// Height of the header the proofs are valid against
+	pub height: u64,
+	/// Hex encoded proofs in the order of the requested commitments, None for outputs
+	/// that are not in the UTXO set
+	pub proofs: Vec<Option<String>>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PoolInfo {
 	/// Size of the pool
@@ -798,6 +840,196 @@ pub struct Libp2pMessages {
 	pub libp2p_messages: Vec<libp2p_connection::ReceivedMessage>,
 }
 
+/// Libp2p messages from the node persistent store
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Libp2pStoredMessages {
+	/// Current node time
+	pub current_time: i64,
+	/// Sequence number of the last stored message
+	pub last_seq: u64,
+	/// Messages ordered by sequence number
+	pub messages: Vec<libp2p_message_store::StoredMessage>,
+}
+
+/// Libp2p messaging topic that this node is listening on
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Libp2pTopic {
+	/// Topic name
+	pub topic: String,
+	/// Minimal integrity fee that the received messages should pay
+	pub min_fee: u64,
+}
+
+/// Client key of the onion service client authorization
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OnionClientKey {
+	/// Name of the client
+	pub name: String,
+	/// x25519 public key of the client, base32 encoded
+	pub public_key: String,
+	/// Line for the client tor `ClientOnionAuthDir` auth file, `<onion address>:descriptor:x25519:<private key>`.
+	/// Returned only when the key is generated, the node doesn't store the private key.
+	pub client_auth: Option<String>,
+}
+
+/// Output of the UTXO set that belongs to the view key
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ViewKeyOutput {
+	/// The output commitment (as hex string)
+	pub commit: String,
+	/// Output value
+	pub value: u64,
+	/// Key identifier of the output (as hex string)
+	pub key_id: String,
+	/// BIP32 derivation path of the output, like 'm/0/1'
+	pub path: String,
+	/// Whether the commitment is using the regular switch commitment
+	pub switch_commitment: bool,
+	/// Whether the output is a coinbase
+	pub is_coinbase: bool,
+	/// MMR Position
+	pub mmr_index: u64,
+	/// Block height at which the output is found
+	pub height: u64,
+}
+
+/// State of the view key scan job
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ViewKeyScanState {
+	/// Scan is in progress
+	Running,
+	/// Whole requested range is scanned
+	Finished,
+	/// Scan was cancelled by request
+	Cancelled,
+	/// Scan failed with the error
+	Failed(String),
+}
+
+/// Progress and result of the view key scan job
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ViewKeyScanStatus {
+	/// Job id, use it to query the scan status
+	pub job_id: u64,
+	/// Current state of the scan
+	pub state: ViewKeyScanState,
+	/// First output MMR index of the scan
+	pub start_index: u64,
+	/// Last output MMR index that was scanned
+	pub last_scanned_index: u64,
+	/// Last output MMR index of the scan
+	pub highest_index: u64,
+	/// Found outputs
+	pub outputs: Vec<ViewKeyOutput>,
+}
+
+/// Type of the watched item
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WatchItemType {
+	/// Output, identified by the commitment
+	Output,
+	/// Kernel, identified by the excess commitment
+	Kernel,
+}
+
+/// Item of the watch list
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchItem {
+	/// Type of the item
+	pub item_type: WatchItemType,
+	/// Output commitment or kernel excess (as hex string)
+	pub commit: String,
+	/// Optional label of the client, it is returned with the events
+	pub label: Option<String>,
+}
+
+/// Event type of the watched item
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum WatchEventType {
+	/// Output or kernel is included into the block
+	Confirmed,
+	/// Output is spent by the block
+	Spent,
+	/// Block with the output or kernel was removed from the chain by reorg
+	Unconfirmed,
+	/// Block with the output spend was removed from the chain by reorg
+	SpendReverted,
+}
+
+/// Event of the watched item
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchEvent {
+	/// Sequence number of the event, grows with every new event
+	pub seq: u64,
+	/// Type of the event
+	pub event_type: WatchEventType,
+	/// Type of the watched item
+	pub item_type: WatchItemType,
+	/// Output commitment or kernel excess (as hex string)
+	pub commit: String,
+	/// Label of the watched item
+	pub label: Option<String>,
+	/// Height of the block
+	pub height: u64,
+	/// Hash of the block (as hex string)
+	pub block_hash: String,
+}
+
+/// Watch list events, result of the long poll request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchEvents {
+	/// Sequence number of the last event of the node. Use it for the next request.
+	pub last_seq: u64,
+	/// Events with the sequence number after requested one
+	pub events: Vec<WatchEvent>,
+}
+
+/// Result of the individual transaction pool check
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TxPoolCheck {
+	/// The check
+	pub check: PoolCheck,
+	/// True if the transaction passed the check
+	pub passed: bool,
+	/// Error code of the failed check, see [`error_codes`](../error_codes/index.html)
+	pub code: Option<i32>,
+	/// Error of the failed check
+	pub error: Option<String>,
+}
+
+/// Transaction pool verdict of the dry run, the transaction is not added to the pool
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TxPoolVerdict {
+	/// Hash of the transaction
+	pub tx_hash: String,
+	/// True if the transaction passed all checks
+	pub accepted: bool,
+	/// Results of the checks, in the order they were done
+	pub checks: Vec<TxPoolCheck>,
+}
+
+impl TxPoolVerdict {
+	pub fn new(tx_hash: String, checks: Vec<(PoolCheck, Result<(), PoolError>)>) -> TxPoolVerdict {
+		let checks: Vec<TxPoolCheck> = checks
+			.into_iter()
+			.map(|(check, res)| {
+				let error = res.err().map(rest::Error::from);
+				TxPoolCheck {
+					check,
+					passed: error.is_none(),
+					code: error.as_ref().map(|e| e.code()),
+					error: error.map(|e| e.message()),
+				}
+			})
+			.collect();
+		TxPoolVerdict {
+			tx_hash,
+			accepted: checks.iter().all(|c| c.passed),
+			checks,
+		}
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
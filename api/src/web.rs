@@ -41,6 +41,9 @@ where
 				StatusCode::INTERNAL_SERVER_ERROR,
 				format!("P2P Error, {}", err),
 			),
+			Error::Pool { message, .. } | Error::Chain { message, .. } => {
+				response(StatusCode::INTERNAL_SERVER_ERROR, message)
+			}
 		},
 	}
 }
@@ -0,0 +1,102 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_api as api;
+use mwc_chain as chain;
+use mwc_pool as pool;
+use mwc_util as util;
+
+use crate::api::{error_codes, Error, ErrorData};
+use crate::pool::PoolError;
+use crate::util::secp::pedersen::Commitment;
+use crate::util::ToHex;
+use serde_json::json;
+
+#[test]
+fn error_serialization() {
+	let err = Error::NotFound("Output 08ab".to_string());
+	assert_eq!(
+		serde_json::to_value(&err).unwrap(),
+		json!({"code": error_codes::NOT_FOUND, "message": "Output 08ab", "data": null})
+	);
+	let back: Error = serde_json::from_value(serde_json::to_value(&err).unwrap()).unwrap();
+	assert_eq!(back, err);
+
+	let err = Error::from(PoolError::LowFeeTransaction {
+		fee: 10,
+		required_fee: 25,
+	});
+	assert_eq!(err.code(), error_codes::POOL_LOW_FEE_TRANSACTION);
+	assert_eq!(
+		err.data(),
+		Some(&ErrorData::LowFeeTransaction {
+			fee: 10,
+			required_fee: 25
+		})
+	);
+	let val = serde_json::to_value(&err).unwrap();
+	assert_eq!(
+		val["data"],
+		json!({"LowFeeTransaction": {"fee": 10, "required_fee": 25}})
+	);
+	assert_eq!(
+		val["message"],
+		json!("Tx Pool Low fee transaction 10, required fee 25")
+	);
+	let back: Error = serde_json::from_value(val).unwrap();
+	assert_eq!(back, err);
+
+	let err = Error::from(PoolError::ImmatureCoinbase { mature_at: 10 });
+	assert_eq!(err.code(), error_codes::POOL_IMMATURE_COINBASE);
+	assert_eq!(
+		err.data(),
+		Some(&ErrorData::ImmatureCoinbase { mature_at: 10 })
+	);
+
+	let commit = Commitment::from_vec(vec![9; 33]);
+	let hex = commit.to_hex();
+	let err = Error::from(PoolError::DuplicateCommitment(commit));
+	assert_eq!(err.code(), error_codes::POOL_DUPLICATE_COMMITMENT);
+	assert_eq!(
+		err.data(),
+		Some(&ErrorData::DuplicateCommitment { commit: hex })
+	);
+}
+
+#[test]
+fn error_with_context() {
+	let err = Error::from_chain("Failed to push tx", chain::Error::ImmatureCoinbase(10));
+	assert_eq!(err.code(), error_codes::CHAIN_IMMATURE_COINBASE);
+	assert_eq!(
+		err.data(),
+		Some(&ErrorData::ImmatureCoinbase { mature_at: 10 })
+	);
+	assert_eq!(
+		err.message(),
+		"Failed to push tx, Attempt to spend immature coinbase, mature at height 10"
+	);
+
+	let err = Error::from_pool("Failed to get chain head", PoolError::OverCapacity);
+	assert_eq!(err.code(), error_codes::POOL_OVER_CAPACITY);
+	assert!(err.message().starts_with("Failed to get chain head, "));
+}
+
+#[test]
+fn legacy_error_deserialization() {
+	let err: Error = serde_json::from_value(json!({"Internal": "Failed"})).unwrap();
+	assert_eq!(err, Error::Internal("Failed".to_string()));
+	assert_eq!(err.code(), error_codes::INTERNAL);
+	let err: Error = serde_json::from_value(json!({"RequestError": "Bad hash"})).unwrap();
+	assert_eq!(err, Error::RequestError("Bad hash".to_string()));
+}
@@ -0,0 +1,194 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_api as api;
+use mwc_core as core;
+use mwc_p2p as p2p;
+use mwc_pool as pool;
+
+use crate::api::{error_codes, Foreign, ForeignRpc, Owner, OwnerRpc, PoolDryRun};
+use crate::core::core::hash::Hash;
+use crate::core::core::{BlockHeader, BlockSums, Inputs, OutputIdentifier, Transaction};
+use crate::p2p::libp2p_connection::{self, ReceivedMessage};
+use crate::pool::{BlockChain, NoopPoolAdapter, PoolError, TransactionPool};
+use chrono::Utc;
+use easy_jsonrpc_mw::{Handler, MaybeReply};
+use mwc_util::RwLock;
+use serde_json::{json, Value};
+use std::sync::Weak;
+
+/// The libp2p topic handlers don't touch the chain, the chain adapter is only needed to
+/// satisfy the Foreign API type parameters.
+struct NoChainAdapter {}
+
+impl BlockChain for NoChainAdapter {
+	fn verify_coinbase_maturity(&self, _inputs: &Inputs) -> Result<(), PoolError> {
+		unimplemented!()
+	}
+	fn verify_tx_lock_height(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		unimplemented!()
+	}
+	fn validate_tx(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		unimplemented!()
+	}
+	fn validate_inputs(&self, _inputs: &Inputs) -> Result<Vec<OutputIdentifier>, PoolError> {
+		unimplemented!()
+	}
+	fn chain_head(&self) -> Result<BlockHeader, PoolError> {
+		unimplemented!()
+	}
+	fn get_block_header(&self, _hash: &Hash) -> Result<BlockHeader, PoolError> {
+		unimplemented!()
+	}
+	fn get_block_sums(&self, _hash: &Hash) -> Result<BlockSums, PoolError> {
+		unimplemented!()
+	}
+	fn replay_attack_check(&self, _tx: &Transaction) -> Result<(), PoolError> {
+		unimplemented!()
+	}
+}
+
+type NoChainPool = RwLock<TransactionPool<NoChainAdapter, NoopPoolAdapter>>;
+
+fn owner_api() -> Owner {
+	let tx_pool: Weak<dyn PoolDryRun> = Weak::<NoChainPool>::new();
+	Owner::new(
+		Weak::new(),
+		Weak::new(),
+		Weak::new(),
+		tx_pool,
+		None,
+		None,
+		None,
+	)
+}
+
+fn foreign_api() -> Foreign<NoChainAdapter, NoopPoolAdapter> {
+	Foreign::new(Weak::new(), Weak::new(), Weak::new(), Weak::new())
+}
+
+fn call<H: Handler + ?Sized>(api: &H, method: &str, params: Value) -> Value {
+	let request = json!({
+		"jsonrpc": "2.0",
+		"method": method,
+		"params": params,
+		"id": 1
+	});
+	match api.handle_request(request) {
+		MaybeReply::Reply(r) => r,
+		MaybeReply::DontReply => panic!("No reply for {}", method),
+	}
+}
+
+#[test]
+fn owner_libp2p_topics() {
+	let owner = owner_api();
+	let owner = &owner as &dyn OwnerRpc;
+
+	let res = call(owner, "add_libp2p_topic", json!(["OwnerTopic", 1000]));
+	assert_eq!(res["result"]["Ok"], json!(true));
+	// Already listening, only the fee is updated
+	let res = call(owner, "add_libp2p_topic", json!(["OwnerTopic", 2000]));
+	assert_eq!(res["result"]["Ok"], json!(false));
+	let res = call(owner, "get_libp2p_topics", json!([]));
+	let topics = res["result"]["Ok"].as_array().unwrap();
+	assert!(topics.contains(&json!({"topic": "OwnerTopic", "min_fee": 2000})));
+
+	let res = call(owner, "add_libp2p_topic", json!(["", 1000]));
+	assert_eq!(res["result"]["Err"]["code"], json!(error_codes::ARGUMENT));
+
+	let res = call(owner, "remove_libp2p_topic", json!(["OwnerTopic"]));
+	assert_eq!(res["result"]["Ok"], json!(true));
+	let res = call(owner, "remove_libp2p_topic", json!(["OwnerTopic"]));
+	assert_eq!(res["result"]["Ok"], json!(false));
+	let res = call(owner, "get_libp2p_topics", json!([]));
+	let topics = res["result"]["Ok"].as_array().unwrap();
+	assert!(topics.iter().all(|t| t["topic"] != json!("OwnerTopic")));
+}
+
+#[test]
+fn owner_publish_libp2p_message() {
+	let owner = owner_api();
+	let owner = &owner as &dyn OwnerRpc;
+
+	let res = call(owner, "publish_libp2p_message", json!(["", "0011"]));
+	assert_eq!(res["result"]["Err"]["code"], json!(error_codes::ARGUMENT));
+	let res = call(
+		owner,
+		"publish_libp2p_message",
+		json!(["PublishTopic", "xyz"]),
+	);
+	assert_eq!(res["result"]["Err"]["code"], json!(error_codes::ARGUMENT));
+	// libp2p node is not running in the test
+	let res = call(
+		owner,
+		"publish_libp2p_message",
+		json!(["PublishTopic", "0011"]),
+	);
+	assert_eq!(res["result"]["Err"]["code"], json!(error_codes::P2P));
+}
+
+#[test]
+fn foreign_libp2p_topic_messages() {
+	let foreign = foreign_api();
+	let foreign = &foreign as &dyn ForeignRpc;
+
+	let now = Utc::now().timestamp();
+	let message = |timestamp: i64, topic: &str, message: &str| ReceivedMessage {
+		timestamp,
+		peer_id: "peer".to_string(),
+		topic: topic.to_string(),
+		fee: 1000,
+		message: message.to_string(),
+	};
+	libp2p_connection::inject_received_messaged(vec![
+		message(now - 100, "ForeignTopicA", "a1"),
+		message(now - 50, "ForeignTopicB", "b1"),
+		message(now - 10, "ForeignTopicA", "a2"),
+	]);
+
+	let messages = |topic: Value, from_time: Value| -> Vec<Value> {
+		let res = call(
+			foreign,
+			"get_libp2p_topic_messages",
+			json!([topic, from_time]),
+		);
+		assert!(res["result"]["Ok"]["current_time"].as_i64().unwrap() >= now);
+		res["result"]["Ok"]["libp2p_messages"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.filter(|m| m["topic"].as_str().unwrap().starts_with("ForeignTopic"))
+			.map(|m| m["message"].clone())
+			.collect()
+	};
+
+	assert_eq!(
+		messages(json!(null), json!(null)),
+		vec![json!("a1"), json!("b1"), json!("a2")]
+	);
+	assert_eq!(
+		messages(json!("ForeignTopicA"), json!(null)),
+		vec![json!("a1"), json!("a2")]
+	);
+	assert_eq!(
+		messages(json!("ForeignTopicA"), json!(now - 100)),
+		vec![json!("a2")]
+	);
+	assert_eq!(
+		messages(json!(null), json!(now - 60)),
+		vec![json!("b1"), json!("a2")]
+	);
+	assert!(messages(json!("ForeignTopicC"), json!(null)).is_empty());
+}
@@ -0,0 +1,302 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_api as api;
+use mwc_chain as chain;
+use mwc_core as core;
+use mwc_keychain as keychain;
+use mwc_util as util;
+
+use crate::api::{Error, Owner, PoolDryRun, TxPoolVerdict, ViewKeyScanState, ViewKeyScanStatus};
+use crate::chain::types::NoopAdapter;
+use crate::chain::{Chain, Options};
+use crate::core::core::{Block, KernelFeatures, Transaction};
+use crate::core::libtx::{self, build, ProofBuilder};
+use crate::core::{consensus, genesis, global, pow};
+use crate::keychain::{
+	ExtKeychain, ExtKeychainPath, Identifier, Keychain, SwitchCommitmentType, ViewKey,
+};
+use crate::util::secp::pedersen::Commitment;
+use crate::util::secp::Secp256k1;
+use crate::util::ToHex;
+use chrono::Duration;
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Instant;
+
+// The scan doesn't need the pool
+struct NoPool;
+
+impl PoolDryRun for NoPool {
+	fn dry_run_transaction(
+		&self,
+		_tx: Transaction,
+		_secp: &Secp256k1,
+	) -> Result<TxPoolVerdict, Error> {
+		unimplemented!()
+	}
+}
+
+fn clean_output_dir(dir_name: &str) {
+	let _ = fs::remove_dir_all(dir_name);
+}
+
+fn init_chain(dir_name: &str, keychain: &ExtKeychain) -> Chain {
+	let key_id = ExtKeychain::derive_key_id(0, 1, 0, 0, 0);
+	let reward = libtx::reward::output(
+		keychain,
+		&ProofBuilder::new(keychain),
+		&key_id,
+		0,
+		false,
+		0,
+		keychain.secp(),
+	)
+	.unwrap();
+	Chain::init(
+		dir_name.to_string(),
+		Arc::new(NoopAdapter {}),
+		genesis::genesis_dev().with_reward(reward.0, reward.1),
+		pow::verify_size,
+		false,
+	)
+	.unwrap()
+}
+
+// Mine a block with the given txs on top of the chain head, reward goes to `key_id`.
+fn add_block(chain: &Chain, txs: &[Transaction], keychain: &ExtKeychain, key_id: &Identifier) {
+	let prev = chain.head_header().unwrap();
+	let next_header_info = consensus::next_difficulty(
+		prev.height + 1,
+		chain.difficulty_iter().unwrap(),
+		&mut VecDeque::new(),
+	);
+	let fees = txs.iter().map(|tx| tx.fee(prev.height + 1)).sum();
+	let reward = libtx::reward::output(
+		keychain,
+		&ProofBuilder::new(keychain),
+		key_id,
+		fees,
+		false,
+		prev.height + 1,
+		keychain.secp(),
+	)
+	.unwrap();
+	let mut b = Block::new(
+		&prev,
+		txs,
+		next_header_info.difficulty,
+		reward,
+		keychain.secp(),
+	)
+	.unwrap();
+	b.header.timestamp = prev.timestamp + Duration::seconds(60);
+	b.header.pow.secondary_scaling = next_header_info.secondary_scaling;
+	chain.set_txhashset_roots(&mut b).unwrap();
+	pow::pow_size(
+		&mut b.header,
+		next_header_info.difficulty,
+		global::proofsize(),
+		global::min_edge_bits(),
+	)
+	.unwrap();
+	chain.process_block(b, Options::MINE).unwrap();
+}
+
+fn wait_for_scan(owner: &Owner, job_id: u64) -> ViewKeyScanStatus {
+	let start = Instant::now();
+	loop {
+		let status = owner.get_view_key_scan(job_id).unwrap();
+		if status.state != ViewKeyScanState::Running {
+			return status;
+		}
+		assert!(start.elapsed().as_secs() < 60, "Scan {} is stuck", job_id);
+		thread::sleep(std::time::Duration::from_millis(10));
+	}
+}
+
+// (commit, value, path, is_coinbase, height) of the found outputs
+fn found(status: &ViewKeyScanStatus) -> Vec<(String, u64, String, bool, u64)> {
+	status
+		.outputs
+		.iter()
+		.map(|o| {
+			(
+				o.commit.clone(),
+				o.value,
+				o.path.clone(),
+				o.is_coinbase,
+				o.height,
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn view_key_scan() {
+	let chain_dir = ".mwc.view_key_scan";
+	clean_output_dir(chain_dir);
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let other = ExtKeychain::from_random_seed(false).unwrap();
+	let secp = keychain.secp();
+	let chain = Arc::new(init_chain(chain_dir, &other));
+
+	let key_id = |d1, d2| ExtKeychainPath::new(2, d1, d2, 0, 0).to_identifier();
+	let expected_output = |key_id: &Identifier, value, is_coinbase, height| {
+		let commit = keychain
+			.commit(value, key_id, SwitchCommitmentType::Regular)
+			.unwrap();
+		(
+			commit.0.to_hex(),
+			value,
+			key_id.to_bip_32_string(),
+			is_coinbase,
+			height,
+		)
+	};
+
+	// Coinbases at 1, 2 and 4 are ours, 3 is not
+	for height in 1..=4 {
+		if height == 3 {
+			add_block(&chain, &[], &other, &key_id(0, 3));
+		} else {
+			add_block(&chain, &[], &keychain, &key_id(0, height));
+		}
+	}
+	// Spend the coinbase of the block 1 into 4 outputs, the block 5 coinbase is not ours
+	let amount = consensus::reward(0, 1);
+	let fee: u32 = 2;
+	let values: Vec<u64> = vec![1_000, 2_000, 3_000, amount - fee as u64 - 6_000];
+	let mut parts = vec![build::coinbase_input(amount, key_id(0, 1))];
+	for (i, value) in values.iter().enumerate() {
+		parts.push(build::output(*value, key_id(1, i as u32)));
+	}
+	let tx = build::transaction(
+		KernelFeatures::Plain { fee: fee.into() },
+		&parts,
+		&keychain,
+		&ProofBuilder::new(&keychain),
+	)
+	.unwrap();
+	add_block(&chain, &[tx], &other, &key_id(0, 5));
+	assert_eq!(chain.head().unwrap().height, 5);
+
+	// Spent coinbase of the block 1 is not reported
+	let tx_outputs: Vec<_> = values
+		.iter()
+		.enumerate()
+		.map(|(i, value)| expected_output(&key_id(1, i as u32), *value, false, 5))
+		.collect();
+	let mut expected = vec![
+		expected_output(&key_id(0, 2), consensus::reward(0, 2), true, 2),
+		expected_output(&key_id(0, 4), consensus::reward(0, 4), true, 4),
+	];
+	expected.extend(tx_outputs.iter().cloned());
+	expected.sort();
+
+	let owner = Owner::new(
+		Arc::downgrade(&chain),
+		Weak::new(),
+		Weak::new(),
+		Weak::<NoPool>::new(),
+		None,
+		None,
+		None,
+	);
+	let public_root_key = keychain
+		.public_root_key()
+		.serialize_vec(secp, true)
+		.to_hex();
+	let rewind_hash = ViewKey::rewind_hash(secp, keychain.public_root_key()).to_hex();
+
+	// Whole chain with the public root key
+	let job_id = owner
+		.start_view_key_scan(None, Some(public_root_key.clone()), None, None)
+		.unwrap();
+	let status = wait_for_scan(&owner, job_id);
+	assert_eq!(status.state, ViewKeyScanState::Finished);
+	assert_eq!(status.last_scanned_index, status.highest_index);
+	let mut outputs = found(&status);
+	outputs.sort();
+	assert_eq!(outputs, expected);
+	for output in &status.outputs {
+		let commit = Commitment::from_vec(util::from_hex(&output.commit).unwrap());
+		let (_, pos) = chain.get_unspent(commit).unwrap().unwrap();
+		assert_eq!((pos.pos, pos.height), (output.mmr_index, output.height));
+	}
+
+	// Block 5 only, with the rewind hash and the matching public root key
+	let job_id = owner
+		.start_view_key_scan(
+			Some(rewind_hash.clone()),
+			Some(public_root_key.clone()),
+			Some(5),
+			Some(5),
+		)
+		.unwrap();
+	let status = wait_for_scan(&owner, job_id);
+	assert_eq!(status.state, ViewKeyScanState::Finished);
+	let mut outputs = found(&status);
+	outputs.sort();
+	let mut block_5 = tx_outputs.clone();
+	block_5.sort();
+	assert_eq!(outputs, block_5);
+
+	// Rewind hash alone finds the same outputs
+	let job_id = owner
+		.start_view_key_scan(Some(rewind_hash), None, None, None)
+		.unwrap();
+	let mut outputs = found(&wait_for_scan(&owner, job_id));
+	outputs.sort();
+	assert_eq!(outputs, expected);
+
+	// Other wallet finds only its own outputs
+	let other_key = other.public_root_key().serialize_vec(secp, true).to_hex();
+	let job_id = owner
+		.start_view_key_scan(None, Some(other_key), None, None)
+		.unwrap();
+	let status = wait_for_scan(&owner, job_id);
+	assert_eq!(status.state, ViewKeyScanState::Finished);
+	let mut heights: Vec<u64> = status.outputs.iter().map(|o| o.height).collect();
+	heights.sort();
+	assert_eq!(heights, vec![0, 3, 5]);
+
+	// Cancelled scan keeps the outputs found before the cancellation
+	let job_id = owner
+		.start_view_key_scan(None, Some(public_root_key.clone()), None, None)
+		.unwrap();
+	owner.cancel_view_key_scan(job_id).unwrap();
+	let status = wait_for_scan(&owner, job_id);
+	assert_eq!(status.state, ViewKeyScanState::Cancelled);
+	for output in found(&status) {
+		assert!(expected.contains(&output));
+	}
+
+	// Unknown jobs and invalid arguments
+	assert!(owner.get_view_key_scan(job_id + 100).is_err());
+	assert!(owner.cancel_view_key_scan(job_id + 100).is_err());
+	assert!(owner.start_view_key_scan(None, None, None, None).is_err());
+	assert!(owner
+		.start_view_key_scan(None, Some(public_root_key.clone()), Some(4), Some(3))
+		.is_err());
+	let other_hash = ViewKey::rewind_hash(secp, other.public_root_key()).to_hex();
+	assert!(owner
+		.start_view_key_scan(Some(other_hash), Some(public_root_key), None, None)
+		.is_err());
+
+	clean_output_dir(chain_dir);
+}
@@ -16,7 +16,7 @@
 //! Facade and handler for the rest of the blockchain implementation
 //! and mostly the chain pipeline.
 
-use crate::core::core::merkle_proof::MerkleProof;
+use crate::core::core::merkle_proof::{KernelMerkleProof, MerkleProof};
 use crate::core::core::{
 	Block, BlockHeader, BlockSums, Committed, Inputs, KernelFeatures, Output, OutputIdentifier,
 	Transaction, TxKernel,
@@ -56,6 +56,10 @@ use std::{collections::HashMap, io::Cursor};
 /// When evicting, very old orphans are evicted first
 const MAX_ORPHAN_AGE_SECS: u64 = 3000;
 
+/// Max depth of the header below the head for the output merkle proof. The proof against
+/// an older header rewinds the txhashset under the write lock, so the block processing waits.
+pub const MAX_MERKLE_PROOF_REWIND: u64 = 60;
+
 /// Banned block. We don't accept any blockchain with this has
 pub const BLOCK_TO_BAN: &str = "00020440a401086e57e1b7a92ebb0277c7f7fd47a38269ecc6789c2a80333725";
 
@@ -64,6 +68,15 @@ pub struct Orphan {
 	pub block: Block,
 	pub opts: Options,
 	added: Instant,
+	// Stateless validation is done ahead of time, while the block is waiting for its parent
+	validated: bool,
+}
+
+impl Orphan {
+	/// Whether the block passed the stateless validation ahead of time
+	pub fn is_validated(&self) -> bool {
+		self.validated
+	}
 }
 
 pub struct OrphanBlockPool {
@@ -164,6 +177,36 @@ impl OrphanBlockPool {
 		return orphans.remove(&header_hash);
 	}
 
+	/// Orphans above the height that didn't pass the stateless validation yet, lowest first.
+	fn get_unvalidated(&self, above_height: u64, limit: usize) -> Vec<Orphan> {
+		let orphans = self.orphans.read();
+		let height_idx = self.height_idx.read();
+		let mut heights: Vec<u64> = height_idx
+			.keys()
+			.filter(|h| **h > above_height)
+			.cloned()
+			.collect();
+		heights.sort_unstable();
+		heights
+			.iter()
+			.filter_map(|h| height_idx.get(h))
+			.flatten()
+			.filter_map(|h| orphans.get(h))
+			.filter(|o| !o.validated)
+			.take(limit)
+			.cloned()
+			.collect()
+	}
+
+	/// Mark the orphan as validated, unless it was replaced since it was taken for validation.
+	fn mark_validated(&self, hash: &Hash, added: Instant) {
+		if let Some(orphan) = self.orphans.write().get_mut(hash) {
+			if orphan.added == added {
+				orphan.validated = true;
+			}
+		}
+	}
+
 	/// Get list of ophan's hashes
 	pub fn get_orphan_list(&self) -> HashSet<Hash> {
 		self.orphans
@@ -562,11 +605,15 @@ impl Chain {
 				// this block is expected to be from the main chain, we are expecting approve long sequence, not a short branch
 				if header.hash() == b.hash() {
 					blocks.push(b.clone());
+					let mut validated_blocks = HashSet::new();
 					loop {
 						let last_block = blocks.last().unwrap();
 						let next_hegiht = last_block.header.height + 1;
 						if let Ok(header) = self.get_header_by_height(next_hegiht) {
 							if let Some(orphan) = self.orphans.get_orphan(&header.hash()) {
+								if orphan.validated {
+									validated_blocks.insert(orphan.block.hash());
+								}
 								blocks.push(orphan.block);
 								continue; // can process the next block
 							}
@@ -582,7 +629,7 @@ impl Chain {
 						// good, we can process multiple blocks, it should be faster than one by one
 						let block_hashes: Vec<(u64, Hash)> =
 							blocks.iter().map(|b| (b.header.height, b.hash())).collect();
-						match self.process_block_multiple(&blocks, opts) {
+						match self.process_block_multiple(&blocks, validated_blocks, opts) {
 							Ok(tip) => {
 								// We are good, let's clean up the orphans
 								for (height, hash) in block_hashes {
@@ -718,6 +765,7 @@ impl Chain {
 			block: block.clone(),
 			opts,
 			added: Instant::now(),
+			validated: false,
 		};
 		self.orphans.add(orphan);
 
@@ -800,9 +848,76 @@ impl Chain {
 	// attempt to add multiple blocks that came in the sequence from 0 first to last
 	// Note, it is expected that check_block was called for all blocks at 'blocks'.
 	// Since they are orphans - check_block was called to them when they were added to orphan pool.
+	// While the blocks are applied, the queued orphans above them are validated ahead of time.
 	fn process_block_multiple(
 		&self,
 		blocks: &Vec<Block>,
+		validated_blocks: HashSet<Hash>,
+		opts: Options,
+	) -> Result<Option<Tip>, Error> {
+		let last_height = blocks.last().unwrap().header.height;
+		// Look ahead thread doesn't see the thread local settings, we need to pass them.
+		let chain_type = global::get_chain_type();
+		let nrd_enabled = global::is_nrd_enabled();
+		crossbeam::thread::scope(|s| {
+			s.spawn(|_| {
+				global::set_local_chain_type(chain_type);
+				global::set_local_nrd_enabled(nrd_enabled);
+				self.validate_orphans_ahead(last_height)
+			});
+			self.apply_block_multiple(blocks, validated_blocks, opts)
+		})
+		.map_err(|_| Error::Other("crossbeam runtime error".to_string()))?
+	}
+
+	// Stateless validation of the queued orphans above the height. Orphans that pass are
+	// not validated again when they are processed. Failed ones are left for the regular
+	// processing, it will reject them.
+	fn validate_orphans_ahead(&self, above_height: u64) {
+		let orphans = self.orphans.get_unvalidated(
+			above_height,
+			self.pibd_params.get_block_validation_lookahead(),
+		);
+		// Parent header is known from the headers sync, otherwise the orphan waits
+		let queued: Vec<_> = orphans
+			.iter()
+			.filter_map(|o| {
+				self.store
+					.get_previous_header(&o.block.header)
+					.ok()
+					.map(|prev| (o, prev.total_kernel_offset))
+			})
+			.collect();
+		if queued.is_empty() {
+			return;
+		}
+
+		let blocks: Vec<_> = queued
+			.iter()
+			.map(|(o, prev_offset)| (&o.block, prev_offset.clone()))
+			.collect();
+		let results = pipe::validate_blocks_stateless(
+			&blocks,
+			self.pibd_params.get_block_validation_threads(),
+			self.secp(),
+		);
+		for ((o, _), res) in queued.iter().zip(results) {
+			match res {
+				Ok(()) => self.orphans.mark_validated(&o.block.hash(), o.added),
+				Err(e) => debug!(
+					"validate_orphans_ahead: block {} at {} failed, {}",
+					o.block.hash(),
+					o.block.header.height,
+					e
+				),
+			}
+		}
+	}
+
+	fn apply_block_multiple(
+		&self,
+		blocks: &Vec<Block>,
+		validated_blocks: HashSet<Hash>,
 		opts: Options,
 	) -> Result<Option<Tip>, Error> {
 		let (head, fork_point, prev_head) = {
@@ -811,6 +926,7 @@ impl Chain {
 			let batch = self.store.batch_write()?;
 			let prev_head = batch.head()?;
 			let mut ctx = self.new_ctx(opts, batch, &mut header_pmmr, &mut txhashset)?;
+			ctx.validated_blocks = validated_blocks;
 
 			let (head, fork_point) = pipe::process_blocks_series(
 				&blocks,
@@ -904,6 +1020,8 @@ impl Chain {
 			header_allowed: Box::new(move |header| {
 				pipe::validate_header_denylist(header, &denylist)
 			}),
+			validation_threads: self.pibd_params.get_block_validation_threads(),
+			validated_blocks: HashSet::new(),
 			header_pmmr,
 			txhashset,
 			batch,
@@ -1206,6 +1324,98 @@ impl Chain {
 		Ok(merkle_proof)
 	}
 
+	/// Return a merkle proof for the unspent output valid against the output root of the
+	/// header `header_hash` (chain head if None). The header must be on the current chain,
+	/// at most MAX_MERKLE_PROOF_REWIND blocks below the head.
+	/// Returns the header, the output, its output MMR position (0-based) and the proof.
+	pub fn get_merkle_proof_at_header(
+		&self,
+		commit: Commitment,
+		header_hash: Option<Hash>,
+	) -> Result<(BlockHeader, OutputIdentifier, u64, MerkleProof), Error> {
+		{
+			// The proof against the head doesn't need the rewind, the read locks are enough
+			let header_pmmr = self.header_pmmr.read();
+			let txhashset = self.txhashset.read();
+			let header = self.proof_header(&header_pmmr, header_hash)?;
+			let head = self.head()?;
+			if header.hash() == head.last_block_h {
+				let (output, pos) = txhashset
+					.get_unspent(commit)?
+					.ok_or_else(|| Error::OutputNotFound(format!("Commit {:?}", commit)))?;
+				let proof = txhashset.readonly_merkle_proof(commit)?;
+				return Ok((header, output, pos.pos - 1, proof));
+			}
+			if head.height - header.height > MAX_MERKLE_PROOF_REWIND {
+				return Err(Error::Other(format!(
+					"header {} is {} blocks below the head, the limit is {}",
+					header.hash(),
+					head.height - header.height,
+					MAX_MERKLE_PROOF_REWIND
+				)));
+			}
+		}
+
+		let mut header_pmmr = self.header_pmmr.write();
+		let mut txhashset = self.txhashset.write();
+		let header = self.proof_header(&header_pmmr, header_hash)?;
+		let (output, pos0, proof) =
+			txhashset::extending_readonly(&mut header_pmmr, &mut txhashset, |ext, batch| {
+				self.rewind_and_apply_fork(&header, ext, batch)?;
+				ext.extension.output_merkle_proof(&commit, batch)
+			})?;
+		Ok((header, output, pos0, proof))
+	}
+
+	/// Return a merkle proof for the kernel with the given excess, valid against the kernel
+	/// root of the header `header_hash` (chain head if None). The header must be on the current
+	/// chain. `min_height` and `max_height` limit the kernel search, same as for
+	/// `get_kernel_height`, the search never goes above the header. Returns the height of the block with the kernel, the header
+	/// and the proof, or None if the kernel is not found.
+	pub fn get_kernel_merkle_proof(
+		&self,
+		excess: &Commitment,
+		header_hash: Option<Hash>,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(u64, BlockHeader, KernelMerkleProof)>, Error> {
+		let header = self.proof_header(&self.header_pmmr.read(), header_hash)?;
+		// Kernels above the proof header are not included at it
+		let max_height = Some(max_height.map_or(header.height, |h| h.min(header.height)));
+		let (_, height, mmr_index) = match self.get_kernel_height(excess, min_height, max_height)? {
+			Some(k) => k,
+			None => return Ok(None),
+		};
+
+		let txhashset = self.txhashset.read();
+		// The kernel might be reorged out since the lookup
+		let proof = txhashset.kernel_merkle_proof(mmr_index, &header)?;
+		if proof.kernel.excess != *excess {
+			return Ok(None);
+		}
+		Ok(Some((height, header, proof)))
+	}
+
+	// Header for the merkle proofs, chain head if `header_hash` is None.
+	// Header must be on the current chain.
+	fn proof_header(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		header_hash: Option<Hash>,
+	) -> Result<BlockHeader, Error> {
+		let head = self.head()?;
+		let header = self.get_block_header(&header_hash.unwrap_or(head.last_block_h))?;
+		if header.height > head.height
+			|| header_pmmr.get_header_hash_by_height(header.height)? != header.hash()
+		{
+			return Err(Error::Other(format!(
+				"header {} is not on current chain",
+				header.hash()
+			)));
+		}
+		Ok(header)
+	}
+
 	/// Return a merkle proof valid for the current output pmmr state at the
 	/// given pos
 	pub fn get_merkle_proof_for_pos(&self, commit: Commitment) -> Result<MerkleProof, Error> {
@@ -2014,21 +2224,37 @@ impl Chain {
 		min_height: Option<u64>,
 		max_height: Option<u64>,
 	) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+		let mut res = self.get_kernels_height(&[*excess], min_height, max_height)?;
+		Ok(res.pop().flatten())
+	}
+
+	/// Gets the kernels with given excesses and the block heights they are included in.
+	/// The kernel MMR is scanned once for all excesses, under a single read lock.
+	/// Result is in the same order as `excesses`.
+	pub fn get_kernels_height(
+		&self,
+		excesses: &[Commitment],
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Vec<Option<(TxKernel, u64, u64)>>, Error> {
+		let not_found = || excesses.iter().map(|_| None).collect();
 		let head = self.head()?;
 
 		if let (Some(min), Some(max)) = (min_height, max_height) {
 			if min > max {
-				return Ok(None);
+				return Ok(not_found());
 			}
 		}
 
+		let header_pmmr = self.header_pmmr.read();
+
 		let min_index = match min_height {
 			Some(0) => None,
 			Some(h) => {
 				if h > head.height {
-					return Ok(None);
+					return Ok(not_found());
 				}
-				let header = self.get_header_by_height(h)?;
+				let header = self.get_block_header(&header_pmmr.get_header_hash_by_height(h)?)?;
 				let prev_header = self.get_previous_header(&header)?;
 				Some(prev_header.kernel_mmr_size + 1)
 			}
@@ -2040,26 +2266,43 @@ impl Chain {
 				if h > head.height {
 					None
 				} else {
-					let header = self.get_header_by_height(h)?;
+					let header =
+						self.get_block_header(&header_pmmr.get_header_hash_by_height(h)?)?;
 					Some(header.kernel_mmr_size)
 				}
 			}
 			None => None,
 		};
 
-		let (kernel, mmr_index) = match self
+		// The kernel MMR scan is long, don't block the header processing while it runs
+		drop(header_pmmr);
+
+		let excesses_set: HashSet<Commitment> = excesses.iter().cloned().collect();
+		let found = self
 			.txhashset
 			.read()
-			.find_kernel(&excess, min_index, max_index)
-		{
-			Some(k) => k,
-			None => return Ok(None),
-		};
+			.find_kernels(&excesses_set, min_index, max_index);
 
-		let header = self.get_header_for_kernel_index(mmr_index, min_height, max_height)?;
-
-		Ok(Some((kernel, header.height, mmr_index)))
+		let header_pmmr = self.header_pmmr.read();
+		let max_height = max_height.unwrap_or(head.height).min(head.height);
+		let mut res = Vec::with_capacity(excesses.len());
+		for excess in excesses {
+			match found.get(excess) {
+				Some((kernel, mmr_index)) => {
+					let header = self.header_for_kernel_index(
+						&header_pmmr,
+						*mmr_index,
+						min_height,
+						max_height,
+					)?;
+					res.push(Some((kernel.clone(), header.height, *mmr_index)));
+				}
+				None => res.push(None),
+			}
+		}
+		Ok(res)
 	}
+
 	/// Gets the block header in which a given kernel mmr index appears in the txhashset.
 	pub fn get_header_for_kernel_index(
 		&self,
@@ -2067,13 +2310,23 @@ impl Chain {
 		min_height: Option<u64>,
 		max_height: Option<u64>,
 	) -> Result<BlockHeader, Error> {
-		let header_pmmr = self.header_pmmr.read();
-
-		let mut min = min_height.unwrap_or(0).saturating_sub(1);
-		let mut max = match max_height {
+		let max_height = match max_height {
 			Some(h) => h,
 			None => self.head()?.height,
 		};
+		let header_pmmr = self.header_pmmr.read();
+		self.header_for_kernel_index(&header_pmmr, kernel_mmr_index, min_height, max_height)
+	}
+
+	fn header_for_kernel_index(
+		&self,
+		header_pmmr: &PMMRHandle<BlockHeader>,
+		kernel_mmr_index: u64,
+		min_height: Option<u64>,
+		max_height: u64,
+	) -> Result<BlockHeader, Error> {
+		let mut min = min_height.unwrap_or(0).saturating_sub(1);
+		let mut max = max_height;
 
 		loop {
 			let search_height = max - (max - min) / 2;
@@ -2097,6 +2350,44 @@ impl Chain {
 		}
 	}
 
+	/// Gets the block headers in the height range, both ends are included.
+	/// Header hashes are read under a single header MMR read lock, so the result is consistent.
+	pub fn get_headers_by_height(
+		&self,
+		start_height: u64,
+		end_height: u64,
+	) -> Result<Vec<BlockHeader>, Error> {
+		let header_pmmr = self.header_pmmr.read();
+		let mut headers = vec![];
+		for height in start_height..=end_height {
+			let hash = header_pmmr.get_header_hash_by_height(height)?;
+			headers.push(self.get_block_header(&hash)?);
+		}
+		Ok(headers)
+	}
+
+	/// Return merkle proofs valid for the current output pmmr state, built under a single
+	/// txhashset read lock. Proofs are in the same order as `commits`, None if the output is
+	/// not in the UTXO set. The head header the proofs are valid against is returned as well.
+	pub fn get_merkle_proofs_for_pos(
+		&self,
+		commits: &[Commitment],
+	) -> Result<(BlockHeader, Vec<Option<MerkleProof>>), Error> {
+		let txhashset = self.txhashset.read();
+		let header = self.head_header()?;
+		let mut res = Vec::with_capacity(commits.len());
+		for commit in commits {
+			match txhashset.readonly_merkle_proof(*commit) {
+				Ok(proof) => res.push(Some(proof)),
+				Err(Error::StoreErr(NotFoundErr(_), _)) | Err(Error::OutputNotFound(_)) => {
+					res.push(None)
+				}
+				Err(e) => return Err(e),
+			}
+		}
+		Ok((header, res))
+	}
+
 	/// Verifies the given block header is actually on the current chain.
 	/// Checks the header_by_height index to verify the header is where we say
 	/// it is
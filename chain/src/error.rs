@@ -46,6 +46,9 @@ pub enum Error {
 	/// Block header invalid hash, explicitly rejected
 	#[error("Block hash explicitly rejected by chain")]
 	InvalidHash,
+	/// Block header conflicts with a known checkpoint
+	#[error("Block header at height {0} conflicts with checkpoint {1}")]
+	CheckpointMismatch(u64, Hash),
 	/// Scaling factor between primary and secondary PoW is invalid
 	#[error("Wrong scaling factor")]
 	InvalidScaling,
@@ -88,8 +91,9 @@ pub enum Error {
 	#[error("Duplicate Commitment: {0:?}")]
 	DuplicateCommitment(Commitment),
 	/// Attempt to spend a coinbase output before it sufficiently matures.
-	#[error("Attempt to spend immature coinbase")]
-	ImmatureCoinbase,
+	/// Height of the block where the coinbase can be spent.
+	#[error("Attempt to spend immature coinbase, mature at height {0}")]
+	ImmatureCoinbase(u64),
 	/// Error validating a Merkle proof (coinbase output)
 	#[error("Error validating merkle proof, {0}")]
 	MerkleProof(String),
@@ -156,6 +156,16 @@ impl PibdParams {
 		)
 	}
 
+	/// Number of worker threads for the stateless validation of the blocks series.
+	pub fn get_block_validation_threads(&self) -> usize {
+		cmp::max(1, self.cpu_num)
+	}
+
+	/// Number of queued blocks to validate ahead of time, while the current blocks are applied.
+	pub fn get_block_validation_lookahead(&self) -> usize {
+		cmp::max(1, self.cpu_num) * 4
+	}
+
 	/// Number of simultaneous requests for segments we should make per available peer. Note this is currently
 	/// divisible by 3 to try and evenly spread requests amount the 3 main MMRs (Bitmap segments
 	/// will always be requested first)
@@ -24,6 +24,7 @@ use crate::core::core::{
 use crate::core::global;
 use crate::core::pow;
 use crate::error::Error;
+use crate::keychain::BlindingFactor;
 use crate::store;
 use crate::txhashset;
 use crate::types::{CommitPos, Options, Tip};
@@ -31,7 +32,8 @@ use mwc_core::consensus::HeaderDifficultyInfo;
 use mwc_core::core::Transaction;
 use mwc_util::secp::Secp256k1;
 use mwc_util::RwLock;
-use std::collections::{HashSet, VecDeque};
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::iter::FromIterator;
 
 /// Contextual information required to process a new block and either reject or
@@ -43,6 +45,10 @@ pub struct BlockContext<'a> {
 	pub pow_verifier: fn(&BlockHeader) -> Result<(), pow::Error>,
 	/// Custom fn allowing arbitrary header validation rules (denylist) to be applied.
 	pub header_allowed: Box<dyn Fn(&BlockHeader) -> Result<(), Error>>,
+	/// Number of worker threads for the stateless blocks validation.
+	pub validation_threads: usize,
+	/// Blocks of the series that already passed the stateless validation ahead of time.
+	pub validated_blocks: HashSet<Hash>,
 	/// The active txhashset (rewindable MMRs) to use for block processing.
 	pub txhashset: &'a mut txhashset::TxHashSet,
 	/// The active header MMR handle.
@@ -53,6 +59,7 @@ pub struct BlockContext<'a> {
 
 lazy_static! {
 	static ref INVALID_BLOCK_HASHES: RwLock<HashSet<Hash>> = RwLock::new(HashSet::new());
+	static ref CHECKPOINTS: RwLock<HashMap<u64, Hash>> = RwLock::new(HashMap::new());
 }
 
 /// Setup the banned header hashes defined at the config.
@@ -68,6 +75,62 @@ pub fn init_invalid_lock_hashes(hashed: &Option<Vec<String>>) -> Result<(), Erro
 	Ok(())
 }
 
+/// Setup the user defined header checkpoints from the config.
+/// Every entry is expected in the "<height>:<hash>" format.
+pub fn init_checkpoints(checkpoints: &Option<Vec<String>>) -> Result<(), Error> {
+	if let Some(cps) = checkpoints.as_ref() {
+		let mut checkpoints = CHECKPOINTS.write();
+		for cp in cps {
+			let (height, hash) = parse_checkpoint(cp)
+				.map_err(|e| Error::Other(format!("Unable to parse checkpoint {}, {}", cp, e)))?;
+			checkpoints.insert(height, hash);
+		}
+	}
+	Ok(())
+}
+
+fn parse_checkpoint(cp: &str) -> Result<(u64, Hash), String> {
+	let mut parts = cp.splitn(2, ':');
+	let height = parts
+		.next()
+		.unwrap_or("")
+		.trim()
+		.parse::<u64>()
+		.map_err(|e| format!("invalid height, {}", e))?;
+	let hash = Hash::from_hex(parts.next().ok_or("expected <height>:<hash>")?.trim())
+		.map_err(|e| format!("invalid hash, {}", e))?;
+	Ok((height, hash))
+}
+
+/// Checkpoint header hash at the given height, if any.
+/// User defined checkpoints take priority over the hard-coded ones for the active chain type.
+pub fn get_checkpoint(height: u64) -> Option<Hash> {
+	if let Some(hash) = CHECKPOINTS.read().get(&height) {
+		return Some(hash.clone());
+	}
+	global::get_checkpoints()
+		.iter()
+		.find(|(h, _)| *h == height)
+		.and_then(|(_, hash)| Hash::from_hex(hash).ok())
+}
+
+/// Validate header against the known checkpoints.
+/// Returns a "CheckpointMismatch" error which is "bad_data" and will result in peer being banned.
+pub fn validate_header_checkpoint(header: &BlockHeader) -> Result<(), Error> {
+	if let Some(hash) = get_checkpoint(header.height) {
+		if hash != header.hash() {
+			error!(
+				"Header {} at {} conflicts with checkpoint {}. Rejecting it!",
+				header.hash(),
+				header.height,
+				hash
+			);
+			return Err(Error::CheckpointMismatch(header.height, hash));
+		}
+	}
+	Ok(())
+}
+
 // If this block has greater total difficulty than treat as unknown in current context.
 // If it matches current chain head (latest or previous hash) then we know about it.
 // If it exists in the local db then we know about it.
@@ -217,12 +280,14 @@ pub fn process_blocks_series(
 		// Note: We still want to process the full block if we have seen this header before
 		// as we may have processed it "header first" and not yet processed the full block.
 		process_block_header(&b.header, ctx, cache_values)?;
-
-		// Validate the block itself, make sure it is internally consistent.
-		// Use the verifier_cache for verifying rangeproofs and kernel signatures.
-		validate_block(b, ctx, secp)?;
 	}
 
+	// Validate the blocks themselves, make sure they are internally consistent.
+	// Rangeproofs, kernel signatures, cut-through and weight don't depend on the chain state,
+	// so the whole series is validated by the worker threads before applying it to the txhashset.
+	// Blocks that were validated ahead of time, while they were queued, are skipped.
+	validate_blocks(blocks, ctx, secp)?;
+
 	// Get previous header from the db.
 	let prev = prev_header_store(&first_block.header, &mut ctx.batch)?;
 
@@ -530,6 +595,9 @@ fn validate_header(
 	// Apply any ctx specific header validation (denylist) rules.
 	validate_header_ctx(header, ctx)?;
 
+	// Any header that conflicts with a checkpoint is on a chain we never accept.
+	validate_header_checkpoint(header)?;
+
 	// First I/O cost, delayed as late as possible.
 	let prev = prev_header_store(header, &ctx.batch)?;
 
@@ -619,16 +687,88 @@ fn validate_header(
 	Ok(())
 }
 
-fn validate_block(
-	block: &Block,
+/// Stateless validation of the blocks series, the first failure is reported.
+fn validate_blocks(
+	blocks: &Vec<Block>,
 	ctx: &mut BlockContext<'_>,
 	secp: &Secp256k1,
 ) -> Result<(), Error> {
-	let prev = ctx.batch.get_previous_header(&block.header)?;
-	block
-		.validate(&prev.total_kernel_offset, secp)
-		.map_err(|e| Error::Block(e))?;
-	Ok(())
+	debug_assert!(!blocks.is_empty());
+
+	// Kernel offset of the previous block is needed for every block. Series is sequential,
+	// so only the first one requires the db.
+	let mut prev_offset = ctx
+		.batch
+		.get_previous_header(&blocks[0].header)?
+		.total_kernel_offset;
+	let mut queued = Vec::with_capacity(blocks.len());
+	for b in blocks {
+		if !ctx.validated_blocks.contains(&b.hash()) {
+			queued.push((b, prev_offset));
+		}
+		prev_offset = b.header.total_kernel_offset.clone();
+	}
+
+	validate_blocks_stateless(&queued, ctx.validation_threads, secp)
+		.into_iter()
+		.collect()
+}
+
+/// Stateless validation (rangeproofs, kernel signatures, cut-through, weight) of the blocks,
+/// every block with the total kernel offset of its previous header. Blocks are split between
+/// the worker threads, the result is reported for every block.
+pub fn validate_blocks_stateless(
+	blocks: &[(&Block, BlindingFactor)],
+	threads: usize,
+	secp: &Secp256k1,
+) -> Vec<Result<(), Error>> {
+	let threads = cmp::min(threads, blocks.len());
+	if threads <= 1 {
+		return blocks
+			.iter()
+			.map(|(b, prev_offset)| b.validate(prev_offset, secp).map_err(Error::Block))
+			.collect();
+	}
+
+	// Worker threads don't see the thread local settings, we need to pass them.
+	let chain_type = global::get_chain_type();
+	let nrd_enabled = global::is_nrd_enabled();
+
+	let chunk_size = (blocks.len() + threads - 1) / threads;
+	let validate_result = crossbeam::thread::scope(|s| {
+		let handles: Vec<_> = blocks
+			.chunks(chunk_size)
+			.map(|blocks| {
+				let handle = s.spawn(move |_| -> Vec<Result<(), Error>> {
+					global::set_local_chain_type(chain_type);
+					global::set_local_nrd_enabled(nrd_enabled);
+					blocks
+						.iter()
+						.map(|(b, prev_offset)| b.validate(prev_offset, secp).map_err(Error::Block))
+						.collect()
+				});
+				(handle, blocks.len())
+			})
+			.collect();
+
+		let mut results = Vec::with_capacity(blocks.len());
+		for (h, len) in handles {
+			match h.join() {
+				Ok(res) => results.extend(res),
+				Err(_) => {
+					results.extend(vec![
+						Err(Error::Other("crossbeam runtime error".to_string()));
+						len
+					])
+				}
+			}
+		}
+		results
+	});
+
+	validate_result.unwrap_or_else(|_| {
+		vec![Err(Error::Other("crossbeam runtime error".to_string())); blocks.len()]
+	})
 }
 
 /// Verify the block is not spending coinbase outputs before they have sufficiently matured.
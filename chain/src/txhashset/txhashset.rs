@@ -19,7 +19,7 @@
 use crate::core::consensus::WEEK_HEIGHT;
 use crate::core::core::committed::Committed;
 use crate::core::core::hash::{Hash, Hashed};
-use crate::core::core::merkle_proof::MerkleProof;
+use crate::core::core::merkle_proof::{KernelMerkleProof, MerkleProof};
 use crate::core::core::pmmr::{self, Backend, ReadablePMMR, ReadonlyPMMR, RewindablePMMR, PMMR};
 use crate::core::core::{
 	Block, BlockHeader, KernelFeatures, Output, OutputIdentifier, Segment, TxKernel,
@@ -39,7 +39,7 @@ use crossbeam::thread::ScopedJoinHandle;
 use mwc_store::pmmr::{clean_files_by_prefix, PMMRBackend};
 use mwc_util::secp::Secp256k1;
 use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -442,6 +442,32 @@ impl TxHashSet {
 		None
 	}
 
+	/// Find kernels with given excesses in a single backward pass from `max_index` to
+	/// `min_index`. Returns found kernels with their MMR index.
+	/// NOTE: same as `find_kernel`, this linear search can be VERY expensive
+	pub fn find_kernels(
+		&self,
+		excesses: &HashSet<Commitment>,
+		min_index: Option<u64>,
+		max_index: Option<u64>,
+	) -> HashMap<Commitment, (TxKernel, u64)> {
+		let min_index = min_index.unwrap_or(1);
+		let max_index = max_index.unwrap_or(self.kernel_pmmr_h.size);
+
+		let mut res = HashMap::new();
+		let pmmr = ReadonlyPMMR::at(&self.kernel_pmmr_h.backend, self.kernel_pmmr_h.size);
+		let mut index = max_index + 1;
+		while index > min_index && res.len() < excesses.len() {
+			index -= 1;
+			if let Some(kernel) = pmmr.get_data(index - 1) {
+				if excesses.contains(&kernel.excess) && !res.contains_key(&kernel.excess) {
+					res.insert(kernel.excess, (kernel, index));
+				}
+			}
+		}
+		res
+	}
+
 	/// Get MMR roots.
 	pub fn roots(&self) -> Result<TxHashSetRoots, Error> {
 		debug!(
@@ -475,6 +501,43 @@ impl TxHashSet {
 			.map_err(|e| Error::MerkleProof(format!("Commit {:?}, pos {}, {}", commit, pos0, e)))
 	}
 
+	/// build a new merkle proof for the given output commitment, doesn't require
+	/// the write access to the txhashset
+	pub fn readonly_merkle_proof(&self, commit: Commitment) -> Result<MerkleProof, Error> {
+		let pos0 = self.commit_index.get_output_pos(&commit)?;
+		ReadonlyPMMR::at(&self.output_pmmr_h.backend, self.output_pmmr_h.size)
+			.merkle_proof(pos0)
+			.map_err(|e| Error::MerkleProof(format!("Commit {:?}, pos {}, {}", commit, pos0, e)))
+	}
+
+	/// build a merkle proof for the kernel at `mmr_index` (1-based) against the kernel MMR
+	/// of the given header.
+	pub fn kernel_merkle_proof(
+		&self,
+		mmr_index: u64,
+		header: &BlockHeader,
+	) -> Result<KernelMerkleProof, Error> {
+		if mmr_index == 0 || mmr_index > header.kernel_mmr_size {
+			return Err(Error::TxKernelNotFound);
+		}
+		let pos0 = mmr_index - 1;
+		let pmmr = self.kernel_pmmr_at(header);
+		let kernel = pmmr.get_data(pos0).ok_or(Error::TxKernelNotFound)?;
+		let proof = pmmr.merkle_proof(pos0).map_err(|e| {
+			Error::MerkleProof(format!(
+				"Kernel at pos {}, header {}, {}",
+				pos0,
+				header.hash(),
+				e
+			))
+		})?;
+		Ok(KernelMerkleProof {
+			kernel,
+			mmr_index,
+			proof,
+		})
+	}
+
 	/// Compact the MMR data files and flush the rm logs
 	pub fn compact(
 		&mut self,
@@ -1519,6 +1582,27 @@ impl<'a> Extension<'a> {
 		Ok(merkle_proof)
 	}
 
+	/// Build a merkle proof for the unspent output with the given commitment against the
+	/// current (possibly rewound) state of the extension. The position comes from the
+	/// output_pos index of the batch, which is rewound together with the extension.
+	/// Returns the output with its MMR position (0-based) and the proof.
+	pub fn output_merkle_proof(
+		&self,
+		commit: &Commitment,
+		batch: &Batch<'_>,
+	) -> Result<(OutputIdentifier, u64, MerkleProof), Error> {
+		let pos0 = batch.get_output_pos(commit)?;
+		let output = self
+			.output_pmmr
+			.get_data(pos0)
+			.ok_or_else(|| Error::OutputNotFound(format!("Commit {:?} at pos {}", commit, pos0)))?;
+		let merkle_proof = self
+			.output_pmmr
+			.merkle_proof(pos0)
+			.map_err(|e| Error::MerkleProof(format!("Commit {:?}, pos {}, {}", commit, pos0, e)))?;
+		Ok((output, pos0, merkle_proof))
+	}
+
 	/// Saves a snapshot of the output and rangeproof MMRs to disk.
 	/// Specifically - saves a snapshot of the utxo file, tagged with
 	/// the block hash as filename suffix.
@@ -178,23 +178,28 @@ impl<'a> UTXOView<'a> {
 			.map(|x| self.validate_input(x.commitment(), batch))
 			.collect();
 
-		// Find the max pos of any coinbase being spent.
-		let pos = spent?
-			.iter()
+		// Find the max pos and height of any coinbase being spent.
+		let coinbase: Vec<_> = spent?
+			.into_iter()
 			.filter_map(|(out, pos)| {
 				if out.features.is_coinbase() {
-					Some(pos.pos)
+					Some(pos)
 				} else {
 					None
 				}
 			})
-			.max();
+			.collect();
+		let pos = coinbase.iter().map(|pos| pos.pos).max();
 
 		if let Some(pos) = pos {
+			// Height where the latest of the coinbase outputs can be spent
+			let mature_at = coinbase.iter().map(|pos| pos.height).max().unwrap_or(0)
+				+ global::coinbase_maturity();
+
 			// If we have not yet reached 1440 blocks then
 			// we can fail immediately as coinbase cannot be mature.
 			if height < global::coinbase_maturity() {
-				return Err(Error::ImmatureCoinbase);
+				return Err(Error::ImmatureCoinbase(mature_at));
 			}
 
 			// Find the "cutoff" pos in the output MMR based on the
@@ -206,7 +211,7 @@ impl<'a> UTXOView<'a> {
 			// If any output pos exceed the cutoff_pos
 			// we know they have not yet sufficiently matured.
 			if pos > cutoff_pos {
-				return Err(Error::ImmatureCoinbase);
+				return Err(Error::ImmatureCoinbase(mature_at));
 			}
 		}
 
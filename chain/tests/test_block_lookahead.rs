@@ -0,0 +1,81 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+
+use self::chain_test_helper::{clean_output_dir, init_chain, mine_chain};
+use chain::{Error, Options};
+use mwc_chain as chain;
+use mwc_core::core::hash::Hashed;
+use mwc_util as util;
+
+/// Queued orphans are validated while the blocks before them are applied.
+#[test]
+fn validate_queued_blocks_ahead() {
+	let src_dir = ".mwc.lookahead_src";
+	let dest_dir = ".mwc.lookahead_dest";
+	util::init_test_logger();
+	clean_output_dir(src_dir);
+	clean_output_dir(dest_dir);
+
+	// Multiple blocks are processed together only below 100 blocks from the header head
+	let src_chain = mine_chain(src_dir, 130);
+	let genesis = src_chain
+		.get_block(&src_chain.get_header_by_height(0).unwrap().hash())
+		.unwrap();
+	let dest_chain = init_chain(dest_dir, genesis);
+
+	let headers: Vec<_> = (1..130)
+		.map(|h| src_chain.get_header_by_height(h).unwrap())
+		.collect();
+	let sync_head = dest_chain.header_head().unwrap();
+	dest_chain
+		.sync_block_headers(&headers, sync_head, Options::SYNC)
+		.unwrap();
+
+	let blocks: Vec<_> = headers
+		.iter()
+		.take(5)
+		.map(|h| src_chain.get_block(&h.hash()).unwrap())
+		.collect();
+
+	// Blocks 3..5 arrive first and wait for the block 2
+	for b in &blocks[2..] {
+		assert_eq!(
+			dest_chain.process_block(b.clone(), Options::SYNC),
+			Err(Error::Orphan(String::new()))
+		);
+		assert!(!dest_chain.get_orphan(&b.hash()).unwrap().is_validated());
+	}
+
+	// Applying the block 1 validates the queued ones ahead of time
+	dest_chain
+		.process_block(blocks[0].clone(), Options::SYNC)
+		.unwrap();
+	assert_eq!(dest_chain.head().unwrap().height, 1);
+	for b in &blocks[2..] {
+		assert!(dest_chain.get_orphan(&b.hash()).unwrap().is_validated());
+	}
+
+	// Block 2 unlocks the whole series
+	dest_chain
+		.process_block(blocks[1].clone(), Options::SYNC)
+		.unwrap();
+	assert_eq!(dest_chain.head().unwrap().height, 5);
+	assert_eq!(dest_chain.orphans_len(), 0);
+	assert_eq!(dest_chain.head().unwrap().last_block_h, blocks[4].hash());
+
+	clean_output_dir(src_dir);
+	clean_output_dir(dest_dir);
+}
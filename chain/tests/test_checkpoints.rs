@@ -0,0 +1,137 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+
+use self::chain_test_helper::{clean_output_dir, mine_chain};
+use chain::{Error, Options};
+use chrono::Duration;
+use mwc_chain as chain;
+use mwc_core::core::hash::Hashed;
+use mwc_core::core::{Block, BlockHeader};
+use mwc_core::libtx::{self, ProofBuilder};
+use mwc_core::pow::{self, Difficulty};
+use mwc_core::{genesis, global};
+use mwc_keychain::{ExtKeychain, ExtKeychainPath, Keychain};
+use mwc_util as util;
+use mwc_util::ToHex;
+
+// Block on top of prev with a random proof, valid for processing with SKIP_POW only.
+fn prepare_fork_block(chain: &chain::Chain, prev: &BlockHeader, diff: u64) -> Block {
+	let kc = ExtKeychain::from_random_seed(false).unwrap();
+	let key_id = ExtKeychainPath::new(1, 100, 0, 0, 0).to_identifier();
+	let reward = libtx::reward::output(
+		&kc,
+		&ProofBuilder::new(&kc),
+		&key_id,
+		0,
+		false,
+		prev.height + 1,
+		kc.secp(),
+	)
+	.unwrap();
+	let mut b = Block::new(prev, &[], Difficulty::from_num(diff), reward, kc.secp()).unwrap();
+	b.header.timestamp = prev.timestamp + Duration::seconds(60);
+	b.header.pow.total_difficulty = prev.total_difficulty() + Difficulty::from_num(diff);
+	b.header.pow.proof = pow::Proof::random(global::proofsize());
+	chain.set_txhashset_roots(&mut b).unwrap();
+	b
+}
+
+/// Fork that conflicts with a configured checkpoint is rejected, even if it has more work.
+#[test]
+fn reject_fork_conflicting_with_checkpoint() {
+	let chain_dir = ".mwc.checkpoints";
+	util::init_test_logger();
+	clean_output_dir(chain_dir);
+
+	let chain = mine_chain(chain_dir, 4);
+	let checkpoint = chain.get_header_by_height(2).unwrap();
+	chain::pipe::init_checkpoints(&Some(vec![format!("2:{}", checkpoint.hash().to_hex())]))
+		.unwrap();
+	assert_eq!(chain::pipe::get_checkpoint(2), Some(checkpoint.hash()));
+	assert_eq!(chain::pipe::validate_header_checkpoint(&checkpoint), Ok(()));
+
+	// Alternative block at the checkpoint height, with much more work than the current chain.
+	let fork_point = chain.get_header_by_height(1).unwrap();
+	let fork = prepare_fork_block(&chain, &fork_point, 1_000_000);
+	assert!(fork.header.total_difficulty() > chain.head().unwrap().total_difficulty);
+
+	let expected = Error::CheckpointMismatch(2, checkpoint.hash());
+	assert_eq!(
+		chain::pipe::validate_header_checkpoint(&fork.header),
+		Err(expected.clone())
+	);
+	assert_eq!(
+		chain.process_block_header(&fork.header, Options::SKIP_POW),
+		Err(expected.clone())
+	);
+	assert_eq!(
+		chain.process_block(fork.clone(), Options::SKIP_POW),
+		Err(expected.clone())
+	);
+	// Header sync refuses it as well, the peer that sent it is banned for the bad data
+	let sync_head = chain.header_head().unwrap();
+	assert_eq!(
+		chain.sync_block_headers(
+			&[fork.header.clone()],
+			sync_head,
+			Options::SYNC | Options::SKIP_POW
+		),
+		Err(expected.clone())
+	);
+	assert_eq!(chain.header_head().unwrap(), sync_head);
+	assert!(expected.is_bad_data());
+
+	// The checkpointed chain is kept.
+	assert_eq!(chain.head().unwrap().height, 3);
+	assert_eq!(
+		chain.get_header_by_height(2).unwrap().hash(),
+		checkpoint.hash()
+	);
+
+	// Fork above the checkpoint is not affected.
+	let fork_point = chain.get_header_by_height(2).unwrap();
+	let fork = prepare_fork_block(&chain, &fork_point, 1_000_000);
+	assert!(chain
+		.process_block_header(&fork.header, Options::SKIP_POW)
+		.is_ok());
+
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn invalid_checkpoint_config() {
+	assert!(chain::pipe::init_checkpoints(&Some(vec!["abc".to_string()])).is_err());
+	assert!(chain::pipe::init_checkpoints(&Some(vec!["10".to_string()])).is_err());
+	assert!(chain::pipe::init_checkpoints(&Some(vec!["10:xyz".to_string()])).is_err());
+	assert!(chain::pipe::init_checkpoints(&None).is_ok());
+}
+
+/// Hard-coded checkpoints are the real headers of the networks.
+#[test]
+fn hard_coded_checkpoints() {
+	global::set_local_chain_type(global::ChainTypes::Mainnet);
+	assert_eq!(
+		chain::pipe::get_checkpoint(0),
+		Some(genesis::genesis_main().hash())
+	);
+	global::set_local_chain_type(global::ChainTypes::Floonet);
+	assert_eq!(
+		chain::pipe::get_checkpoint(0),
+		Some(genesis::genesis_floo().hash())
+	);
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	assert!(global::get_checkpoints().is_empty());
+}
@@ -155,7 +155,7 @@ fn test_coinbase_maturity() {
 		match chain.verify_coinbase_maturity(&coinbase_txn.inputs()) {
 			Ok(_) => {}
 			Err(e) => match e {
-				Error::ImmatureCoinbase => {}
+				Error::ImmatureCoinbase(_) => {}
 				_ => panic!("Expected transaction error with immature coinbase."),
 			},
 		}
@@ -277,7 +277,7 @@ fn test_coinbase_maturity() {
 			match chain.verify_coinbase_maturity(&coinbase_txn.inputs()) {
 				Ok(_) => {}
 				Err(e) => match e {
-					Error::ImmatureCoinbase => {}
+					Error::ImmatureCoinbase(_) => {}
 					_ => panic!("Expected transaction error with immature coinbase."),
 				},
 			}
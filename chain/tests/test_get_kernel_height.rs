@@ -18,6 +18,7 @@ use mwc_util as util;
 mod chain_test_helper;
 
 use self::chain_test_helper::{clean_output_dir, mine_chain};
+use mwc_core::core::hash::Hashed;
 use util::secp::pedersen::Commitment;
 
 #[test]
@@ -109,3 +110,61 @@ fn test_get_kernel_height() {
 
 	clean_output_dir(chain_dir);
 }
+
+#[test]
+fn test_get_kernels_height() {
+	let chain_dir = ".mwc.get_kernels_height";
+	clean_output_dir(chain_dir);
+	let chain = mine_chain(chain_dir, 5);
+	assert_eq!(chain.head().unwrap().height, 4);
+
+	let mut excesses = vec![];
+	let mut commits = vec![];
+	for height in 1..=4 {
+		let header = chain.get_header_by_height(height).unwrap();
+		let block = chain.get_block(&header.hash()).unwrap();
+		excesses.push(block.kernels()[0].excess);
+		commits.push(block.outputs()[0].commitment());
+	}
+	excesses.push(Commitment::from_vec(vec![]));
+
+	// batch lookup must match the single kernel lookups, missing kernel is None
+	let kernels = chain.get_kernels_height(&excesses, None, None).unwrap();
+	assert_eq!(kernels.len(), 5);
+	for (excess, kernel) in excesses.iter().zip(kernels.iter()) {
+		let single = chain.get_kernel_height(excess, None, None).unwrap();
+		assert_eq!(
+			kernel.as_ref().map(|k| (k.0.excess, k.1, k.2)),
+			single.map(|k| (k.0.excess, k.1, k.2))
+		);
+	}
+	assert_eq!(kernels[0].as_ref().unwrap().1, 1);
+	assert_eq!(kernels[3].as_ref().unwrap().1, 4);
+	assert!(kernels[4].is_none());
+
+	// height window is applied to every kernel
+	let kernels = chain
+		.get_kernels_height(&excesses, Some(2), Some(3))
+		.unwrap();
+	let heights: Vec<_> = kernels.iter().map(|k| k.as_ref().map(|k| k.1)).collect();
+	assert_eq!(heights, vec![None, Some(2), Some(3), None, None]);
+
+	// headers range, both ends are included
+	let headers = chain.get_headers_by_height(1, 3).unwrap();
+	let heights: Vec<_> = headers.iter().map(|h| h.height).collect();
+	assert_eq!(heights, vec![1, 2, 3]);
+	assert!(chain.get_headers_by_height(3, 10).is_err());
+
+	// merkle proofs are built against the head
+	let (header, proofs) = chain.get_merkle_proofs_for_pos(&commits).unwrap();
+	assert_eq!(header.hash(), chain.head().unwrap().last_block_h);
+	assert_eq!(proofs.len(), 4);
+	for (commit, proof) in commits.iter().zip(proofs.iter()) {
+		assert_eq!(
+			proof.as_ref().unwrap(),
+			&chain.get_merkle_proof_for_pos(*commit).unwrap()
+		);
+	}
+
+	clean_output_dir(chain_dir);
+}
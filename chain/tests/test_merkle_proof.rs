@@ -0,0 +1,243 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod chain_test_helper;
+
+use self::chain_test_helper::{clean_output_dir, genesis_block, init_chain, mine_chain};
+use chrono::Duration;
+use mwc_chain::{Chain, Options, MAX_MERKLE_PROOF_REWIND};
+use mwc_core::core::hash::Hashed;
+use mwc_core::core::merkle_proof::{verify_merkle_proof, KernelMerkleProof};
+use mwc_core::core::{Block, KernelFeatures, Transaction};
+use mwc_core::libtx::{self, build, ProofBuilder};
+use mwc_core::{consensus, global, pow};
+use mwc_keychain::{ExtKeychain, ExtKeychainPath, Identifier, Keychain};
+use std::collections::VecDeque;
+
+// Mine a block with the given txs on top of the chain head, reward goes to `key_id`.
+fn add_block(chain: &Chain, txs: &[Transaction], keychain: &ExtKeychain, key_id: &Identifier) {
+	let prev = chain.head_header().unwrap();
+	let next_header_info = consensus::next_difficulty(
+		prev.height + 1,
+		chain.difficulty_iter().unwrap(),
+		&mut VecDeque::new(),
+	);
+	let fees = txs.iter().map(|tx| tx.fee(prev.height + 1)).sum();
+	let reward = libtx::reward::output(
+		keychain,
+		&ProofBuilder::new(keychain),
+		key_id,
+		fees,
+		false,
+		prev.height + 1,
+		keychain.secp(),
+	)
+	.unwrap();
+	let mut b = Block::new(
+		&prev,
+		txs,
+		next_header_info.difficulty,
+		reward,
+		keychain.secp(),
+	)
+	.unwrap();
+	b.header.timestamp = prev.timestamp + Duration::seconds(60);
+	b.header.pow.secondary_scaling = next_header_info.secondary_scaling;
+	chain.set_txhashset_roots(&mut b).unwrap();
+	pow::pow_size(
+		&mut b.header,
+		next_header_info.difficulty,
+		global::proofsize(),
+		global::min_edge_bits(),
+	)
+	.unwrap();
+	chain.process_block(b, Options::MINE).unwrap();
+}
+
+#[test]
+fn test_merkle_proof_at_header() {
+	let chain_dir = ".mwc.merkle_proof_at_header";
+	clean_output_dir(chain_dir);
+	let chain = mine_chain(chain_dir, 6);
+	assert_eq!(chain.head().unwrap().height, 5);
+
+	let header_2 = chain.get_header_by_height(2).unwrap();
+	let block_2 = chain.get_block(&header_2.hash()).unwrap();
+	let commit = block_2.outputs()[0].commitment();
+
+	// proofs against the header where the output is included, a later header and the head
+	for height in 2..=5 {
+		let header = chain.get_header_by_height(height).unwrap();
+		let (proof_header, output, pos0, proof) = chain
+			.get_merkle_proof_at_header(commit, Some(header.hash()))
+			.unwrap();
+		assert_eq!(proof_header.hash(), header.hash());
+		assert_eq!(output.commitment(), commit);
+		verify_merkle_proof(&header, &output, pos0 + 1, &proof).unwrap();
+	}
+
+	let (head_header, output, pos0, proof) =
+		chain.get_merkle_proof_at_header(commit, None).unwrap();
+	assert_eq!(head_header.hash(), chain.head().unwrap().last_block_h);
+	verify_merkle_proof(&head_header, &output, pos0 + 1, &proof).unwrap();
+
+	// proof for the head doesn't match an older header
+	let header_4 = chain.get_header_by_height(4).unwrap();
+	assert!(verify_merkle_proof(&header_4, &output, pos0 + 1, &proof).is_err());
+
+	// output is not included yet at height 1
+	let header_1 = chain.get_header_by_height(1).unwrap();
+	assert!(chain
+		.get_merkle_proof_at_header(commit, Some(header_1.hash()))
+		.is_err());
+
+	clean_output_dir(chain_dir);
+}
+
+/// The proof against a header too far below the head is refused, the rewind is limited.
+#[test]
+fn test_merkle_proof_rewind_limit() {
+	let chain_dir = ".mwc.merkle_proof_rewind_limit";
+	clean_output_dir(chain_dir);
+	let chain = mine_chain(chain_dir, MAX_MERKLE_PROOF_REWIND + 3);
+	let head = chain.head().unwrap();
+	assert_eq!(head.height, MAX_MERKLE_PROOF_REWIND + 2);
+
+	let header_1 = chain.get_header_by_height(1).unwrap();
+	let commit = chain.get_block(&header_1.hash()).unwrap().outputs()[0].commitment();
+	assert!(chain
+		.get_merkle_proof_at_header(commit, Some(header_1.hash()))
+		.is_err());
+
+	let header_2 = chain.get_header_by_height(2).unwrap();
+	let (_, output, pos0, proof) = chain
+		.get_merkle_proof_at_header(commit, Some(header_2.hash()))
+		.unwrap();
+	verify_merkle_proof(&header_2, &output, pos0 + 1, &proof).unwrap();
+	assert_eq!(chain.head().unwrap(), head);
+
+	clean_output_dir(chain_dir);
+}
+
+/// Output spent since the header still has a proof against that header.
+#[test]
+fn test_merkle_proof_spent_output() {
+	let chain_dir = ".mwc.merkle_proof_spent_output";
+	clean_output_dir(chain_dir);
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	let keychain = ExtKeychain::from_random_seed(false).unwrap();
+	let chain = init_chain(chain_dir, genesis_block(&keychain));
+
+	let key_ids: Vec<_> = (1..=5)
+		.map(|n| ExtKeychainPath::new(1, n, 0, 0, 0).to_identifier())
+		.collect();
+	for key_id in &key_ids[..3] {
+		add_block(&chain, &[], &keychain, key_id);
+	}
+
+	let header_3 = chain.get_header_by_height(3).unwrap();
+	let block_1 = chain
+		.get_block(&chain.get_header_by_height(1).unwrap().hash())
+		.unwrap();
+	let commit = block_1.outputs()[0].commitment();
+
+	// spend the coinbase of the block 1 at height 4
+	let amount = consensus::reward(0, 1);
+	let tx = build::transaction(
+		KernelFeatures::Plain { fee: 2.into() },
+		&[
+			build::coinbase_input(amount, key_ids[0].clone()),
+			build::output(amount - 2, key_ids[4].clone()),
+		],
+		&keychain,
+		&ProofBuilder::new(&keychain),
+	)
+	.unwrap();
+	add_block(&chain, &[tx], &keychain, &key_ids[3]);
+	assert_eq!(chain.head().unwrap().height, 4);
+
+	// the output is spent at the head, but still unspent at the height 3
+	assert!(chain.get_merkle_proof_at_header(commit, None).is_err());
+	let (proof_header, output, pos0, proof) = chain
+		.get_merkle_proof_at_header(commit, Some(header_3.hash()))
+		.unwrap();
+	assert_eq!(proof_header.hash(), header_3.hash());
+	assert_eq!(output.commitment(), commit);
+	verify_merkle_proof(&header_3, &output, pos0 + 1, &proof).unwrap();
+
+	// the chain state is not affected by the rewind
+	assert_eq!(chain.head().unwrap().height, 4);
+	chain.validate(false).unwrap();
+
+	clean_output_dir(chain_dir);
+}
+
+#[test]
+fn test_kernel_merkle_proof() {
+	let chain_dir = ".mwc.kernel_merkle_proof";
+	clean_output_dir(chain_dir);
+	let chain = mine_chain(chain_dir, 6);
+
+	let header_3 = chain.get_header_by_height(3).unwrap();
+	let block_3 = chain.get_block(&header_3.hash()).unwrap();
+	let excess = block_3.kernels()[0].excess;
+
+	for height in 3..=5 {
+		let header = chain.get_header_by_height(height).unwrap();
+		let (kernel_height, proof_header, proof) = chain
+			.get_kernel_merkle_proof(&excess, Some(header.hash()), None, None)
+			.unwrap()
+			.unwrap();
+		assert_eq!(kernel_height, 3);
+		assert_eq!(proof_header.hash(), header.hash());
+		assert_eq!(proof.kernel.excess, excess);
+		proof.verify(&header).unwrap();
+
+		// proof survives the hex round trip
+		let proof = KernelMerkleProof::from_hex(&proof.to_hex()).unwrap();
+		proof.verify(&header).unwrap();
+		if height > 3 {
+			assert!(proof.verify(&header_3).is_err());
+		}
+	}
+
+	// kernel is not included yet at height 2, the search stops at the header
+	let header_2 = chain.get_header_by_height(2).unwrap();
+	assert!(chain
+		.get_kernel_merkle_proof(&excess, Some(header_2.hash()), None, None)
+		.unwrap()
+		.is_none());
+	assert!(chain
+		.get_kernel_merkle_proof(&excess, Some(header_2.hash()), None, Some(5))
+		.unwrap()
+		.is_none());
+	// max height below the header still limits the search
+	assert!(chain
+		.get_kernel_merkle_proof(&excess, Some(header_3.hash()), None, Some(2))
+		.unwrap()
+		.is_none());
+	assert!(chain
+		.get_kernel_merkle_proof(&excess, None, Some(3), Some(3))
+		.unwrap()
+		.is_some());
+
+	// unknown kernel
+	let unknown = block_3.outputs()[0].commitment();
+	assert!(chain
+		.get_kernel_merkle_proof(&unknown, None, None, None)
+		.unwrap()
+		.is_none());
+
+	clean_output_dir(chain_dir);
+}
@@ -160,6 +160,16 @@ fn comments() -> HashMap<String, String> {
                 .to_string(),
 	);
 
+	retval.insert(
+		"checkpoints".to_string(),
+		"
+#A list of additional header checkpoints in \"<height>:<hash>\" format. Any header chain
+#that conflicts with them (or with the built-in checkpoints) is rejected during sync.
+#checkpoints = [\"1000:735cf2a4492b437e292a295549c31df5f1e8e6d09e58ed20abdd808c2261d1f1\"]
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"libp2p_enabled".to_string(),
 		"
@@ -176,6 +186,66 @@ fn comments() -> HashMap<String, String> {
 			.to_string(),
 	);
 
+	retval.insert(
+		"libp2p_messages_max_age_sec".to_string(),
+		"
+#Received libp2p messages are stored at the node db, so the consumers can read them later.
+#Messages retention time in seconds. Default value: 86400
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_messages_max_per_topic".to_string(),
+		"
+#Max number of the stored libp2p messages per topic, the oldest messages are deleted. Default value: 10000
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_peer_msg_rate".to_string(),
+		"
+#Rate limit for the libp2p messages published by a single peer, messages per second. The messages
+#are counted by the author, not by the peer that forwarded them. Directly connected peers that
+#keep flooding the network are banned. Default value: 0.5
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_peer_msg_burst".to_string(),
+		"
+#Max burst of the libp2p messages published by a single peer. Default value: 20
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_topic_msg_rate".to_string(),
+		"
+#Rate limit for the libp2p messages on a single topic, messages per second. Default value: 10
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_topic_msg_burst".to_string(),
+		"
+#Max burst of the libp2p messages on a single topic. Default value: 200
+"
+		.to_string(),
+	);
+
+	retval.insert(
+		"libp2p_peer_ban_score".to_string(),
+		"
+#Penalty score of the libp2p peer when it is banned. Every message over the rate limit from
+#the peer adds 1, every invalid message adds 10, the score is halved every minute. Default value: 50
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"run_tui".to_string(),
 		"
@@ -195,6 +265,14 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"test_miner_threads".to_string(),
+		"
+#Number of CPU solver threads for the test miner (default 1).
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"[server.webhook_config]".to_string(),
 		"
@@ -220,6 +298,10 @@ fn comments() -> HashMap<String, String> {
 #The url where a POST request will be sent when a new block is received by a peer.
 #block_received_url = \"http://127.0.0.1:8080/block\"
 
+#The url where a POST request will be sent for every watch list event (output or kernel
+#confirmation, spend or reorg). Watch list is managed with the owner API.
+#watch_list_url = \"http://127.0.0.1:8080/watch\"
+
 #The number of worker threads that will be assigned to making the http requests.
 "
 		.to_string(),
@@ -233,6 +315,33 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"[server.tor_config]".to_string(),
+		"
+#########################################
+### TOR CONFIGURATION                 ###
+#########################################
+
+#Tor daemon that is already running can be used instead of starting a tor process.
+#The node publishes its onion service with the tor control protocol (ADD_ONION).
+#control_addr = \"127.0.0.1:9051\"
+
+#Password for the control port, if the daemon uses HashedControlPassword.
+#control_password = \"password\"
+
+#Cookie file for the control port, if the daemon uses CookieAuthentication.
+#By default the cookie file location reported by the daemon is used.
+#control_cookie_file = \"/var/run/tor/control.authcookie\"
+
+#x25519 public keys (base32) of the clients that are authorized to connect to the
+#node onion service. If no clients are authorized, the onion service is public.
+#Client keys can be generated and revoked with the owner API as well, changes are
+#applied at the node restart.
+#authorized_clients = [\"<base32 public key>\"]
+"
+		.to_string(),
+	);
+
 	retval.insert(
 		"[server.dandelion_config]".to_string(),
 		"
@@ -356,6 +465,46 @@ fn comments() -> HashMap<String, String> {
 # A preferred dandelion_peer, mainly used for testing dandelion
 # dandelion_peer = \"10.0.0.1:13144\"
 
+#maximum number of outbound connections to the peers of the same network group
+#(IPv4 /16 subnet, IPv6 /32 subnet, onion address group). Local addresses are not limited.
+#peer_max_outbound_per_group = 2
+
+#number of the long lived outbound peers that are saved as anchors and
+#reconnected first after the restart
+#peer_anchor_count = 2
+
+#number of the outbound peers that relay only headers and blocks, no transactions
+#and peer addresses. They are not counted in peer_max_outbound_count.
+#peer_block_relay_only_count = 2
+
+#upload cap for all peer connections in bytes per second, the uploading peers
+#get a fair share of it. Upload is unlimited if not set.
+#upload_cap = 1000000
+
+#daily upload quota in bytes. When it is exceeded, the node stops serving the
+#historical blocks and PIBD segments until the next UTC day.
+#daily_upload_quota = 10000000000
+
+#SOCKS5 proxy for the outbound peer connections, for example a corporate egress
+#proxy or a tor/i2p gateway. The node doesn't listen through the proxy.
+#[server.p2p_config.socks5_proxy]
+#host = \"127.0.0.1\"
+#port = 1080
+#username and password are optional, for the username/password authentication
+#username = \"user\"
+#password = \"password\"
+#policy for the IP and the onion addresses: Proxy or Direct (connect the same way
+#as without the proxy, through the node Tor if it is enabled). Default is Proxy.
+#clearnet = \"Proxy\"
+#onion = \"Proxy\"
+
+#I2P transport for the peer connections. The node connects to the SAM v3 bridge
+#of the local I2P router, the node I2P destination key is stored at <db_root>/i2p.
+#[server.p2p_config.i2p_config]
+#sam_addr = \"127.0.0.1:7656\"
+#accept the inbound connections at the node I2P address, default is true
+#inbound = true
+
 #########################################
 ### MEMPOOL CONFIGURATION             ###
 #########################################
@@ -41,6 +41,10 @@ pub const NANO_MWC: u64 = 1;
 /// (adjusting the reward accordingly).
 pub const BLOCK_TIME_SEC: u64 = 60;
 
+/// Blocks with the timestamp more than 12 block intervals in the future are refused
+/// (as in bitcoin).
+pub const FUTURE_TIME_LIMIT_SEC: i64 = 12 * BLOCK_TIME_SEC as i64;
+
 /// MWC - Here is a block reward.
 /// The block subsidy amount, one mwc per second on average
 //pub const REWARD: u64 = BLOCK_TIME_SEC * MWC_BASE;
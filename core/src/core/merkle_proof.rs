@@ -17,6 +17,7 @@
 
 use crate::core::hash::Hash;
 use crate::core::pmmr;
+use crate::core::{BlockHeader, OutputIdentifier, TxKernel};
 use crate::ser;
 use crate::ser::{PMMRIndexHashable, Readable, Reader, Writeable, Writer};
 use util::ToHex;
@@ -27,6 +28,12 @@ pub enum MerkleProofError {
 	/// Merkle proof root hash does not match when attempting to verify.
 	#[error("Merkle Proof root mismatch")]
 	RootMismatch,
+	/// Merkle proof MMR size doesn't match the header output MMR size.
+	#[error("Merkle Proof MMR size mismatch")]
+	SizeMismatch,
+	/// Element position is not a leaf inside of the MMR.
+	#[error("Merkle Proof invalid element position")]
+	InvalidPosition,
 }
 
 /// A Merkle proof that proves a particular element exists in the MMR.
@@ -157,3 +164,105 @@ impl MerkleProof {
 		}
 	}
 }
+
+/// Stateless verification of the output Merkle proof against the block header.
+/// Checks that the output at MMR index `mmr_index` (1-based, as the node API returns it)
+/// is included in the output MMR committed to by `header.output_root`.
+/// Doesn't need any chain data, so it can be used by the light clients.
+pub fn verify_merkle_proof(
+	header: &BlockHeader,
+	output: &OutputIdentifier,
+	mmr_index: u64,
+	proof: &MerkleProof,
+) -> Result<(), MerkleProofError> {
+	verify_leaf(
+		header.output_root,
+		header.output_mmr_size,
+		output,
+		mmr_index,
+		proof,
+	)
+}
+
+// Verify the proof for the leaf at `mmr_index` (1-based) against the MMR root and size
+fn verify_leaf(
+	root: Hash,
+	mmr_size: u64,
+	element: &dyn PMMRIndexHashable,
+	mmr_index: u64,
+	proof: &MerkleProof,
+) -> Result<(), MerkleProofError> {
+	if proof.mmr_size != mmr_size {
+		return Err(MerkleProofError::SizeMismatch);
+	}
+	if mmr_index == 0 || mmr_index > proof.mmr_size || !pmmr::is_leaf(mmr_index - 1) {
+		return Err(MerkleProofError::InvalidPosition);
+	}
+	proof.verify(root, element, mmr_index - 1)
+}
+
+/// Merkle proof of the kernel inclusion into the kernel MMR. Kernel MMR is never pruned,
+/// so the proof can be built for any header at or after the block with the kernel.
+/// Intended for the payment proofs and atomic swaps, where one party needs to prove
+/// to another that the transaction is on chain.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KernelMerkleProof {
+	/// The kernel
+	pub kernel: TxKernel,
+	/// Kernel MMR index (1-based)
+	pub mmr_index: u64,
+	/// Merkle proof for the kernel MMR
+	pub proof: MerkleProof,
+}
+
+impl Writeable for KernelMerkleProof {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		self.kernel.write(writer)?;
+		writer.write_u64(self.mmr_index)?;
+		self.proof.write(writer)?;
+		Ok(())
+	}
+}
+
+impl Readable for KernelMerkleProof {
+	fn read<R: Reader>(reader: &mut R) -> Result<KernelMerkleProof, ser::Error> {
+		let kernel = TxKernel::read(reader)?;
+		let mmr_index = reader.read_u64()?;
+		let proof = MerkleProof::read(reader)?;
+		Ok(KernelMerkleProof {
+			kernel,
+			mmr_index,
+			proof,
+		})
+	}
+}
+
+impl KernelMerkleProof {
+	/// Serialize the kernel Merkle proof as a hex string
+	pub fn to_hex(&self) -> String {
+		let mut vec = Vec::new();
+		ser::serialize_default(&mut vec, &self).expect("serialization failed");
+		vec.to_hex()
+	}
+
+	/// Convert hex string representation back to a kernel Merkle proof instance
+	pub fn from_hex(hex: &str) -> Result<KernelMerkleProof, String> {
+		let bytes = util::from_hex(hex)
+			.map_err(|e| format!("Kernel Merkle Proof HEX conversion error, {}", e))?;
+		let res = ser::deserialize_default(&mut &bytes[..])
+			.map_err(|e| format!("failed to deserialize a Kernel Merkle Proof, {}", e))?;
+		Ok(res)
+	}
+
+	/// Verifies that the kernel is included in the kernel MMR committed to by
+	/// `header.kernel_root`. Doesn't need any chain data.
+	pub fn verify(&self, header: &BlockHeader) -> Result<(), MerkleProofError> {
+		verify_leaf(
+			header.kernel_root,
+			header.kernel_mmr_size,
+			&self.kernel,
+			self.mmr_index,
+			&self.proof,
+		)
+	}
+}
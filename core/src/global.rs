@@ -30,7 +30,7 @@ use crate::pow::{self, new_cuckarood_ctx, new_cuckatoo_ctx, PoWContext, Proof};
 use crate::ser::ProtocolVersion;
 use std::cell::Cell;
 use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 use util::OneTime;
 
@@ -142,6 +142,21 @@ pub const FLOONET_DNS_SEEDS: &'static [&'static str] = &[
 	"wt635fgwmhokk25lv7y2jvrg63mokg7nfni5owrtzalz3nx22dgjytid.onion",
 ];
 
+/// Hard-coded header checkpoints (height, header hash) for mainnet.
+/// Any header chain that disagrees with these is rejected during sync.
+/// Only the genesis is pinned here, the later checkpoints are loaded from the
+/// `checkpoints` list of the server config.
+pub const MAINNET_CHECKPOINTS: &'static [(u64, &'static str)] = &[(
+	0,
+	"e29e3a72496d85c5ada8186323016f4c7951880f77f3c8867d3b8cd3bf306c3d",
+)];
+
+/// Hard-coded header checkpoints (height, header hash) for floonet.
+pub const FLOONET_CHECKPOINTS: &'static [(u64, &'static str)] = &[(
+	0,
+	"a10f32177e0b8de4495637c5735577512963cb3dca42ee893fc9c5fade29dfa7",
+)];
+
 /// Types of chain a server can run with, dictates the genesis block and
 /// and mining parameters used.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -440,6 +455,16 @@ pub fn txhashset_archive_interval() -> u64 {
 	}
 }
 
+/// Hard-coded header checkpoints for the active chain type.
+/// Testing chains have no checkpoints.
+pub fn get_checkpoints() -> &'static [(u64, &'static str)] {
+	match get_chain_type() {
+		ChainTypes::Mainnet => MAINNET_CHECKPOINTS,
+		ChainTypes::Floonet => FLOONET_CHECKPOINTS,
+		_ => &[],
+	}
+}
+
 /// Are we in production mode?
 /// Production defined as a live public network, testnet[n] or mainnet.
 pub fn is_production_mode() -> bool {
@@ -647,6 +672,50 @@ mod test {
 		set_local_chain_type(ChainTypes::Mainnet);
 		test_header_len(genesis_main());
 	}
+
+	#[test]
+	fn checkpoints_match_genesis() {
+		use crate::core::hash::Hashed;
+		use util::ToHex;
+
+		set_local_chain_type(ChainTypes::Mainnet);
+		assert_eq!(
+			get_checkpoints()[0],
+			(0, genesis_main().hash().to_hex().as_str())
+		);
+
+		set_local_chain_type(ChainTypes::Floonet);
+		assert_eq!(
+			get_checkpoints()[0],
+			(0, genesis_floo().hash().to_hex().as_str())
+		);
+
+		set_local_chain_type(ChainTypes::AutomatedTesting);
+		assert!(get_checkpoints().is_empty());
+	}
+
+	#[test]
+	fn mock_time_and_difficulty() {
+		set_local_chain_type(ChainTypes::AutomatedTesting);
+		assert_eq!(get_mock_time(), None);
+		assert_eq!(get_mock_difficulty(), None);
+
+		set_mock_time(Some(1_700_000_000));
+		set_mock_difficulty(Some(5));
+		assert_eq!(get_mock_time(), Some(1_700_000_000));
+		assert_eq!(get_mock_difficulty(), Some(5));
+
+		// production networks never use the mock values
+		set_local_chain_type(ChainTypes::Mainnet);
+		assert_eq!(get_mock_time(), None);
+		assert_eq!(get_mock_difficulty(), None);
+
+		set_local_chain_type(ChainTypes::AutomatedTesting);
+		set_mock_time(None);
+		set_mock_difficulty(None);
+		assert_eq!(get_mock_time(), None);
+		assert_eq!(get_mock_difficulty(), None);
+	}
 }
 
 /// Checking running status if the server
@@ -663,3 +732,43 @@ pub fn request_server_stop() {
 pub fn get_server_running_controller() -> Arc<AtomicBool> {
 	SERVER_RUNNING.clone()
 }
+
+/// Mock timestamp (seconds) for the new blocks, 0 means not set. Testing chains only.
+static MOCK_TIME: AtomicI64 = AtomicI64::new(0);
+
+/// Mock difficulty for the generated blocks, 0 means not set. Testing chains only.
+static MOCK_DIFFICULTY: AtomicU64 = AtomicU64::new(0);
+
+/// Set the mock time that will be used for the timestamps of the new blocks.
+/// `None` switch back to the system clock.
+pub fn set_mock_time(timestamp: Option<i64>) {
+	MOCK_TIME.store(timestamp.unwrap_or(0), Ordering::SeqCst)
+}
+
+/// Mock time for the new blocks. Always None for production networks.
+pub fn get_mock_time() -> Option<i64> {
+	if is_production_mode() {
+		return None;
+	}
+	match MOCK_TIME.load(Ordering::SeqCst) {
+		0 => None,
+		t => Some(t),
+	}
+}
+
+/// Set the difficulty of the blocks generated on demand, the consensus difficulty is not affected.
+/// `None` switch back to the consensus difficulty adjustment.
+pub fn set_mock_difficulty(difficulty: Option<u64>) {
+	MOCK_DIFFICULTY.store(difficulty.unwrap_or(0), Ordering::SeqCst)
+}
+
+/// Mock difficulty for the generated blocks. Always None for production networks.
+pub fn get_mock_difficulty() -> Option<u64> {
+	if is_production_mode() {
+		return None;
+	}
+	match MOCK_DIFFICULTY.load(Ordering::SeqCst) {
+		0 => None,
+		d => Some(d),
+	}
+}
@@ -538,10 +538,162 @@ pub fn verify_dual_key(
 	verify_completed_sig(&secp, &sig.0, &pubkey, Some(&pubkey), &msg)
 }
 
+/// Adaptor point `T = t*G` of the adaptor secret `t`. Adaptor pre-signatures are locked
+/// to this point, the signature can be completed only with the knowledge of `t`.
+pub fn adaptor_point(secp: &Secp256k1, adaptor_secret: &SecretKey) -> Result<PublicKey, Error> {
+	Ok(PublicKey::from_secret_key(secp, adaptor_secret)?)
+}
+
+// Nonce sum of the completed signature, the public nonces of all signers plus the adaptor point
+fn adaptor_nonce_sum(
+	secp: &Secp256k1,
+	pub_nonce_sum: &PublicKey,
+	adaptor_point: &PublicKey,
+) -> Result<PublicKey, Error> {
+	Ok(PublicKey::from_combination(
+		secp,
+		vec![pub_nonce_sum, adaptor_point],
+	)?)
+}
+
+/// Length of the nonce part of the aggsig signature. The raw signature data is the
+/// x coordinate of the public nonce sum `R` followed by the scalar `s`, both 32 bytes
+/// big endian.
+const SIG_NONCE_LEN: usize = 32;
+
+/// The scalar `s` of the signature, the part after the public nonce sum x coordinate.
+fn signature_scalar(secp: &Secp256k1, sig: &Signature) -> Result<SecretKey, Error> {
+	Ok(SecretKey::from_slice(
+		secp,
+		&sig.to_raw_data()[SIG_NONCE_LEN..],
+	)?)
+}
+
+/// Signature with the same public nonce sum and the scalar `s` replaced.
+fn with_signature_scalar(sig: &Signature, s: &SecretKey) -> Result<Signature, Error> {
+	let mut raw = sig.to_raw_data();
+	raw[SIG_NONCE_LEN..].copy_from_slice(&s.0);
+	Ok(Signature::from_raw_data(&raw)?)
+}
+
+/// Creates the adaptor pre-signature on the kernel message. It is a partial signature
+/// where the adaptor point is added to the nonce sum, so the final signature is valid
+/// only after the adaptor secret is added with
+/// [`complete_adaptor_signature`](fn.complete_adaptor_signature.html).
+///
+/// # Arguments
+///
+/// * `secp` - A Secp256k1 Context initialized for Signing
+/// * `sec_key` - The signer's secret key
+/// * `sec_nonce` - The signer's secret nonce
+/// * `pub_nonce_sum` - The sum of the public nonces of all signers, without the adaptor point
+/// * `adaptor_point` - The adaptor point `T = t*G`
+/// * `pubkey_sum` - The sum of the public keys of all signers (the kernel excess)
+/// * `msg` - The kernel message to sign
+pub fn create_adaptor_signature(
+	secp: &Secp256k1,
+	sec_key: &SecretKey,
+	sec_nonce: &SecretKey,
+	pub_nonce_sum: &PublicKey,
+	adaptor_point: &PublicKey,
+	pubkey_sum: &PublicKey,
+	msg: &secp::Message,
+) -> Result<Signature, Error> {
+	let nonce_sum = adaptor_nonce_sum(secp, pub_nonce_sum, adaptor_point)?;
+	calculate_partial_sig(secp, sec_key, sec_nonce, &nonce_sum, Some(pubkey_sum), msg)
+}
+
+/// Verifies the adaptor pre-signature of the signer with the public key `pubkey`. All
+/// values must be identical to those provided to
+/// [`create_adaptor_signature`](fn.create_adaptor_signature.html). A valid pre-signature
+/// guarantees that whoever completes the signature reveals the adaptor secret.
+pub fn verify_adaptor_signature(
+	secp: &Secp256k1,
+	pre_sig: &Signature,
+	pub_nonce_sum: &PublicKey,
+	adaptor_point: &PublicKey,
+	pubkey: &PublicKey,
+	pubkey_sum: &PublicKey,
+	msg: &secp::Message,
+) -> Result<(), Error> {
+	let nonce_sum = adaptor_nonce_sum(secp, pub_nonce_sum, adaptor_point)?;
+	verify_partial_sig(secp, pre_sig, &nonce_sum, pubkey, Some(pubkey_sum), msg)
+}
+
+/// Completes the adaptor pre-signature with the adaptor secret. `partial_sigs` are the
+/// partial signatures of the other signers (empty for a single signer), created with the
+/// same nonce sum, adaptor point included. Returns the final signature, verified against
+/// `pubkey_sum`.
+pub fn complete_adaptor_signature(
+	secp: &Secp256k1,
+	pre_sig: &Signature,
+	partial_sigs: &[Signature],
+	adaptor_secret: &SecretKey,
+	pub_nonce_sum: &PublicKey,
+	pubkey_sum: &PublicKey,
+	msg: &secp::Message,
+) -> Result<Signature, Error> {
+	let adaptor_point = adaptor_point(secp, adaptor_secret)?;
+	let nonce_sum = adaptor_nonce_sum(secp, pub_nonce_sum, &adaptor_point)?;
+	let mut sigs = vec![pre_sig];
+	sigs.extend(partial_sigs.iter());
+	let sig = add_signatures(secp, sigs, &nonce_sum)?;
+	let s = signature_scalar(secp, &sig)?;
+
+	// The signers' nonces are negated if the nonce sum y coordinate is not a quadratic
+	// residue, the adaptor secret must follow them. Only one of the candidates is valid.
+	let candidates = vec![
+		secp.blind_sum(vec![s.clone(), adaptor_secret.clone()], vec![])?,
+		secp.blind_sum(vec![s], vec![adaptor_secret.clone()])?,
+	];
+	for candidate in candidates {
+		let sig = with_signature_scalar(&sig, &candidate)?;
+		if verify_completed_sig(secp, &sig, pubkey_sum, Some(pubkey_sum), msg).is_ok() {
+			return Ok(sig);
+		}
+	}
+	Err(Error::Signature(
+		"Unable to complete the adaptor signature".to_string(),
+	))
+}
+
+/// Extracts the adaptor secret from the final signature, published for example in the
+/// kernel, and the adaptor pre-signature. `partial_sigs` are the partial signatures of
+/// the other signers, as provided to
+/// [`complete_adaptor_signature`](fn.complete_adaptor_signature.html).
+pub fn extract_adaptor_secret(
+	secp: &Secp256k1,
+	sig: &Signature,
+	pre_sig: &Signature,
+	partial_sigs: &[Signature],
+	pub_nonce_sum: &PublicKey,
+	adaptor_point: &PublicKey,
+) -> Result<SecretKey, Error> {
+	let nonce_sum = adaptor_nonce_sum(secp, pub_nonce_sum, adaptor_point)?;
+	let mut sigs = vec![pre_sig];
+	sigs.extend(partial_sigs.iter());
+	let pre_s = signature_scalar(secp, &add_signatures(secp, sigs, &nonce_sum)?)?;
+	let s = signature_scalar(secp, sig)?;
+
+	let candidates = vec![
+		secp.blind_sum(vec![s.clone()], vec![pre_s.clone()])?,
+		secp.blind_sum(vec![pre_s], vec![s])?,
+	];
+	for candidate in candidates {
+		if PublicKey::from_secret_key(secp, &candidate)? == *adaptor_point {
+			return Ok(candidate);
+		}
+	}
+	Err(Error::Signature(
+		"Signature doesn't reveal the adaptor secret".to_string(),
+	))
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
 	use rand::{thread_rng, Rng};
+	use util::ToHex;
 
 	#[test]
 	fn batch_signature() {
@@ -559,4 +711,187 @@ mod test {
 		let pk2 = PublicKey::from_secret_key(&secp, &sk2).unwrap();
 		verify_dual_key(&secp, &msg, &batch_sig, &pk1, &pk2).unwrap();
 	}
+
+	fn test_key(secp: &Secp256k1, byte: u8) -> SecretKey {
+		SecretKey::from_slice(secp, &[byte; 32]).unwrap()
+	}
+
+	fn test_msg(byte: u8) -> Message {
+		Message::from_slice(&[byte; 32]).unwrap()
+	}
+
+	// Single signer known answer vectors: (secret key, nonce, adaptor secret, message)
+	// bytes, the expected pre-signature and the completed signature. The first vector
+	// has the nonce sum with the quadratic residue y, the others need the negated nonces.
+	const ADAPTOR_VECTORS: [(u8, u8, u8, u8, &str, &str); 4] = [
+		(
+			1,
+			2,
+			3,
+			4,
+			"62c0a046dacce86ddd0343c6d3c7c79c2208ba0d9c9cf24a6d046d21d21f90f7\
+			 f43d12cc7eeff9e6d1ee4abdd0cc64f7e265ef1f1ef7522ef99476a5c99b7b89",
+			"62c0a046dacce86ddd0343c6d3c7c79c2208ba0d9c9cf24a6d046d21d21f90f7\
+			 f74015cf81f2fce9d4f14dc0d3cf67fae568f22221fa5531fc9779a8cc9e7e8c",
+		),
+		(
+			5,
+			6,
+			7,
+			8,
+			"2f1b310f4c065331bc0d79ba4661bb9822d67d7c4a1b0a1892e1fd0cd23aa68d\
+			 c6548838fc3cf3ea2cb5f738f8a5eaa79c3ce9578c3216b97e57f0820bf9e579",
+			"2f1b310f4c065331bc0d79ba4661bb9822d67d7c4a1b0a1892e1fd0cd23aa68d\
+			 bf4d8131f535ece325aef031f19ee3a09535e250852b0fb27750e97b04f2de72",
+		),
+		(
+			0x11,
+			0x22,
+			0x33,
+			0x44,
+			"9ac20335eb38768d2052be1dbbc3c8f6178407458e51e6b4ad22f1d91758895b\
+			 ba246d742401976f9e3961ec2c6bd8b0598c66d832cb4218b221f3e66593d2b6",
+			"9ac20335eb38768d2052be1dbbc3c8f6178407458e51e6b4ad22f1d91758895b\
+			 86f13a40f0ce643c6b062eb8f938a57d265933a4ff980ee57eeec0b332609f83",
+		),
+		(
+			0x7f,
+			0x3c,
+			0x5a,
+			0x01,
+			"fb3447ea16574cbec8b8d91bec35921f5096d4986f47089d68e27d923c3b44b7\
+			 a44a69ffd5026c4436db79decdc95a5a2dcea3bbcdf51fd1154cb1591eaaba2b",
+			"fb3447ea16574cbec8b8d91bec35921f5096d4986f47089d68e27d923c3b44b7\
+			 49f00fa57aa811e9dc811f84736effffd3744961739ac576baf256fec4505fd1",
+		),
+	];
+
+	#[test]
+	fn adaptor_signature_single() {
+		let secp = Secp256k1::with_caps(secp::ContextFlag::Full);
+		for (key, nonce, secret, msg, pre_sig_hex, sig_hex) in ADAPTOR_VECTORS.iter() {
+			let sk = test_key(&secp, *key);
+			let nonce = test_key(&secp, *nonce);
+			let t = test_key(&secp, *secret);
+			let msg = test_msg(*msg);
+			let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+			let pub_nonce = PublicKey::from_secret_key(&secp, &nonce).unwrap();
+			let t_point = adaptor_point(&secp, &t).unwrap();
+
+			let pre_sig =
+				create_adaptor_signature(&secp, &sk, &nonce, &pub_nonce, &t_point, &pk, &msg)
+					.unwrap();
+			assert_eq!(pre_sig.to_raw_data().to_hex(), *pre_sig_hex);
+			verify_adaptor_signature(&secp, &pre_sig, &pub_nonce, &t_point, &pk, &pk, &msg)
+				.unwrap();
+			// pre-signature is not a valid signature
+			assert!(verify_completed_sig(&secp, &pre_sig, &pk, Some(&pk), &msg).is_err());
+			// locked to the adaptor point
+			let other_point = adaptor_point(&secp, &test_key(&secp, 0x55)).unwrap();
+			assert!(verify_adaptor_signature(
+				&secp,
+				&pre_sig,
+				&pub_nonce,
+				&other_point,
+				&pk,
+				&pk,
+				&msg
+			)
+			.is_err());
+
+			let sig = complete_adaptor_signature(&secp, &pre_sig, &[], &t, &pub_nonce, &pk, &msg)
+				.unwrap();
+			assert_eq!(sig.to_raw_data().to_hex(), *sig_hex);
+			verify_completed_sig(&secp, &sig, &pk, Some(&pk), &msg).unwrap();
+			assert!(complete_adaptor_signature(
+				&secp,
+				&pre_sig,
+				&[],
+				&test_key(&secp, 0x55),
+				&pub_nonce,
+				&pk,
+				&msg
+			)
+			.is_err());
+
+			let extracted =
+				extract_adaptor_secret(&secp, &sig, &pre_sig, &[], &pub_nonce, &t_point).unwrap();
+			assert_eq!(extracted, t);
+			assert!(
+				extract_adaptor_secret(&secp, &sig, &pre_sig, &[], &pub_nonce, &other_point)
+					.is_err()
+			);
+		}
+	}
+
+	#[test]
+	fn adaptor_signature_two_party() {
+		let secp = Secp256k1::with_caps(secp::ContextFlag::Full);
+		let msg = test_msg(9);
+		let sk1 = SecretKey::new(&secp, &mut thread_rng());
+		let sk2 = SecretKey::new(&secp, &mut thread_rng());
+		let nonce1 = create_secnonce(&secp).unwrap();
+		let nonce2 = create_secnonce(&secp).unwrap();
+		let t = SecretKey::new(&secp, &mut thread_rng());
+		let t_point = adaptor_point(&secp, &t).unwrap();
+
+		let pk1 = PublicKey::from_secret_key(&secp, &sk1).unwrap();
+		let pk2 = PublicKey::from_secret_key(&secp, &sk2).unwrap();
+		let pubkey_sum = PublicKey::from_combination(&secp, vec![&pk1, &pk2]).unwrap();
+		let pub_nonce1 = PublicKey::from_secret_key(&secp, &nonce1).unwrap();
+		let pub_nonce2 = PublicKey::from_secret_key(&secp, &nonce2).unwrap();
+		let pub_nonce_sum =
+			PublicKey::from_combination(&secp, vec![&pub_nonce1, &pub_nonce2]).unwrap();
+		let nonce_sum = adaptor_nonce_sum(&secp, &pub_nonce_sum, &t_point).unwrap();
+
+		// first party signs normally with the adaptor point included in the nonce sum
+		let sig1 = calculate_partial_sig(&secp, &sk1, &nonce1, &nonce_sum, Some(&pubkey_sum), &msg)
+			.unwrap();
+		verify_partial_sig(&secp, &sig1, &nonce_sum, &pk1, Some(&pubkey_sum), &msg).unwrap();
+
+		// second party creates the pre-signature
+		let pre_sig = create_adaptor_signature(
+			&secp,
+			&sk2,
+			&nonce2,
+			&pub_nonce_sum,
+			&t_point,
+			&pubkey_sum,
+			&msg,
+		)
+		.unwrap();
+		verify_adaptor_signature(
+			&secp,
+			&pre_sig,
+			&pub_nonce_sum,
+			&t_point,
+			&pk2,
+			&pubkey_sum,
+			&msg,
+		)
+		.unwrap();
+
+		// without the adaptor secret the partial signatures don't make a valid signature
+		let incomplete = add_signatures(&secp, vec![&sig1, &pre_sig], &nonce_sum).unwrap();
+		assert!(
+			verify_completed_sig(&secp, &incomplete, &pubkey_sum, Some(&pubkey_sum), &msg).is_err()
+		);
+
+		let sig = complete_adaptor_signature(
+			&secp,
+			&pre_sig,
+			&[sig1.clone()],
+			&t,
+			&pub_nonce_sum,
+			&pubkey_sum,
+			&msg,
+		)
+		.unwrap();
+		verify_completed_sig(&secp, &sig, &pubkey_sum, Some(&pubkey_sum), &msg).unwrap();
+
+		let extracted =
+			extract_adaptor_secret(&secp, &sig, &pre_sig, &[sig1], &pub_nonce_sum, &t_point)
+				.unwrap();
+		assert_eq!(extracted, t);
+	}
 }
@@ -0,0 +1,329 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Building blocks for the n-of-n shared outputs. Every party holds a share of the
+//! output blinding factor, so the output can be created and spent only if all
+//! parties cooperate.
+//!
+//! Public shares of the parties (kernel excess shares and output blind shares) are summed,
+//! so a party that publishes its share last could pick it as `own - sum(others)` and take
+//! the control of the sum (rogue key attack). To prevent that every party publishes its
+//! public share together with [`share_proof`], the proof that it knows the secret of
+//! the share. Shares without a valid proof, checked with [`verify_share_proof`],
+//! must be rejected.
+//!
+//! Kernel signing is done in rounds:
+//! 1. Every party creates a [`PartyNonce`] and publishes the commitment to its public nonce.
+//! 2. When all commitments are received, parties reveal the public nonces and check them
+//!    with [`verify_nonce_reveal`]. Nobody can choose a nonce depending on the others.
+//! 3. Every party publishes its public excess share and the partial signature, built with
+//!    [`calculate_partial_kernel_sig`]. Signatures are combined with [`finalize_kernel`].
+//!
+//! The range proof for the shared output is built with [`MultiPartyRangeProof`], where
+//! parties exchange T1/T2 public keys in the first round and tau_x shares in the second.
+
+use crate::core::hash::Hash;
+use crate::core::TxKernel;
+use crate::libtx::aggsig;
+use crate::libtx::error::Error;
+use blake2::blake2b::blake2b;
+use rand::thread_rng;
+use util::secp::key::{PublicKey, SecretKey};
+use util::secp::pedersen::{Commitment, RangeProof};
+use util::secp::{Message, Secp256k1, Signature};
+
+/// Secret and public nonce of the party for the kernel signature.
+pub struct PartyNonce {
+	sec_nonce: SecretKey,
+	pub_nonce: PublicKey,
+}
+
+impl PartyNonce {
+	/// New random nonce
+	pub fn new(secp: &Secp256k1) -> Result<PartyNonce, Error> {
+		let sec_nonce = aggsig::create_secnonce(secp)?;
+		let pub_nonce = PublicKey::from_secret_key(secp, &sec_nonce)?;
+		Ok(PartyNonce {
+			sec_nonce,
+			pub_nonce,
+		})
+	}
+
+	/// Public nonce, revealed in the second round
+	pub fn pub_nonce(&self) -> &PublicKey {
+		&self.pub_nonce
+	}
+
+	/// Commitment to the public nonce, published in the first round
+	pub fn commitment(&self, secp: &Secp256k1) -> Hash {
+		nonce_commitment(secp, &self.pub_nonce)
+	}
+}
+
+/// Commitment to the public nonce
+pub fn nonce_commitment(secp: &Secp256k1, pub_nonce: &PublicKey) -> Hash {
+	let bytes = pub_nonce.serialize_vec(secp, true);
+	Hash::from_vec(blake2b(32, &[], &bytes[..]).as_bytes())
+}
+
+/// Check that the revealed public nonce matches the commitment published in the first round
+pub fn verify_nonce_reveal(
+	secp: &Secp256k1,
+	commitment: &Hash,
+	pub_nonce: &PublicKey,
+) -> Result<(), Error> {
+	if nonce_commitment(secp, pub_nonce) != *commitment {
+		return Err(Error::Signature(
+			"Revealed nonce doesn't match the nonce commitment".to_string(),
+		));
+	}
+	Ok(())
+}
+
+/// Sum of the public keys (nonces, T1/T2 shares) of all parties
+pub fn sum_public_keys(secp: &Secp256k1, keys: &[PublicKey]) -> Result<PublicKey, Error> {
+	let keys: Vec<&PublicKey> = keys.iter().collect();
+	Ok(PublicKey::from_combination(secp, keys)?)
+}
+
+/// Public share of the party in the kernel excess (or in the shared output blinding factor),
+/// as a commitment to zero value.
+pub fn public_excess_share(
+	secp: &Secp256k1,
+	excess_share: &SecretKey,
+) -> Result<Commitment, Error> {
+	Ok(secp.commit(0, excess_share.clone())?)
+}
+
+/// Proof that the party knows the secret of its public share, signature of the share
+/// with the share secret.
+pub fn share_proof(secp: &Secp256k1, share: &SecretKey) -> Result<Signature, Error> {
+	let public_share = public_excess_share(secp, share)?;
+	let pubkey = public_share.to_pubkey(secp)?;
+	let msg = share_proof_msg(&public_share)?;
+	aggsig::sign_single(secp, &msg, share, None, Some(&pubkey))
+}
+
+/// Verify the proof of another party that it knows the secret of its public share
+pub fn verify_share_proof(
+	secp: &Secp256k1,
+	public_share: &Commitment,
+	proof: &Signature,
+) -> Result<(), Error> {
+	let msg = share_proof_msg(public_share)?;
+	aggsig::verify_single_from_commit(secp, proof, &msg, public_share)
+}
+
+// Message of the share proof, hash of the public share with the domain prefix
+fn share_proof_msg(public_share: &Commitment) -> Result<Message, Error> {
+	let mut data = b"mwc_multisig_share".to_vec();
+	data.extend_from_slice(&public_share.0);
+	Ok(Message::from_slice(blake2b(32, &[], &data).as_bytes())?)
+}
+
+/// Kernel excess, sum of the public excess shares of all parties
+pub fn sum_excess_shares(secp: &Secp256k1, shares: &[Commitment]) -> Result<Commitment, Error> {
+	Ok(secp.commit_sum(shares.to_vec(), vec![])?)
+}
+
+/// Commitment of the shared output with the total `value`, built from the public blind
+/// shares of all parties
+pub fn shared_output_commit(
+	secp: &Secp256k1,
+	value: u64,
+	public_blind_shares: &[Commitment],
+) -> Result<Commitment, Error> {
+	let mut commits = public_blind_shares.to_vec();
+	commits.push(secp.commit_value(value)?);
+	Ok(secp.commit_sum(commits, vec![])?)
+}
+
+/// Partial kernel signature of the party. `excess_share` is the party share of the kernel
+/// excess, `nonce_sum` is the sum of the public nonces of all parties and `excess` is the
+/// kernel excess, the sum of all public excess shares.
+pub fn calculate_partial_kernel_sig(
+	secp: &Secp256k1,
+	kernel: &TxKernel,
+	excess_share: &SecretKey,
+	nonce: &PartyNonce,
+	nonce_sum: &PublicKey,
+	excess: &Commitment,
+) -> Result<Signature, Error> {
+	let msg = kernel.msg_to_sign()?;
+	let pubkey_sum = excess.to_pubkey(secp)?;
+	aggsig::calculate_partial_sig(
+		secp,
+		excess_share,
+		&nonce.sec_nonce,
+		nonce_sum,
+		Some(&pubkey_sum),
+		&msg,
+	)
+}
+
+/// Verify the partial kernel signature of another party against its public excess share.
+pub fn verify_partial_kernel_sig(
+	secp: &Secp256k1,
+	kernel: &TxKernel,
+	sig: &Signature,
+	public_excess_share: &Commitment,
+	nonce_sum: &PublicKey,
+	excess: &Commitment,
+) -> Result<(), Error> {
+	let msg = kernel.msg_to_sign()?;
+	let pubkey = public_excess_share.to_pubkey(secp)?;
+	let pubkey_sum = excess.to_pubkey(secp)?;
+	aggsig::verify_partial_sig(secp, sig, nonce_sum, &pubkey, Some(&pubkey_sum), &msg)
+}
+
+/// Combine the partial signatures of all parties into the kernel signature. Sets the kernel
+/// excess and signature and verifies the resulting kernel.
+pub fn finalize_kernel(
+	secp: &Secp256k1,
+	kernel: &mut TxKernel,
+	partial_sigs: &[Signature],
+	nonce_sum: &PublicKey,
+	excess: &Commitment,
+) -> Result<(), Error> {
+	let sigs: Vec<&Signature> = partial_sigs.iter().collect();
+	let sig = aggsig::add_signatures(secp, sigs, nonce_sum)?;
+	kernel.excess = *excess;
+	kernel.excess_sig = sig;
+	kernel.verify(secp)?;
+	Ok(())
+}
+
+/// Party state of the round based bulletproof build for the shared output.
+///
+/// All parties must use the same `common_nonce`. It is a secret shared between the parties,
+/// anybody who knows it can rewind the proof.
+/// 1. Every party calls `round1` and publishes T1 and T2.
+/// 2. With the sums of T1 and T2 every party calls `round2` and publishes its tau_x share.
+/// 3. Any party calls `finalize` with the sum of the tau_x shares to get the proof.
+pub struct MultiPartyRangeProof {
+	value: u64,
+	blind_share: SecretKey,
+	common_nonce: SecretKey,
+	private_nonce: SecretKey,
+	commit: Commitment,
+	extra_data: Option<Vec<u8>>,
+}
+
+impl MultiPartyRangeProof {
+	/// Start the proof build for the shared output `commit` with the total `value`.
+	/// `blind_share` is the party share of the output blinding factor.
+	pub fn new(
+		secp: &Secp256k1,
+		value: u64,
+		blind_share: SecretKey,
+		common_nonce: SecretKey,
+		commit: Commitment,
+		extra_data: Option<Vec<u8>>,
+	) -> MultiPartyRangeProof {
+		MultiPartyRangeProof {
+			value,
+			blind_share,
+			common_nonce,
+			private_nonce: SecretKey::new(secp, &mut thread_rng()),
+			commit,
+			extra_data,
+		}
+	}
+
+	/// First round, returns the party T1 and T2 shares
+	pub fn round1(&self, secp: &Secp256k1) -> Result<(PublicKey, PublicKey), Error> {
+		let mut t_one = self.placeholder_key(secp)?;
+		let mut t_two = self.placeholder_key(secp)?;
+		secp.bullet_proof_multisig(
+			self.value,
+			self.blind_share.clone(),
+			self.common_nonce.clone(),
+			self.extra_data.clone(),
+			None,
+			None,
+			Some(&mut t_one),
+			Some(&mut t_two),
+			vec![self.commit],
+			Some(&self.private_nonce),
+			1,
+		)
+		.ok_or_else(|| Error::RangeProof("Multi-party proof round 1 is failed".to_string()))?;
+		Ok((t_one, t_two))
+	}
+
+	/// Second round, returns the party tau_x share. `t_one_sum` and `t_two_sum` are
+	/// the sums of the T1 and T2 shares of all parties.
+	pub fn round2(
+		&self,
+		secp: &Secp256k1,
+		t_one_sum: &PublicKey,
+		t_two_sum: &PublicKey,
+	) -> Result<SecretKey, Error> {
+		let mut tau_x = SecretKey::new(secp, &mut thread_rng());
+		let mut t_one = t_one_sum.clone();
+		let mut t_two = t_two_sum.clone();
+		secp.bullet_proof_multisig(
+			self.value,
+			self.blind_share.clone(),
+			self.common_nonce.clone(),
+			self.extra_data.clone(),
+			None,
+			Some(&mut tau_x),
+			Some(&mut t_one),
+			Some(&mut t_two),
+			vec![self.commit],
+			Some(&self.private_nonce),
+			2,
+		)
+		.ok_or_else(|| Error::RangeProof("Multi-party proof round 2 is failed".to_string()))?;
+		Ok(tau_x)
+	}
+
+	/// Build the proof from the sums of all T1, T2 and tau_x shares. The proof is verified
+	/// against the shared output commitment.
+	pub fn finalize(
+		&self,
+		secp: &Secp256k1,
+		t_one_sum: &PublicKey,
+		t_two_sum: &PublicKey,
+		tau_x_shares: &[SecretKey],
+	) -> Result<RangeProof, Error> {
+		let mut tau_x = secp.blind_sum(tau_x_shares.to_vec(), vec![])?;
+		let mut t_one = t_one_sum.clone();
+		let mut t_two = t_two_sum.clone();
+		let proof = secp
+			.bullet_proof_multisig(
+				self.value,
+				self.blind_share.clone(),
+				self.common_nonce.clone(),
+				self.extra_data.clone(),
+				None,
+				Some(&mut tau_x),
+				Some(&mut t_one),
+				Some(&mut t_two),
+				vec![self.commit],
+				Some(&self.private_nonce),
+				0,
+			)
+			.ok_or_else(|| Error::RangeProof("Unable to build multi-party proof".to_string()))?;
+		secp.verify_bullet_proof(self.commit, proof, self.extra_data.clone())
+			.map_err(|e| Error::RangeProof(format!("Invalid multi-party proof, {}", e)))?;
+		Ok(proof)
+	}
+
+	// Output parameter for the secp call, value is overwritten
+	fn placeholder_key(&self, secp: &Secp256k1) -> Result<PublicKey, Error> {
+		Ok(PublicKey::from_secret_key(secp, &self.private_nonce)?)
+	}
+}
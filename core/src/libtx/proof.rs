@@ -20,7 +20,7 @@ use blake2::blake2b::blake2b;
 use keychain::extkey_bip32::BIP32MwcHasher;
 use keychain::{Identifier, Keychain, SwitchCommitmentType, ViewKey};
 use std::convert::TryFrom;
-use util::secp::key::SecretKey;
+use util::secp::key::{PublicKey, SecretKey};
 use util::secp::pedersen::{Commitment, ProofMessage, RangeProof};
 use util::secp::{self, Secp256k1};
 use zeroize::Zeroize;
@@ -444,6 +444,85 @@ impl ProofBuild for ViewKey {
 	}
 }
 
+/// Proof builder that knows only the rewind hash of the wallet (blake2b of the public root key).
+/// It can't build proofs, but it can recognise and rewind the outputs for any derivation path
+/// and switch commitment type. The commitment can't be recalculated from the public data,
+/// the bulletproof rewind is checking the recovered value and blinding factor against the
+/// commitment instead. Used by the node to scan the UTXO set for the view-only wallets.
+pub struct RewindHashBuilder {
+	rewind_hash: Vec<u8>,
+}
+
+impl RewindHashBuilder {
+	/// Creates a new builder from the rewind hash
+	pub fn new(rewind_hash: Vec<u8>) -> Self {
+		RewindHashBuilder { rewind_hash }
+	}
+
+	/// Creates a new builder from the public root key of the wallet
+	pub fn from_public_root_key(secp: &Secp256k1, public_root_key: PublicKey) -> Self {
+		Self::new(ViewKey::rewind_hash(secp, public_root_key))
+	}
+
+	/// Rewind hash of the wallet
+	pub fn rewind_hash(&self) -> &Vec<u8> {
+		&self.rewind_hash
+	}
+}
+
+impl ProofBuild for RewindHashBuilder {
+	fn rewind_nonce(&self, secp: &Secp256k1, commit: &Commitment) -> Result<SecretKey, Error> {
+		let res = blake2b(32, &commit.0, &self.rewind_hash);
+		SecretKey::from_slice(secp, res.as_bytes()).map_err(|e| {
+			Error::RangeProof(format!(
+				"Unable to rewind nonce for commit {:?}, {}",
+				commit, e
+			))
+		})
+	}
+
+	fn private_nonce(&self, _secp: &Secp256k1, _commit: &Commitment) -> Result<SecretKey, Error> {
+		Err(Error::Other(
+			"Rewind hash builder can't create the proofs".to_string(),
+		))
+	}
+
+	fn proof_message(
+		&self,
+		_secp: &Secp256k1,
+		_id: &Identifier,
+		_switch: SwitchCommitmentType,
+	) -> Result<ProofMessage, Error> {
+		Err(Error::Other(
+			"Rewind hash builder can't create the proofs".to_string(),
+		))
+	}
+
+	fn check_output(
+		&self,
+		_secp: &Secp256k1,
+		_commit: &Commitment,
+		_amount: u64,
+		message: ProofMessage,
+	) -> Result<Option<(Identifier, SwitchCommitmentType)>, Error> {
+		if message.len() != 20 {
+			return Ok(None);
+		}
+		let msg = message.as_bytes();
+		let exp: [u8; 2] = [0; 2];
+		if msg[..2] != exp {
+			return Ok(None);
+		}
+		let switch = match SwitchCommitmentType::try_from(msg[2]) {
+			Ok(s) => s,
+			Err(_) => return Ok(None),
+		};
+		let depth = u8::min(msg[3], 4);
+		let id = Identifier::from_serialized_path(depth, &msg[4..]);
+		Ok(Some((id, switch)))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -509,6 +588,41 @@ mod tests {
 		assert_ne!(commit_a, commit_b);
 	}
 
+	#[test]
+	fn rewind_hash_builder() {
+		let rng = &mut thread_rng();
+		let keychain = ExtKeychain::from_random_seed(false).unwrap();
+		let builder = ProofBuilder::new(&keychain);
+		let rewind_builder =
+			RewindHashBuilder::from_public_root_key(keychain.secp(), keychain.public_root_key());
+		assert_eq!(builder.rewind_hash, rewind_builder.rewind_hash);
+
+		let amount = rng.gen();
+		let id = ExtKeychain::derive_key_id(3, rng.gen(), rng.gen(), rng.gen(), 0);
+		for switch in &[SwitchCommitmentType::Regular, SwitchCommitmentType::None] {
+			let commit = keychain.commit(amount, &id, *switch).unwrap();
+			let proof = create(&keychain, &builder, amount, &id, *switch, commit, None).unwrap();
+			let rewind = rewind(keychain.secp(), &rewind_builder, commit, None, proof).unwrap();
+			assert!(rewind.is_some());
+			let (r_amount, r_id, r_switch) = rewind.unwrap();
+			assert_eq!(r_amount, amount);
+			assert_eq!(r_id, id);
+			assert_eq!(r_switch, *switch);
+		}
+
+		// Outputs of other wallets are not recognised
+		let other_keychain = ExtKeychain::from_random_seed(false).unwrap();
+		let other_builder = RewindHashBuilder::from_public_root_key(
+			other_keychain.secp(),
+			other_keychain.public_root_key(),
+		);
+		let switch = SwitchCommitmentType::Regular;
+		let commit = keychain.commit(amount, &id, switch).unwrap();
+		let proof = create(&keychain, &builder, amount, &id, switch, commit, None).unwrap();
+		let rewind = rewind(keychain.secp(), &other_builder, commit, None, proof).unwrap();
+		assert!(rewind.is_none());
+	}
+
 	#[test]
 	fn view_key() {
 		// TODO
@@ -26,8 +26,15 @@
 use crate::global;
 use crate::pow::common::CuckooParams;
 use crate::pow::error::Error;
-use crate::pow::siphash::siphash_block;
+use crate::pow::siphash::{siphash_block, SipHash24};
 use crate::pow::{PoWContext, Proof};
+use std::collections::HashMap;
+
+/// Longest path we follow in the cuckoo forest while looking for cycles
+const MAX_PATH_LEN: usize = 8192;
+
+/// Number of the edges that share one block of siphash values
+const SIPHASH_BLOCK_SIZE: usize = 64;
 
 /// Instantiate a new CuckaroozContext as a PowContext. Note that this can't
 /// be moved in the PoWContext trait as this particular trait needs to be
@@ -37,7 +44,8 @@ pub fn new_cuckarooz_ctx(edge_bits: u8, proof_size: usize) -> Result<Box<dyn PoW
 	Ok(Box::new(CuckaroozContext { params }))
 }
 
-/// Cuckarooz cycle context. Only includes the verifier for now.
+/// Cuckarooz cycle context. Includes the verifier and a simple CPU solver,
+/// usable only with small edge bits (tests and private networks).
 pub struct CuckaroozContext {
 	params: CuckooParams,
 }
@@ -53,7 +61,9 @@ impl PoWContext for CuckaroozContext {
 	}
 
 	fn find_cycles(&mut self) -> Result<Vec<Proof>, Error> {
-		unimplemented!()
+		let mut edges = self.edges();
+		self.trim(&mut edges);
+		self.find_cycles_in(&edges)
 	}
 
 	fn verify(&self, proof: &Proof) -> Result<(), Error> {
@@ -140,6 +150,149 @@ impl PoWContext for CuckaroozContext {
 	}
 }
 
+impl CuckaroozContext {
+	/// All the graph edges (nonce, u, v). Same as siphash_block for every nonce,
+	/// but each block of hashes is computed only once.
+	fn edges(&self) -> Vec<(u64, u64, u64)> {
+		let mut edges = Vec::with_capacity(self.params.num_edges as usize);
+		let mut hashes = [0u64; SIPHASH_BLOCK_SIZE];
+		for block in (0..self.params.num_edges).step_by(SIPHASH_BLOCK_SIZE) {
+			let mut siphash = SipHash24::new(&self.params.siphash_keys);
+			for (i, hash) in hashes.iter_mut().enumerate() {
+				// 21 is standard siphash rotation constant
+				siphash.hash(block + i as u64, 21);
+				*hash = siphash.digest();
+			}
+			// every hash is xored with all the later hashes of its block
+			for i in (0..SIPHASH_BLOCK_SIZE - 1).rev() {
+				hashes[i] ^= hashes[i + 1];
+			}
+			for (i, edge) in hashes.iter().enumerate() {
+				edges.push((
+					block + i as u64,
+					edge & self.params.node_mask,
+					(edge >> 32) & self.params.node_mask,
+				));
+			}
+		}
+		edges
+	}
+
+	/// Remove the edges with a lone endpoint, they can't be a part of any cycle.
+	fn trim(&self, edges: &mut Vec<(u64, u64, u64)>) {
+		let mut degrees = vec![0u8; self.params.node_mask as usize + 1];
+		loop {
+			for d in degrees.iter_mut() {
+				*d = 0;
+			}
+			for (_, u, v) in edges.iter() {
+				degrees[*u as usize] = degrees[*u as usize].saturating_add(1);
+				degrees[*v as usize] = degrees[*v as usize].saturating_add(1);
+			}
+			let len = edges.len();
+			edges.retain(|(_, u, v)| degrees[*u as usize] > 1 && degrees[*v as usize] > 1);
+			if edges.len() == len {
+				break;
+			}
+		}
+	}
+
+	/// Walk from the node to the root of its tree in the cuckoo forest.
+	fn path(cuckoo: &HashMap<u64, u64>, mut node: u64) -> Result<Vec<u64>, Error> {
+		let mut path = vec![node];
+		while let Some(next) = cuckoo.get(&node) {
+			node = *next;
+			path.push(node);
+			if path.len() > MAX_PATH_LEN {
+				return Err(Error::Path);
+			}
+		}
+		Ok(path)
+	}
+
+	/// Finds the cycles of proof_size length for the trimmed edges (nonce, u, v)
+	fn find_cycles_in(&self, edges: &[(u64, u64, u64)]) -> Result<Vec<Proof>, Error> {
+		let mut cuckoo: HashMap<u64, u64> = HashMap::new();
+		let mut sols = vec![];
+		for &(_, u, v) in edges {
+			if u == v {
+				continue;
+			}
+			let us = Self::path(&cuckoo, u)?;
+			let vs = Self::path(&cuckoo, v)?;
+			if us.last() == vs.last() {
+				// Both ends are in the same tree, walk back from the root to the join point
+				let (mut nu, mut nv) = (us.len() - 1, vs.len() - 1);
+				while nu > 0 && nv > 0 && us[nu - 1] == vs[nv - 1] {
+					nu -= 1;
+					nv -= 1;
+				}
+				if nu + nv + 1 == self.params.proof_size {
+					if let Some(proof) = self.recover_proof(edges, (u, v), &us[..=nu], &vs[..=nv]) {
+						sols.push(proof);
+					}
+				}
+				continue;
+			}
+			// Join the trees, reversing the shorter path
+			if us.len() < vs.len() {
+				for i in (0..us.len() - 1).rev() {
+					cuckoo.insert(us[i + 1], us[i]);
+				}
+				cuckoo.insert(u, v);
+			} else {
+				for i in (0..vs.len() - 1).rev() {
+					cuckoo.insert(vs[i + 1], vs[i]);
+				}
+				cuckoo.insert(v, u);
+			}
+		}
+		if sols.is_empty() {
+			Err(Error::NoSolution)
+		} else {
+			Ok(sols)
+		}
+	}
+
+	/// Map the cycle node pairs back into the edge nonces
+	fn recover_proof(
+		&self,
+		edges: &[(u64, u64, u64)],
+		uv: (u64, u64),
+		us: &[u64],
+		vs: &[u64],
+	) -> Option<Proof> {
+		let key = |a: u64, b: u64| if a < b { (a, b) } else { (b, a) };
+		let mut cycle: HashMap<(u64, u64), usize> = HashMap::new();
+		*cycle.entry(key(uv.0, uv.1)).or_insert(0) += 1;
+		for path in &[us, vs] {
+			for w in path.windows(2) {
+				*cycle.entry(key(w[0], w[1])).or_insert(0) += 1;
+			}
+		}
+
+		let mut nonces = Vec::with_capacity(self.params.proof_size);
+		for &(n, u, v) in edges {
+			if let Some(cnt) = cycle.get_mut(&key(u, v)) {
+				if *cnt > 0 {
+					*cnt -= 1;
+					nonces.push(n);
+				}
+			}
+		}
+		if nonces.len() != self.params.proof_size {
+			return None;
+		}
+
+		let mut proof = Proof::new(nonces);
+		proof.edge_bits = (self.params.edge_mask + 1).trailing_zeros() as u8;
+		match self.verify(&proof) {
+			Ok(_) => Some(proof),
+			Err(_) => None,
+		}
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -192,6 +345,40 @@ mod test {
 		assert!(ctx29.verify(&Proof::zero(42)).is_err());
 	}
 
+	#[test]
+	fn cuckarooz19_solve() {
+		global::set_local_chain_type(global::ChainTypes::Mainnet);
+		let mut ctx = new_impl(19, 42);
+		ctx.params.siphash_keys = V1_19_HASH.clone();
+		let proofs = ctx.find_cycles().unwrap();
+		assert_eq!(proofs.len(), 1);
+		assert_eq!(proofs[0].nonces, V1_19_SOL.to_vec());
+		assert_eq!(proofs[0].edge_bits, 19);
+	}
+
+	#[test]
+	fn cuckarooz_solve() {
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		let edge_bits = global::min_edge_bits();
+		let proof_size = global::proofsize();
+		let mut ctx = new_impl(edge_bits, proof_size);
+		let header = [0u8; 80].to_vec();
+
+		// empty header, first nonce with a solution
+		for nonce in 0..4 {
+			ctx.set_header_nonce(header.clone(), Some(nonce), true)
+				.unwrap();
+			assert!(ctx.find_cycles().is_err());
+		}
+		ctx.set_header_nonce(header, Some(4), true).unwrap();
+		let proofs = ctx.find_cycles().unwrap();
+		assert_eq!(
+			proofs[0].nonces,
+			vec![301, 377, 464, 606, 622, 759, 763, 876]
+		);
+		assert!(ctx.verify(&proofs[0]).is_ok());
+	}
+
 	fn new_impl(edge_bits: u8, proof_size: usize) -> CuckaroozContext {
 		let params = CuckooParams::new(edge_bits, edge_bits + 1, proof_size).unwrap();
 		CuckaroozContext { params }
@@ -177,6 +177,28 @@ fn next_target_adjustment() {
 	);
 }
 
+/// Mock difficulty is for the generated blocks only, the consensus adjustment ignores it
+#[test]
+fn next_difficulty_ignores_mock_difficulty() {
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	let mut hi = HeaderDifficultyInfo::from_diff_scaling(
+		Difficulty::from_num(10000),
+		AR_SCALE_DAMP_FACTOR as u32,
+	);
+	hi.is_secondary = false;
+	let just_enough = DIFFICULTY_ADJUST_WINDOW + 1;
+	let mut cache_values = VecDeque::new();
+
+	global::set_mock_difficulty(Some(7));
+	let next = next_difficulty(
+		1,
+		repeat(BLOCK_TIME_SEC, hi, just_enough, None),
+		&mut cache_values,
+	);
+	global::set_mock_difficulty(None);
+	assert_eq!(next.difficulty, Difficulty::from_num(10000));
+}
+
 // Builds an iterator for next difficulty calculation with the provided
 // constant time interval, difficulty and total length.
 fn repeat(
@@ -15,11 +15,13 @@
 
 mod common;
 
-use self::core::core::merkle_proof::MerkleProof;
-use self::core::core::pmmr::{ReadablePMMR, VecBackend, PMMR};
+use self::core::core::merkle_proof::{verify_merkle_proof, MerkleProof, MerkleProofError};
+use self::core::core::pmmr::{ReadablePMMR, RewindablePMMR, VecBackend, PMMR};
+use self::core::core::{BlockHeader, OutputFeatures, OutputIdentifier};
 use self::core::ser::{self, PMMRIndexHashable};
 use crate::common::TestElem;
 use mwc_core as core;
+use util::secp::pedersen::Commitment;
 
 #[test]
 fn empty_merkle_proof() {
@@ -188,3 +190,52 @@ fn pmmr_merkle_proof() {
 	assert_eq!(proof.path, vec![pos_9, pos_6]);
 	assert!(proof.verify(pmmr.root().unwrap(), &elems[6], 10).is_ok());
 }
+
+#[test]
+fn verify_output_merkle_proof_historical_header() {
+	let outputs: Vec<OutputIdentifier> = (1..=7)
+		.map(|x| OutputIdentifier::new(OutputFeatures::Plain, &Commitment::from_vec(vec![x; 33])))
+		.collect();
+
+	let mut ba = VecBackend::new();
+	let mut header = BlockHeader::default();
+	{
+		let mut pmmr = PMMR::new(&mut ba);
+		for out in &outputs[..3] {
+			pmmr.push(out).unwrap();
+		}
+		header.output_root = pmmr.root().unwrap();
+		header.output_mmr_size = pmmr.size;
+		for out in &outputs[3..] {
+			pmmr.push(out).unwrap();
+		}
+	}
+
+	// output at pos0 3 is the third leaf, proof is built from the rewound view
+	let mut rewindable: RewindablePMMR<'_, OutputIdentifier, _> =
+		RewindablePMMR::at(&ba, ba.size());
+	rewindable.rewind(header.output_mmr_size).unwrap();
+	let proof = rewindable.as_readonly().merkle_proof(3).unwrap();
+	assert_eq!(proof.mmr_size, header.output_mmr_size);
+
+	assert_eq!(verify_merkle_proof(&header, &outputs[2], 4, &proof), Ok(()));
+	assert_eq!(
+		verify_merkle_proof(&header, &outputs[1], 4, &proof),
+		Err(MerkleProofError::RootMismatch)
+	);
+	assert_eq!(
+		verify_merkle_proof(&header, &outputs[2], 3, &proof),
+		Err(MerkleProofError::InvalidPosition)
+	);
+	assert_eq!(
+		verify_merkle_proof(&header, &outputs[2], 0, &proof),
+		Err(MerkleProofError::InvalidPosition)
+	);
+
+	let mut later_header = header.clone();
+	later_header.output_mmr_size += 1;
+	assert_eq!(
+		verify_merkle_proof(&later_header, &outputs[2], 4, &proof),
+		Err(MerkleProofError::SizeMismatch)
+	);
+}
@@ -0,0 +1,253 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End to end tests for the n-of-n shared outputs: funding of the shared output
+//! and its spend, both validated with `Transaction::validate`.
+
+use crate::core::core::transaction::{
+	FeeFields, Input, Inputs, KernelFeatures, Output, OutputFeatures, Transaction, TxKernel,
+	Weighting,
+};
+use crate::core::global;
+use crate::core::libtx::multisig::{self, MultiPartyRangeProof, PartyNonce};
+use mwc_core as core;
+use rand::thread_rng;
+use util::secp::key::SecretKey;
+use util::secp::pedersen::Commitment;
+use util::secp::{ContextFlag, Secp256k1};
+
+const FEE: u64 = 1_000_000;
+
+// Runs the kernel signing rounds for all parties, `excess_shares` are the secret
+// excess shares of the parties.
+fn sign_kernel(secp: &Secp256k1, kernel: &mut TxKernel, excess_shares: &[SecretKey]) {
+	// Round 1: nonce commitments
+	let nonces: Vec<PartyNonce> = excess_shares
+		.iter()
+		.map(|_| PartyNonce::new(secp).unwrap())
+		.collect();
+	let commitments: Vec<_> = nonces.iter().map(|n| n.commitment(secp)).collect();
+
+	// Round 2: nonce reveal, every party checks the others
+	for (commitment, nonce) in commitments.iter().zip(nonces.iter()) {
+		multisig::verify_nonce_reveal(secp, commitment, nonce.pub_nonce()).unwrap();
+	}
+	let other = PartyNonce::new(secp).unwrap();
+	assert!(multisig::verify_nonce_reveal(secp, &commitments[0], other.pub_nonce()).is_err());
+
+	let pub_nonces: Vec<_> = nonces.iter().map(|n| *n.pub_nonce()).collect();
+	let nonce_sum = multisig::sum_public_keys(secp, &pub_nonces).unwrap();
+
+	// Round 3: partial signatures
+	let public_shares: Vec<Commitment> = excess_shares
+		.iter()
+		.map(|e| multisig::public_excess_share(secp, e).unwrap())
+		.collect();
+	let excess = multisig::sum_excess_shares(secp, &public_shares).unwrap();
+	let sigs: Vec<_> = excess_shares
+		.iter()
+		.zip(nonces.iter())
+		.map(|(share, nonce)| {
+			multisig::calculate_partial_kernel_sig(secp, kernel, share, nonce, &nonce_sum, &excess)
+				.unwrap()
+		})
+		.collect();
+	for (sig, public_share) in sigs.iter().zip(public_shares.iter()) {
+		multisig::verify_partial_kernel_sig(secp, kernel, sig, public_share, &nonce_sum, &excess)
+			.unwrap();
+	}
+	if sigs.len() > 1 {
+		assert!(multisig::verify_partial_kernel_sig(
+			secp,
+			kernel,
+			&sigs[0],
+			&public_shares[1],
+			&nonce_sum,
+			&excess
+		)
+		.is_err());
+	}
+
+	multisig::finalize_kernel(secp, kernel, &sigs, &nonce_sum, &excess).unwrap();
+}
+
+fn plain_kernel() -> TxKernel {
+	TxKernel::with_features(KernelFeatures::Plain {
+		fee: FeeFields::new(0, FEE).unwrap(),
+	})
+}
+
+fn shared_output_fund_and_spend(parties: usize) {
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	let secp = Secp256k1::with_caps(ContextFlag::Commit);
+
+	// Every party contributes one own coin into the shared output
+	let coin_values: Vec<u64> = (0..parties).map(|i| 10_000_000 * (i as u64 + 1)).collect();
+	let coin_blinds: Vec<SecretKey> = (0..parties)
+		.map(|_| SecretKey::new(&secp, &mut thread_rng()))
+		.collect();
+	let inputs: Vec<Input> = coin_values
+		.iter()
+		.zip(coin_blinds.iter())
+		.map(|(v, b)| Input::new(OutputFeatures::Plain, secp.commit(*v, b.clone()).unwrap()))
+		.collect();
+	let shared_value = coin_values.iter().sum::<u64>() - FEE;
+
+	// Shared output, nobody knows the whole blinding factor
+	let blind_shares: Vec<SecretKey> = (0..parties)
+		.map(|_| SecretKey::new(&secp, &mut thread_rng()))
+		.collect();
+	let public_blind_shares: Vec<Commitment> = blind_shares
+		.iter()
+		.map(|b| multisig::public_excess_share(&secp, b).unwrap())
+		.collect();
+	// Every party proves that it knows the secret of its public share
+	for (b, public_share) in blind_shares.iter().zip(public_blind_shares.iter()) {
+		let proof = multisig::share_proof(&secp, b).unwrap();
+		multisig::verify_share_proof(&secp, public_share, &proof).unwrap();
+	}
+	let shared_commit =
+		multisig::shared_output_commit(&secp, shared_value, &public_blind_shares).unwrap();
+
+	// Multi-party range proof
+	let common_nonce = SecretKey::new(&secp, &mut thread_rng());
+	let builders: Vec<MultiPartyRangeProof> = blind_shares
+		.iter()
+		.map(|b| {
+			MultiPartyRangeProof::new(
+				&secp,
+				shared_value,
+				b.clone(),
+				common_nonce.clone(),
+				shared_commit,
+				None,
+			)
+		})
+		.collect();
+	let (t_ones, t_twos): (Vec<_>, Vec<_>) =
+		builders.iter().map(|b| b.round1(&secp).unwrap()).unzip();
+	let t_one_sum = multisig::sum_public_keys(&secp, &t_ones).unwrap();
+	let t_two_sum = multisig::sum_public_keys(&secp, &t_twos).unwrap();
+	let tau_x_shares: Vec<SecretKey> = builders
+		.iter()
+		.map(|b| b.round2(&secp, &t_one_sum, &t_two_sum).unwrap())
+		.collect();
+	let proof = builders[0]
+		.finalize(&secp, &t_one_sum, &t_two_sum, &tau_x_shares)
+		.unwrap();
+	// missing tau_x share of the last party makes the proof invalid
+	assert!(builders[0]
+		.finalize(&secp, &t_one_sum, &t_two_sum, &tau_x_shares[..parties - 1])
+		.is_err());
+	let shared_output = Output::new(OutputFeatures::Plain, shared_commit, proof);
+
+	// Funding kernel, party excess share is its output share minus its coin blind
+	let mut kernel = plain_kernel();
+	let excess_shares: Vec<SecretKey> = blind_shares
+		.iter()
+		.zip(coin_blinds.iter())
+		.map(|(b, c)| secp.blind_sum(vec![b.clone()], vec![c.clone()]).unwrap())
+		.collect();
+	sign_kernel(&secp, &mut kernel, &excess_shares);
+	let fund_tx = Transaction::new(Inputs::from(&inputs[..]), &[shared_output], &[kernel]);
+	fund_tx
+		.validate(Weighting::AsTransaction, 1, &secp)
+		.unwrap();
+
+	// Spend the shared output to the first party
+	let spend_value = shared_value - FEE;
+	let recipient_blind = SecretKey::new(&secp, &mut thread_rng());
+	let recipient_commit = secp.commit(spend_value, recipient_blind.clone()).unwrap();
+	let nonce = SecretKey::new(&secp, &mut thread_rng());
+	let recipient_proof = secp.bullet_proof(
+		spend_value,
+		recipient_blind.clone(),
+		nonce.clone(),
+		nonce,
+		None,
+		None,
+	);
+	let recipient_output = Output::new(OutputFeatures::Plain, recipient_commit, recipient_proof);
+
+	let mut kernel = plain_kernel();
+	let excess_shares: Vec<SecretKey> = blind_shares
+		.iter()
+		.enumerate()
+		.map(|(i, b)| {
+			if i == 0 {
+				secp.blind_sum(vec![recipient_blind.clone()], vec![b.clone()])
+					.unwrap()
+			} else {
+				secp.blind_sum(vec![], vec![b.clone()]).unwrap()
+			}
+		})
+		.collect();
+	sign_kernel(&secp, &mut kernel, &excess_shares);
+	let spend_input = Input::new(OutputFeatures::Plain, shared_commit);
+	let spend_tx = Transaction::new(
+		Inputs::from(&[spend_input][..]),
+		&[recipient_output],
+		&[kernel],
+	);
+	spend_tx
+		.validate(Weighting::AsTransaction, 1, &secp)
+		.unwrap();
+
+	// Kernel signed without one of the parties is not valid
+	let mut kernel = plain_kernel();
+	sign_kernel(&secp, &mut kernel, &excess_shares[..parties - 1]);
+	let incomplete_tx = Transaction::new(
+		Inputs::from(&[spend_input][..]),
+		&[recipient_output],
+		&[kernel],
+	);
+	assert!(incomplete_tx
+		.validate(Weighting::AsTransaction, 1, &secp)
+		.is_err());
+}
+
+#[test]
+fn shared_output_2_of_2() {
+	shared_output_fund_and_spend(2);
+}
+
+#[test]
+fn shared_output_3_of_3() {
+	shared_output_fund_and_spend(3);
+}
+
+#[test]
+fn rogue_share_is_rejected() {
+	let secp = Secp256k1::with_caps(ContextFlag::Commit);
+	let honest = SecretKey::new(&secp, &mut thread_rng());
+	let honest_share = multisig::public_excess_share(&secp, &honest).unwrap();
+
+	// Attacker publishes own share minus the honest share, so the sum is the attacker's key
+	let attacker = SecretKey::new(&secp, &mut thread_rng());
+	let attacker_share = multisig::public_excess_share(&secp, &attacker).unwrap();
+	let rogue_share = secp
+		.commit_sum(vec![attacker_share], vec![honest_share])
+		.unwrap();
+	assert_eq!(
+		multisig::sum_excess_shares(&secp, &[honest_share, rogue_share]).unwrap(),
+		attacker_share
+	);
+
+	// Attacker doesn't know the secret of the rogue share, so it can't prove it
+	let proof = multisig::share_proof(&secp, &attacker).unwrap();
+	assert!(multisig::verify_share_proof(&secp, &rogue_share, &proof).is_err());
+	let proof = multisig::share_proof(&secp, &honest).unwrap();
+	assert!(multisig::verify_share_proof(&secp, &rogue_share, &proof).is_err());
+	multisig::verify_share_proof(&secp, &honest_share, &proof).unwrap();
+}
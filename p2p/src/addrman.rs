@@ -0,0 +1,363 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Address manager for the outbound connections. Peer addresses are placed into
+//! the 'new' (never connected) and 'tried' (connected at least once) tables.
+//! Tables consist of the fixed size buckets, the bucket and position are selected
+//! with the node secret key from the network group of the address and the group
+//! of the peer that sent it. A single source or a single network group can fill
+//! only a few buckets, so the attacker can't flood the tables with its addresses
+//! and eclipse the node.
+
+use crate::mwc_core::core::hash::Hashed;
+use crate::types::PeerAddr;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Number of buckets at the 'new' table
+pub const NEW_BUCKET_COUNT: usize = 256;
+/// Number of buckets at the 'tried' table
+pub const TRIED_BUCKET_COUNT: usize = 64;
+/// Number of addresses at a single bucket
+pub const BUCKET_SIZE: usize = 64;
+
+/// Number of 'new' buckets that addresses from a single source group can occupy
+const NEW_BUCKETS_PER_SOURCE_GROUP: u64 = 16;
+/// Number of 'tried' buckets that addresses of a single group can occupy
+const TRIED_BUCKETS_PER_GROUP: u64 = 8;
+
+/// Network group of the peer address. Peers from the same group are likely
+/// controlled by the same operator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetGroup {
+	/// IPv4 /16 subnet
+	Ipv4([u8; 2]),
+	/// IPv6 /32 subnet
+	Ipv6([u8; 4]),
+	/// Onion addresses, grouped by the address prefix
+	Onion([u8; 4]),
+	/// I2P addresses, grouped by the address prefix
+	I2p([u8; 4]),
+	/// Loopback, private and link local addresses
+	Local,
+}
+
+impl NetGroup {
+	/// Network group of the address
+	pub fn of(addr: &PeerAddr) -> NetGroup {
+		match addr {
+			PeerAddr::Ip(socket_addr) => match socket_addr.ip() {
+				IpAddr::V4(ip) => Self::of_ipv4(&ip),
+				IpAddr::V6(ip) => match ip.to_ipv4() {
+					// ipv4 mapped addresses
+					Some(ipv4) if !ip.is_loopback() && !ip.is_unspecified() => Self::of_ipv4(&ipv4),
+					_ => Self::of_ipv6(&ip),
+				},
+			},
+			PeerAddr::Onion(address) => NetGroup::Onion(Self::address_prefix(address)),
+			PeerAddr::I2p(address) => NetGroup::I2p(Self::address_prefix(address)),
+		}
+	}
+
+	// First 4 characters of the base32 address (20 bits), the scheme is skipped.
+	// A single character gives only 32 groups for all hidden service peers.
+	fn address_prefix(address: &str) -> [u8; 4] {
+		let address = address.rsplit("://").next().unwrap_or(address);
+		let mut prefix = [0u8; 4];
+		for (p, c) in prefix.iter_mut().zip(address.bytes()) {
+			*p = c.to_ascii_lowercase();
+		}
+		prefix
+	}
+
+	fn of_ipv4(ip: &Ipv4Addr) -> NetGroup {
+		if ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() {
+			NetGroup::Local
+		} else {
+			let octets = ip.octets();
+			NetGroup::Ipv4([octets[0], octets[1]])
+		}
+	}
+
+	fn of_ipv6(ip: &Ipv6Addr) -> NetGroup {
+		let segments = ip.segments();
+		// loopback, unique local fc00::/7 and link local fe80::/10
+		if ip.is_loopback()
+			|| ip.is_unspecified()
+			|| (segments[0] & 0xfe00) == 0xfc00
+			|| (segments[0] & 0xffc0) == 0xfe80
+		{
+			NetGroup::Local
+		} else {
+			let octets = ip.octets();
+			NetGroup::Ipv6([octets[0], octets[1], octets[2], octets[3]])
+		}
+	}
+
+	/// Local addresses are not limited, they are used by the test and the private setups
+	pub fn is_local(&self) -> bool {
+		*self == NetGroup::Local
+	}
+
+	fn to_bytes(&self) -> Vec<u8> {
+		match self {
+			NetGroup::Ipv4(b) => vec![1, b[0], b[1]],
+			NetGroup::Ipv6(b) => vec![2, b[0], b[1], b[2], b[3]],
+			NetGroup::Onion(p) => [&[3u8][..], &p[..]].concat(),
+			NetGroup::I2p(p) => [&[4u8][..], &p[..]].concat(),
+			NetGroup::Local => vec![0],
+		}
+	}
+}
+
+impl fmt::Display for NetGroup {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			NetGroup::Ipv4(b) => write!(f, "{}.{}.0.0/16", b[0], b[1]),
+			NetGroup::Ipv6(b) => {
+				write!(f, "{:02x}{:02x}:{:02x}{:02x}::/32", b[0], b[1], b[2], b[3])
+			}
+			NetGroup::Onion(p) => write!(f, "onion:{}", String::from_utf8_lossy(p)),
+			NetGroup::I2p(p) => write!(f, "i2p:{}", String::from_utf8_lossy(p)),
+			NetGroup::Local => write!(f, "local"),
+		}
+	}
+}
+
+/// Tables of the address manager
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Table {
+	/// Addresses that we never connected to
+	New,
+	/// Addresses that we connected to at least once
+	Tried,
+}
+
+/// Result of adding the address to the 'new' table
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddResult {
+	/// Address is added
+	Added,
+	/// Address is already at one of the tables
+	Known,
+	/// Position is taken by another address
+	Collision(PeerAddr),
+}
+
+/// Buckets of the addresses. Bucket maps the position to the address.
+struct Buckets {
+	buckets: Vec<HashMap<usize, PeerAddr>>,
+	size: usize,
+}
+
+impl Buckets {
+	fn new(count: usize) -> Buckets {
+		Buckets {
+			buckets: vec![HashMap::new(); count],
+			size: 0,
+		}
+	}
+
+	fn get(&self, bucket: usize, pos: usize) -> Option<&PeerAddr> {
+		self.buckets[bucket].get(&pos)
+	}
+
+	fn insert(&mut self, bucket: usize, pos: usize, addr: PeerAddr) {
+		if self.buckets[bucket].insert(pos, addr).is_none() {
+			self.size += 1;
+		}
+	}
+
+	fn remove(&mut self, bucket: usize, pos: usize) -> Option<PeerAddr> {
+		let res = self.buckets[bucket].remove(&pos);
+		if res.is_some() {
+			self.size -= 1;
+		}
+		res
+	}
+
+	/// Random address. Bucket is selected first, so the crowded buckets don't
+	/// have advantage over the others.
+	fn select<R: Rng>(&self, rng: &mut R) -> Option<PeerAddr> {
+		let non_empty: Vec<&HashMap<usize, PeerAddr>> =
+			self.buckets.iter().filter(|b| !b.is_empty()).collect();
+		if non_empty.is_empty() {
+			return None;
+		}
+		let bucket = non_empty[rng.gen_range(0, non_empty.len())];
+		bucket.values().nth(rng.gen_range(0, bucket.len())).cloned()
+	}
+}
+
+/// Address manager with the bucketed 'new' and 'tried' tables
+pub struct AddrMan {
+	key: Vec<u8>,
+	new: Buckets,
+	tried: Buckets,
+	// Location of every address: table, bucket and position
+	index: HashMap<PeerAddr, (Table, usize, usize)>,
+}
+
+impl AddrMan {
+	/// Create an empty address manager. The `key` is the node secret that randomizes
+	/// the buckets, it must be the same after the restart.
+	pub fn new(key: &[u8]) -> AddrMan {
+		AddrMan {
+			key: key.to_vec(),
+			new: Buckets::new(NEW_BUCKET_COUNT),
+			tried: Buckets::new(TRIED_BUCKET_COUNT),
+			index: HashMap::new(),
+		}
+	}
+
+	fn keyed_hash(&self, parts: &[&[u8]]) -> u64 {
+		let mut data = self.key.clone();
+		for p in parts {
+			data.extend_from_slice(&(p.len() as u32).to_be_bytes());
+			data.extend_from_slice(p);
+		}
+		data.hash().to_u64()
+	}
+
+	fn addr_bytes(addr: &PeerAddr) -> Vec<u8> {
+		addr.as_key().into_bytes()
+	}
+
+	fn new_location(&self, addr: &PeerAddr, source: &PeerAddr) -> (usize, usize) {
+		let group = NetGroup::of(addr).to_bytes();
+		let source_group = NetGroup::of(source).to_bytes();
+		let slot = self.keyed_hash(&[&group[..], &source_group[..]]) % NEW_BUCKETS_PER_SOURCE_GROUP;
+		let bucket = self.keyed_hash(&[&source_group[..], &slot.to_be_bytes()[..]]) as usize
+			% NEW_BUCKET_COUNT;
+		(bucket, self.position(Table::New, bucket, addr))
+	}
+
+	fn tried_location(&self, addr: &PeerAddr) -> (usize, usize) {
+		let group = NetGroup::of(addr).to_bytes();
+		let slot = self.keyed_hash(&[&Self::addr_bytes(addr)[..]]) % TRIED_BUCKETS_PER_GROUP;
+		let bucket =
+			self.keyed_hash(&[&group[..], &slot.to_be_bytes()[..]]) as usize % TRIED_BUCKET_COUNT;
+		(bucket, self.position(Table::Tried, bucket, addr))
+	}
+
+	fn position(&self, table: Table, bucket: usize, addr: &PeerAddr) -> usize {
+		let table = [table as u8];
+		let bucket = (bucket as u64).to_be_bytes();
+		self.keyed_hash(&[&table[..], &bucket[..], &Self::addr_bytes(addr)[..]]) as usize
+			% BUCKET_SIZE
+	}
+
+	fn table(&mut self, table: Table) -> &mut Buckets {
+		match table {
+			Table::New => &mut self.new,
+			Table::Tried => &mut self.tried,
+		}
+	}
+
+	/// Add the address to the 'new' table. `source` is the peer that sent the address.
+	/// If the position is taken, the existing address is replaced only if `is_terrible`
+	/// returns true for it. Returns true if the address was added.
+	pub fn add<F>(&mut self, addr: &PeerAddr, source: &PeerAddr, is_terrible: F) -> bool
+	where
+		F: Fn(&PeerAddr) -> bool,
+	{
+		match self.try_add(addr, source) {
+			AddResult::Added => true,
+			AddResult::Known => false,
+			AddResult::Collision(existing) => {
+				is_terrible(&existing) && self.replace(&existing, addr, source)
+			}
+		}
+	}
+
+	/// Add the address to the 'new' table if its position is free. On collision the
+	/// caller can check the existing address and call `replace`.
+	pub fn try_add(&mut self, addr: &PeerAddr, source: &PeerAddr) -> AddResult {
+		if self.index.contains_key(addr) {
+			return AddResult::Known;
+		}
+		let (bucket, pos) = self.new_location(addr, source);
+		if let Some(existing) = self.new.get(bucket, pos) {
+			return AddResult::Collision(existing.clone());
+		}
+		self.new.insert(bucket, pos, addr.clone());
+		self.index.insert(addr.clone(), (Table::New, bucket, pos));
+		AddResult::Added
+	}
+
+	/// Replace the `existing` address with `addr` at the 'new' table. Returns false if
+	/// `existing` is not at the position of `addr` anymore, or `addr` is already known.
+	pub fn replace(&mut self, existing: &PeerAddr, addr: &PeerAddr, source: &PeerAddr) -> bool {
+		if self.index.contains_key(addr) {
+			return false;
+		}
+		let (bucket, pos) = self.new_location(addr, source);
+		if self.new.get(bucket, pos) != Some(existing) {
+			return false;
+		}
+		self.remove(existing);
+		self.new.insert(bucket, pos, addr.clone());
+		self.index.insert(addr.clone(), (Table::New, bucket, pos));
+		true
+	}
+
+	/// Move the address to the 'tried' table after the successful connection. If the
+	/// position is taken, the existing address goes back to the 'new' table.
+	pub fn mark_tried(&mut self, addr: &PeerAddr) {
+		if let Some((Table::Tried, _, _)) = self.index.get(addr) {
+			return;
+		}
+		self.remove(addr);
+
+		let (bucket, pos) = self.tried_location(addr);
+		if let Some(existing) = self.tried.remove(bucket, pos) {
+			self.index.remove(&existing);
+			// Evicted address is still good, keep it if there is a room
+			self.add(&existing, &existing, |_| false);
+		}
+		self.tried.insert(bucket, pos, addr.clone());
+		self.index.insert(addr.clone(), (Table::Tried, bucket, pos));
+	}
+
+	/// Remove the address from the tables
+	pub fn remove(&mut self, addr: &PeerAddr) -> bool {
+		match self.index.remove(addr) {
+			Some((table, bucket, pos)) => {
+				self.table(table).remove(bucket, pos);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Table of the address, None if the address is not known
+	pub fn table_of(&self, addr: &PeerAddr) -> Option<Table> {
+		self.index.get(addr).map(|(table, _, _)| *table)
+	}
+
+	/// Random address from the table
+	pub fn select<R: Rng>(&self, table: Table, rng: &mut R) -> Option<PeerAddr> {
+		match table {
+			Table::New => self.new.select(rng),
+			Table::Tried => self.tried.select(rng),
+		}
+	}
+
+	/// Number of addresses at the 'new' and at the 'tried' tables
+	pub fn size(&self) -> (usize, usize) {
+		(self.new.size, self.tried.size)
+	}
+}
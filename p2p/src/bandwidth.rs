@@ -0,0 +1,242 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Upload bandwidth limits for the peer connections. The global upload cap is
+//! shared by all peers, every peer that is uploading gets a fair share of it, so
+//! a single peer downloading the history can't take the whole link. The daily
+//! quota counts the uploaded bytes per UTC day, when it is exceeded the node
+//! stops serving the historical blocks and the PIBD segments.
+
+use crate::types::P2PConfig;
+use crate::util::Mutex;
+use chrono::Utc;
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Peer that uploaded during this interval is sharing the upload cap
+const ACTIVE_PEER_INTERVAL: Duration = Duration::from_secs(1);
+/// Large messages are written by chunks, so they are shaped as well
+const WRITE_CHUNK_SIZE: usize = 16 * 1024;
+/// Smallest chunk, for the very low upload caps
+const MIN_WRITE_CHUNK_SIZE: usize = 256;
+/// Longest wait after a chunk. The receiver must get the message body data well
+/// before its read timeout (BODY_IO_TIMEOUT).
+const MAX_CHUNK_WAIT: Duration = Duration::from_secs(5);
+
+/// Token bucket with one second burst
+struct TokenBucket {
+	tokens: f64,
+	last: Instant,
+}
+
+impl TokenBucket {
+	fn new() -> TokenBucket {
+		TokenBucket {
+			// Full bucket, it is clamped to the rate on the first take
+			tokens: f64::INFINITY,
+			last: Instant::now(),
+		}
+	}
+
+	/// Take `bytes` from the bucket that is refilled with `rate` bytes per second.
+	/// Returns how long to wait until the bucket is out of the debt.
+	fn take(&mut self, bytes: u64, rate: f64) -> Duration {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.last).as_secs_f64();
+		self.last = now;
+		self.tokens = (self.tokens + elapsed * rate).min(rate);
+		self.tokens -= bytes as f64;
+		if self.tokens >= 0.0 {
+			Duration::from_secs(0)
+		} else {
+			Duration::from_secs_f64(-self.tokens / rate)
+		}
+	}
+}
+
+/// Uploaded bytes for the current UTC day
+struct DailyUpload {
+	day: i64,
+	bytes: u64,
+}
+
+/// Upload limits shared by all peer connections of the server
+pub struct Bandwidth {
+	upload_cap: Option<u64>,
+	daily_upload_quota: Option<u64>,
+	global: Mutex<TokenBucket>,
+	daily: Mutex<DailyUpload>,
+	// Peers with the recent uploads, by the shaper id
+	active_peers: Mutex<HashMap<usize, Instant>>,
+	next_id: AtomicUsize,
+}
+
+impl Bandwidth {
+	/// Limits from the p2p config
+	pub fn new(config: &P2PConfig) -> Bandwidth {
+		Bandwidth {
+			upload_cap: config.upload_cap.filter(|cap| *cap > 0),
+			daily_upload_quota: config.daily_upload_quota,
+			global: Mutex::new(TokenBucket::new()),
+			daily: Mutex::new(DailyUpload {
+				day: Self::today(),
+				bytes: 0,
+			}),
+			active_peers: Mutex::new(HashMap::new()),
+			next_id: AtomicUsize::new(0),
+		}
+	}
+
+	fn today() -> i64 {
+		Utc::now().timestamp() / 86_400
+	}
+
+	/// Shaper for the new peer connection
+	pub fn peer_shaper(self: &Arc<Self>) -> PeerShaper {
+		PeerShaper {
+			id: self.next_id.fetch_add(1, Ordering::Relaxed),
+			bandwidth: self.clone(),
+			bucket: TokenBucket::new(),
+		}
+	}
+
+	/// Bytes uploaded today
+	pub fn uploaded_today(&self) -> u64 {
+		let daily = self.daily.lock();
+		if daily.day == Self::today() {
+			daily.bytes
+		} else {
+			0
+		}
+	}
+
+	/// Check if the daily upload quota is used up. Historical blocks and PIBD
+	/// segments are not served until the next UTC day.
+	pub fn upload_quota_exceeded(&self) -> bool {
+		match self.daily_upload_quota {
+			Some(quota) => self.uploaded_today() >= quota,
+			None => false,
+		}
+	}
+
+	fn record_upload(&self, bytes: u64) {
+		let quota = match self.daily_upload_quota {
+			Some(quota) => quota,
+			None => return,
+		};
+		let mut daily = self.daily.lock();
+		let today = Self::today();
+		if daily.day != today {
+			daily.day = today;
+			daily.bytes = 0;
+		}
+		let before = daily.bytes;
+		daily.bytes = daily.bytes.saturating_add(bytes);
+		if before < quota && daily.bytes >= quota {
+			warn!(
+				"Daily upload quota {} bytes is exceeded, not serving historical blocks and PIBD segments until the next UTC day",
+				quota
+			);
+		}
+	}
+
+	/// Number of the peers sharing the upload cap, including `id`
+	fn active_peers(&self, id: usize) -> usize {
+		let now = Instant::now();
+		let mut active_peers = self.active_peers.lock();
+		active_peers.insert(id, now);
+		active_peers.retain(|_, last| now.duration_since(*last) < ACTIVE_PEER_INTERVAL);
+		active_peers.len()
+	}
+
+	fn remove_peer(&self, id: usize) {
+		self.active_peers.lock().remove(&id);
+	}
+}
+
+/// Upload shaper of a single peer connection
+pub struct PeerShaper {
+	id: usize,
+	bandwidth: Arc<Bandwidth>,
+	bucket: TokenBucket,
+}
+
+impl PeerShaper {
+	/// Account the uploaded bytes. Blocks the caller while the peer is over its
+	/// fair share or the server is over the upload cap. The wait is limited by
+	/// MAX_CHUNK_WAIT, the rest of the debt is paid with the next writes.
+	pub fn consume(&mut self, bytes: u64) {
+		self.bandwidth.record_upload(bytes);
+		let cap = match self.bandwidth.upload_cap {
+			Some(cap) => cap as f64,
+			None => return,
+		};
+		let fair_share = cap / self.bandwidth.active_peers(self.id) as f64;
+		let peer_wait = self.bucket.take(bytes, fair_share);
+		let global_wait = self.bandwidth.global.lock().take(bytes, cap);
+		let wait = cmp::min(cmp::max(peer_wait, global_wait), MAX_CHUNK_WAIT);
+		if wait > Duration::from_secs(0) {
+			thread::sleep(wait);
+		}
+	}
+
+	/// Size of the next write. Chunk is sent with the current fair share of the peer
+	/// within MAX_CHUNK_WAIT.
+	pub fn chunk_size(&self) -> usize {
+		match self.bandwidth.upload_cap {
+			Some(cap) => {
+				let fair_share = cap as f64 / self.bandwidth.active_peers(self.id) as f64;
+				let size = (fair_share * MAX_CHUNK_WAIT.as_secs_f64()) as usize;
+				cmp::min(cmp::max(size, MIN_WRITE_CHUNK_SIZE), WRITE_CHUNK_SIZE)
+			}
+			None => WRITE_CHUNK_SIZE,
+		}
+	}
+}
+
+impl Drop for PeerShaper {
+	fn drop(&mut self) {
+		self.bandwidth.remove_peer(self.id);
+	}
+}
+
+/// Writer that passes the written bytes through the peer shaper
+pub struct ShapedWriter<'a, W: Write> {
+	inner: &'a mut W,
+	shaper: &'a mut PeerShaper,
+}
+
+impl<'a, W: Write> ShapedWriter<'a, W> {
+	pub fn new(inner: &'a mut W, shaper: &'a mut PeerShaper) -> ShapedWriter<'a, W> {
+		ShapedWriter { inner, shaper }
+	}
+}
+
+impl<'a, W: Write> Write for ShapedWriter<'a, W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = cmp::min(buf.len(), self.shaper.chunk_size());
+		let n = self.inner.write(&buf[..len])?;
+		self.shaper.consume(n as u64);
+		Ok(n)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
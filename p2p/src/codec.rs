@@ -24,6 +24,7 @@
 use crate::msg::{Message, MsgHeader, MsgHeaderWrapper, Type};
 use crate::mwc_core::global::header_size_bytes;
 use crate::mwc_core::ser::{BufReader, ProtocolVersion, Readable};
+use crate::transport::PeerStream;
 use crate::types::{AttachmentMeta, AttachmentUpdate, Error};
 use crate::{
 	msg::HeadersData,
@@ -34,7 +35,6 @@ use mwc_core::ser::Reader;
 use std::cmp::min;
 use std::io::Read;
 use std::mem;
-use std::net::TcpStream;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use MsgHeaderWrapper::*;
@@ -66,14 +66,14 @@ impl State {
 
 pub struct Codec {
 	pub version: ProtocolVersion,
-	stream: TcpStream,
+	stream: Box<dyn PeerStream>,
 	buffer: BytesMut,
 	state: State,
 	bytes_read: usize,
 }
 
 impl Codec {
-	pub fn new(version: ProtocolVersion, stream: TcpStream) -> Self {
+	pub fn new(version: ProtocolVersion, stream: Box<dyn PeerStream>) -> Self {
 		Self {
 			version,
 			stream,
@@ -84,7 +84,7 @@ impl Codec {
 	}
 
 	/// Destroy the codec and return the reader
-	pub fn stream(self) -> TcpStream {
+	pub fn stream(self) -> Box<dyn PeerStream> {
 		self.stream
 	}
 
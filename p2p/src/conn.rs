@@ -21,16 +21,18 @@
 //! forces us to go through some additional gymnastic to loop over the async
 //! stream and make sure we get the right number of bytes out.
 
+use crate::bandwidth::{PeerShaper, ShapedWriter};
 use crate::codec::{Codec, BODY_IO_TIMEOUT};
 use crate::msg::{write_message, Consumed, Message, Msg};
 use crate::mwc_core::ser::ProtocolVersion;
+use crate::transport::PeerStream;
 use crate::types::Error;
 use crate::util::{RateCounter, RwLock};
 use crossbeam::channel::{RecvTimeoutError, TryRecvError};
 use mwc_chain::SyncState;
 use std::fs::File;
 use std::io::{self, Write};
-use std::net::{Shutdown, TcpStream};
+use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
@@ -176,10 +178,11 @@ impl Tracker {
 /// the current thread, instead just returns a future and the Connection
 /// itself.
 pub fn listen<H>(
-	stream: TcpStream,
+	stream: Box<dyn PeerStream>,
 	version: ProtocolVersion,
 	tracker: Arc<Tracker>,
 	sync_state: Arc<SyncState>,
+	shaper: PeerShaper,
 	handler: H,
 ) -> io::Result<(ConnHandle, StopHandle)>
 where
@@ -202,6 +205,7 @@ where
 		stopped.clone(),
 		tracker,
 		sync_state,
+		shaper,
 	)?;
 
 	Ok((
@@ -215,7 +219,7 @@ where
 }
 
 fn poll<H>(
-	conn: TcpStream,
+	conn: Box<dyn PeerStream>,
 	conn_handle: ConnHandle,
 	version: ProtocolVersion,
 	handler: H,
@@ -223,6 +227,7 @@ fn poll<H>(
 	stopped: Arc<AtomicBool>,
 	tracker: Arc<Tracker>,
 	sync_state: Arc<SyncState>,
+	mut shaper: PeerShaper,
 ) -> io::Result<(JoinHandle<()>, JoinHandle<()>)>
 where
 	H: MessageHandler,
@@ -364,8 +369,12 @@ where
 				retry_send = Err(());
 				match maybe_data {
 					Ok(data) => {
-						let written =
-							try_break!(write_message(&mut writer, &data, writer_tracker.clone()));
+						// Upload limits are applied while the messages are written
+						let written = try_break!(write_message(
+							&mut ShapedWriter::new(&mut writer, &mut shaper),
+							&data,
+							writer_tracker.clone()
+						));
 						if written.is_none() {
 							retry_send = Ok(data);
 						}
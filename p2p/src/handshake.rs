@@ -19,14 +19,15 @@ use crate::mwc_core::core::hash::Hash;
 use crate::mwc_core::pow::Difficulty;
 use crate::mwc_core::ser::ProtocolVersion;
 use crate::peer::Peer;
+use crate::transport::PeerStream;
 use crate::types::{
-	Capabilities, Direction, Error, P2PConfig, PeerAddr, PeerAddr::Ip, PeerAddr::Onion, PeerInfo,
-	PeerLiveInfo,
+	Capabilities, ConnectionType, Direction, Error, P2PConfig, PeerAddr, PeerAddr::Ip,
+	PeerAddr::Onion, PeerInfo, PeerLiveInfo,
 };
 use crate::util::RwLock;
 use rand::{thread_rng, Rng};
 use std::collections::VecDeque;
-use std::net::{SocketAddr, TcpStream};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -103,8 +104,9 @@ impl Handshake {
 		capabilities: Capabilities,
 		total_difficulty: Difficulty,
 		self_addr: PeerAddr,
-		conn: &mut TcpStream,
+		conn: &mut Box<dyn PeerStream>,
 		peer_addr: Option<PeerAddr>,
+		connection_type: ConnectionType,
 	) -> Result<PeerInfo, Error> {
 		// Set explicit timeouts on the tcp stream for hand/shake messages.
 		// Once the peer is up and running we will set new values for these.
@@ -124,6 +126,8 @@ impl Handshake {
 			}
 		});
 
+		// Old peers don't read the connection type, we just don't send them
+		// transactions and addresses over the block relay only connection.
 		let hand = Hand {
 			version: self.protocol_version,
 			capabilities,
@@ -133,6 +137,7 @@ impl Handshake {
 			sender_addr: self_addr.clone(),
 			receiver_addr: peer_addr.clone(),
 			user_agent: USER_AGENT.to_string(),
+			connection_type,
 		};
 
 		// write and read the handshake response
@@ -175,6 +180,7 @@ impl Handshake {
 			} else {
 				Direction::Outbound
 			},
+			connection_type,
 		};
 
 		// If denied then we want to close the connection
@@ -202,7 +208,7 @@ impl Handshake {
 		&self,
 		capab: Capabilities,
 		total_difficulty: Difficulty,
-		conn: &mut TcpStream,
+		conn: &mut Box<dyn PeerStream>,
 	) -> Result<PeerInfo, Error> {
 		// Set explicit timeouts on the tcp stream for hand/shake messages.
 		// Once the peer is up and running we will set new values for these.
@@ -247,6 +253,7 @@ impl Handshake {
 			} else {
 				Direction::Inbound
 			},
+			connection_type: hand.connection_type,
 		};
 
 		// At this point we know the published ip and port of the peer
@@ -271,7 +278,11 @@ impl Handshake {
 		let msg = Msg::new(Type::Shake, shake, negotiated_version)?;
 		write_message(conn, &vec![msg], self.tracker.clone())?;
 
-		trace!("Success handshake with {}.", peer_info.addr);
+		trace!(
+			"Success handshake with {}, {:?} connection.",
+			peer_info.addr,
+			peer_info.connection_type
+		);
 
 		Ok(peer_info)
 	}
@@ -290,7 +301,7 @@ impl Handshake {
 }
 
 /// Resolve the correct peer_addr based on the connection and the advertised port.
-fn resolve_peer_addr(advertised: PeerAddr, conn: &TcpStream) -> PeerAddr {
+fn resolve_peer_addr(advertised: PeerAddr, conn: &Box<dyn PeerStream>) -> PeerAddr {
 	match advertised {
 		Ip(socket_addr) => {
 			let port = socket_addr.port();
@@ -300,6 +311,6 @@ fn resolve_peer_addr(advertised: PeerAddr, conn: &TcpStream) -> PeerAddr {
 				advertised
 			}
 		}
-		Onion(_) => advertised,
+		Onion(_) | PeerAddr::I2p(_) => advertised,
 	}
 }
@@ -0,0 +1,397 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! I2P transport with the SAM v3 bridge of the I2P router. The node creates a
+//! STREAM session with its own destination, the peer connections are made with
+//! STREAM CONNECT and STREAM ACCEPT. After the SAM handshake the socket to the
+//! bridge is the raw stream to the peer.
+//! Protocol spec: https://geti2p.net/en/docs/api/samv3
+
+use crate::types::Error;
+use data_encoding::{Encoding, Specification, BASE32_NOPAD};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::util::RwLock;
+
+/// Default address of the SAM bridge
+pub const DEFAULT_SAM_ADDR: &str = "127.0.0.1:7656";
+/// Suffix of the I2P base32 addresses
+pub const B32_SUFFIX: &str = ".b32.i2p";
+/// Length of the base32 encoded destination hash
+const B32_HASH_LEN: usize = 52;
+
+const SAM_VERSION: &str = "3.1";
+/// EdDSA_SHA512_Ed25519 destinations
+const SIGNATURE_TYPE: u32 = 7;
+/// I2P tunnels are slow to build, the SAM replies might take a while
+const SAM_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_LINE_LEN: usize = 16 * 1024;
+/// File with the private key of the node destination
+const DESTINATION_KEY_FILE: &str = "destination.key";
+
+lazy_static! {
+	/// I2P base64 uses '-' and '~' instead of '+' and '/'
+	static ref I2P_BASE64: Encoding = {
+		let mut spec = Specification::new();
+		spec.symbols
+			.push_str("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-~");
+		spec.padding = Some('=');
+		spec.encoding().unwrap()
+	};
+}
+
+/// Check if the address is I2P base32 address, `^[a-z2-7]{52}\.b32\.i2p$`.
+/// The address goes to the SAM commands, so nothing else is accepted.
+pub fn is_i2p_address(addr: &str) -> bool {
+	addr.len() == B32_HASH_LEN + B32_SUFFIX.len()
+		&& addr.ends_with(B32_SUFFIX)
+		&& addr[..B32_HASH_LEN]
+			.bytes()
+			.all(|c| (b'a'..=b'z').contains(&c) || (b'2'..=b'7').contains(&c))
+}
+
+/// Base32 address (`<52 chars>.b32.i2p`) of the I2P destination. `destination` is the
+/// base64 public destination as SAM reports it.
+pub fn b32_address(destination: &str) -> Result<String, Error> {
+	let dest = I2P_BASE64
+		.decode(destination.as_bytes())
+		.map_err(|e| Error::I2p(format!("Invalid destination {}, {}", destination, e)))?;
+	let hash = Sha256::digest(&dest);
+	Ok(format!(
+		"{}{}",
+		BASE32_NOPAD.encode(&hash).to_lowercase(),
+		B32_SUFFIX
+	))
+}
+
+fn sam_error(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// Socket to the SAM bridge. Lines are read byte by byte, so nothing is buffered
+/// and the socket can become the peer stream.
+struct SamSocket {
+	stream: TcpStream,
+}
+
+impl SamSocket {
+	/// Connect to the bridge and negotiate the protocol version
+	fn connect(sam_addr: &str) -> io::Result<SamSocket> {
+		let stream = TcpStream::connect(sam_addr)?;
+		stream.set_read_timeout(Some(SAM_TIMEOUT))?;
+		stream.set_write_timeout(Some(SAM_TIMEOUT))?;
+		let mut socket = SamSocket { stream };
+		socket.command(&format!(
+			"HELLO VERSION MIN={} MAX={}",
+			SAM_VERSION, SAM_VERSION
+		))?;
+		Ok(socket)
+	}
+
+	fn read_line(&mut self) -> io::Result<String> {
+		let mut line = vec![];
+		let mut byte = [0u8; 1];
+		loop {
+			if self.stream.read(&mut byte)? == 0 {
+				return Err(io::Error::new(
+					io::ErrorKind::UnexpectedEof,
+					"SAM bridge closed the connection",
+				));
+			}
+			if byte[0] == b'\n' {
+				break;
+			}
+			line.push(byte[0]);
+			if line.len() > MAX_LINE_LEN {
+				return Err(sam_error("SAM reply is too long".into()));
+			}
+		}
+		String::from_utf8(line)
+			.map(|l| l.trim_end_matches('\r').to_string())
+			.map_err(|e| sam_error(format!("Invalid SAM reply, {}", e)))
+	}
+
+	/// Send the command and read the reply. Returns the reply values, fails if the
+	/// reply RESULT is not OK.
+	fn command(&mut self, cmd: &str) -> io::Result<HashMap<String, String>> {
+		self.stream.write_all(format!("{}\n", cmd).as_bytes())?;
+		let reply = self.read_line()?;
+		let values = parse_reply(&reply);
+		match values.get("RESULT") {
+			Some(result) if result != "OK" => Err(sam_error(format!(
+				"SAM command {} failed with {}, {}",
+				cmd.split_whitespace()
+					.take(2)
+					.collect::<Vec<&str>>()
+					.join(" "),
+				result,
+				values.get("MESSAGE").cloned().unwrap_or_default()
+			))),
+			_ => Ok(values),
+		}
+	}
+
+	/// Read the value of the reply, fails if it is missing
+	fn value(values: &HashMap<String, String>, key: &str) -> io::Result<String> {
+		values
+			.get(key)
+			.cloned()
+			.ok_or(sam_error(format!("{} is not found at SAM reply", key)))
+	}
+
+	/// Done with SAM, the socket is the peer stream now
+	fn into_stream(self) -> io::Result<TcpStream> {
+		self.stream.set_read_timeout(None)?;
+		self.stream.set_write_timeout(None)?;
+		Ok(self.stream)
+	}
+}
+
+/// Parse `KEY=VALUE` pairs of the SAM reply, values might be quoted
+fn parse_reply(reply: &str) -> HashMap<String, String> {
+	let mut res = HashMap::new();
+	let mut chars = reply.chars().peekable();
+	loop {
+		while chars.peek() == Some(&' ') {
+			chars.next();
+		}
+		if chars.peek().is_none() {
+			break;
+		}
+		let mut key = String::new();
+		while let Some(&c) = chars.peek() {
+			if c == ' ' || c == '=' {
+				break;
+			}
+			key.push(c);
+			chars.next();
+		}
+		let mut value = String::new();
+		if chars.peek() == Some(&'=') {
+			chars.next();
+			if chars.peek() == Some(&'"') {
+				chars.next();
+				while let Some(c) = chars.next() {
+					match c {
+						'\\' => {
+							if let Some(c) = chars.next() {
+								value.push(c);
+							}
+						}
+						'"' => break,
+						c => value.push(c),
+					}
+				}
+			} else {
+				while let Some(&c) = chars.peek() {
+					if c == ' ' {
+						break;
+					}
+					value.push(c);
+					chars.next();
+				}
+			}
+		}
+		res.insert(key, value);
+	}
+	res
+}
+
+/// Read the node destination private key, or generate and save a new one
+fn load_or_create_destination(sam_addr: &str, keys_dir: &Path) -> Result<String, Error> {
+	let key_file = keys_dir.join(DESTINATION_KEY_FILE);
+	if key_file.exists() {
+		let key = fs::read_to_string(&key_file)
+			.map_err(|e| Error::I2p(format!("Unable to read {}, {}", key_file.display(), e)))?;
+		return Ok(key.trim().to_string());
+	}
+
+	let mut socket = SamSocket::connect(sam_addr)
+		.map_err(|e| Error::I2p(format!("Unable to connect to SAM bridge, {}", e)))?;
+	let reply = socket
+		.command(&format!("DEST GENERATE SIGNATURE_TYPE={}", SIGNATURE_TYPE))
+		.map_err(|e| Error::I2p(format!("Unable to generate destination, {}", e)))?;
+	let key = SamSocket::value(&reply, "PRIV").map_err(|e| Error::I2p(e.to_string()))?;
+
+	fs::create_dir_all(keys_dir).map_err(|e| {
+		Error::I2p(format!(
+			"Unable to create dir {}, {}",
+			keys_dir.display(),
+			e
+		))
+	})?;
+	fs::write(&key_file, &key)
+		.map_err(|e| Error::I2p(format!("Unable to write {}, {}", key_file.display(), e)))?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(&key_file, fs::Permissions::from_mode(0o600)).map_err(|e| {
+			Error::I2p(format!(
+				"Unable to set permissions for {}, {}",
+				key_file.display(),
+				e
+			))
+		})?;
+	}
+	Ok(key)
+}
+
+/// Session at the SAM bridge. The session lives while the control socket is open.
+struct SessionState {
+	session_id: String,
+	control: TcpStream,
+}
+
+/// STREAM session of the node at the SAM bridge. If the bridge closes the session,
+/// it is created again with the same destination.
+pub struct I2pSession {
+	sam_addr: String,
+	// Private key of the node destination
+	destination: String,
+	address: String,
+	state: RwLock<SessionState>,
+}
+
+impl I2pSession {
+	/// Create the session at the SAM bridge `sam_addr`. The destination key is stored at
+	/// `keys_dir`, so the node I2P address is the same after the restart.
+	pub fn new(sam_addr: &str, keys_dir: &Path) -> Result<I2pSession, Error> {
+		let destination = load_or_create_destination(sam_addr, keys_dir)?;
+		let (state, address) = Self::create_session(sam_addr, &destination)?;
+		Ok(I2pSession {
+			sam_addr: sam_addr.to_string(),
+			destination,
+			address,
+			state: RwLock::new(state),
+		})
+	}
+
+	fn create_session(sam_addr: &str, destination: &str) -> Result<(SessionState, String), Error> {
+		// Session id must be unique at the bridge, the session of the previous run might be still alive
+		let session_id = format!("mwc{:08x}", rand::thread_rng().gen::<u32>());
+
+		let mut control = SamSocket::connect(sam_addr)
+			.map_err(|e| Error::I2p(format!("Unable to connect to SAM bridge, {}", e)))?;
+		control
+			.command(&format!(
+				"SESSION CREATE STYLE=STREAM ID={} DESTINATION={} SIGNATURE_TYPE={}",
+				session_id, destination, SIGNATURE_TYPE
+			))
+			.map_err(|e| Error::I2p(format!("Unable to create session, {}", e)))?;
+		let reply = control
+			.command("NAMING LOOKUP NAME=ME")
+			.map_err(|e| Error::I2p(format!("Unable to read session destination, {}", e)))?;
+		let public = SamSocket::value(&reply, "VALUE").map_err(|e| Error::I2p(e.to_string()))?;
+		let address = b32_address(&public)?;
+		// Control socket is idle from now on
+		control
+			.stream
+			.set_read_timeout(None)
+			.map_err(|e| Error::I2p(e.to_string()))?;
+
+		Ok((
+			SessionState {
+				session_id,
+				control: control.stream,
+			},
+			address,
+		))
+	}
+
+	/// I2P base32 address of the node
+	pub fn address(&self) -> &str {
+		&self.address
+	}
+
+	/// Check if the SAM bridge still keeps the session, it closes the control socket
+	/// when the session is gone.
+	pub fn is_alive(&self) -> bool {
+		let state = self.state.read();
+		if state.control.set_nonblocking(true).is_err() {
+			return false;
+		}
+		let mut byte = [0u8; 1];
+		let alive = match state.control.peek(&mut byte) {
+			Ok(0) => false,
+			Ok(_) => true,
+			Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+		};
+		alive && state.control.set_nonblocking(false).is_ok()
+	}
+
+	/// Close the current session and create a new one with the same destination
+	pub fn recreate(&self) -> Result<(), Error> {
+		let mut state = self.state.write();
+		// The bridge doesn't allow two sessions with the same destination
+		let _ = state.control.shutdown(Shutdown::Both);
+		let (new_state, address) = Self::create_session(&self.sam_addr, &self.destination)?;
+		if address != self.address {
+			return Err(Error::I2p(format!(
+				"Session destination is changed from {} to {}",
+				self.address, address
+			)));
+		}
+		*state = new_state;
+		Ok(())
+	}
+
+	fn session_id(&self) -> String {
+		self.state.read().session_id.clone()
+	}
+
+	/// Connect to the peer with I2P base32 address
+	pub fn connect(&self, address: &str) -> io::Result<TcpStream> {
+		if !is_i2p_address(address) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("Invalid I2P address {:?}", address),
+			));
+		}
+		let mut socket = SamSocket::connect(&self.sam_addr)?;
+		let reply = socket.command(&format!("NAMING LOOKUP NAME={}", address))?;
+		let destination = SamSocket::value(&reply, "VALUE")?;
+		socket.command(&format!(
+			"STREAM CONNECT ID={} DESTINATION={} SILENT=false",
+			self.session_id(),
+			destination
+		))?;
+		socket.into_stream()
+	}
+
+	/// Wait for the inbound connection. Returns the stream and the I2P base32 address of the peer.
+	pub fn accept(&self) -> io::Result<(TcpStream, String)> {
+		let mut socket = SamSocket::connect(&self.sam_addr)?;
+		socket.command(&format!(
+			"STREAM ACCEPT ID={} SILENT=false",
+			self.session_id()
+		))?;
+		// Waiting for the peer, there is no timeout
+		socket.stream.set_read_timeout(None)?;
+		// The first line is the peer destination, optionally followed by the ports
+		let line = socket.read_line()?;
+		let destination = line
+			.split_whitespace()
+			.next()
+			.ok_or(sam_error("Peer destination is not found".into()))?;
+		let address = b32_address(destination).map_err(|e| sam_error(e.to_string()))?;
+		Ok((socket.into_stream()?, address))
+	}
+}
@@ -40,16 +40,23 @@ extern crate log;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod addrman;
+pub mod bandwidth;
 mod codec;
 mod conn;
 pub mod handshake;
+pub mod i2p;
 pub mod libp2p_connection;
+pub mod libp2p_message_store;
+pub mod libp2p_rate_limit;
 pub mod msg;
 mod peer;
 mod peers;
 mod protocol;
+pub mod proxy;
 mod serv;
 pub mod store;
+pub mod transport;
 pub mod types;
 
 pub use crate::conn::SEND_CHANNEL_CAP;
@@ -57,9 +64,11 @@ pub use crate::peer::Peer;
 pub use crate::peers::Peers;
 pub use crate::serv::{DummyAdapter, Server};
 pub use crate::store::{PeerData, State};
+pub use crate::transport::{PeerListener, PeerStream, TcpTransport, Transport};
 pub use crate::types::{
-	Capabilities, ChainAdapter, Direction, Error, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
-	Seeding, TxHashSetRead, MAX_BLOCK_HEADERS, MAX_LOCATORS, MAX_PEER_ADDRS,
+	Capabilities, ChainAdapter, ConnectionType, Direction, Error, I2pConfig, P2PConfig, PeerAddr,
+	PeerInfo, ProxyPolicy, ReasonForBan, Seeding, Socks5ProxyConfig, TxHashSetRead,
+	MAX_BLOCK_HEADERS, MAX_LOCATORS, MAX_PEER_ADDRS,
 };
 
 pub use crate::libp2p_connection::{
@@ -38,6 +38,11 @@ use mwc_libp2p::gossipsub::{
 };
 use mwc_libp2p::gossipsub::{Gossipsub, MessageAcceptance, TopicHash};
 
+use crate::libp2p_message_store::{Libp2pMessageStore, StoredMessage};
+use crate::libp2p_rate_limit::{
+	peer_score_params, GossipRateLimiter, GossipRateLimits, RateLimitRule, INVALID_MESSAGE_PENALTY,
+	PEER_PENALTY,
+};
 use crate::mwc_core::global;
 use crate::types::Error;
 use crate::PeerAddr;
@@ -57,6 +62,7 @@ use mwc_util::{Mutex, OnionV3Address, OnionV3AddressError, ToHex};
 use rand::seq::SliceRandom;
 use std::collections::VecDeque;
 use std::convert::TryInto;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
 use std::{
@@ -109,6 +115,9 @@ lazy_static! {
 
 	/// Received messages
 	static ref MESSAGING_RECEIVED: RwLock<VecDeque<ReceivedMessage>> = RwLock::new(VecDeque::new());
+
+	/// Persistent storage of the received messages
+	static ref MESSAGING_STORE: RwLock<Option<Arc<Libp2pMessageStore>>> = RwLock::new(None);
 }
 
 // Message with same integrity output consensus
@@ -195,16 +204,22 @@ fn listener_handler(sender_address: &String, topic: &TopicHash, data: Vec<u8>, f
 			);
 
 			// Everything looks good so far. We can keep the data
+			let received = ReceivedMessage {
+				timestamp: Utc::now().timestamp(),
+				peer_id: sender_address.clone(),
+				topic: topic_str.clone(),
+				fee,
+				message: message_str,
+			};
+			if let Some(store) = MESSAGING_STORE.read().as_ref() {
+				if let Err(e) = store.add_message(&received) {
+					error!("Unable to save received libp2p message, {}", e);
+				}
+			}
 			{
 				let mut messages = MESSAGING_RECEIVED.write();
-				messages.retain(|m| m.message != message_str || m.peer_id != *sender_address);
-				messages.push_back(ReceivedMessage {
-					timestamp: Utc::now().timestamp(),
-					peer_id: sender_address.clone(),
-					topic: topic_str.clone(),
-					fee,
-					message: message_str,
-				});
+				messages.retain(|m| m.message != received.message || m.peer_id != *sender_address);
+				messages.push_back(received);
 				while messages.len() > MESSAGING_RECEIVED_LIMIT {
 					messages.pop_front();
 				}
@@ -277,6 +292,30 @@ pub fn get_received_messages_num() -> usize {
 	MESSAGING_RECEIVED.read().len()
 }
 
+/// Set the persistent storage for the received messages
+pub fn set_messages_store(store: Arc<Libp2pMessageStore>) {
+	MESSAGING_STORE.write().replace(store);
+}
+
+/// Read the stored messages with sequence number greater than `after_seq`.
+/// Returns the last stored sequence number and the messages.
+pub fn get_stored_messages(
+	topic: Option<&str>,
+	after_seq: u64,
+	limit: usize,
+) -> Result<(u64, Vec<StoredMessage>), Error> {
+	match MESSAGING_STORE.read().as_ref() {
+		Some(store) => {
+			let last_seq = store.last_seq();
+			let messages = store.get_messages(topic, after_seq, limit)?;
+			Ok((last_seq, messages))
+		}
+		None => Err(Error::Libp2pError(
+			"libp2p messages store is not available".into(),
+		)),
+	}
+}
+
 /// Stop listening on the topic
 pub fn remove_topic_from_libp2p(topic: &str) {
 	// remove topic and handler
@@ -332,6 +371,11 @@ pub fn publish_message(topic: &Topic, integrity_message: Vec<u8>) -> Option<Mess
 	}
 }
 
+/// Publish the integrity message on the topic by its name. Returns the id of the published message
+pub fn publish_topic_message(topic_str: &str, integrity_message: Vec<u8>) -> Option<String> {
+	publish_message(&Topic::new(topic_str), integrity_message).map(|msg_id| msg_id.to_string())
+}
+
 /// Request number of established connections to libp2p
 pub fn get_libp2p_connections() -> Vec<PeerId> {
 	match &*LIBP2P_SWARM.lock() {
@@ -366,12 +410,14 @@ pub fn add_new_peer(peer: &PeerAddr) -> Result<(), Error> {
 
 /// Created libp2p listener for Socks5 tor address.
 /// tor_socks_port - listener port, param from  SocksPort 127.0.0.1:51234
+/// rate_limits - limits for the number of messages per peer and per topic
 /// output_validation_fn - kernel excess validation method. Return height RangeProof if that output was seen during last 24 hours (last 1440 blocks)
 pub async fn run_libp2p_node(
 	tor_socks_port: u16,
 	tor_secret: &[u8; 32],
 	libp2p_port: u16,
 	fee_base: u64,
+	rate_limits: GossipRateLimits,
 	kernel_validation_fn: Arc<impl Fn(&Commitment) -> Result<Option<TxKernel>, Error>>,
 	stop_mutex: std::sync::Arc<std::sync::Mutex<u32>>,
 ) -> Result<(), Error> {
@@ -449,9 +495,14 @@ pub async fn run_libp2p_node(
 	let connections_number_low = gossipsub_config.mesh_n_high();
 
 	// build a gossipsub network behaviour
-	let gossipsub: gossipsub::Gossipsub =
+	let mut gossipsub: gossipsub::Gossipsub =
 		gossipsub::Gossipsub::new(MessageAuthenticity::Signed(id_keys), gossipsub_config)
 			.expect("Correct configuration");
+	// Peer scoring, rejected messages and rate limit penalties lower the peer score
+	let (score_params, score_thresholds) = peer_score_params(&rate_limits);
+	gossipsub
+		.with_peer_score(score_params, score_thresholds)
+		.map_err(|e| Error::Libp2pError(format!("Invalid peer score params, {}", e)))?;
 
 	// subscribes to our topic
 
@@ -489,6 +540,7 @@ pub async fn run_libp2p_node(
 	init_libp2p_swarm(swarm);
 
 	let mut requests_cash: HashMap<Commitment, VecDeque<i64>> = HashMap::new();
+	let mut rate_limiter = GossipRateLimiter::new(rate_limits);
 	let mut last_cash_clean = Instant::now();
 	let mut last_reconnect = Instant::now();
 	let secp = secp.clone();
@@ -593,6 +645,44 @@ pub async fn run_libp2p_node(
 								} else {
 									// We get the regular message and we need to validate it now.
 
+									// Rate limits are checked first, validation is expensive. The per peer limit is
+									// applied to the author of the message, the messages are signed. The forwarding
+									// peers only relay the author's messages, they are not penalized for them.
+									let author = message.source.unwrap_or(peer_id);
+									if let Err(rule) = rate_limiter.check(
+										&author.to_string(),
+										&message.topic.to_string(),
+										Instant::now(),
+									) {
+										debug!(
+											"libp2p message of {} from {} on topic {} is dropped by the {:?} rate limit",
+											author, peer_id, message.topic, rule
+										);
+										let gossip = swarm.get_behaviour();
+										let _ = gossip.report_message_validation_result(
+											&id,
+											&peer_id,
+											MessageAcceptance::Ignore,
+										);
+										// The flooding author is penalized only if it is connected to us directly
+										if rule == RateLimitRule::Peer && author == peer_id {
+											let (score, banned) = rate_limiter.penalize(
+												&peer_id.to_string(),
+												PEER_PENALTY,
+												Instant::now(),
+											);
+											gossip.set_application_score(&peer_id, score);
+											if banned {
+												warn!(
+													"Banning libp2p peer {} for the messages flood",
+													peer_id
+												);
+												gossip.disconnect_peer(peer_id, true);
+											}
+										}
+										continue;
+									}
+
 									let gossip = swarm.get_behaviour();
 
 									let acceptance = match validate_integrity_message(
@@ -635,9 +725,25 @@ pub async fn run_libp2p_node(
 									};
 
 									debug!("report_message_validation_result as {:?}", acceptance);
+									let rejected = matches!(acceptance, MessageAcceptance::Reject);
 									let _ = gossip.report_message_validation_result(
 										&id, &peer_id, acceptance,
 									);
+									if rejected {
+										let (score, banned) = rate_limiter.penalize(
+											&peer_id.to_string(),
+											INVALID_MESSAGE_PENALTY,
+											Instant::now(),
+										);
+										gossip.set_application_score(&peer_id, score);
+										if banned {
+											warn!(
+												"Banning libp2p peer {} for the invalid messages",
+												peer_id
+											);
+											gossip.disconnect_peer(peer_id, true);
+										}
+									}
 								}
 							}
 							_ => {}
@@ -658,6 +764,11 @@ pub async fn run_libp2p_node(
 					requests_cash.retain(|_commit, history| {
 						*history.back().unwrap_or(&0) > history_time_limit
 					});
+					for (peer, score) in rate_limiter.cleanup(now) {
+						if let Ok(peer_id) = PeerId::from_str(&peer) {
+							swarm.set_application_score(&peer_id, score);
+						}
+					}
 				}
 
 				// Will try to reconnect if needed every 15 seconds.
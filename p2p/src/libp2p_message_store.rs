@@ -0,0 +1,346 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistent storage for the received libp2p gossip messages. Messages are kept
+//! per topic with retention by age and by count. Every message gets a sequence
+//! number, consumers read the stream with their own cursor (last read sequence
+//! number), so reading doesn't affect other consumers.
+
+use chrono::Utc;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::libp2p_connection::ReceivedMessage;
+use crate::mwc_core::core::hash::{Hash, Hashed};
+use crate::mwc_core::ser::{self, DeserializationMode, Readable, Reader, Writeable, Writer};
+use mwc_store::{self, u64_to_key, Error};
+use mwc_util::Mutex;
+
+const DB_NAME: &str = "libp2p_messages";
+const STORE_SUBPATH: &str = "libp2p";
+
+const MESSAGE_PREFIX: u8 = b'M';
+// Last used sequence number. Messages might be pruned, but the sequence must continue
+const LAST_SEQ_PREFIX: u8 = b'S';
+
+/// Max size of the message that the store accepts
+const MAX_MESSAGE_SIZE: usize = 100_000;
+
+/// Received libp2p message as it is stored in the db
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoredMessage {
+	/// Sequence number of the message, use it as a cursor for the reading
+	pub seq: u64,
+	/// Unix timestamp when this message was received
+	pub timestamp: i64,
+	/// Peer who send the message
+	pub peer_id: String,
+	/// Topic that received the message
+	pub topic: String,
+	/// Integrity fee that was paid
+	pub fee: u64,
+	/// The message.
+	pub message: String,
+}
+
+impl StoredMessage {
+	fn uniq_hash(&self) -> Hash {
+		message_hash(&self.peer_id, &self.message)
+	}
+}
+
+fn message_hash(peer_id: &str, message: &str) -> Hash {
+	let mut data = peer_id.as_bytes().to_vec();
+	data.extend_from_slice(message.as_bytes());
+	data.hash()
+}
+
+impl Writeable for StoredMessage {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		if self.message.len() > MAX_MESSAGE_SIZE {
+			return Err(ser::Error::TooLargeWriteErr(format!(
+				"libp2p message is too long, {} bytes",
+				self.message.len()
+			)));
+		}
+		ser_multiwrite!(
+			writer,
+			[write_u64, self.seq],
+			[write_i64, self.timestamp],
+			[write_bytes, &self.peer_id],
+			[write_bytes, &self.topic],
+			[write_u64, self.fee],
+			[write_bytes, &self.message]
+		);
+		Ok(())
+	}
+}
+
+fn read_string<R: Reader>(reader: &mut R) -> Result<String, ser::Error> {
+	String::from_utf8(reader.read_bytes_len_prefix()?)
+		.map_err(|e| ser::Error::CorruptedData(format!("Fail to read libp2p message, {}", e)))
+}
+
+impl Readable for StoredMessage {
+	fn read<R: Reader>(reader: &mut R) -> Result<StoredMessage, ser::Error> {
+		let seq = reader.read_u64()?;
+		let timestamp = reader.read_i64()?;
+		let peer_id = read_string(reader)?;
+		let topic = read_string(reader)?;
+		let fee = reader.read_u64()?;
+		let message = read_string(reader)?;
+		Ok(StoredMessage {
+			seq,
+			timestamp,
+			peer_id,
+			topic,
+			fee,
+			message,
+		})
+	}
+}
+
+/// Index entry of the stored message
+struct IndexEntry {
+	seq: u64,
+	timestamp: i64,
+	hash: Hash,
+}
+
+struct StoreState {
+	next_seq: u64,
+	// Messages of every topic, ordered by sequence number
+	topics: HashMap<String, VecDeque<IndexEntry>>,
+	// Hashes of the stored messages, used to skip the duplicates
+	known: HashSet<Hash>,
+}
+
+/// Storage for the received libp2p messages
+pub struct Libp2pMessageStore {
+	db: mwc_store::Store,
+	max_age_sec: i64,
+	max_per_topic: usize,
+	state: Mutex<StoreState>,
+}
+
+impl Libp2pMessageStore {
+	/// Open the messages store under the provided root path.
+	/// `max_age_sec` and `max_per_topic` define the retention of the messages.
+	pub fn new(
+		db_root: &str,
+		max_age_sec: u64,
+		max_per_topic: usize,
+	) -> Result<Libp2pMessageStore, Error> {
+		let db = mwc_store::Store::new(db_root, Some(DB_NAME), Some(STORE_SUBPATH), None)?;
+
+		let last_seq = db.get_ser::<u64>(&[LAST_SEQ_PREFIX], None)?.unwrap_or(0);
+		let mut state = StoreState {
+			next_seq: last_seq + 1,
+			topics: HashMap::new(),
+			known: HashSet::new(),
+		};
+
+		// Keys are big endian sequence numbers, so the messages are ordered
+		let protocol_version = db.protocol_version();
+		for msg in db.iter(&[MESSAGE_PREFIX], move |_, mut v| {
+			ser::deserialize::<StoredMessage, _>(
+				&mut v,
+				protocol_version,
+				DeserializationMode::default(),
+			)
+			.map_err(From::from)
+		})? {
+			let hash = msg.uniq_hash();
+			state.next_seq = state.next_seq.max(msg.seq + 1);
+			state.known.insert(hash);
+			state
+				.topics
+				.entry(msg.topic)
+				.or_insert(VecDeque::new())
+				.push_back(IndexEntry {
+					seq: msg.seq,
+					timestamp: msg.timestamp,
+					hash,
+				});
+		}
+
+		let store = Libp2pMessageStore {
+			db,
+			max_age_sec: max_age_sec as i64,
+			max_per_topic: max_per_topic.max(1),
+			state: Mutex::new(state),
+		};
+
+		{
+			let mut state = store.state.lock();
+			let batch = store.db.batch_write()?;
+			let pruned = store.prune(&state, None, &batch)?;
+			batch.commit()?;
+			Self::remove_pruned(&mut state, &pruned);
+			info!(
+				"libp2p messages store is loaded with {} messages, {} expired messages are deleted",
+				state.known.len(),
+				pruned.len()
+			);
+		}
+
+		Ok(store)
+	}
+
+	/// Save the received message. Returns the sequence number of the message
+	/// or None if the message is already stored.
+	pub fn add_message(&self, msg: &ReceivedMessage) -> Result<Option<u64>, Error> {
+		let hash = message_hash(&msg.peer_id, &msg.message);
+
+		let mut state = self.state.lock();
+		if state.known.contains(&hash) {
+			return Ok(None);
+		}
+
+		let stored = StoredMessage {
+			seq: state.next_seq,
+			timestamp: msg.timestamp,
+			peer_id: msg.peer_id.clone(),
+			topic: msg.topic.clone(),
+			fee: msg.fee,
+			message: msg.message.clone(),
+		};
+
+		let entry = IndexEntry {
+			seq: stored.seq,
+			timestamp: stored.timestamp,
+			hash,
+		};
+
+		let batch = self.db.batch_write()?;
+		batch.put_ser(&u64_to_key(MESSAGE_PREFIX, stored.seq), &stored)?;
+		batch.put_ser(&[LAST_SEQ_PREFIX], &stored.seq)?;
+		let pruned = self.prune(&state, Some((&stored.topic, &entry)), &batch)?;
+		batch.commit()?;
+
+		// The index is updated only when the db is updated
+		state.next_seq += 1;
+		state.known.insert(hash);
+		state
+			.topics
+			.entry(stored.topic.clone())
+			.or_insert(VecDeque::new())
+			.push_back(entry);
+		Self::remove_pruned(&mut state, &pruned);
+		Ok(Some(stored.seq))
+	}
+
+	/// Read the messages with sequence number greater than `after_seq`, ordered by
+	/// sequence number. `topic` None means all topics.
+	pub fn get_messages(
+		&self,
+		topic: Option<&str>,
+		after_seq: u64,
+		limit: usize,
+	) -> Result<Vec<StoredMessage>, Error> {
+		let time_limit = Utc::now().timestamp() - self.max_age_sec;
+		let mut seqs: Vec<u64> = {
+			let state = self.state.lock();
+			state
+				.topics
+				.iter()
+				.filter(|(t, _)| topic.map(|topic| topic == t.as_str()).unwrap_or(true))
+				.flat_map(|(_, entries)| {
+					entries
+						.iter()
+						.filter(|e| e.seq > after_seq && e.timestamp > time_limit)
+						.map(|e| e.seq)
+				})
+				.collect()
+		};
+		seqs.sort();
+		seqs.truncate(limit);
+
+		let mut res = Vec::with_capacity(seqs.len());
+		for seq in seqs {
+			// Message might be pruned by the concurrent write, it is fine to skip it
+			if let Some(msg) = self
+				.db
+				.get_ser::<StoredMessage>(&u64_to_key(MESSAGE_PREFIX, seq), None)?
+			{
+				res.push(msg);
+			}
+		}
+		Ok(res)
+	}
+
+	/// Sequence number of the last stored message, 0 if no messages were stored
+	pub fn last_seq(&self) -> u64 {
+		self.state.lock().next_seq - 1
+	}
+
+	/// Number of the stored messages
+	pub fn messages_num(&self) -> usize {
+		self.state.lock().known.len()
+	}
+
+	// Delete expired messages and the oldest messages of the topics that are over the limit.
+	// `pending` is the new message that is not in the index yet. Returns sequence numbers
+	// and hashes of the deleted messages, the index must be updated after the commit.
+	fn prune(
+		&self,
+		state: &StoreState,
+		pending: Option<(&str, &IndexEntry)>,
+		batch: &mwc_store::Batch<'_>,
+	) -> Result<Vec<(u64, Hash)>, Error> {
+		let time_limit = Utc::now().timestamp() - self.max_age_sec;
+		let mut deleted: Vec<(u64, Hash)> = vec![];
+
+		let mut topics: Vec<&str> = state.topics.keys().map(|t| t.as_str()).collect();
+		if let Some((topic, _)) = pending {
+			if !state.topics.contains_key(topic) {
+				topics.push(topic);
+			}
+		}
+
+		for topic in topics {
+			let entries = state
+				.topics
+				.get(topic)
+				.into_iter()
+				.flat_map(|entries| entries.iter())
+				.chain(pending.filter(|(t, _)| *t == topic).map(|(_, e)| e));
+			// Expired messages can be anywhere in the queue, timestamps are not ordered
+			let (expired, fresh): (Vec<&IndexEntry>, Vec<&IndexEntry>) =
+				entries.partition(|e| e.timestamp <= time_limit);
+			let over_limit = fresh.len().saturating_sub(self.max_per_topic);
+			for e in expired
+				.into_iter()
+				.chain(fresh.into_iter().take(over_limit))
+			{
+				batch.delete(&u64_to_key(MESSAGE_PREFIX, e.seq))?;
+				deleted.push((e.seq, e.hash));
+			}
+		}
+		Ok(deleted)
+	}
+
+	fn remove_pruned(state: &mut StoreState, pruned: &[(u64, Hash)]) {
+		if pruned.is_empty() {
+			return;
+		}
+		let seqs: HashSet<u64> = pruned.iter().map(|(seq, _)| *seq).collect();
+		for entries in state.topics.values_mut() {
+			entries.retain(|e| !seqs.contains(&e.seq));
+		}
+		state.topics.retain(|_, entries| !entries.is_empty());
+		for (_, hash) in pruned {
+			state.known.remove(hash);
+		}
+	}
+}
@@ -0,0 +1,250 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rate limits for the libp2p gossip messages. Token buckets per message author
+//! and per topic. Integrity fee protects from the spam, but a flood that pays the
+//! minimal fee still can overload the nodes, so the rate of the messages is limited.
+//! Penalties of the peers are reported to gossipsub as the application specific score,
+//! so the flooding peers are excluded from the gossip before they are banned.
+
+use mwc_libp2p::gossipsub::{PeerScoreParams, PeerScoreThresholds};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Penalty score for a single message that was dropped by the author rule
+pub const PEER_PENALTY: f64 = 1.0;
+/// Penalty score for a message that failed the validation
+pub const INVALID_MESSAGE_PENALTY: f64 = 10.0;
+/// Penalty score decay, the score is halved every that period
+const PEER_PENALTY_HALF_LIFE_SEC: f64 = 60.0;
+
+/// Number of messages dropped by the per author rule
+static DROPPED_BY_PEER: AtomicU64 = AtomicU64::new(0);
+/// Number of messages dropped by the per topic rule
+static DROPPED_BY_TOPIC: AtomicU64 = AtomicU64::new(0);
+/// Number of peers that were banned because of the flood
+static BANNED_PEERS: AtomicU64 = AtomicU64::new(0);
+
+/// Rate limits for the libp2p gossip messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct GossipRateLimits {
+	/// Messages per second that a single peer can publish, counted by the message author
+	pub peer_rate: f64,
+	/// Max burst of the messages published by a single peer
+	pub peer_burst: f64,
+	/// Messages per second for a single topic
+	pub topic_rate: f64,
+	/// Max burst of the messages for a single topic
+	pub topic_burst: f64,
+	/// Peer penalty score when the peer is banned
+	pub peer_ban_score: f64,
+}
+
+impl Default for GossipRateLimits {
+	fn default() -> GossipRateLimits {
+		GossipRateLimits {
+			peer_rate: 0.5,
+			peer_burst: 20.0,
+			topic_rate: 10.0,
+			topic_burst: 200.0,
+			peer_ban_score: 50.0,
+		}
+	}
+}
+
+/// Gossipsub peer scoring for the rate limits. Application specific score is the negative
+/// penalty of the peer, thresholds are set so the peer stops getting the gossip, then
+/// its messages are not published and then ignored before it reaches the ban score.
+pub fn peer_score_params(limits: &GossipRateLimits) -> (PeerScoreParams, PeerScoreThresholds) {
+	let params = PeerScoreParams {
+		app_specific_weight: 1.0,
+		// Peers are connected over Tor, all of them have the same local IP
+		ip_colocation_factor_weight: 0.0,
+		..PeerScoreParams::default()
+	};
+	let thresholds = PeerScoreThresholds {
+		gossip_threshold: -limits.peer_ban_score * 0.2,
+		publish_threshold: -limits.peer_ban_score * 0.5,
+		graylist_threshold: -limits.peer_ban_score * 0.8,
+		..PeerScoreThresholds::default()
+	};
+	(params, thresholds)
+}
+
+/// Rule that dropped the message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitRule {
+	/// Too many messages from the author
+	Peer,
+	/// Too many messages on the topic
+	Topic,
+}
+
+/// Counters of the dropped messages
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GossipRateLimitStats {
+	/// Number of messages dropped by the per author rule
+	pub dropped_by_peer: u64,
+	/// Number of messages dropped by the per topic rule
+	pub dropped_by_topic: u64,
+	/// Number of peers that were banned because of the flood
+	pub banned_peers: u64,
+}
+
+/// Get the counters of the messages dropped by the rate limits
+pub fn get_rate_limit_stats() -> GossipRateLimitStats {
+	GossipRateLimitStats {
+		dropped_by_peer: DROPPED_BY_PEER.load(Ordering::Relaxed),
+		dropped_by_topic: DROPPED_BY_TOPIC.load(Ordering::Relaxed),
+		banned_peers: BANNED_PEERS.load(Ordering::Relaxed),
+	}
+}
+
+struct TokenBucket {
+	tokens: f64,
+	last: Instant,
+}
+
+impl TokenBucket {
+	fn new(burst: f64, now: Instant) -> TokenBucket {
+		TokenBucket {
+			tokens: burst,
+			last: now,
+		}
+	}
+
+	fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * rate).min(burst);
+		self.last = now;
+	}
+
+	fn take(&mut self, rate: f64, burst: f64, now: Instant) -> bool {
+		self.refill(rate, burst, now);
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+struct Penalty {
+	score: f64,
+	last: Instant,
+}
+
+impl Penalty {
+	fn decay(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+		self.score *= 0.5f64.powf(elapsed / PEER_PENALTY_HALF_LIFE_SEC);
+		self.last = now;
+	}
+}
+
+/// Token buckets per message author and per topic
+pub struct GossipRateLimiter {
+	limits: GossipRateLimits,
+	peers: HashMap<String, TokenBucket>,
+	topics: HashMap<String, TokenBucket>,
+	penalties: HashMap<String, Penalty>,
+}
+
+impl GossipRateLimiter {
+	/// Create a rate limiter with provided limits
+	pub fn new(limits: GossipRateLimits) -> GossipRateLimiter {
+		GossipRateLimiter {
+			limits,
+			peers: HashMap::new(),
+			topics: HashMap::new(),
+			penalties: HashMap::new(),
+		}
+	}
+
+	/// Check if the message of the author on the topic is within the limits. The author is the
+	/// peer that signed the message, the forwarding peers are not limited, they only relay it.
+	/// Returns the rule that dropped the message.
+	pub fn check(&mut self, author: &str, topic: &str, now: Instant) -> Result<(), RateLimitRule> {
+		let limits = &self.limits;
+		let peer_ok = self
+			.peers
+			.entry(author.to_string())
+			.or_insert_with(|| TokenBucket::new(limits.peer_burst, now))
+			.take(limits.peer_rate, limits.peer_burst, now);
+		if !peer_ok {
+			DROPPED_BY_PEER.fetch_add(1, Ordering::Relaxed);
+			return Err(RateLimitRule::Peer);
+		}
+
+		let topic_ok = self
+			.topics
+			.entry(topic.to_string())
+			.or_insert_with(|| TokenBucket::new(limits.topic_burst, now))
+			.take(limits.topic_rate, limits.topic_burst, now);
+		if !topic_ok {
+			DROPPED_BY_TOPIC.fetch_add(1, Ordering::Relaxed);
+			return Err(RateLimitRule::Topic);
+		}
+		Ok(())
+	}
+
+	/// Add penalty to the peer that violated the rate limit or sent invalid message.
+	/// Returns the application score for gossipsub and true if the peer reached the ban level.
+	pub fn penalize(&mut self, peer: &str, penalty: f64, now: Instant) -> (f64, bool) {
+		let p = self.penalties.entry(peer.to_string()).or_insert(Penalty {
+			score: 0.0,
+			last: now,
+		});
+		p.decay(now);
+		p.score += penalty;
+		let app_score = -p.score;
+		if p.score >= self.limits.peer_ban_score {
+			self.penalties.remove(peer);
+			BANNED_PEERS.fetch_add(1, Ordering::Relaxed);
+			(app_score, true)
+		} else {
+			(app_score, false)
+		}
+	}
+
+	/// Remove the buckets that are full and the penalties that are decayed,
+	/// so the peers that are gone are not tracked.
+	/// Returns the updated application scores of the penalized peers.
+	pub fn cleanup(&mut self, now: Instant) -> Vec<(String, f64)> {
+		let limits = self.limits.clone();
+		self.peers.retain(|_, b| {
+			b.refill(limits.peer_rate, limits.peer_burst, now);
+			b.tokens < limits.peer_burst
+		});
+		self.topics.retain(|_, b| {
+			b.refill(limits.topic_rate, limits.topic_burst, now);
+			b.tokens < limits.topic_burst
+		});
+		let mut scores = Vec::new();
+		self.penalties.retain(|peer, p| {
+			p.decay(now);
+			let keep = p.score >= PEER_PENALTY / 10.0;
+			scores.push((peer.clone(), if keep { -p.score } else { 0.0 }));
+			keep
+		});
+		scores
+	}
+
+	/// Number of tracked peers and topics
+	pub fn tracked_num(&self) -> (usize, usize) {
+		(self.peers.len(), self.topics.len())
+	}
+}
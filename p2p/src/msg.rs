@@ -30,14 +30,14 @@ use crate::mwc_core::ser::{
 };
 use crate::mwc_core::{consensus, global};
 use crate::types::{
-	AttachmentMeta, AttachmentUpdate, Capabilities, Error, PeerAddr, ReasonForBan,
+	AttachmentMeta, AttachmentUpdate, Capabilities, ConnectionType, Error, PeerAddr, ReasonForBan,
 	MAX_BLOCK_HEADERS, MAX_LOCATORS, MAX_PEER_ADDRS,
 };
 use crate::util::secp::pedersen::RangeProof;
 use bytes::Bytes;
 use num::FromPrimitive;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::sync::Arc;
 use std::{fmt, thread, time::Duration};
 
@@ -425,6 +425,9 @@ pub struct Hand {
 	pub receiver_addr: PeerAddr,
 	/// name of version of the software
 	pub user_agent: String,
+	/// what the sender wants to exchange over this connection. It is not a node
+	/// capability, so it is sent as a separate field at the end of the message.
+	pub connection_type: ConnectionType,
 }
 
 impl Writeable for Hand {
@@ -446,6 +449,10 @@ impl Writeable for Hand {
 		}
 		writer.write_bytes(&self.user_agent)?;
 		self.genesis.write(writer)?;
+		// Old peers ignore the trailing data, so the full relay connection is not marked
+		if self.connection_type == ConnectionType::BlockRelayOnly {
+			writer.write_u8(1)?;
+		}
 		Ok(())
 	}
 }
@@ -462,6 +469,15 @@ impl Readable for Hand {
 		let user_agent = String::from_utf8(ua)
 			.map_err(|e| ser::Error::CorruptedData(format!("Fail to read User Agent, {}", e)))?;
 		let genesis = Hash::read(reader)?;
+		// Connection type is optional, old peers don't send it
+		let connection_type = match reader.read_u8() {
+			Ok(1) => ConnectionType::BlockRelayOnly,
+			Ok(_) => ConnectionType::FullRelay,
+			Err(ser::Error::IOErr(_, kind)) if kind == io::ErrorKind::UnexpectedEof => {
+				ConnectionType::FullRelay
+			}
+			Err(e) => return Err(e),
+		};
 		Ok(Hand {
 			version,
 			capabilities,
@@ -471,6 +487,7 @@ impl Readable for Hand {
 			sender_addr,
 			receiver_addr,
 			user_agent,
+			connection_type,
 		})
 	}
 }
@@ -16,7 +16,7 @@
 use crate::serv::Server;
 use crate::util::{Mutex, RwLock};
 use std::fmt;
-use std::net::{Shutdown, TcpStream};
+use std::net::Shutdown;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -37,9 +37,10 @@ use crate::mwc_core::pow::Difficulty;
 use crate::mwc_core::ser::Writeable;
 use crate::mwc_core::{core, global};
 use crate::protocol::Protocol;
+use crate::transport::PeerStream;
 use crate::types::{
-	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
-	TxHashSetRead,
+	Capabilities, ChainAdapter, ConnectionType, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo,
+	ReasonForBan, TxHashSetRead,
 };
 use crate::util::secp::pedersen::RangeProof;
 use chrono::prelude::Utc;
@@ -81,17 +82,24 @@ impl Peer {
 	// Only accept and connect can be externally used to build a peer
 	fn new(
 		info: PeerInfo,
-		conn: TcpStream,
+		conn: Box<dyn PeerStream>,
 		adapter: Arc<dyn NetAdapter>,
 		sync_state: Arc<SyncState>,
 		server: Server,
 	) -> std::io::Result<Peer> {
 		let state = Arc::new(RwLock::new(State::Connected));
 		let tracking_adapter = TrackingAdapter::new(adapter);
+		let shaper = server.bandwidth.peer_shaper();
 		let handler = Protocol::new(Arc::new(tracking_adapter.clone()), info.clone(), server);
 		let tracker = Arc::new(conn::Tracker::new());
-		let (sendh, stoph) =
-			conn::listen(conn, info.version, tracker.clone(), sync_state, handler)?;
+		let (sendh, stoph) = conn::listen(
+			conn,
+			info.version,
+			tracker.clone(),
+			sync_state,
+			shaper,
+			handler,
+		)?;
 		let send_handle = Mutex::new(sendh);
 		let stop_handle = Mutex::new(stoph);
 		Ok(Peer {
@@ -105,7 +113,7 @@ impl Peer {
 	}
 
 	pub fn accept(
-		mut conn: TcpStream,
+		mut conn: Box<dyn PeerStream>,
 		capab: Capabilities,
 		total_difficulty: Difficulty,
 		hs: &Handshake,
@@ -132,29 +140,27 @@ impl Peer {
 	}
 
 	pub fn connect(
-		mut conn: TcpStream,
+		mut conn: Box<dyn PeerStream>,
 		capab: Capabilities,
 		total_difficulty: Difficulty,
 		self_addr: PeerAddr,
 		hs: &Handshake,
 		adapter: Arc<dyn NetAdapter>,
 		peer_addr: Option<PeerAddr>,
+		connection_type: ConnectionType,
 		sync_state: Arc<SyncState>,
 		server: Server,
 	) -> Result<Peer, Error> {
 		debug!("connect: handshaking with {:?}", self_addr);
 
-		let info = if peer_addr.is_some() {
-			hs.initiate(
-				capab,
-				total_difficulty,
-				self_addr,
-				&mut conn,
-				Some(peer_addr.clone().unwrap()),
-			)
-		} else {
-			hs.initiate(capab, total_difficulty, self_addr, &mut conn, None)
-		};
+		let info = hs.initiate(
+			capab,
+			total_difficulty,
+			self_addr,
+			&mut conn,
+			peer_addr.clone(),
+			connection_type,
+		);
 		match info {
 			Ok(info) => Ok(Peer::new(info, conn, adapter, sync_state, server)?),
 			Err(e) => {
@@ -301,6 +307,9 @@ impl Peer {
 	}
 
 	pub fn send_tx_kernel_hash(&self, h: Hash) -> Result<bool, Error> {
+		if self.info.is_block_relay_only() {
+			return Ok(false);
+		}
 		if !self.tracking_adapter.has_recv(h) {
 			debug!("Send tx kernel hash {} to {}", h, self.info.addr);
 			self.send(h, msg::Type::TransactionKernel)?;
@@ -319,6 +328,10 @@ impl Peer {
 	/// We support broadcast of lightweight tx kernel hash
 	/// so track known txs by kernel hash.
 	pub fn send_transaction(&self, tx: &core::Transaction) -> Result<bool, Error> {
+		// No transactions for the block relay only peers
+		if self.info.is_block_relay_only() {
+			return Ok(false);
+		}
 		let kernel = &tx.kernels()[0];
 
 		if self
@@ -347,6 +360,9 @@ impl Peer {
 	/// Note: tracking adapter is ignored for stem transactions (while under
 	/// embargo).
 	pub fn send_stem_transaction(&self, tx: &core::Transaction) -> Result<(), Error> {
+		if self.info.is_block_relay_only() {
+			return Err(Error::NoDandelionRelay);
+		}
 		debug!("Send (stem) tx {} to {}", tx.hash(), self.info.addr);
 		self.send(tx, msg::Type::StemTransaction)
 	}
@@ -383,6 +399,10 @@ impl Peer {
 		capab: Capabilities,
 		use_tor_connection: bool,
 	) -> Result<(), Error> {
+		// Block relay only peers don't exchange addresses
+		if self.info.is_block_relay_only() {
+			return Ok(());
+		}
 		let capab = if use_tor_connection {
 			capab | Capabilities::TOR_ADDRESS
 		} else {
@@ -660,6 +680,10 @@ impl ChainAdapter for TrackingAdapter {
 		self.adapter.get_block(h, peer_info)
 	}
 
+	fn get_block_header(&self, h: &Hash) -> Option<core::BlockHeader> {
+		self.adapter.get_block_header(h)
+	}
+
 	fn txhashset_read(&self, h: Hash) -> Option<TxHashSetRead> {
 		self.adapter.txhashset_read(h)
 	}
@@ -809,8 +833,8 @@ impl NetAdapter for TrackingAdapter {
 		self.adapter.find_peer_addrs(capab)
 	}
 
-	fn peer_addrs_received(&self, addrs: Vec<PeerAddr>) {
-		self.adapter.peer_addrs_received(addrs)
+	fn peer_addrs_received(&self, source: &PeerAddr, addrs: Vec<PeerAddr>) {
+		self.adapter.peer_addrs_received(source, addrs)
 	}
 
 	fn peer_difficulty(&self, addr: &PeerAddr, diff: Difficulty, height: u64) {
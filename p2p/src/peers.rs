@@ -21,6 +21,7 @@ use std::sync::Arc;
 
 use rand::prelude::*;
 
+use crate::addrman::{AddResult, AddrMan, NetGroup, Table};
 use crate::chain;
 use crate::chain::txhashset::BitmapChunk;
 use crate::msg::PeerAddrs;
@@ -43,6 +44,9 @@ use mwc_util::StopState;
 
 const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
 
+/// Outbound peer needs to stay connected that long to become an anchor
+const ANCHOR_MIN_CONNECTION_TIME: i64 = 600;
+
 struct PeersCapabilities {
 	capabilities: Capabilities,
 	time: DateTime<Utc>,
@@ -56,6 +60,8 @@ pub struct Peers {
 	stop_state: Arc<StopState>,
 	boost_peers_capabilities: RwLock<PeersCapabilities>,
 	excluded_peers: Arc<RwLock<HashSet<PeerAddr>>>,
+	addrman: RwLock<AddrMan>,
+	anchors: RwLock<Vec<PeerAddr>>,
 }
 
 impl Peers {
@@ -65,6 +71,11 @@ impl Peers {
 		config: P2PConfig,
 		stop_state: Arc<StopState>,
 	) -> Peers {
+		let addrman = Self::load_addrman(&store);
+		let anchors = store.get_anchors().unwrap_or_else(|e| {
+			error!("Unable to read anchor peers, {}", e);
+			vec![]
+		});
 		Peers {
 			adapter,
 			store,
@@ -76,7 +87,36 @@ impl Peers {
 				time: DateTime::default(),
 			}),
 			excluded_peers: Arc::new(RwLock::new(HashSet::new())),
+			addrman: RwLock::new(addrman),
+			anchors: RwLock::new(anchors),
+		}
+	}
+
+	/// Build the address manager tables from the stored peers. The source of the
+	/// addresses is not stored, the address itself is used instead.
+	fn load_addrman(store: &PeerStore) -> AddrMan {
+		let key = store.addrman_key().unwrap_or_else(|e| {
+			error!("Unable to read address manager key, {}", e);
+			(0..32).map(|_| thread_rng().gen::<u8>()).collect()
+		});
+		let mut addrman = AddrMan::new(&key);
+		match store.peers_iter() {
+			Ok(peers) => {
+				for p in peers.filter(|p| p.flags != State::Banned) {
+					addrman.add(&p.addr, &p.addr, |_| false);
+					if p.last_connected > 0 && p.capabilities != Capabilities::UNKNOWN {
+						addrman.mark_tried(&p.addr);
+					}
+				}
+			}
+			Err(e) => error!("Unable to read peers for address manager, {}", e),
 		}
+		let (new_count, tried_count) = addrman.size();
+		debug!(
+			"Address manager loaded {} new and {} tried peers",
+			new_count, tried_count
+		);
+		addrman
 	}
 
 	/// Mark those peers as excluded, so the will never be in 'connected' list
@@ -128,6 +168,7 @@ impl Peers {
 	/// returned so the server can run it.
 	pub fn add_connected(&self, peer: Arc<Peer>) -> Result<(), Error> {
 		let peer_data: PeerData;
+		let outbound: bool;
 		{
 			// Scope for peers vector lock - dont hold the peers lock while adding to lmdb
 			let mut peers = self.peers.try_write_for(LOCK_TIMEOUT).ok_or_else(|| {
@@ -144,8 +185,17 @@ impl Peers {
 				last_connected: Utc::now().timestamp(),
 			};
 			info!("Adding newly connected Healthy peer {}.", peer_data.addr);
+			outbound = peer.info.is_outbound();
 			peers.insert(peer_data.addr.clone(), peer);
 		}
+		{
+			let mut addrman = self.addrman.write();
+			if outbound {
+				addrman.mark_tried(&peer_data.addr);
+			} else {
+				addrman.add(&peer_data.addr, &peer_data.addr, |_| false);
+			}
+		}
 		if let Err(e) = self.save_peer(&peer_data) {
 			error!("Could not save connected peer address: {:?}", e);
 		}
@@ -392,6 +442,7 @@ impl Peers {
 
 	/// Get and delete peer from the store by address. It is needed for peer renaming
 	pub fn delete_peer(&self, peer_addr: &PeerAddr) -> Result<(), Error> {
+		self.addrman.write().remove(peer_addr);
 		self.store.delete_peer(peer_addr).map_err(From::from)
 	}
 
@@ -412,9 +463,152 @@ impl Peers {
 
 	/// Updates the state of a peer in store
 	pub fn update_state(&self, peer_addr: &PeerAddr, new_state: State) -> Result<(), Error> {
-		self.store
-			.update_state(peer_addr, new_state)
-			.map_err(From::from)
+		self.store.update_state(peer_addr, new_state)?;
+		// Banned addresses stay at the address manager tables, they are never selected and
+		// are replaced first. So after the unban the address keeps the bucket of its original
+		// source. Only the addresses that are not known yet (banned before the start) are added.
+		if new_state != State::Banned {
+			self.addrman.write().add(peer_addr, peer_addr, |_| false);
+		}
+		Ok(())
+	}
+
+	/// Select up to `count` peers for the outbound connections from the address manager.
+	/// Peers are picked from the 'new' and 'tried' tables with equal chances. Only healthy
+	/// and not connected peers with the capabilities `cap` are returned.
+	pub fn select_peers(&self, cap: Capabilities, count: usize) -> Vec<PeerData> {
+		// Candidates are picked under the address manager lock, the store and the connected
+		// peers are checked after it is released.
+		let mut candidates: Vec<PeerAddr> = vec![];
+		{
+			let addrman = self.addrman.read();
+			let (new_count, tried_count) = addrman.size();
+			let mut rng = thread_rng();
+			let mut selected: HashSet<PeerAddr> = HashSet::new();
+			// Selection is random, the same addresses can be picked many times
+			for _ in 0..count * 10 {
+				let table = if tried_count == 0 {
+					Table::New
+				} else if new_count == 0 || rng.gen_bool(0.5) {
+					Table::Tried
+				} else {
+					Table::New
+				};
+				let addr = match addrman.select(table, &mut rng) {
+					Some(addr) => addr,
+					None => break,
+				};
+				if selected.insert(addr.clone()) {
+					candidates.push(addr);
+				}
+			}
+		}
+
+		let mut res: Vec<PeerData> = vec![];
+		let mut missing: Vec<PeerAddr> = vec![];
+		for addr in candidates {
+			if res.len() >= count {
+				break;
+			}
+			if self.is_known(&addr).unwrap_or(true) {
+				continue;
+			}
+			match self.store.get_peer(&addr) {
+				Ok(p) => {
+					if p.flags == State::Healthy
+						&& (p.capabilities == Capabilities::UNKNOWN || p.capabilities.contains(cap))
+					{
+						res.push(p);
+					}
+				}
+				Err(_) => missing.push(addr),
+			}
+		}
+		// Expired peers are deleted from the store, forget them as well
+		if !missing.is_empty() {
+			let mut addrman = self.addrman.write();
+			for addr in &missing {
+				addrman.remove(addr);
+			}
+		}
+		res
+	}
+
+	/// Number of the connected outbound peers at every network group
+	pub fn outbound_groups(&self) -> HashMap<NetGroup, usize> {
+		let mut groups: HashMap<NetGroup, usize> = HashMap::new();
+		for p in self.iter().outbound().connected() {
+			*groups.entry(NetGroup::of(&p.info.addr)).or_insert(0) += 1;
+		}
+		groups
+	}
+
+	/// Peers that are not limited by the network group: preferred, allowed and anchor
+	/// peers, and the local addresses.
+	fn is_group_exempt(&self, addr: &PeerAddr) -> bool {
+		NetGroup::of(addr).is_local()
+			|| self
+				.config
+				.peers_preferred
+				.as_ref()
+				.map(|p| p.contains(addr))
+				.unwrap_or(false)
+			|| self
+				.config
+				.peers_allow
+				.as_ref()
+				.map(|p| p.contains(addr))
+				.unwrap_or(false)
+			|| self.anchors.read().contains(addr)
+	}
+
+	/// Check if the network group of the address already has enough outbound connections
+	pub fn is_group_limited(&self, addr: &PeerAddr) -> bool {
+		if self.is_group_exempt(addr) {
+			return false;
+		}
+		let group = NetGroup::of(addr);
+		let count = self
+			.iter()
+			.outbound()
+			.connected()
+			.filter(|p| NetGroup::of(&p.info.addr) == group)
+			.count();
+		count >= self.config.peer_max_outbound_per_group() as usize
+	}
+
+	/// Anchor peers from the previous run, they are reconnected first
+	pub fn get_anchors(&self) -> Vec<PeerAddr> {
+		self.anchors.read().clone()
+	}
+
+	/// Save the longest connected outbound peers as anchors. Only peers that stayed
+	/// connected for a while qualify, so the anchors of the previous run are not
+	/// replaced with the seeds right after the start.
+	pub fn update_anchors(&self) {
+		let anchor_count = self.config.peer_anchor_count() as usize;
+		let min_first_seen = Utc::now() - Duration::seconds(ANCHOR_MIN_CONNECTION_TIME);
+		let mut candidates: Vec<(DateTime<Utc>, PeerAddr)> = self
+			.iter()
+			.outbound()
+			.connected()
+			.map(|p| (p.info.first_seen(), p.info.addr.clone()))
+			.filter(|(first_seen, _)| *first_seen < min_first_seen)
+			.collect();
+		candidates.sort_by_key(|(first_seen, _)| *first_seen);
+		let anchors: Vec<PeerAddr> = candidates
+			.into_iter()
+			.take(anchor_count)
+			.map(|(_, addr)| addr)
+			.collect();
+		if anchors.is_empty() || *self.anchors.read() == anchors {
+			return;
+		}
+		debug!("Updating anchor peers: {:?}", anchors);
+		if let Err(e) = self.store.save_anchors(anchors.clone()) {
+			error!("Unable to save anchor peers, {}", e);
+		}
+		*self.anchors.write() = anchors;
 	}
 
 	/// Iterate over the peer list and prune all peers we have
@@ -466,8 +660,55 @@ impl Peers {
 			}
 		}
 
-		// closure to build an iterator of our inbound peers
-		let outbound_peers = || self.iter().outbound().connected().into_iter();
+		// block relay only peers are counted separately from the full outbound peers
+		let block_relay_peers = || {
+			self.iter()
+				.outbound()
+				.block_relay_only()
+				.connected()
+				.into_iter()
+		};
+		let excess_block_relay_count = block_relay_peers()
+			.count()
+			.saturating_sub(config.peer_block_relay_only_count() as usize);
+		if excess_block_relay_count > 0 {
+			let mut addrs = block_relay_peers()
+				.map(|x| x.info.addr.clone())
+				.take(excess_block_relay_count)
+				.collect();
+			rm.append(&mut addrs);
+		}
+
+		// closure to build an iterator of our outbound peers
+		let outbound_peers = || self.iter().outbound().full_relay().connected().into_iter();
+		let anchors = self.get_anchors();
+
+		// too many outbound connections to the same network group, the newest ones are dropped
+		let max_per_group = config.peer_max_outbound_per_group() as usize;
+		for (group, count) in self.outbound_groups() {
+			if count <= max_per_group || group.is_local() {
+				continue;
+			}
+			let mut peer_infos: Vec<_> = self
+				.iter()
+				.outbound()
+				.connected()
+				.map(|x| x.info.clone())
+				.filter(|x| NetGroup::of(&x.addr) == group && !self.is_group_exempt(&x.addr))
+				.collect();
+			peer_infos.sort_unstable_by_key(|x| cmp::Reverse(x.first_seen()));
+			let excess = count - max_per_group;
+			info!(
+				"clean_peers, {} outbound peers at network group {}, dropping {}",
+				count, group, excess
+			);
+			let mut addrs = peer_infos
+				.into_iter()
+				.map(|x| x.addr)
+				.take(excess)
+				.collect();
+			rm.append(&mut addrs);
+		}
 
 		if boost_capability != Capabilities::UNKNOWN {
 			// at max half of peers can be with wrong capability. Others let's close. Random order is fine
@@ -477,7 +718,9 @@ impl Peers {
 			let mut addrs = outbound_peers()
 				.map(|x| x.info.clone())
 				.filter(|x| {
-					!preferred_peers.contains(&x.addr) && !x.capabilities.contains(boost_capability)
+					!preferred_peers.contains(&x.addr)
+						&& !anchors.contains(&x.addr)
+						&& !x.capabilities.contains(boost_capability)
 				})
 				.map(|x| x.addr)
 				.take(excess_outgoing_count)
@@ -496,7 +739,7 @@ impl Peers {
 		if excess_outgoing_count > 0 {
 			let mut peer_infos: Vec<_> = outbound_peers()
 				.map(|x| x.info.clone())
-				.filter(|x| !preferred_peers.contains(&x.addr))
+				.filter(|x| !preferred_peers.contains(&x.addr) && !anchors.contains(&x.addr))
 				.collect();
 			peer_infos.sort_unstable_by_key(|x| x.total_difficulty());
 			let mut addrs = peer_infos
@@ -538,6 +781,7 @@ impl Peers {
 	}
 
 	pub fn stop(&self) {
+		self.update_anchors();
 		let mut peers = self.peers.write();
 		for peer in peers.values() {
 			peer.stop();
@@ -549,12 +793,28 @@ impl Peers {
 
 	/// We have enough outbound connected peers
 	pub fn enough_outbound_peers(&self) -> bool {
-		self.iter().outbound().connected().count()
+		self.iter().outbound().full_relay().connected().count()
 			>= self
 				.config
 				.peer_min_preferred_outbound_count(self.is_sync_mode()) as usize
 	}
 
+	/// Number of the block relay only outbound connections to open. They are opened
+	/// after we have enough full outbound peers, the block relay only peers don't
+	/// share the addresses.
+	pub fn block_relay_peers_needed(&self) -> usize {
+		if !self.enough_outbound_peers() {
+			return 0;
+		}
+		(self.config.peer_block_relay_only_count() as usize).saturating_sub(
+			self.iter()
+				.outbound()
+				.block_relay_only()
+				.connected()
+				.count(),
+		)
+	}
+
 	/// Removes those peers that seem to have expired
 	pub fn remove_expired(&self) {
 		let now = Utc::now();
@@ -686,6 +946,10 @@ impl ChainAdapter for Peers {
 		self.adapter.get_block(h, peer_info)
 	}
 
+	fn get_block_header(&self, h: &Hash) -> Option<core::BlockHeader> {
+		self.adapter.get_block_header(h)
+	}
+
 	fn txhashset_read(&self, h: Hash) -> Option<TxHashSetRead> {
 		self.adapter.txhashset_read(h)
 	}
@@ -844,15 +1108,43 @@ impl NetAdapter for Peers {
 	}
 
 	/// A list of peers has been received from one of our peers.
-	fn peer_addrs_received(&self, peer_addrs: Vec<PeerAddr>) {
-		trace!("Received {} peer addrs, saving.", peer_addrs.len());
-		let mut to_save: Vec<PeerData> = Vec::new();
-		for pa in peer_addrs {
-			if let Ok(e) = self.exists_peer(&pa) {
-				if e {
-					continue;
+	fn peer_addrs_received(&self, source: &PeerAddr, peer_addrs: Vec<PeerAddr>) {
+		trace!(
+			"Received {} peer addrs from {}, saving.",
+			peer_addrs.len(),
+			source
+		);
+		let peer_addrs: Vec<PeerAddr> = peer_addrs
+			.into_iter()
+			.filter(|pa| !self.exists_peer(pa).unwrap_or(false))
+			.collect();
+
+		// Don't hold the address manager lock while reading the store
+		let mut accepted: Vec<PeerAddr> = Vec::new();
+		let mut collisions: Vec<(PeerAddr, PeerAddr)> = Vec::new();
+		{
+			let mut addrman = self.addrman.write();
+			for pa in peer_addrs {
+				match addrman.try_add(&pa, source) {
+					AddResult::Added => accepted.push(pa),
+					AddResult::Known => {}
+					AddResult::Collision(existing) => collisions.push((pa, existing)),
 				}
 			}
+		}
+		// Bucket position is taken, the address is dropped unless it replaces a bad one
+		for (pa, existing) in collisions {
+			let is_terrible = match self.store.get_peer(&existing) {
+				Ok(p) => p.flags != State::Healthy,
+				Err(_) => true,
+			};
+			if is_terrible && self.addrman.write().replace(&existing, &pa, source) {
+				accepted.push(pa);
+			}
+		}
+
+		let mut to_save: Vec<PeerData> = Vec::new();
+		for pa in accepted {
 			let peer = PeerData {
 				addr: pa,
 				capabilities: Capabilities::UNKNOWN,
@@ -936,6 +1228,20 @@ impl<I: Iterator<Item = Arc<Peer>>> PeersIter<I> {
 		}
 	}
 
+	/// Filter peers that relay transactions, blocks and addresses.
+	pub fn full_relay(self) -> PeersIter<impl Iterator<Item = Arc<Peer>>> {
+		PeersIter {
+			iter: self.iter.filter(|p| !p.info.is_block_relay_only()),
+		}
+	}
+
+	/// Filter peers that relay only headers and blocks.
+	pub fn block_relay_only(self) -> PeersIter<impl Iterator<Item = Arc<Peer>>> {
+		PeersIter {
+			iter: self.iter.filter(|p| p.info.is_block_relay_only()),
+		}
+	}
+
 	/// Filter peers with the provided difficulty comparison fn.
 	///
 	/// with_difficulty(|x| x > diff)
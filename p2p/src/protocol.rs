@@ -14,6 +14,7 @@
 // limitations under the License.
 
 use crate::conn::MessageHandler;
+use crate::mwc_core::consensus;
 use crate::mwc_core::core::{hash::Hashed, CompactBlock};
 use crate::{chain, Capabilities, ReasonForBan};
 
@@ -42,6 +43,11 @@ impl Protocol {
 	}
 }
 
+/// Block at `height` is older than a day, it is not served when the upload quota is exceeded
+fn is_historical(height: u64, head_height: u64) -> bool {
+	height + consensus::DAY_HEIGHT < head_height
+}
+
 impl MessageHandler for Protocol {
 	fn consume(&self, message: Message) -> Result<Consumed, Error> {
 		let adapter = &self.adapter;
@@ -57,6 +63,46 @@ impl MessageHandler for Protocol {
 			return Ok(Consumed::Disconnect);
 		}
 
+		// Block relay only peers exchange headers and blocks, the transactions and
+		// addresses are dropped
+		if self.peer_info.is_block_relay_only() {
+			match message {
+				Message::TransactionKernel(_)
+				| Message::GetTransaction(_)
+				| Message::Transaction(_)
+				| Message::StemTransaction(_)
+				| Message::GetPeerAddrs(_)
+				| Message::PeerAddrs(_) => {
+					debug!(
+						"handler: consume: block relay only peer {}, dropping {}",
+						self.peer_info.addr, message,
+					);
+					return Ok(Consumed::None);
+				}
+				_ => {}
+			}
+		}
+
+		// Daily upload quota is used up, the history is not served
+		let upload_quota_exceeded = self.server.bandwidth.upload_quota_exceeded();
+		if upload_quota_exceeded {
+			match message {
+				Message::TxHashSetRequest(_)
+				| Message::StartPibdSyncRequest(_)
+				| Message::GetOutputBitmapSegment(_)
+				| Message::GetOutputSegment(_)
+				| Message::GetRangeProofSegment(_)
+				| Message::GetKernelSegment(_) => {
+					debug!(
+						"handler: consume: daily upload quota exceeded, dropping {} from {}",
+						message, self.peer_info.addr,
+					);
+					return Ok(Consumed::None);
+				}
+				_ => {}
+			}
+		}
+
 		let consumed = match message {
 			Message::Attachment(_update, _) => {
 				error!("handle_payload: Message::Attachment received but we never requested it. It is disabled in this version of node");
@@ -120,8 +166,20 @@ impl MessageHandler for Protocol {
 
 			Message::GetBlock(h) => {
 				trace!("handle_payload: GetBlock: {}", h);
-				let bo = adapter.get_block(h, &self.peer_info);
-				if let Some(b) = bo {
+				// Only the recent blocks are served when the upload quota is exceeded. Height is
+				// checked with the header, so the historical block is not loaded.
+				let historical = upload_quota_exceeded
+					&& match adapter.get_block_header(&h) {
+						Some(header) => is_historical(header.height, adapter.total_height()?),
+						None => true,
+					};
+				if historical {
+					debug!(
+						"handle_payload: daily upload quota exceeded, not serving historical block {} to {}",
+						h, self.peer_info.addr
+					);
+					Consumed::None
+				} else if let Some(b) = adapter.get_block(h, &self.peer_info) {
 					Consumed::Response(Msg::new(Type::Block, b, self.peer_info.version)?)
 				} else {
 					Consumed::None
@@ -223,8 +281,10 @@ impl MessageHandler for Protocol {
 			}
 
 			Message::GetPeerAddrs(get_peers) => {
-				let mut peers =
-					adapter.find_peer_addrs(get_peers.capabilities & !Capabilities::TOR_ADDRESS);
+				let mut peers = adapter.find_peer_addrs(
+					get_peers.capabilities
+						& !(Capabilities::TOR_ADDRESS | Capabilities::I2P_ADDRESS),
+				);
 
 				// Loopbacks really not interesting for other peers. Loopbacks are possible because of the local setup
 				peers.retain(|p| !p.is_loopback());
@@ -245,6 +305,18 @@ impl MessageHandler for Protocol {
 				} else {
 					peers
 				};
+				// Same for I2P, the old peers can't read the I2P addresses
+				let peers = if !get_peers.capabilities.contains(Capabilities::I2P_ADDRESS) {
+					peers
+						.into_iter()
+						.filter(|p| match p {
+							PeerAddr::I2p(_) => false,
+							_ => true,
+						})
+						.collect()
+				} else {
+					peers
+				};
 
 				Consumed::Response(Msg::new(
 					Type::PeerAddrs,
@@ -269,6 +341,13 @@ impl MessageHandler for Protocol {
 								}
 							}
 						}
+						PeerAddr::I2p(address) => {
+							if self.server.self_i2p_address() == Some(address.as_str()) {
+								debug!("Not pushing self i2p address = {}", address);
+							} else {
+								peers.push(peer);
+							}
+						}
 						PeerAddr::Ip(_) => {
 							if peer.is_loopback() {
 								debug!("Not pushing loopback addresse = {:?}", peer);
@@ -278,7 +357,7 @@ impl MessageHandler for Protocol {
 						}
 					}
 				}
-				adapter.peer_addrs_received(peers);
+				adapter.peer_addrs_received(&self.peer_info.addr, peers);
 				Consumed::None
 			}
 
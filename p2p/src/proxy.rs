@@ -0,0 +1,222 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SOCKS5 proxy for the outbound peer connections. Unlike the node Tor, the proxy
+//! is only used for connecting, the node doesn't listen through it.
+
+use crate::types::{PeerAddr, Socks5ProxyConfig};
+use socks::TargetAddr;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Port of the onion service peers
+const ONION_PEER_PORT: u16 = 80;
+/// Timeout for connecting to the proxy and for every step of the handshake
+pub const PROXY_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0;
+const METHOD_PASSWORD: u8 = 2;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+const PASSWORD_VERSION: u8 = 1;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+/// Target address of the peer for the proxy. Onion addresses are passed as the
+/// domain names, so they are resolved by the proxy. I2P peers are not reachable
+/// with the SOCKS5 proxy, they are connected with the SAM bridge.
+pub fn target_addr(addr: &PeerAddr) -> io::Result<TargetAddr> {
+	match addr {
+		PeerAddr::Ip(ip) => Ok(TargetAddr::Ip(*ip)),
+		PeerAddr::Onion(onion) => Ok(TargetAddr::Domain(onion.clone(), ONION_PEER_PORT)),
+		PeerAddr::I2p(address) => Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!(
+				"I2P address {} can't be connected with SOCKS5 proxy",
+				address
+			),
+		)),
+	}
+}
+
+fn proxy_error(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, msg)
+}
+
+/// Connect to the peer through the SOCKS5 proxy. The username/password authentication
+/// is used if the username is configured. Connecting to the proxy and the handshake
+/// are limited by `PROXY_TIMEOUT`, so a stalled proxy doesn't block the caller.
+pub fn connect(proxy: &Socks5ProxyConfig, addr: &PeerAddr) -> io::Result<TcpStream> {
+	let target = target_addr(addr)?;
+	let proxy_addr = (proxy.host.as_str(), proxy.port)
+		.to_socket_addrs()?
+		.next()
+		.ok_or_else(|| proxy_error(format!("Unable to resolve proxy host {}", proxy.host)))?;
+	let mut stream = TcpStream::connect_timeout(&proxy_addr, PROXY_TIMEOUT)?;
+	stream.set_read_timeout(Some(PROXY_TIMEOUT))?;
+	stream.set_write_timeout(Some(PROXY_TIMEOUT))?;
+
+	authenticate(&mut stream, proxy)?;
+	connect_target(&mut stream, &target)?;
+
+	// Done with the proxy, the stream is the peer stream now
+	stream.set_read_timeout(None)?;
+	stream.set_write_timeout(None)?;
+	Ok(stream)
+}
+
+/// Negotiate the authentication method (RFC 1928) and run the username/password
+/// subnegotiation (RFC 1929) if the proxy asks for it
+fn authenticate(stream: &mut TcpStream, proxy: &Socks5ProxyConfig) -> io::Result<()> {
+	if proxy.username.is_some() {
+		stream.write_all(&[SOCKS_VERSION, 2, METHOD_NO_AUTH, METHOD_PASSWORD])?;
+	} else {
+		stream.write_all(&[SOCKS_VERSION, 1, METHOD_NO_AUTH])?;
+	}
+	let mut reply = [0u8; 2];
+	stream.read_exact(&mut reply)?;
+	if reply[0] != SOCKS_VERSION {
+		return Err(proxy_error(format!(
+			"Invalid SOCKS version {} at proxy reply",
+			reply[0]
+		)));
+	}
+	match (reply[1], &proxy.username) {
+		(METHOD_NO_AUTH, _) => Ok(()),
+		(METHOD_PASSWORD, Some(username)) => {
+			let password = proxy.password.as_deref().unwrap_or("");
+			if username.is_empty() || username.len() > 255 || password.len() > 255 {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidInput,
+					"SOCKS5 username and password must be 1 to 255 bytes long",
+				));
+			}
+			let mut req = vec![PASSWORD_VERSION, username.len() as u8];
+			req.extend_from_slice(username.as_bytes());
+			req.push(password.len() as u8);
+			req.extend_from_slice(password.as_bytes());
+			stream.write_all(&req)?;
+
+			let mut reply = [0u8; 2];
+			stream.read_exact(&mut reply)?;
+			if reply[1] != 0 {
+				return Err(io::Error::new(
+					io::ErrorKind::PermissionDenied,
+					"SOCKS5 proxy rejected the username and password",
+				));
+			}
+			Ok(())
+		}
+		(METHOD_NONE_ACCEPTABLE, _) => Err(io::Error::new(
+			io::ErrorKind::PermissionDenied,
+			"SOCKS5 proxy doesn't accept any of the authentication methods",
+		)),
+		(method, _) => Err(proxy_error(format!(
+			"SOCKS5 proxy selected unexpected authentication method {}",
+			method
+		))),
+	}
+}
+
+/// Send the CONNECT request and read the reply, the bound address is consumed so
+/// the peer data starts right after it
+fn connect_target(stream: &mut TcpStream, target: &TargetAddr) -> io::Result<()> {
+	let mut req = vec![SOCKS_VERSION, CMD_CONNECT, 0];
+	let port = match target {
+		TargetAddr::Ip(SocketAddr::V4(addr)) => {
+			req.push(ATYP_IPV4);
+			req.extend_from_slice(&addr.ip().octets());
+			addr.port()
+		}
+		TargetAddr::Ip(SocketAddr::V6(addr)) => {
+			req.push(ATYP_IPV6);
+			req.extend_from_slice(&addr.ip().octets());
+			addr.port()
+		}
+		TargetAddr::Domain(domain, port) => {
+			if domain.is_empty() || domain.len() > 255 {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidInput,
+					format!("Invalid SOCKS5 target domain {}", domain),
+				));
+			}
+			req.push(ATYP_DOMAIN);
+			req.push(domain.len() as u8);
+			req.extend_from_slice(domain.as_bytes());
+			*port
+		}
+	};
+	req.extend_from_slice(&port.to_be_bytes());
+	stream.write_all(&req)?;
+
+	let mut reply = [0u8; 4];
+	stream.read_exact(&mut reply)?;
+	if reply[0] != SOCKS_VERSION {
+		return Err(proxy_error(format!(
+			"Invalid SOCKS version {} at proxy reply",
+			reply[0]
+		)));
+	}
+	if reply[1] != 0 {
+		return Err(proxy_error(format!(
+			"SOCKS5 proxy failed to connect to {}, {}",
+			target_display(target),
+			reply_message(reply[1])
+		)));
+	}
+	let addr_len = match reply[3] {
+		ATYP_IPV4 => 4,
+		ATYP_IPV6 => 16,
+		ATYP_DOMAIN => {
+			let mut len = [0u8; 1];
+			stream.read_exact(&mut len)?;
+			len[0] as usize
+		}
+		t => {
+			return Err(proxy_error(format!(
+				"Unexpected address type {} at proxy reply",
+				t
+			)))
+		}
+	};
+	// Bound address and port, not needed
+	let mut bound = vec![0u8; addr_len + 2];
+	stream.read_exact(&mut bound)?;
+	Ok(())
+}
+
+fn target_display(target: &TargetAddr) -> String {
+	match target {
+		TargetAddr::Ip(addr) => addr.to_string(),
+		TargetAddr::Domain(domain, port) => format!("{}:{}", domain, port),
+	}
+}
+
+/// Reply codes of RFC 1928
+fn reply_message(rep: u8) -> &'static str {
+	match rep {
+		1 => "general SOCKS server failure",
+		2 => "connection not allowed by ruleset",
+		3 => "network unreachable",
+		4 => "host unreachable",
+		5 => "connection refused",
+		6 => "TTL expired",
+		7 => "command not supported",
+		8 => "address type not supported",
+		_ => "unknown error",
+	}
+}
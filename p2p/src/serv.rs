@@ -15,15 +15,17 @@
 
 use crate::types::PeerAddr::Onion;
 use std::io;
-use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4, TcpListener, TcpStream};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use crate::bandwidth::Bandwidth;
 use crate::chain;
 use crate::chain::txhashset::BitmapChunk;
 use crate::handshake::Handshake;
+use crate::i2p::I2pSession;
 use crate::mwc_core::core;
 use crate::mwc_core::core::hash::Hash;
 use crate::mwc_core::core::{OutputIdentifier, Segment, SegmentIdentifier, TxKernel};
@@ -31,10 +33,12 @@ use crate::mwc_core::global;
 use crate::mwc_core::pow::Difficulty;
 use crate::peer::Peer;
 use crate::peers::Peers;
+use crate::proxy;
 use crate::store::PeerStore;
+use crate::transport::{PeerStream, TcpTransport, Transport};
 use crate::types::{
-	Capabilities, ChainAdapter, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo, ReasonForBan,
-	TxHashSetRead,
+	Capabilities, ChainAdapter, ConnectionType, Error, NetAdapter, P2PConfig, PeerAddr, PeerInfo,
+	ReasonForBan, TxHashSetRead,
 };
 use crate::util::secp::pedersen::RangeProof;
 use crate::util::StopState;
@@ -54,6 +58,9 @@ pub struct Server {
 	sync_state: Arc<SyncState>,
 	stop_state: Arc<StopState>,
 	pub self_onion_address: Option<String>,
+	transport: Arc<dyn Transport>,
+	i2p: Option<Arc<I2pSession>>,
+	pub bandwidth: Arc<Bandwidth>,
 }
 
 // TODO TLS
@@ -70,6 +77,7 @@ impl Server {
 		socks_port: u16,
 		onion_address: Option<String>,
 	) -> Result<Server, Error> {
+		let bandwidth = Arc::new(Bandwidth::new(&config));
 		Ok(Server {
 			config: config.clone(),
 			capabilities,
@@ -88,16 +96,46 @@ impl Server {
 			stop_state,
 			socks_port,
 			self_onion_address: onion_address,
+			transport: Arc::new(TcpTransport),
+			i2p: None,
+			bandwidth,
 		})
 	}
 
+	/// Replace the transport for the peer connections. TCP is used by default.
+	pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+		self.transport = transport;
+		self
+	}
+
+	/// Use the I2P session for the connections to the I2P peers
+	pub fn with_i2p(mut self, session: Arc<I2pSession>) -> Self {
+		self.i2p = Some(session);
+		self
+	}
+
+	/// I2P base32 address of this node, if I2P is configured
+	pub fn self_i2p_address(&self) -> Option<&str> {
+		self.i2p.as_ref().map(|s| s.address())
+	}
+
+	/// Check if the outbound connections to the I2P addresses are possible
+	pub fn can_connect_i2p(&self) -> bool {
+		self.i2p.is_some()
+	}
+
+	/// Check if the outbound connections to the onion addresses are possible, either
+	/// with the node Tor or with the SOCKS5 proxy.
+	pub fn can_connect_onion(&self) -> bool {
+		self.socks_port != 0 || self.config.onion_proxy().is_some()
+	}
+
 	/// Starts a new TCP server and listen to incoming connections. This is a
 	/// blocking call until the TCP server stops.
 	pub fn listen(&self) -> Result<(), Error> {
 		// start TCP listener and handle incoming connections
 		let addr = SocketAddr::new(self.config.host, self.config.port);
-		let listener = TcpListener::bind(addr)?;
-		listener.set_nonblocking(true)?;
+		let listener = self.transport.bind(addr)?;
 
 		let sleep_time = Duration::from_millis(5);
 		loop {
@@ -109,14 +147,6 @@ impl Server {
 
 			match listener.accept() {
 				Ok((stream, peer_addr)) => {
-					// We want out TCP stream to be in blocking mode.
-					// The TCP listener is in nonblocking mode so we *must* explicitly
-					// move the accepted TCP stream into blocking mode (or all kinds of
-					// bad things can and will happen).
-					// A nonblocking TCP listener will accept nonblocking TCP streams which
-					// we do not want.
-					stream.set_nonblocking(false)?;
-
 					let mut peer_addr = PeerAddr::Ip(peer_addr);
 
 					// attempt to see if it an ipv4-mapped ipv6
@@ -170,9 +200,81 @@ impl Server {
 		Ok(())
 	}
 
+	/// Accept the incoming connections at the node I2P destination. This is a
+	/// blocking call until the server stops.
+	pub fn listen_i2p(&self) -> Result<(), Error> {
+		let session = match &self.i2p {
+			Some(session) => session.clone(),
+			None => return Err(Error::I2p("I2P is not configured".to_string())),
+		};
+
+		loop {
+			if self.stop_state.is_stopped() {
+				break;
+			}
+			// Pause peer ingress connection request. Only for tests.
+			if self.stop_state.is_paused() {
+				thread::sleep(Duration::from_secs(1));
+				continue;
+			}
+
+			match session.accept() {
+				Ok((stream, address)) => {
+					let peer_addr = PeerAddr::I2p(address);
+					if self.stop_state.is_stopped() {
+						break;
+					}
+					if self.check_undesirable_addr(&peer_addr) {
+						if let Err(e) = stream.shutdown(Shutdown::Both) {
+							debug!("Error shutting down conn: {:?}", e);
+						}
+						continue;
+					}
+					match self.handle_new_peer(Box::new(stream)) {
+						Err(Error::ConnectionClose(err)) => {
+							debug!("shutting down, ignoring a new peer, {}", err)
+						}
+						Err(e) => {
+							debug!("Error accepting peer {}: {:?}", peer_addr, e);
+							let _ = self.peers.add_banned(peer_addr, ReasonForBan::BadHandshake);
+						}
+						Ok(_) => {}
+					}
+				}
+				Err(e) => {
+					// SAM bridge might be restarting, don't spin on it
+					warn!("Couldn't accept I2P connection: {}", e);
+					thread::sleep(Duration::from_secs(10));
+					if !session.is_alive() {
+						match session.recreate() {
+							Ok(_) => info!("I2P session is created again"),
+							Err(e) => warn!("Unable to create I2P session, {}", e),
+						}
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
 	/// Asks the server to connect to a new peer. Directly returns the peer if
 	/// we're already connected to the provided address.
 	pub fn connect(&self, addr: &PeerAddr) -> Result<Arc<Peer>, Error> {
+		self.connect_with_type(addr, ConnectionType::FullRelay)
+	}
+
+	/// Asks the server to open a block relay only connection to a new peer. Only headers
+	/// and blocks are relayed with this peer. Directly returns the peer if we're already
+	/// connected to the provided address.
+	pub fn connect_block_relay_only(&self, addr: &PeerAddr) -> Result<Arc<Peer>, Error> {
+		self.connect_with_type(addr, ConnectionType::BlockRelayOnly)
+	}
+
+	fn connect_with_type(
+		&self,
+		addr: &PeerAddr,
+		connection_type: ConnectionType,
+	) -> Result<Arc<Peer>, Error> {
 		if self.stop_state.is_stopped() {
 			return Err(Error::ConnectionClose(String::from("node is stopping")));
 		}
@@ -211,6 +313,7 @@ impl Server {
 					}
 					debug!("not self, connecting to {}", address);
 				}
+				PeerAddr::I2p(_) => {}
 				Ip(_) => {
 					if addr.is_loopback() {
 						debug!("error trying to connect with self: {:?}", addr);
@@ -220,6 +323,14 @@ impl Server {
 			}
 		}
 
+		// check if the I2P address is self
+		if let (PeerAddr::I2p(address), Some(self_address)) = (addr, self.self_i2p_address()) {
+			if address == self_address {
+				debug!("error trying to connect with self: {}", address);
+				return Err(Error::PeerWithSelf);
+			}
+		}
+
 		if let Some(p) = self.peers.get_connected_peer(addr) {
 			// if we're already connected to the addr, just return the peer
 			trace!("connect_peer: already connected {}", addr);
@@ -236,36 +347,41 @@ impl Server {
 		let peer_addr;
 		let self_addr;
 
-		let stream = match addr.clone() {
+		let stream: Box<dyn PeerStream> = match addr.clone() {
 			PeerAddr::Ip(address) => {
 				// we do this, not a good solution, but for now, we'll use it. Other side usually detects with ip.
 				self_addr = PeerAddr::Ip(SocketAddr::new(self.config.host, self.config.port));
-				if self.socks_port != 0 {
+				if let Some(proxy_config) = self.config.clearnet_proxy() {
+					peer_addr = Some(PeerAddr::Ip(address));
+					Box::new(proxy::connect(proxy_config, addr)?)
+				} else if self.socks_port != 0 {
 					peer_addr = Some(PeerAddr::Ip(address));
 					let proxy_addr =
 						SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), self.socks_port);
 					let socks5_stream_ref =
 						tor_stream::TorStream::connect_with_address(proxy_addr, address);
 					match socks5_stream_ref {
-						Ok(socks5_stream) => socks5_stream.unwrap(),
+						Ok(socks5_stream) => Box::new(socks5_stream.unwrap()),
 						Err(e) => {
 							return Err(Error::Connection(e));
 						}
 					}
 				} else {
 					peer_addr = Some(PeerAddr::Ip(address));
-					TcpStream::connect_timeout(&address, Duration::from_secs(10))?
+					self.transport.connect(&address, Duration::from_secs(10))?
 				}
 			}
 			PeerAddr::Onion(onion_address) => {
-				if self.socks_port != 0 {
-					self_addr = PeerAddr::Onion(
-						self.self_onion_address
-							.as_ref()
-							.unwrap_or(&"unknown".to_string())
-							.to_string(),
-					);
-					peer_addr = Some(PeerAddr::Onion(onion_address.clone()));
+				self_addr = PeerAddr::Onion(
+					self.self_onion_address
+						.as_ref()
+						.unwrap_or(&"unknown".to_string())
+						.to_string(),
+				);
+				peer_addr = Some(PeerAddr::Onion(onion_address.clone()));
+				if let Some(proxy_config) = self.config.onion_proxy() {
+					Box::new(proxy::connect(proxy_config, addr)?)
+				} else if self.socks_port != 0 {
 					let proxy_addr =
 						SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), self.socks_port);
 					let onion_target: socks::TargetAddr =
@@ -273,7 +389,7 @@ impl Server {
 					let socks5_stream_ref =
 						tor_stream::TorStream::connect_with_address(proxy_addr, onion_target);
 					match socks5_stream_ref {
-						Ok(socks5_stream) => socks5_stream.unwrap(),
+						Ok(socks5_stream) => Box::new(socks5_stream.unwrap()),
 						Err(e) => {
 							return Err(Error::Connection(e));
 						}
@@ -286,6 +402,20 @@ impl Server {
 					)));
 				}
 			}
+			PeerAddr::I2p(address) => {
+				let session = match &self.i2p {
+					Some(session) => session,
+					None => {
+						return Err(Error::ConnectionClose(format!(
+							"Failed connect to I2P address {} because I2P is not configured",
+							address
+						)));
+					}
+				};
+				self_addr = PeerAddr::I2p(session.address().to_string());
+				peer_addr = Some(PeerAddr::I2p(address.clone()));
+				Box::new(session.connect(&address)?)
+			}
 		};
 
 		match Ok(stream) {
@@ -300,6 +430,7 @@ impl Server {
 					&self.handshake,
 					self.peers.clone(),
 					peer_addr,
+					connection_type,
 					self.sync_state.clone(),
 					(*self).clone(),
 				)?;
@@ -320,7 +451,7 @@ impl Server {
 		}
 	}
 
-	fn handle_new_peer(&self, stream: TcpStream) -> Result<(), Error> {
+	fn handle_new_peer(&self, stream: Box<dyn PeerStream>) -> Result<(), Error> {
 		if self.stop_state.is_stopped() {
 			return Err(Error::ConnectionClose(String::from("Server is stopping")));
 		}
@@ -364,35 +495,47 @@ impl Server {
 	/// addresses (NAT), network distribution is improved if they choose
 	/// different sets of peers themselves. In addition, it prevent potential
 	/// duplicate connections, malicious or not.
-	fn check_undesirable(&self, stream: &TcpStream) -> bool {
-		if self.peers.iter().inbound().connected().count() as u32
-			>= self.config.peer_max_inbound_count() + self.config.peer_listener_buffer_count()
-		{
-			debug!("Accepting new connection will exceed peer limit, refusing connection.");
+	fn check_undesirable(&self, stream: &Box<dyn PeerStream>) -> bool {
+		match stream.peer_addr() {
+			Ok(peer_addr) => self.check_undesirable_addr(&PeerAddr::Ip(peer_addr)),
+			Err(_) => self.inbound_limit_reached(),
+		}
+	}
+
+	/// Same as check_undesirable, for the peer address that is known from the transport
+	fn check_undesirable_addr(&self, peer_addr: &PeerAddr) -> bool {
+		if self.inbound_limit_reached() {
+			return true;
+		}
+		if self.peers.is_banned(peer_addr) {
+			debug!("Peer {} banned, refusing connection.", peer_addr);
 			return true;
 		}
-		if let Ok(peer_addr) = stream.peer_addr() {
-			let peer_addr = PeerAddr::Ip(peer_addr.clone());
-			if self.peers.is_banned(&peer_addr) {
-				debug!("Peer {} banned, refusing connection.", peer_addr);
+		// The call to is_known() can fail due to contention on the peers map.
+		// If it fails we want to default to refusing the connection.
+		match self.peers.is_known(peer_addr) {
+			Ok(true) => {
+				debug!("Peer {} already known, refusing connection.", peer_addr);
 				return true;
 			}
-			// The call to is_known() can fail due to contention on the peers map.
-			// If it fails we want to default to refusing the connection.
-			match self.peers.is_known(&peer_addr) {
-				Ok(true) => {
-					debug!("Peer {} already known, refusing connection.", peer_addr);
-					return true;
-				}
-				Err(_) => {
-					error!(
-						"Peer {} is_known check failed, refusing connection.",
-						peer_addr
-					);
-					return true;
-				}
-				_ => (),
+			Err(_) => {
+				error!(
+					"Peer {} is_known check failed, refusing connection.",
+					peer_addr
+				);
+				return true;
 			}
+			_ => (),
+		}
+		false
+	}
+
+	fn inbound_limit_reached(&self) -> bool {
+		if self.peers.iter().inbound().connected().count() as u32
+			>= self.config.peer_max_inbound_count() + self.config.peer_listener_buffer_count()
+		{
+			debug!("Accepting new connection will exceed peer limit, refusing connection.");
+			return true;
 		}
 		false
 	}
@@ -471,6 +614,9 @@ impl ChainAdapter for DummyAdapter {
 	fn get_block(&self, _: Hash, _: &PeerInfo) -> Option<core::Block> {
 		None
 	}
+	fn get_block_header(&self, _: &Hash) -> Option<core::BlockHeader> {
+		None
+	}
 	fn txhashset_read(&self, _h: Hash) -> Option<TxHashSetRead> {
 		unimplemented!()
 	}
@@ -609,7 +755,7 @@ impl NetAdapter for DummyAdapter {
 	fn find_peer_addrs(&self, _: Capabilities) -> Vec<PeerAddr> {
 		vec![]
 	}
-	fn peer_addrs_received(&self, _: Vec<PeerAddr>) {}
+	fn peer_addrs_received(&self, _: &PeerAddr, _: Vec<PeerAddr>) {}
 	fn peer_difficulty(&self, _: &PeerAddr, _: Difficulty, _: u64) {}
 	fn is_banned(&self, _: &PeerAddr) -> bool {
 		false
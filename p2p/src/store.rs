@@ -19,6 +19,7 @@ use chrono::Utc;
 use num::FromPrimitive;
 use rand::thread_rng;
 
+use crate::msg::PeerAddrs;
 use crate::mwc_core::ser::{self, DeserializationMode, Readable, Reader, Writeable, Writer};
 use crate::types::{Capabilities, PeerAddr, ReasonForBan};
 use mwc_store::{self, option_to_not_found, to_key, Error};
@@ -28,6 +29,10 @@ const DB_NAME: &str = "peerV2";
 const STORE_SUBPATH: &str = "peers";
 
 const PEER_PREFIX: u8 = b'P';
+// Secret key of the address manager buckets
+const ADDRMAN_KEY_PREFIX: u8 = b'K';
+// Anchor outbound peers, they are reconnected first after the restart
+const ANCHORS_PREFIX: u8 = b'A';
 
 // Types of messages
 enum_from_primitive! {
@@ -193,6 +198,33 @@ impl PeerStore {
 		Ok(peers)
 	}
 
+	/// Secret key for the address manager buckets. The key is generated on the first call.
+	pub fn addrman_key(&self) -> Result<Vec<u8>, Error> {
+		if let Some(key) = self.db.get_ser::<Vec<u8>>(&[ADDRMAN_KEY_PREFIX], None)? {
+			return Ok(key);
+		}
+		let key: Vec<u8> = (0..32).map(|_| thread_rng().gen::<u8>()).collect();
+		let batch = self.db.batch_write()?;
+		batch.put_ser(&[ADDRMAN_KEY_PREFIX], &key)?;
+		batch.commit()?;
+		Ok(key)
+	}
+
+	/// Anchor peers that were saved with save_anchors
+	pub fn get_anchors(&self) -> Result<Vec<PeerAddr>, Error> {
+		Ok(self
+			.db
+			.get_ser::<PeerAddrs>(&[ANCHORS_PREFIX], None)?
+			.map(|a| a.peers)
+			.unwrap_or(vec![]))
+	}
+
+	pub fn save_anchors(&self, anchors: Vec<PeerAddr>) -> Result<(), Error> {
+		let batch = self.db.batch_write()?;
+		batch.put_ser(&[ANCHORS_PREFIX], &PeerAddrs { peers: anchors })?;
+		batch.commit()
+	}
+
 	/// Iterator over all known peers.
 	pub fn peers_iter(&self) -> Result<impl Iterator<Item = PeerData>, Error> {
 		let key = to_key(PEER_PREFIX, "");
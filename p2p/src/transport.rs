@@ -0,0 +1,101 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transport abstraction for the peer connections. The node is using TCP,
+//! other implementations (like the in-memory network of the simulator) can be
+//! plugged into the p2p server instead.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Bidirectional stream to the peer. Reader and writer are working from the
+/// different threads, so the stream must be clonable.
+pub trait PeerStream: Read + Write + Send + Sync {
+	/// Create a new handle to the same underlying stream
+	fn try_clone(&self) -> io::Result<Box<dyn PeerStream>>;
+	/// Address of the other side of the connection
+	fn peer_addr(&self) -> io::Result<SocketAddr>;
+	/// Read timeout, None means blocking read
+	fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+	/// Write timeout, None means blocking write
+	fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()>;
+	/// Shutdown the connection, both reader and writer will be affected
+	fn shutdown(&self, how: Shutdown) -> io::Result<()>;
+}
+
+/// Listener for the incoming peer connections
+pub trait PeerListener: Send {
+	/// Non blocking accept. Returns WouldBlock error if there are no pending connections.
+	fn accept(&self) -> io::Result<(Box<dyn PeerStream>, SocketAddr)>;
+}
+
+/// Factory for the peer connections
+pub trait Transport: Send + Sync {
+	/// Start listening for the incoming connections at addr
+	fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn PeerListener>>;
+	/// Connect to the peer at addr
+	fn connect(&self, addr: &SocketAddr, timeout: Duration) -> io::Result<Box<dyn PeerStream>>;
+}
+
+impl PeerStream for TcpStream {
+	fn try_clone(&self) -> io::Result<Box<dyn PeerStream>> {
+		Ok(Box::new(TcpStream::try_clone(self)?))
+	}
+
+	fn peer_addr(&self) -> io::Result<SocketAddr> {
+		TcpStream::peer_addr(self)
+	}
+
+	fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+		TcpStream::set_read_timeout(self, dur)
+	}
+
+	fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+		TcpStream::set_write_timeout(self, dur)
+	}
+
+	fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+		TcpStream::shutdown(self, how)
+	}
+}
+
+impl PeerListener for TcpListener {
+	fn accept(&self) -> io::Result<(Box<dyn PeerStream>, SocketAddr)> {
+		let (stream, peer_addr) = TcpListener::accept(self)?;
+		// We want out TCP stream to be in blocking mode.
+		// The TCP listener is in nonblocking mode so we *must* explicitly
+		// move the accepted TCP stream into blocking mode (or all kinds of
+		// bad things can and will happen).
+		// A nonblocking TCP listener will accept nonblocking TCP streams which
+		// we do not want.
+		stream.set_nonblocking(false)?;
+		Ok((Box::new(stream), peer_addr))
+	}
+}
+
+/// Default TCP transport
+pub struct TcpTransport;
+
+impl Transport for TcpTransport {
+	fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn PeerListener>> {
+		let listener = TcpListener::bind(addr)?;
+		listener.set_nonblocking(true)?;
+		Ok(Box::new(listener))
+	}
+
+	fn connect(&self, addr: &SocketAddr, timeout: Duration) -> io::Result<Box<dyn PeerStream>> {
+		Ok(Box::new(TcpStream::connect_timeout(addr, timeout)?))
+	}
+}
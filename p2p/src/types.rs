@@ -13,6 +13,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::i2p;
+use crate::types::PeerAddr::I2p;
 use crate::types::PeerAddr::Ip;
 use crate::types::PeerAddr::Onion;
 use std::convert::From;
@@ -70,6 +72,15 @@ const PEER_MIN_PREFERRED_OUTBOUND_COUNT: u32 = 8;
 /// During sync process we want to boost peers discovery.
 const PEER_BOOST_OUTBOUND_COUNT: u32 = 20;
 
+/// The max outbound peer count that share the same network group (/16 subnet, onion group)
+const PEER_MAX_OUTBOUND_PER_GROUP: u32 = 2;
+
+/// The number of the outbound peers that are kept as anchors across restarts
+const PEER_ANCHOR_COUNT: u32 = 2;
+
+/// The block relay only outbound peer count, they are not included into PEER_MAX_OUTBOUND_COUNT
+const PEER_BLOCK_RELAY_ONLY_COUNT: u32 = 2;
+
 /// The peer listener buffer count. Allows temporarily accepting more connections
 /// than allowed by PEER_MAX_INBOUND_COUNT to encourage network bootstrapping.
 const PEER_LISTENER_BUFFER_COUNT: u32 = 8;
@@ -115,6 +126,8 @@ pub enum Error {
 	Internal(String),
 	#[error("libp2p error: {0}")]
 	Libp2pError(String),
+	#[error("i2p error: {0}")]
+	I2p(String),
 }
 
 impl From<ser::Error> for Error {
@@ -142,6 +155,8 @@ impl From<io::Error> for Error {
 pub enum PeerAddr {
 	Ip(SocketAddr),
 	Onion(String),
+	/// I2P destination, base32 address `<52 chars>.b32.i2p`
+	I2p(String),
 }
 
 impl Writeable for PeerAddr {
@@ -174,6 +189,16 @@ impl Writeable for PeerAddr {
 				writer.write_u8(2)?;
 				writer.write_bytes(onion)?;
 			}
+			I2p(address) => {
+				if address.len() > 100 {
+					return Err(ser::Error::TooLargeWriteErr(format!(
+						"Unreasonable long i2p address. UA length is {}",
+						address.len()
+					)));
+				}
+				writer.write_u8(3)?;
+				writer.write_bytes(address)?;
+			}
 		}
 		Ok(())
 	}
@@ -200,6 +225,17 @@ impl Readable for PeerAddr {
 					ipv6, port, 0, 0,
 				))))
 			}
+		} else if v4_or_v6 == 3 {
+			let address = reader.read_bytes_len_prefix()?;
+			let address = String::from_utf8(address).unwrap_or("".to_string());
+			// Address goes to the SAM commands, it must be a valid base32 address
+			if !i2p::is_i2p_address(&address) {
+				return Err(ser::Error::CorruptedData(format!(
+					"Invalid i2p address {:?}",
+					address
+				)));
+			}
+			Ok(PeerAddr::I2p(address))
 		} else {
 			// '2' is used for onion addresses now
 			let oa = reader.read_bytes_len_prefix()?;
@@ -254,6 +290,9 @@ impl std::hash::Hash for PeerAddr {
 			Onion(onion) => {
 				onion.hash(state);
 			}
+			I2p(address) => {
+				address.hash(state);
+			}
 		}
 	}
 }
@@ -277,6 +316,10 @@ impl PartialEq for PeerAddr {
 				Onion(other_onion) => onion == other_onion,
 				_ => false,
 			},
+			I2p(address) => match other {
+				I2p(other_address) => address == other_address,
+				_ => false,
+			},
 		}
 	}
 }
@@ -291,6 +334,7 @@ impl std::fmt::Display for PeerAddr {
 				let onion_address = &onion.to_string();
 				write!(f, "tor://{}", onion_address)
 			}
+			I2p(address) => write!(f, "i2p://{}", address),
 		}
 	}
 }
@@ -304,6 +348,9 @@ impl PeerAddr {
 	}
 
 	pub fn from_str(addr: &str) -> PeerAddr {
+		if i2p::is_i2p_address(addr) {
+			return PeerAddr::I2p(addr.to_string());
+		}
 		let socket_addr = SocketAddr::from_str(addr);
 		if socket_addr.is_err() {
 			let socket_addrs = addr.to_socket_addrs();
@@ -330,6 +377,7 @@ impl PeerAddr {
 				}
 			}
 			Onion(onion) => format!("{}", onion),
+			I2p(address) => format!("{}", address),
 		}
 	}
 
@@ -340,6 +388,11 @@ impl PeerAddr {
 					"requested TOR pub key from IP address".to_string(),
 				))
 			}
+			I2p(_address) => {
+				return Err(Error::Internal(
+					"requested TOR pub key from I2P address".to_string(),
+				))
+			}
 			Onion(onion) => {
 				if onion.ends_with(".onion") {
 					let onion = &onion[..(onion.len() - ".onion".len())];
@@ -354,7 +407,7 @@ impl PeerAddr {
 	pub fn is_loopback(&self) -> bool {
 		match self {
 			Ip(ip) => ip.ip().is_loopback(),
-			Onion(_) => {
+			Onion(_) | I2p(_) => {
 				false // we can't detect self onion address here in any case
 			}
 		}
@@ -392,6 +445,28 @@ pub struct P2PConfig {
 	pub peer_listener_buffer_count: Option<u32>,
 
 	pub dandelion_peer: Option<PeerAddr>,
+
+	/// Max outbound connections to the peers of the same network group
+	pub peer_max_outbound_per_group: Option<u32>,
+
+	/// Number of the outbound peers that are reconnected first after the restart
+	pub peer_anchor_count: Option<u32>,
+
+	/// Number of the outbound peers that relay only headers and blocks
+	pub peer_block_relay_only_count: Option<u32>,
+
+	/// Upload cap for all peer connections, bytes per second. Unlimited if not set
+	pub upload_cap: Option<u64>,
+
+	/// Daily upload quota in bytes. When it is exceeded, historical blocks and PIBD
+	/// segments are not served until the next UTC day
+	pub daily_upload_quota: Option<u64>,
+
+	/// SOCKS5 proxy for the outbound peer connections
+	pub socks5_proxy: Option<Socks5ProxyConfig>,
+
+	/// I2P transport with the SAM v3 bridge
+	pub i2p_config: Option<I2pConfig>,
 }
 
 /// Default address for peer-to-peer connections.
@@ -412,6 +487,13 @@ impl Default for P2PConfig {
 			peer_min_preferred_outbound_count: None,
 			peer_listener_buffer_count: None,
 			dandelion_peer: None,
+			peer_max_outbound_per_group: None,
+			peer_anchor_count: None,
+			peer_block_relay_only_count: None,
+			upload_cap: None,
+			daily_upload_quota: None,
+			socks5_proxy: None,
+			i2p_config: None,
 		}
 	}
 }
@@ -466,6 +548,119 @@ impl P2PConfig {
 			None => PEER_LISTENER_BUFFER_COUNT,
 		}
 	}
+
+	/// return maximum outbound peer connections count for a single network group
+	pub fn peer_max_outbound_per_group(&self) -> u32 {
+		match self.peer_max_outbound_per_group {
+			Some(n) => n,
+			None => PEER_MAX_OUTBOUND_PER_GROUP,
+		}
+	}
+
+	/// return number of the anchor outbound peers
+	pub fn peer_anchor_count(&self) -> u32 {
+		match self.peer_anchor_count {
+			Some(n) => n,
+			None => PEER_ANCHOR_COUNT,
+		}
+	}
+
+	/// return number of the block relay only outbound peers
+	pub fn peer_block_relay_only_count(&self) -> u32 {
+		match self.peer_block_relay_only_count {
+			Some(n) => n,
+			None => PEER_BLOCK_RELAY_ONLY_COUNT,
+		}
+	}
+
+	/// return capabilities to request with the peer addresses, so the peers send the
+	/// addresses of the networks that this node can connect to, besides IP and Tor.
+	pub fn address_capabilities(&self) -> Capabilities {
+		if self.i2p_config.is_some() {
+			Capabilities::I2P_ADDRESS
+		} else {
+			Capabilities::UNKNOWN
+		}
+	}
+
+	/// return the proxy for the outbound connections to the IP addresses
+	pub fn clearnet_proxy(&self) -> Option<&Socks5ProxyConfig> {
+		self.socks5_proxy
+			.as_ref()
+			.filter(|p| p.clearnet_policy() == ProxyPolicy::Proxy)
+	}
+
+	/// return the proxy for the outbound connections to the onion addresses
+	pub fn onion_proxy(&self) -> Option<&Socks5ProxyConfig> {
+		self.socks5_proxy
+			.as_ref()
+			.filter(|p| p.onion_policy() == ProxyPolicy::Proxy)
+	}
+}
+
+/// How the outbound connections to the address type are routed when the proxy is configured
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ProxyPolicy {
+	/// Connect through the SOCKS5 proxy
+	Proxy,
+	/// Don't use the SOCKS5 proxy, connect the same way as without it
+	/// (through the node Tor if it is enabled)
+	Direct,
+}
+
+/// SOCKS5 proxy for the outbound peer connections, like a corporate egress proxy
+/// or a tor/i2p gateway.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Socks5ProxyConfig {
+	/// Proxy host, IP address or DNS name
+	pub host: String,
+	/// Proxy port
+	pub port: u16,
+	/// User name for the username/password authentication
+	pub username: Option<String>,
+	/// Password for the username/password authentication
+	pub password: Option<String>,
+	/// Policy for the IP addresses, Proxy by default
+	pub clearnet: Option<ProxyPolicy>,
+	/// Policy for the onion addresses, Proxy by default. The proxy must be able to
+	/// resolve the onion addresses.
+	pub onion: Option<ProxyPolicy>,
+}
+
+impl Socks5ProxyConfig {
+	/// return policy for the IP addresses
+	pub fn clearnet_policy(&self) -> ProxyPolicy {
+		self.clearnet.unwrap_or(ProxyPolicy::Proxy)
+	}
+
+	/// return policy for the onion addresses
+	pub fn onion_policy(&self) -> ProxyPolicy {
+		self.onion.unwrap_or(ProxyPolicy::Proxy)
+	}
+}
+
+/// I2P transport for the peer connections. The node connects to the I2P router with
+/// the SAM v3 bridge, I2P peers are connected and accepted through it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct I2pConfig {
+	/// SAM bridge address, 127.0.0.1:7656 by default
+	pub sam_addr: Option<String>,
+	/// Accept the inbound connections at the node I2P destination, true by default
+	pub inbound: Option<bool>,
+}
+
+impl I2pConfig {
+	/// return SAM bridge address
+	pub fn sam_addr(&self) -> String {
+		self.sam_addr
+			.clone()
+			.unwrap_or(i2p::DEFAULT_SAM_ADDR.to_string())
+	}
+
+	/// return true if the inbound connections are accepted
+	pub fn inbound(&self) -> bool {
+		self.inbound.unwrap_or(true)
+	}
 }
 
 /// Type of seeding the server will use to find other peers on the network.
@@ -510,13 +705,15 @@ bitflags! {
 		const BLOCK_HIST = 0b0100_0000;
 		/// Can provide PIBD Headers Hashes
 		const HEADERS_HASH = 0b1000_0000;
+		/// Can send/receive i2p addresses
+		const I2P_ADDRESS = 0b1_0000_0000;
 	}
 }
 
 /// Default capabilities.
 impl Capabilities {
 	/// Capability instance to match node features
-	pub fn new(tor: bool, archive_mode: bool) -> Self {
+	pub fn new(tor: bool, i2p: bool, archive_mode: bool) -> Self {
 		let mut res = Capabilities::HEADER_HIST
 			| Capabilities::TXHASHSET_HIST
 			| Capabilities::PEER_LIST
@@ -527,6 +724,9 @@ impl Capabilities {
 		if tor {
 			res |= Capabilities::TOR_ADDRESS;
 		}
+		if i2p {
+			res |= Capabilities::I2P_ADDRESS;
+		}
 		if archive_mode {
 			res |= Capabilities::BLOCK_HIST;
 		}
@@ -545,6 +745,22 @@ enum_from_primitive! {
 	}
 }
 
+/// What the peers exchange over the connection
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ConnectionType {
+	/// Transactions, headers, blocks and peer addresses
+	FullRelay,
+	/// Only headers and blocks. No transactions, stem transactions or peer addresses,
+	/// so the connection can't be used to link the transactions to the node.
+	BlockRelayOnly,
+}
+
+impl Default for ConnectionType {
+	fn default() -> ConnectionType {
+		ConnectionType::FullRelay
+	}
+}
+
 // Ban reason
 enum_from_primitive! {
 	#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -580,6 +796,7 @@ pub struct PeerInfo {
 	pub version: ProtocolVersion,
 	pub addr: PeerAddr,
 	pub direction: Direction,
+	pub connection_type: ConnectionType,
 	pub live_info: Arc<RwLock<PeerLiveInfo>>,
 }
 
@@ -609,6 +826,11 @@ impl PeerInfo {
 		self.direction == Direction::Inbound || self.direction == Direction::InboundTor
 	}
 
+	/// Only headers and blocks are relayed with this peer
+	pub fn is_block_relay_only(&self) -> bool {
+		self.connection_type == ConnectionType::BlockRelayOnly
+	}
+
 	/// The current height of the peer.
 	pub fn height(&self) -> u64 {
 		self.live_info.read().height
@@ -658,6 +880,8 @@ pub struct PeerInfoDisplay {
 	pub version: ProtocolVersion,
 	pub addr: PeerAddr,
 	pub direction: Direction,
+	#[serde(default)]
+	pub connection_type: ConnectionType,
 	pub total_difficulty: Difficulty,
 	pub height: u64,
 }
@@ -670,6 +894,7 @@ impl From<PeerInfo> for PeerInfoDisplay {
 			version: info.version,
 			addr: info.clone().addr,
 			direction: info.direction,
+			connection_type: info.connection_type,
 			total_difficulty: info.total_difficulty(),
 			height: info.height(),
 		}
@@ -751,6 +976,9 @@ pub trait ChainAdapter: Sync + Send {
 	/// Converts block to v2 compatibility if necessary (based on peer protocol version).
 	fn get_block(&self, h: Hash, peer_info: &PeerInfo) -> Option<core::Block>;
 
+	/// Gets a block header by its hash.
+	fn get_block_header(&self, h: &Hash) -> Option<core::BlockHeader>;
+
 	/// Provides a reading view into the current txhashset state as well as
 	/// the required indexes for a consumer to rewind to a consistant state
 	/// at the provided block hash.
@@ -864,8 +1092,8 @@ pub trait NetAdapter: ChainAdapter {
 	/// addresses.
 	fn find_peer_addrs(&self, capab: Capabilities) -> Vec<PeerAddr>;
 
-	/// A list of peers has been received from one of our peers.
-	fn peer_addrs_received(&self, _: Vec<PeerAddr>);
+	/// A list of peers has been received from one of our peers, `source` is the sender.
+	fn peer_addrs_received(&self, source: &PeerAddr, _: Vec<PeerAddr>);
 
 	/// Heard total_difficulty from a connected peer (via ping/pong).
 	fn peer_difficulty(&self, _: &PeerAddr, _: Difficulty, _: u64);
@@ -0,0 +1,252 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_p2p::addrman::{AddResult, AddrMan, NetGroup, Table, BUCKET_SIZE};
+use mwc_p2p::msg::PeerAddrs;
+use mwc_p2p::store::PeerStore;
+use mwc_p2p::{DummyAdapter, P2PConfig, PeerAddr, Peers};
+use mwc_util::StopState;
+use std::collections::HashSet;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+fn ipv4(a: u8, b: u8, c: u8, d: u8) -> PeerAddr {
+	PeerAddr::Ip(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(a, b, c, d)), 3414))
+}
+
+#[test]
+fn addrman_net_groups() {
+	assert_eq!(NetGroup::of(&ipv4(8, 8, 4, 4)), NetGroup::Ipv4([8, 8]));
+	assert_eq!(
+		NetGroup::of(&ipv4(8, 8, 4, 4)),
+		NetGroup::of(&ipv4(8, 8, 200, 1))
+	);
+	assert_ne!(
+		NetGroup::of(&ipv4(8, 8, 4, 4)),
+		NetGroup::of(&ipv4(8, 9, 4, 4))
+	);
+	assert!(NetGroup::of(&ipv4(127, 0, 0, 1)).is_local());
+	assert!(NetGroup::of(&ipv4(192, 168, 1, 10)).is_local());
+	assert!(NetGroup::of(&PeerAddr::from_str("[::1]:3414")).is_local());
+	assert_eq!(
+		NetGroup::of(&PeerAddr::from_str("[2001:db8:1::1]:3414")),
+		NetGroup::Ipv6([0x20, 0x01, 0x0d, 0xb8])
+	);
+	assert_eq!(
+		NetGroup::of(&PeerAddr::Onion("abcdef.onion".to_string())),
+		NetGroup::Onion(*b"abcd")
+	);
+	// Onion addresses with the same first character are at the different groups
+	assert_ne!(
+		NetGroup::of(&PeerAddr::Onion("abcdef.onion".to_string())),
+		NetGroup::of(&PeerAddr::Onion("axyzef.onion".to_string()))
+	);
+	// Scheme and the case don't change the group
+	assert_eq!(
+		NetGroup::of(&PeerAddr::Onion("http://ABCDef.onion".to_string())),
+		NetGroup::Onion(*b"abcd")
+	);
+	assert_eq!(NetGroup::Onion(*b"abcd").to_string(), "onion:abcd");
+	assert_eq!(NetGroup::Ipv4([8, 8]).to_string(), "8.8.0.0/16");
+}
+
+#[test]
+fn addrman_source_flood() {
+	let mut addrman = AddrMan::new(&[7u8; 32]);
+	let source = ipv4(1, 2, 3, 4);
+
+	// A single source sends a lot of addresses from many groups
+	let mut added = 0;
+	for i in 0..20_000u32 {
+		let addr = ipv4(20 + (i % 200) as u8, (i / 200) as u8, 1, 1);
+		if addrman.add(&addr, &source, |_| false) {
+			added += 1;
+		}
+	}
+	// Only a few buckets are available for the single source group
+	assert!(added <= 16 * BUCKET_SIZE);
+	assert_eq!(addrman.size(), (added, 0));
+
+	// Other sources still have the room
+	let addr = ipv4(99, 1, 1, 1);
+	let mut other_added = false;
+	for i in 0..10 {
+		if addrman.add(&addr, &ipv4(50 + i, 1, 1, 1), |_| false) {
+			other_added = true;
+			break;
+		}
+	}
+	assert!(other_added);
+}
+
+#[test]
+fn addrman_tried_and_remove() {
+	let mut addrman = AddrMan::new(&[1u8; 32]);
+	let source = ipv4(1, 2, 3, 4);
+	let addr = ipv4(8, 8, 8, 8);
+
+	assert!(addrman.add(&addr, &source, |_| false));
+	assert!(!addrman.add(&addr, &source, |_| false));
+	assert_eq!(addrman.table_of(&addr), Some(Table::New));
+	assert_eq!(addrman.select(Table::Tried, &mut rand::thread_rng()), None);
+
+	addrman.mark_tried(&addr);
+	assert_eq!(addrman.table_of(&addr), Some(Table::Tried));
+	assert_eq!(addrman.size(), (0, 1));
+	assert_eq!(
+		addrman.select(Table::Tried, &mut rand::thread_rng()),
+		Some(addr.clone())
+	);
+
+	assert!(addrman.remove(&addr));
+	assert!(!addrman.remove(&addr));
+	assert_eq!(addrman.table_of(&addr), None);
+	assert_eq!(addrman.size(), (0, 0));
+}
+
+#[test]
+fn addrman_replace_terrible() {
+	let mut addrman = AddrMan::new(&[3u8; 32]);
+	let source = ipv4(1, 2, 3, 4);
+
+	// Fill the buckets of the source until the first collision
+	let mut known: HashSet<PeerAddr> = HashSet::new();
+	let mut collided = None;
+	for i in 0..10_000u32 {
+		let addr = ipv4(30, (i / 250) as u8, (i % 250) as u8, 1);
+		if addrman.add(&addr, &source, |_| false) {
+			known.insert(addr);
+		} else {
+			collided = Some(addr);
+			break;
+		}
+	}
+	let collided = collided.unwrap();
+	// Terrible address at the position is replaced
+	assert!(addrman.add(&collided, &source, |_| true));
+	assert_eq!(addrman.table_of(&collided), Some(Table::New));
+	assert_eq!(addrman.size().0, known.len());
+}
+
+#[test]
+fn addrman_collision_replace() {
+	let mut addrman = AddrMan::new(&[5u8; 32]);
+	let source = ipv4(1, 2, 3, 4);
+
+	let mut collision = None;
+	for i in 0..10_000u32 {
+		let addr = ipv4(40, (i / 250) as u8, (i % 250) as u8, 1);
+		match addrman.try_add(&addr, &source) {
+			AddResult::Added => {}
+			AddResult::Known => panic!("address {} is not known yet", addr),
+			AddResult::Collision(existing) => {
+				collision = Some((addr, existing));
+				break;
+			}
+		}
+	}
+	let (addr, existing) = collision.unwrap();
+	assert_eq!(addrman.try_add(&existing, &source), AddResult::Known);
+
+	// Only the address at the position is replaced
+	let other = ipv4(40, 200, 200, 200);
+	assert!(!addrman.replace(&other, &addr, &source));
+	let size = addrman.size();
+	assert!(addrman.replace(&existing, &addr, &source));
+	assert_eq!(addrman.size(), size);
+	assert_eq!(addrman.table_of(&addr), Some(Table::New));
+	assert_eq!(addrman.table_of(&existing), None);
+	assert!(!addrman.replace(&existing, &addr, &source));
+}
+
+fn init_peers(db_root: &str, config: P2PConfig) -> Peers {
+	let store = PeerStore::new(db_root).unwrap();
+	Peers::new(
+		store,
+		Arc::new(DummyAdapter {}),
+		config,
+		Arc::new(StopState::new()),
+	)
+}
+
+#[test]
+fn peers_group_limit() {
+	let db_root = ".mwc_peers_group_limit";
+	let _ = fs::remove_dir_all(db_root);
+
+	let public = ipv4(8, 8, 8, 8);
+	let preferred = ipv4(9, 9, 9, 9);
+	let anchor = ipv4(11, 1, 1, 1);
+	PeerStore::new(db_root)
+		.unwrap()
+		.save_anchors(vec![anchor.clone()])
+		.unwrap();
+
+	// No outbound connections are allowed for a group, only the exempt peers pass
+	let config = P2PConfig {
+		peer_max_outbound_per_group: Some(0),
+		peers_preferred: Some(PeerAddrs {
+			peers: vec![preferred.clone()],
+		}),
+		..P2PConfig::default()
+	};
+	let peers = init_peers(db_root, config);
+	assert!(peers.is_group_limited(&public));
+	assert!(peers.is_group_limited(&ipv4(8, 8, 1, 1)));
+	assert!(!peers.is_group_limited(&preferred));
+	assert!(!peers.is_group_limited(&anchor));
+	assert!(!peers.is_group_limited(&ipv4(127, 0, 0, 1)));
+	assert!(!peers.is_group_limited(&ipv4(192, 168, 1, 1)));
+	drop(peers);
+
+	// Default limit, the groups without connections are not limited
+	let peers = init_peers(db_root, P2PConfig::default());
+	assert!(!peers.is_group_limited(&public));
+	assert!(peers.outbound_groups().is_empty());
+	drop(peers);
+
+	let _ = fs::remove_dir_all(db_root);
+}
+
+#[test]
+fn peers_anchors() {
+	let db_root = ".mwc_peers_anchors";
+	let _ = fs::remove_dir_all(db_root);
+
+	let anchors = vec![ipv4(8, 8, 8, 8), ipv4(9, 9, 9, 9)];
+	{
+		let store = PeerStore::new(db_root).unwrap();
+		assert!(store.get_anchors().unwrap().is_empty());
+		store.save_anchors(anchors.clone()).unwrap();
+		// Address manager key is generated once
+		assert_eq!(store.addrman_key().unwrap(), store.addrman_key().unwrap());
+	}
+
+	// Anchors of the previous run are loaded
+	let peers = init_peers(db_root, P2PConfig::default());
+	assert_eq!(peers.get_anchors(), anchors);
+
+	// No long running outbound connections, the anchors are kept
+	peers.update_anchors();
+	assert_eq!(peers.get_anchors(), anchors);
+	peers.stop();
+	drop(peers);
+
+	let peers = init_peers(db_root, P2PConfig::default());
+	assert_eq!(peers.get_anchors(), anchors);
+	drop(peers);
+
+	let _ = fs::remove_dir_all(db_root);
+}
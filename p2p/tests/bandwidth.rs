@@ -0,0 +1,202 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_core as core;
+use mwc_p2p as p2p;
+use mwc_util as util;
+
+use crate::core::core::hash::Hash;
+use crate::core::core::SegmentIdentifier;
+use crate::core::global;
+use crate::core::pow::Difficulty;
+use crate::p2p::bandwidth::{Bandwidth, ShapedWriter};
+use crate::p2p::types::{ConnectionType, PeerAddr};
+use crate::p2p::{P2PConfig, Peer};
+use mwc_chain::SyncState;
+use mwc_util::StopState;
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn bandwidth_unlimited() {
+	let bandwidth = Arc::new(Bandwidth::new(&P2PConfig::default()));
+	let mut shaper = bandwidth.peer_shaper();
+	let mut out: Vec<u8> = vec![];
+
+	let start = Instant::now();
+	ShapedWriter::new(&mut out, &mut shaper)
+		.write_all(&vec![1u8; 1_000_000])
+		.unwrap();
+	assert_eq!(out.len(), 1_000_000);
+	assert!(start.elapsed() < Duration::from_secs(1));
+	assert!(!bandwidth.upload_quota_exceeded());
+}
+
+#[test]
+fn bandwidth_upload_cap() {
+	let config = P2PConfig {
+		upload_cap: Some(100_000),
+		..P2PConfig::default()
+	};
+	let bandwidth = Arc::new(Bandwidth::new(&config));
+
+	// One second burst, then 100 KB/s
+	let mut shaper = bandwidth.peer_shaper();
+	let mut out: Vec<u8> = vec![];
+	let start = Instant::now();
+	ShapedWriter::new(&mut out, &mut shaper)
+		.write_all(&vec![1u8; 250_000])
+		.unwrap();
+	assert_eq!(out.len(), 250_000);
+	assert!(start.elapsed() >= Duration::from_millis(1400));
+
+	// Two peers share the cap
+	let start = Instant::now();
+	let handles: Vec<_> = (0..2)
+		.map(|_| {
+			let mut shaper = bandwidth.peer_shaper();
+			thread::spawn(move || {
+				let mut out: Vec<u8> = vec![];
+				ShapedWriter::new(&mut out, &mut shaper)
+					.write_all(&vec![1u8; 100_000])
+					.unwrap();
+			})
+		})
+		.collect();
+	for h in handles {
+		h.join().unwrap();
+	}
+	assert!(start.elapsed() >= Duration::from_millis(1400));
+}
+
+#[test]
+fn bandwidth_daily_quota() {
+	let config = P2PConfig {
+		daily_upload_quota: Some(50_000),
+		..P2PConfig::default()
+	};
+	let bandwidth = Arc::new(Bandwidth::new(&config));
+	let mut shaper = bandwidth.peer_shaper();
+	let mut out: Vec<u8> = vec![];
+
+	ShapedWriter::new(&mut out, &mut shaper)
+		.write_all(&vec![1u8; 40_000])
+		.unwrap();
+	assert_eq!(bandwidth.uploaded_today(), 40_000);
+	assert!(!bandwidth.upload_quota_exceeded());
+
+	shaper.consume(10_000);
+	assert_eq!(bandwidth.uploaded_today(), 50_000);
+	assert!(bandwidth.upload_quota_exceeded());
+}
+
+#[test]
+fn bandwidth_chunk_size() {
+	let bandwidth = Arc::new(Bandwidth::new(&P2PConfig::default()));
+	assert_eq!(bandwidth.peer_shaper().chunk_size(), 16 * 1024);
+
+	// Chunk is sent within 5 seconds with the peer fair share
+	let config = P2PConfig {
+		upload_cap: Some(1_000),
+		..P2PConfig::default()
+	};
+	let bandwidth = Arc::new(Bandwidth::new(&config));
+	let mut shaper = bandwidth.peer_shaper();
+	assert_eq!(shaper.chunk_size(), 5_000);
+	let other = bandwidth.peer_shaper();
+	assert_eq!(other.chunk_size(), 2_500);
+	drop(other);
+
+	let mut out: Vec<u8> = vec![];
+	let start = Instant::now();
+	let written = ShapedWriter::new(&mut out, &mut shaper)
+		.write(&vec![1u8; 20_000])
+		.unwrap();
+	assert_eq!(written, 5_000);
+	assert!(start.elapsed() < Duration::from_secs(6));
+}
+
+// Server with the exceeded daily quota drops the PIBD requests. DummyAdapter
+// panics if the request reaches it, so the ping after the requests is not processed.
+#[test]
+fn bandwidth_quota_gating() {
+	global::init_global_chain_type(global::ChainTypes::AutomatedTesting);
+	util::init_test_logger();
+
+	let port = TcpListener::bind("127.0.0.1:0")
+		.unwrap()
+		.local_addr()
+		.unwrap()
+		.port();
+	let p2p_config = P2PConfig {
+		host: "127.0.0.1".parse().unwrap(),
+		port,
+		peers_allow: None,
+		peers_deny: None,
+		daily_upload_quota: Some(0),
+		..P2PConfig::default()
+	};
+	let net_adapter = Arc::new(p2p::DummyAdapter {});
+	let server = p2p::Server::new(
+		".mwc_quota",
+		p2p::Capabilities::UNKNOWN,
+		p2p_config.clone(),
+		net_adapter.clone(),
+		Hash::from_vec(&vec![]),
+		Arc::new(SyncState::new()),
+		Arc::new(StopState::new()),
+		0,
+		None,
+	)
+	.unwrap();
+	assert!(server.bandwidth.upload_quota_exceeded());
+	let listen_server = server.clone();
+	let _ = thread::spawn(move || listen_server.listen());
+	thread::sleep(Duration::from_secs(1));
+
+	let addr = SocketAddr::new(p2p_config.host, p2p_config.port);
+	let socket = TcpStream::connect_timeout(&addr, Duration::from_secs(10)).unwrap();
+	let my_addr = PeerAddr::Ip("127.0.0.1:5010".parse().unwrap());
+	let peer = Peer::connect(
+		Box::new(socket),
+		p2p::Capabilities::UNKNOWN,
+		Difficulty::min(),
+		my_addr.clone(),
+		&p2p::handshake::Handshake::new(Hash::from_vec(&vec![]), p2p_config.clone(), None),
+		net_adapter,
+		None,
+		ConnectionType::FullRelay,
+		Arc::new(SyncState::new()),
+		server.clone(),
+	)
+	.unwrap();
+	thread::sleep(Duration::from_secs(1));
+
+	let hash = Hash::from_vec(&vec![1; 32]);
+	peer.send_start_pibd_sync_request(10, hash).unwrap();
+	peer.send_kernel_segment_request(hash, SegmentIdentifier { height: 1, idx: 0 })
+		.unwrap();
+	peer.send_ping(Difficulty::from_num(10), 0).unwrap();
+	thread::sleep(Duration::from_secs(1));
+
+	let server_peer = server.peers.get_connected_peer(&my_addr).unwrap();
+	assert!(server_peer.is_connected());
+	assert_eq!(
+		server_peer.info.total_difficulty(),
+		Difficulty::from_num(10)
+	);
+}
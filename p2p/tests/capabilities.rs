@@ -32,7 +32,7 @@ fn capabilities_contains() {
 
 #[test]
 fn default_capabilities() {
-	let x = Capabilities::new(true, false);
+	let x = Capabilities::new(true, false, false);
 
 	// Check that default capabilities is covered by UNKNOWN.
 	assert!(x.contains(Capabilities::UNKNOWN));
@@ -45,6 +45,9 @@ fn default_capabilities() {
 	assert!(x.contains(Capabilities::TOR_ADDRESS));
 	assert!(x.contains(Capabilities::PIBD_HIST));
 	assert!(x.contains(Capabilities::HEADERS_HASH));
+	// I2P addresses only when I2P is configured
+	assert!(!x.contains(Capabilities::I2P_ADDRESS));
+	assert!(Capabilities::new(true, true, false).contains(Capabilities::I2P_ADDRESS));
 
 	assert_eq!(
 		x,
@@ -0,0 +1,189 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_p2p::i2p::{self, I2pSession};
+use mwc_p2p::PeerAddr;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+const NODE_DEST: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8g-w==";
+const NODE_PRIV: &str = "node-private-key";
+const NODE_B32: &str = "povkq5wegqftr5yjwnvxl6nwfoezlrgfspne3niso2nfoutyqctq.b32.i2p";
+const PEER_DEST: &str = "-~-~AQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHyA=";
+const PEER_B32: &str = "qtqdw54ja6daez7jpllwxjjieznt47g7jyglqj3wpjf4cwq4x6qq.b32.i2p";
+
+// Stand-in SAM bridge. Reports every command, the streams echo the data.
+fn mock_sam_bridge() -> (String, mpsc::Receiver<String>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap().to_string();
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			let stream = stream.unwrap();
+			let tx = tx.clone();
+			thread::spawn(move || handle_sam_connection(stream, tx));
+		}
+	});
+	(addr, rx)
+}
+
+fn handle_sam_connection(mut stream: TcpStream, tx: mpsc::Sender<String>) {
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+	loop {
+		let mut line = String::new();
+		if reader.read_line(&mut line).unwrap_or(0) == 0 {
+			return;
+		}
+		let cmd = line.trim_end().to_string();
+		let _ = tx.send(cmd.clone());
+		let reply = if cmd.starts_with("HELLO VERSION") {
+			"HELLO REPLY RESULT=OK VERSION=3.1".to_string()
+		} else if cmd.starts_with("DEST GENERATE") {
+			format!("DEST REPLY PUB={} PRIV={}", NODE_DEST, NODE_PRIV)
+		} else if cmd.starts_with("SESSION CREATE") {
+			if cmd.contains(&format!("DESTINATION={} ", NODE_PRIV)) {
+				format!("SESSION STATUS RESULT=OK DESTINATION={}", NODE_PRIV)
+			} else {
+				"SESSION STATUS RESULT=INVALID_KEY MESSAGE=\"unknown key\"".to_string()
+			}
+		} else if cmd == "NAMING LOOKUP NAME=ME" {
+			format!("NAMING REPLY RESULT=OK NAME=ME VALUE={}", NODE_DEST)
+		} else if cmd == format!("NAMING LOOKUP NAME={}", PEER_B32) {
+			format!(
+				"NAMING REPLY RESULT=OK NAME={} VALUE={}",
+				PEER_B32, PEER_DEST
+			)
+		} else if cmd.starts_with("NAMING LOOKUP") {
+			"NAMING REPLY RESULT=KEY_NOT_FOUND".to_string()
+		} else if cmd.starts_with("STREAM CONNECT") {
+			stream.write_all(b"STREAM STATUS RESULT=OK\n").unwrap();
+			break;
+		} else if cmd.starts_with("STREAM ACCEPT") {
+			stream.write_all(b"STREAM STATUS RESULT=OK\n").unwrap();
+			stream
+				.write_all(format!("{} FROM_PORT=0 TO_PORT=0\n", PEER_DEST).as_bytes())
+				.unwrap();
+			break;
+		} else {
+			"UNKNOWN REPLY RESULT=I2P_ERROR".to_string()
+		};
+		stream.write_all(format!("{}\n", reply).as_bytes()).unwrap();
+	}
+	// The socket is the peer stream now
+	let mut data = [0u8; 4];
+	reader.read_exact(&mut data).unwrap();
+	stream.write_all(&data).unwrap();
+}
+
+fn check_echo(mut stream: TcpStream) {
+	stream.write_all(b"ping").unwrap();
+	let mut data = [0u8; 4];
+	stream.read_exact(&mut data).unwrap();
+	assert_eq!(&data, b"ping");
+}
+
+#[test]
+fn i2p_b32_address() {
+	assert_eq!(i2p::b32_address(NODE_DEST).unwrap(), NODE_B32);
+	assert_eq!(i2p::b32_address(PEER_DEST).unwrap(), PEER_B32);
+	assert!(i2p::b32_address("not+base64").is_err());
+
+	assert!(i2p::is_i2p_address(PEER_B32));
+	assert!(!i2p::is_i2p_address("unknownpeer.b32.i2p"));
+	assert!(!i2p::is_i2p_address(&format!("{}.b32.i2p", "A".repeat(52))));
+	assert!(!i2p::is_i2p_address(&format!(
+		"{} .b32.i2p",
+		"a".repeat(51)
+	)));
+	assert_ne!(
+		PeerAddr::from_str("ME NAME=x.b32.i2p"),
+		PeerAddr::I2p("ME NAME=x.b32.i2p".to_string())
+	);
+	assert_eq!(
+		PeerAddr::from_str(PEER_B32),
+		PeerAddr::I2p(PEER_B32.to_string())
+	);
+	assert_eq!(
+		PeerAddr::I2p(PEER_B32.to_string()).to_string(),
+		format!("i2p://{}", PEER_B32)
+	);
+}
+
+#[test]
+fn i2p_sam_session() {
+	let (sam_addr, rx) = mock_sam_bridge();
+	let keys_dir = tempfile::tempdir().unwrap();
+
+	let session = I2pSession::new(&sam_addr, keys_dir.path()).unwrap();
+	assert_eq!(session.address(), NODE_B32);
+	let commands: Vec<String> = rx.try_iter().collect();
+	assert!(commands.iter().any(|c| c.starts_with("DEST GENERATE")));
+	assert!(commands
+		.iter()
+		.any(|c| c.starts_with("SESSION CREATE STYLE=STREAM")));
+
+	// Destination key is stored, the address is the same for the next session
+	let session = I2pSession::new(&sam_addr, keys_dir.path()).unwrap();
+	assert_eq!(session.address(), NODE_B32);
+	let commands: Vec<String> = rx.try_iter().collect();
+	assert!(!commands.iter().any(|c| c.starts_with("DEST GENERATE")));
+
+	check_echo(session.connect(PEER_B32).unwrap());
+	assert!(rx
+		.iter()
+		.any(|c| c.starts_with("STREAM CONNECT")
+			&& c.contains(&format!("DESTINATION={} ", PEER_DEST))));
+	let unknown = format!("{}.b32.i2p", "a".repeat(52));
+	let err = session.connect(&unknown).unwrap_err().to_string();
+	assert!(err.contains("KEY_NOT_FOUND"));
+
+	// Invalid addresses are not sent to the bridge
+	let _: Vec<String> = rx.try_iter().collect();
+	let err = session
+		.connect("ME\nSESSION CREATE STYLE=STREAM ID=x DESTINATION=TRANSIENT.b32.i2p")
+		.unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+	assert!(rx.try_iter().next().is_none());
+
+	let (stream, address) = session.accept().unwrap();
+	assert_eq!(address, PEER_B32);
+	check_echo(stream);
+
+	// Session is created again with the same destination
+	assert!(session.is_alive());
+	let _: Vec<String> = rx.try_iter().collect();
+	session.recreate().unwrap();
+	assert_eq!(session.address(), NODE_B32);
+	let commands: Vec<String> = rx.try_iter().collect();
+	assert!(commands
+		.iter()
+		.any(|c| c.starts_with("SESSION CREATE STYLE=STREAM")
+			&& c.contains(&format!("DESTINATION={} ", NODE_PRIV))));
+	check_echo(session.connect(PEER_B32).unwrap());
+}
+
+#[test]
+fn i2p_sam_session_failure() {
+	let (sam_addr, _rx) = mock_sam_bridge();
+	let keys_dir = tempfile::tempdir().unwrap();
+	std::fs::write(keys_dir.path().join("destination.key"), "wrong-key").unwrap();
+	let err = I2pSession::new(&sam_addr, keys_dir.path())
+		.err()
+		.unwrap()
+		.to_string();
+	assert!(err.contains("INVALID_KEY"));
+	assert!(err.contains("unknown key"));
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::Utc;
+use mwc_p2p::libp2p_connection::ReceivedMessage;
+use mwc_p2p::libp2p_message_store::Libp2pMessageStore;
+use std::fs;
+
+fn clean_output_dir(dir_name: &str) {
+	let _ = fs::remove_dir_all(dir_name);
+}
+
+fn message(topic: &str, n: usize, timestamp: i64) -> ReceivedMessage {
+	ReceivedMessage {
+		timestamp,
+		peer_id: "peer".to_string(),
+		topic: topic.to_string(),
+		fee: 10,
+		message: format!("{{\"offer\":{}}}", n),
+	}
+}
+
+#[test]
+fn libp2p_message_store() {
+	let db_root = "target/.libp2p_message_store";
+	clean_output_dir(db_root);
+	let now = Utc::now().timestamp();
+
+	{
+		let store = Libp2pMessageStore::new(db_root, 3600, 3).unwrap();
+		assert_eq!(store.last_seq(), 0);
+
+		for n in 0..5 {
+			assert_eq!(
+				store.add_message(&message("A", n, now)).unwrap(),
+				Some(n as u64 + 1)
+			);
+		}
+		// Duplicates are skipped
+		assert_eq!(store.add_message(&message("A", 4, now)).unwrap(), None);
+		assert_eq!(store.add_message(&message("B", 0, now)).unwrap(), Some(6));
+		// Expired message is pruned right away
+		assert_eq!(
+			store.add_message(&message("B", 1, now - 7200)).unwrap(),
+			Some(7)
+		);
+
+		// Topic A is limited to 3 latest messages
+		assert_eq!(store.messages_num(), 4);
+		let msgs = store.get_messages(Some("A"), 0, 100).unwrap();
+		assert_eq!(
+			msgs.iter().map(|m| m.seq).collect::<Vec<u64>>(),
+			vec![3, 4, 5]
+		);
+	}
+
+	// Messages are persistent, every consumer reads with its own cursor
+	let store = Libp2pMessageStore::new(db_root, 3600, 3).unwrap();
+	assert_eq!(store.last_seq(), 7);
+	let msgs = store.get_messages(None, 0, 2).unwrap();
+	assert_eq!(msgs.iter().map(|m| m.seq).collect::<Vec<u64>>(), vec![3, 4]);
+	let msgs = store.get_messages(None, 4, 100).unwrap();
+	assert_eq!(msgs.iter().map(|m| m.seq).collect::<Vec<u64>>(), vec![5, 6]);
+	assert_eq!(msgs[1].topic, "B");
+	assert_eq!(msgs[1].message, "{\"offer\":0}");
+	assert!(store.get_messages(Some("B"), 6, 100).unwrap().is_empty());
+
+	clean_output_dir(db_root);
+}
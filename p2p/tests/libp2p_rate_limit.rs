@@ -0,0 +1,89 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_p2p::libp2p_rate_limit::{
+	get_rate_limit_stats, peer_score_params, GossipRateLimiter, GossipRateLimits, RateLimitRule,
+	INVALID_MESSAGE_PENALTY, PEER_PENALTY,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn gossip_rate_limits() {
+	let mut limiter = GossipRateLimiter::new(GossipRateLimits {
+		peer_rate: 1.0,
+		peer_burst: 2.0,
+		topic_rate: 1.0,
+		topic_burst: 3.0,
+		peer_ban_score: 3.0,
+	});
+	let stats = get_rate_limit_stats();
+	let now = Instant::now();
+
+	// Peer burst
+	assert_eq!(limiter.check("peer1", "topic", now), Ok(()));
+	assert_eq!(limiter.check("peer1", "topic", now), Ok(()));
+	assert_eq!(
+		limiter.check("peer1", "topic", now),
+		Err(RateLimitRule::Peer)
+	);
+	// Topic burst, shared by the peers
+	assert_eq!(limiter.check("peer2", "topic", now), Ok(()));
+	assert_eq!(
+		limiter.check("peer2", "topic", now),
+		Err(RateLimitRule::Topic)
+	);
+	assert_eq!(limiter.check("peer3", "other_topic", now), Ok(()));
+
+	// Buckets are refilled with the time
+	let later = now + Duration::from_secs(1);
+	assert_eq!(limiter.check("peer1", "other_topic", later), Ok(()));
+	assert_eq!(
+		limiter.check("peer1", "other_topic", later),
+		Err(RateLimitRule::Peer)
+	);
+
+	let new_stats = get_rate_limit_stats();
+	assert_eq!(new_stats.dropped_by_peer - stats.dropped_by_peer, 2);
+	assert_eq!(new_stats.dropped_by_topic - stats.dropped_by_topic, 1);
+
+	// Penalties are decaying, the peer is banned only for the flood
+	assert_eq!(limiter.penalize("peer1", PEER_PENALTY, now), (-1.0, false));
+	assert_eq!(limiter.penalize("peer1", PEER_PENALTY, now), (-2.0, false));
+	let later = now + Duration::from_secs(600);
+	assert!(!limiter.penalize("peer1", PEER_PENALTY, later).1);
+	assert!(!limiter.penalize("peer1", PEER_PENALTY, later).1);
+	assert!(limiter.penalize("peer1", PEER_PENALTY, later).1);
+	// Invalid messages are penalized harder
+	assert!(limiter.penalize("peer2", INVALID_MESSAGE_PENALTY, now).1);
+
+	// Idle peers and topics are not tracked after cleanup, scores of the
+	// penalized peers are reset
+	assert_eq!(limiter.penalize("peer3", PEER_PENALTY, now), (-1.0, false));
+	let scores = limiter.cleanup(now + Duration::from_secs(3600));
+	assert_eq!(scores, vec![("peer3".to_string(), 0.0)]);
+	assert_eq!(limiter.tracked_num(), (0, 0));
+}
+
+#[test]
+fn gossip_peer_score_params() {
+	let limits = GossipRateLimits::default();
+	let (params, thresholds) = peer_score_params(&limits);
+	assert_eq!(params.app_specific_weight, 1.0);
+	assert_eq!(params.ip_colocation_factor_weight, 0.0);
+	// The peer is graylisted before it is banned
+	assert!(thresholds.gossip_threshold < 0.0);
+	assert!(thresholds.publish_threshold < thresholds.gossip_threshold);
+	assert!(thresholds.graylist_threshold < thresholds.publish_threshold);
+	assert!(thresholds.graylist_threshold > -limits.peer_ban_score);
+}
@@ -22,7 +22,7 @@ use mwc_util::StopState;
 use crate::core::core::hash::Hash;
 use crate::core::global;
 use crate::core::pow::Difficulty;
-use crate::p2p::types::PeerAddr;
+use crate::p2p::types::{ConnectionType, PeerAddr};
 use crate::p2p::Peer;
 use mwc_chain::SyncState;
 use std::net::{SocketAddr, TcpListener, TcpStream};
@@ -82,15 +82,16 @@ fn peer_handshake() {
 
 	let my_addr = PeerAddr::Ip("127.0.0.1:5000".parse().unwrap());
 	let peer = Peer::connect(
-		socket,
+		Box::new(socket),
 		p2p::Capabilities::UNKNOWN,
 		Difficulty::min(),
 		my_addr.clone(),
 		&p2p::handshake::Handshake::new(Hash::from_vec(&vec![]), p2p_config.clone(), None),
-		net_adapter,
+		net_adapter.clone(),
 		None,
+		ConnectionType::FullRelay,
 		Arc::new(SyncState::new()),
-		server_inner,
+		server_inner.clone(),
 	)
 	.unwrap();
 
@@ -104,4 +105,32 @@ fn peer_handshake() {
 	let server_peer = server.peers.get_connected_peer(&my_addr).unwrap();
 	assert_eq!(server_peer.info.total_difficulty(), Difficulty::min());
 	assert!(server.peers.iter().connected().count() > 0);
+	assert!(!server_peer.info.is_block_relay_only());
+
+	// Block relay only connection is advertised in the handshake
+	let socket = TcpStream::connect_timeout(&addr, time::Duration::from_secs(10)).unwrap();
+	let relay_addr = PeerAddr::Ip("127.0.0.1:5001".parse().unwrap());
+	let relay_peer = Peer::connect(
+		Box::new(socket),
+		p2p::Capabilities::UNKNOWN,
+		Difficulty::min(),
+		relay_addr.clone(),
+		&p2p::handshake::Handshake::new(Hash::from_vec(&vec![]), p2p_config.clone(), None),
+		net_adapter,
+		None,
+		ConnectionType::BlockRelayOnly,
+		Arc::new(SyncState::new()),
+		server_inner,
+	)
+	.unwrap();
+	assert!(relay_peer.info.is_block_relay_only());
+	thread::sleep(time::Duration::from_secs(1));
+
+	let server_peer = server.peers.get_connected_peer(&relay_addr).unwrap();
+	assert_eq!(
+		server_peer.info.connection_type,
+		ConnectionType::BlockRelayOnly
+	);
+	assert_eq!(server_peer.info.capabilities, relay_peer.info.capabilities);
+	assert_eq!(server.peers.iter().block_relay_only().count(), 1);
 }
@@ -43,14 +43,14 @@ fn test_type_enum() {
 
 #[test]
 fn test_capabilities() {
-	let expected = p2p::types::Capabilities::new(true, false);
+	let expected = p2p::types::Capabilities::new(true, false, false);
 
 	assert_eq!(
 		p2p::types::Capabilities::from_bits_truncate(0b_0000_0000 as u32),
 		p2p::types::Capabilities::UNKNOWN
 	);
 	assert_eq!(
-		p2p::types::Capabilities::from_bits_truncate(0b1_0000_0000 as u32),
+		p2p::types::Capabilities::from_bits_truncate(0b10_0000_0000 as u32),
 		p2p::types::Capabilities::UNKNOWN
 	);
 
@@ -58,6 +58,10 @@ fn test_capabilities() {
 		expected,
 		p2p::types::Capabilities::from_bits_truncate(0b_1011_1111 as u32),
 	);
+	assert_eq!(
+		p2p::types::Capabilities::new(true, true, false),
+		p2p::types::Capabilities::from_bits_truncate(0b1_1011_1111 as u32),
+	);
 
 	assert!(p2p::types::Capabilities::from_bits_truncate(0b_1111_1111 as u32).contains(expected));
 
@@ -66,3 +70,91 @@ fn test_capabilities() {
 			.contains(p2p::types::Capabilities::TX_KERNEL_HASH)
 	);
 }
+
+#[test]
+fn test_peer_addr_i2p() {
+	use mwc_core::ser::{self, DeserializationMode, ProtocolVersion};
+
+	let addr = p2p::PeerAddr::I2p(
+		"qtqdw54ja6daez7jpllwxjjieznt47g7jyglqj3wpjf4cwq4x6qq.b32.i2p".to_string(),
+	);
+	let data = ser::ser_vec(&addr, ProtocolVersion::local()).unwrap();
+	assert_eq!(data[0], 3);
+	let addr2: p2p::PeerAddr = ser::deserialize(
+		&mut &data[..],
+		ProtocolVersion::local(),
+		DeserializationMode::default(),
+	)
+	.unwrap();
+	assert_eq!(addr, addr2);
+
+	let addr = p2p::PeerAddr::I2p("a".repeat(101));
+	assert!(ser::ser_vec(&addr, ProtocolVersion::local()).is_err());
+
+	// Only base32 addresses are accepted from the peers
+	for invalid in &[
+		"",
+		"unknownpeer.b32.i2p",
+		"qtqdw54ja6daez7jpllwxjjieznt47g7jyglqj3wpjf4cwq4x6q1.b32.i2p",
+		"ME\nSESSION CREATE STYLE=STREAM ID=x DESTINATION=TRANSIENT.b32.i2p",
+	] {
+		let addr = p2p::PeerAddr::I2p(invalid.to_string());
+		let data = ser::ser_vec(&addr, ProtocolVersion::local()).unwrap();
+		let res: Result<p2p::PeerAddr, _> = ser::deserialize(
+			&mut &data[..],
+			ProtocolVersion::local(),
+			DeserializationMode::default(),
+		);
+		assert!(res.is_err());
+	}
+}
+
+#[test]
+fn test_hand_connection_type() {
+	use mwc_core::core::hash::Hash;
+	use mwc_core::pow::Difficulty;
+	use mwc_core::ser::{self, DeserializationMode, ProtocolVersion};
+
+	let hand = |connection_type| p2p::msg::Hand {
+		version: ProtocolVersion::local(),
+		capabilities: p2p::types::Capabilities::new(false, false, false),
+		nonce: 1,
+		genesis: Hash::default(),
+		total_difficulty: Difficulty::min(),
+		sender_addr: p2p::PeerAddr::from_str("127.0.0.1:3414"),
+		receiver_addr: p2p::PeerAddr::from_str("127.0.0.1:13414"),
+		user_agent: "test".to_string(),
+		connection_type,
+	};
+	let read = |data: &[u8]| -> p2p::msg::Hand {
+		ser::deserialize(
+			&mut &data[..],
+			ProtocolVersion::local(),
+			DeserializationMode::default(),
+		)
+		.unwrap()
+	};
+
+	let full = ser::ser_vec(
+		&hand(p2p::ConnectionType::FullRelay),
+		ProtocolVersion::local(),
+	)
+	.unwrap();
+	let relay = ser::ser_vec(
+		&hand(p2p::ConnectionType::BlockRelayOnly),
+		ProtocolVersion::local(),
+	)
+	.unwrap();
+	// Full relay hand is the same as the old peers send
+	assert_eq!(relay.len(), full.len() + 1);
+	assert_eq!(read(&full).connection_type, p2p::ConnectionType::FullRelay);
+	let relay_hand = read(&relay);
+	assert_eq!(
+		relay_hand.connection_type,
+		p2p::ConnectionType::BlockRelayOnly
+	);
+	assert_eq!(
+		relay_hand.capabilities,
+		p2p::types::Capabilities::new(false, false, false)
+	);
+}
@@ -0,0 +1,146 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_p2p::proxy;
+use mwc_p2p::{P2PConfig, PeerAddr, ProxyPolicy, Socks5ProxyConfig};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+// Minimal SOCKS5 server for a single connection. Reports the credentials and the
+// target address, then echoes the data.
+fn mock_socks5_proxy() -> (u16, mpsc::Receiver<(Option<String>, String)>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut buf = [0u8; 2];
+		stream.read_exact(&mut buf).unwrap();
+		let mut methods = vec![0u8; buf[1] as usize];
+		stream.read_exact(&mut methods).unwrap();
+
+		let mut credentials = None;
+		if methods.contains(&2) {
+			stream.write_all(&[5, 2]).unwrap();
+			let username = read_str(&mut stream, 1);
+			let password = read_str(&mut stream, 0);
+			credentials = Some(format!("{}:{}", username, password));
+			stream.write_all(&[1, 0]).unwrap();
+		} else {
+			stream.write_all(&[5, 0]).unwrap();
+		}
+
+		let mut req = [0u8; 4];
+		stream.read_exact(&mut req).unwrap();
+		let host = match req[3] {
+			1 => {
+				let mut ip = [0u8; 4];
+				stream.read_exact(&mut ip).unwrap();
+				format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])
+			}
+			3 => read_str(&mut stream, 0),
+			t => panic!("Unexpected address type {}", t),
+		};
+		let mut port = [0u8; 2];
+		stream.read_exact(&mut port).unwrap();
+		tx.send((
+			credentials,
+			format!("{}:{}", host, u16::from_be_bytes(port)),
+		))
+		.unwrap();
+		stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+
+		let mut data = [0u8; 4];
+		stream.read_exact(&mut data).unwrap();
+		stream.write_all(&data).unwrap();
+	});
+	(port, rx)
+}
+
+// Read the length prefixed string, `skip` bytes before the length are ignored
+fn read_str(stream: &mut TcpStream, skip: usize) -> String {
+	let mut prefix = vec![0u8; skip + 1];
+	stream.read_exact(&mut prefix).unwrap();
+	let mut data = vec![0u8; prefix[skip] as usize];
+	stream.read_exact(&mut data).unwrap();
+	String::from_utf8(data).unwrap()
+}
+
+fn proxy_config(port: u16, username: Option<&str>) -> Socks5ProxyConfig {
+	Socks5ProxyConfig {
+		host: "127.0.0.1".to_string(),
+		port,
+		username: username.map(|u| u.to_string()),
+		password: username.map(|_| "secret".to_string()),
+		clearnet: None,
+		onion: Some(ProxyPolicy::Direct),
+	}
+}
+
+fn check_echo(mut stream: TcpStream) {
+	stream.write_all(b"ping").unwrap();
+	let mut data = [0u8; 4];
+	stream.read_exact(&mut data).unwrap();
+	assert_eq!(&data, b"ping");
+}
+
+#[test]
+fn socks5_proxy_connect() {
+	let (port, rx) = mock_socks5_proxy();
+	let addr = PeerAddr::Ip("10.0.0.1:3414".parse().unwrap());
+	let stream = proxy::connect(&proxy_config(port, None), &addr).unwrap();
+	assert_eq!(rx.recv().unwrap(), (None, "10.0.0.1:3414".to_string()));
+	check_echo(stream);
+
+	let onion = "xiqosbqtzpmo6nnxlxnxjbpo2bg7jbwvrjqnz2ie6lmr5uo7ba6idcid";
+	let (port, rx) = mock_socks5_proxy();
+	let addr = PeerAddr::Onion(onion.to_string());
+	let stream = proxy::connect(&proxy_config(port, Some("user")), &addr).unwrap();
+	assert_eq!(
+		rx.recv().unwrap(),
+		(Some("user:secret".to_string()), format!("{}:80", onion))
+	);
+	check_echo(stream);
+}
+
+#[test]
+fn socks5_proxy_policy() {
+	let mut config = P2PConfig::default();
+	assert!(config.clearnet_proxy().is_none());
+	assert!(config.onion_proxy().is_none());
+
+	config.socks5_proxy = Some(proxy_config(1080, None));
+	assert_eq!(config.clearnet_proxy().unwrap().port, 1080);
+	assert!(config.onion_proxy().is_none());
+}
+
+#[test]
+fn socks5_proxy_timeout() {
+	// The proxy accepts the connection but never answers the greeting
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let addr = PeerAddr::Ip("10.0.0.1:3414".parse().unwrap());
+	let start = Instant::now();
+	let err = proxy::connect(&proxy_config(port, None), &addr).unwrap_err();
+	assert!(
+		err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut,
+		"Unexpected error {:?}",
+		err
+	);
+	assert!(start.elapsed() >= proxy::PROXY_TIMEOUT);
+	drop(listener);
+}
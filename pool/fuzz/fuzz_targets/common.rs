@@ -100,7 +100,12 @@ impl BlockChain for ChainAdapter {
 	fn verify_coinbase_maturity(&self, tx: &Transaction) -> Result<(), PoolError> {
 		self.chain
 			.verify_coinbase_maturity(tx)
-			.map_err(|_| PoolError::ImmatureCoinbase)
+			.map_err(|e| match e {
+				chain::Error::ImmatureCoinbase(mature_at) => {
+					PoolError::ImmatureCoinbase { mature_at }
+				}
+				_ => PoolError::Other("failed to verify coinbase maturity".into()),
+			})
 	}
 
 	fn verify_tx_lock_height(&self, tx: &Transaction) -> Result<(), PoolError> {
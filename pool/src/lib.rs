@@ -38,5 +38,5 @@ pub mod types;
 pub use crate::pool::Pool;
 pub use crate::transaction_pool::TransactionPool;
 pub use crate::types::{
-	BlockChain, DandelionConfig, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxSource,
+	BlockChain, DandelionConfig, PoolAdapter, PoolCheck, PoolConfig, PoolEntry, PoolError, TxSource,
 };
@@ -172,12 +172,29 @@ where
 		extra_tx: Option<Transaction>,
 		header: &BlockHeader,
 		secp: &Secp256k1,
+	) -> Result<(), PoolError> {
+		self.validate_add_to_pool(&entry.tx, extra_tx, header, secp)?;
+		// If we get here successfully then we can safely add the entry to the pool.
+		self.log_pool_add(&entry, header);
+		self.entries.push(entry);
+
+		Ok(())
+	}
+
+	/// Validate the pool with the given tx added against the chain state at the
+	/// provided header. The pool itself is not modified.
+	pub fn validate_add_to_pool(
+		&self,
+		tx: &Transaction,
+		extra_tx: Option<Transaction>,
+		header: &BlockHeader,
+		secp: &Secp256k1,
 	) -> Result<(), PoolError> {
 		// Combine all the txs from the pool with any extra txs provided.
 		let mut txs = self.all_transactions();
 
 		// Quick check to see if we have seen this tx before.
-		if txs.contains(&entry.tx) {
+		if txs.contains(tx) {
 			return Err(PoolError::DuplicateTx);
 		}
 
@@ -187,22 +204,17 @@ where
 
 		let agg_tx = if txs.is_empty() {
 			// If we have nothing to aggregate then simply return the tx itself.
-			entry.tx.clone()
+			tx.clone()
 		} else {
 			// Create a single aggregated tx from the existing pool txs and the
 			// new entry
-			txs.push(entry.tx.clone());
+			txs.push(tx.clone());
 			transaction::aggregate(&txs, secp)?
 		};
 
 		// Validate aggregated tx (existing pool + new tx), ignoring tx weight limits.
 		// Validate against known chain state at the provided header.
-		self.validate_raw_tx(&agg_tx, header, Weighting::NoLimit, secp)?;
-		// If we get here successfully then we can safely add the entry to the pool.
-		self.log_pool_add(&entry, header);
-		self.entries.push(entry);
-
-		Ok(())
+		self.validate_raw_tx(&agg_tx, header, Weighting::NoLimit, secp)
 	}
 
 	fn log_pool_add(&self, entry: &PoolEntry, header: &BlockHeader) {
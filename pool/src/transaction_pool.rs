@@ -26,7 +26,9 @@ use self::core::core::{
 use self::core::global;
 use self::util::RwLock;
 use crate::pool::Pool;
-use crate::types::{BlockChain, PoolAdapter, PoolConfig, PoolEntry, PoolError, TxSource};
+use crate::types::{
+	BlockChain, PoolAdapter, PoolCheck, PoolConfig, PoolEntry, PoolError, TxSource,
+};
 use chrono::prelude::*;
 use lru::LruCache;
 use mwc_core as core;
@@ -271,6 +273,117 @@ where
 		Ok(())
 	}
 
+	/// Run the checks of `add_to_pool` without adding the transaction to the pool.
+	/// Nothing is modified, the replay attack cache included, and nothing is relayed.
+	/// The pool state is validated against the txpool (or stempool) with the transaction
+	/// added, only if all other checks passed. Results are reported in the order the
+	/// checks are done.
+	pub fn test_accept(
+		&self,
+		tx: &Transaction,
+		stem: bool,
+		header: &BlockHeader,
+		secp: &Secp256k1,
+	) -> Vec<(PoolCheck, Result<(), PoolError>)> {
+		// Same as for add_to_pool, stem tx that is already in the stempool is fluffed.
+		let stem = stem && !self.stempool.contains_tx(tx);
+
+		let mut checks = vec![];
+		let duplicate = if self.txpool.contains_tx(tx) {
+			Err(PoolError::DuplicateTx)
+		} else {
+			Ok(())
+		};
+		checks.push((PoolCheck::Duplicate, duplicate));
+
+		let entry = PoolEntry::new(tx.clone(), TxSource::PushApi);
+		let entry = if stem {
+			Ok(entry)
+		} else {
+			self.deaggregate_tx(entry, secp)
+		};
+		let entry = match entry {
+			Ok(entry) => entry,
+			Err(e) => {
+				checks.push((PoolCheck::Validation, Err(e)));
+				return checks;
+			}
+		};
+		let tx = &entry.tx;
+
+		checks.push((
+			PoolCheck::KernelVariants,
+			self.verify_kernel_variants(tx, header),
+		));
+		checks.push((PoolCheck::Fee, self.verify_fee(tx)));
+		// Over capacity txpool accepts the transaction and evicts another one.
+		let capacity = match self.verify_capacity(stem) {
+			Err(PoolError::OverCapacity) if !stem => Ok(()),
+			res => res,
+		};
+		checks.push((PoolCheck::Capacity, capacity));
+		checks.push((
+			PoolCheck::Validation,
+			tx.validate(Weighting::AsTransaction, header.height, secp)
+				.map_err(PoolError::InvalidTx),
+		));
+		checks.push((
+			PoolCheck::LockHeight,
+			self.blockchain.verify_tx_lock_height(tx),
+		));
+		checks.push((
+			PoolCheck::ReplayAttack,
+			self.blockchain.replay_attack_check(tx),
+		));
+
+		let extra_tx = if stem {
+			match self.txpool.all_transactions_aggregate(None, secp) {
+				Ok(extra_tx) => extra_tx,
+				Err(e) => {
+					checks.push((PoolCheck::PoolState, Err(e)));
+					return checks;
+				}
+			}
+		} else {
+			None
+		};
+		let spends = if stem {
+			self.stempool.locate_spends(tx, extra_tx.clone(), secp)
+		} else {
+			self.txpool.locate_spends(tx, None, secp)
+		};
+		let (spent_pool, spent_utxo) = match spends {
+			Ok(spends) => {
+				checks.push((PoolCheck::Spends, Ok(())));
+				spends
+			}
+			Err(e) => {
+				checks.push((PoolCheck::Spends, Err(e)));
+				return checks;
+			}
+		};
+		let coinbase_inputs: Vec<_> = spent_utxo
+			.iter()
+			.filter(|x| x.is_coinbase())
+			.cloned()
+			.collect();
+		checks.push((
+			PoolCheck::CoinbaseMaturity,
+			self.blockchain
+				.verify_coinbase_maturity(&coinbase_inputs.as_slice().into()),
+		));
+
+		if checks.iter().any(|(_, res)| res.is_err()) {
+			return checks;
+		}
+		let pool = if stem { &self.stempool } else { &self.txpool };
+		let pool_state = self
+			.convert_tx_v2(entry, &spent_pool, &spent_utxo, secp)
+			.and_then(|entry| pool.validate_add_to_pool(&entry.tx, extra_tx, header, secp));
+		checks.push((PoolCheck::PoolState, pool_state));
+		checks
+	}
+
 	/// Convert a transaction for v2 compatibility.
 	/// We may receive a transaction with "commit only" inputs.
 	/// We convert it to "features and commit" so we can safely relay it to v2 peers.
@@ -417,6 +530,12 @@ where
 	/// Whether the transaction is acceptable to the pool, given both how
 	/// full the pool is and the transaction weight.
 	fn is_acceptable(&self, tx: &Transaction, stem: bool) -> Result<(), PoolError> {
+		self.verify_capacity(stem)?;
+		self.verify_fee(tx)
+	}
+
+	/// Whether the pool (the stempool for the stem transaction) has space for a new transaction.
+	fn verify_capacity(&self, stem: bool) -> Result<(), PoolError> {
 		if self.total_size() > self.config.max_pool_size {
 			return Err(PoolError::OverCapacity);
 		}
@@ -427,13 +546,19 @@ where
 		{
 			return Err(PoolError::OverCapacity);
 		}
+		Ok(())
+	}
 
+	/// Whether the transaction pays the required fee given its weight.
+	fn verify_fee(&self, tx: &Transaction) -> Result<(), PoolError> {
 		// weight for a basic transaction (2 inputs, 2 outputs, 1 kernel) -
 		// (2 * 1) + (2 * 21) + (1 * 3) = 47
 		// minfees = 47 * 500_000 = 23_500_000
 		let header = self.chain_head()?;
-		if tx.shifted_fee(header.height) < tx.accept_fee(header.height) {
-			return Err(PoolError::LowFeeTransaction(tx.shifted_fee(header.height)));
+		let fee = tx.shifted_fee(header.height);
+		let required_fee = tx.accept_fee(header.height);
+		if fee < required_fee {
+			return Err(PoolError::LowFeeTransaction { fee, required_fee });
 		}
 		Ok(())
 	}
@@ -26,6 +26,7 @@ use self::core::global::DEFAULT_ACCEPT_FEE_BASE;
 use chrono::prelude::*;
 use mwc_core as core;
 use mwc_keychain as keychain;
+use mwc_util::secp::pedersen::Commitment;
 
 /// Dandelion "epoch" length.
 const DANDELION_EPOCH_SECS: u16 = 600;
@@ -203,6 +204,32 @@ impl TxSource {
 	}
 }
 
+/// Individual checks of the transaction pool acceptance, reported by the dry run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PoolCheck {
+	/// Transaction is not in the txpool already
+	Duplicate,
+	/// NRD kernels are enabled and allowed by the current header version
+	KernelVariants,
+	/// Transaction pays the required fee
+	Fee,
+	/// Pool has space for the transaction
+	Capacity,
+	/// Rangeproofs, kernel signatures and kernel sums are valid
+	Validation,
+	/// Lock height of the kernels is reached
+	LockHeight,
+	/// Kernels are not a replay of the kernels already on the chain
+	ReplayAttack,
+	/// Spent outputs exist in the pool or in the utxo set
+	Spends,
+	/// Spent coinbase outputs are mature
+	CoinbaseMaturity,
+	/// Transaction can be added to the current pool state (duplicate outputs and kernels,
+	/// NRD relative height, validity of the pool together with the chain state)
+	PoolState,
+}
+
 /// Possible errors when interacting with the transaction pool.
 #[derive(Debug, thiserror::Error, PartialEq)]
 pub enum PoolError {
@@ -223,8 +250,11 @@ pub enum PoolError {
 	#[error("Tx Pool Immature transaction")]
 	ImmatureTransaction,
 	/// Attempt to spend a coinbase output before it has sufficiently matured.
-	#[error("Tx Pool Immature coinbase")]
-	ImmatureCoinbase,
+	#[error("Tx Pool Immature coinbase, mature at height {mature_at}")]
+	ImmatureCoinbase {
+		/// Height of the block where the coinbase can be spent
+		mature_at: u64,
+	},
 	/// Problem propagating a stem tx to the next Dandelion relay node.
 	#[error("Tx Pool Dandelion error")]
 	DandelionError,
@@ -232,11 +262,16 @@ pub enum PoolError {
 	#[error("Tx Pool Over capacity")]
 	OverCapacity,
 	/// Transaction fee is too low given its weight
-	#[error("Tx Pool Low fee transaction {0}")]
-	LowFeeTransaction(u64),
+	#[error("Tx Pool Low fee transaction {fee}, required fee {required_fee}")]
+	LowFeeTransaction {
+		/// Fee of the transaction
+		fee: u64,
+		/// Minimal fee for the transaction weight
+		required_fee: u64,
+	},
 	/// Attempt to add a duplicate output to the pool.
-	#[error("Tx Pool Duplicate commitment")]
-	DuplicateCommitment,
+	#[error("Tx Pool Duplicate commitment {0:?}")]
+	DuplicateCommitment(Commitment),
 	/// Attempt to add a duplicate kernel or output duplicate to spent to the pool.
 	#[error("Tx Pool Duplicate kernel or duplicate output to spent, {0}")]
 	DuplicateKernelOrDuplicateSpent(String),
@@ -55,7 +55,7 @@ fn test_coinbase_maturity() {
 	assert_eq!(
 		pool.add_to_pool(test_source(), tx.clone(), true, &header, chain.secp())
 			.err(),
-		Some(PoolError::ImmatureCoinbase)
+		Some(PoolError::ImmatureCoinbase { mature_at: 4 })
 	);
 
 	// Add 2 more blocks. Original coinbase output is now matured and can be spent.
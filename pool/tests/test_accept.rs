@@ -0,0 +1,105 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod common;
+
+use self::core::global;
+use self::keychain::{ExtKeychain, Keychain};
+use self::pool::types::{PoolCheck, PoolError};
+use crate::common::*;
+use mwc_core as core;
+use mwc_keychain as keychain;
+use mwc_pool as pool;
+use mwc_util as util;
+use std::sync::Arc;
+
+fn failed_checks(checks: &[(PoolCheck, Result<(), PoolError>)]) -> Vec<PoolCheck> {
+	checks
+		.iter()
+		.filter(|(_, res)| res.is_err())
+		.map(|(check, _)| *check)
+		.collect()
+}
+
+/// Test accept reports the same verdict as add_to_pool, but leaves the pool untouched.
+#[test]
+fn test_pool_test_accept() {
+	util::init_test_logger();
+	global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+	global::set_local_accept_fee_base(1);
+	let keychain: ExtKeychain = Keychain::from_random_seed(false).unwrap();
+
+	let db_root = "target/.pool_test_accept";
+	clean_output_dir(db_root.into());
+
+	let genesis = genesis_block(&keychain);
+	let chain = Arc::new(init_chain(db_root, genesis));
+
+	let mut pool = init_transaction_pool(Arc::new(ChainAdapter {
+		chain: chain.clone(),
+	}));
+
+	add_some_blocks(&chain, 4 * 3, &keychain);
+	let header = chain.head_header().unwrap();
+
+	let header_1 = chain.get_header_by_height(1).unwrap();
+	let initial_tx = test_transaction_spending_coinbase(&keychain, &header_1, vec![500, 600]);
+
+	// Valid tx, accepted by the dry run for both txpool and stempool
+	for stem in &[false, true] {
+		let checks = pool.test_accept(&initial_tx, *stem, &header, chain.secp());
+		assert!(failed_checks(&checks).is_empty());
+		assert_eq!(checks.last().unwrap().0, PoolCheck::PoolState);
+	}
+	assert_eq!(pool.total_size(), 0);
+	assert!(pool.stempool.is_empty());
+
+	pool.add_to_pool(
+		test_source(),
+		initial_tx.clone(),
+		false,
+		&header,
+		chain.secp(),
+	)
+	.unwrap();
+	assert_eq!(pool.total_size(), 1);
+
+	// Same tx again is a duplicate
+	let checks = pool.test_accept(&initial_tx, false, &header, chain.secp());
+	assert_eq!(failed_checks(&checks), vec![PoolCheck::Duplicate]);
+
+	// Spending the outputs of the pool tx
+	let tx1 = test_transaction(&keychain, vec![500], vec![469]);
+	let checks = pool.test_accept(&tx1, false, &header, chain.secp());
+	assert!(failed_checks(&checks).is_empty());
+	assert_eq!(pool.total_size(), 1);
+
+	// Double spend of the coinbase spent by the pool tx, rejected by the pool state
+	let double_spend = test_transaction_spending_coinbase(&keychain, &header_1, vec![501]);
+	let checks = pool.test_accept(&double_spend, false, &header, chain.secp());
+	assert_eq!(failed_checks(&checks), vec![PoolCheck::PoolState]);
+	assert!(pool
+		.add_to_pool(test_source(), double_spend, false, &header, chain.secp())
+		.is_err());
+
+	// Spending a non-existent output
+	let bad_tx = test_transaction(&keychain, vec![10_001], vec![9_900]);
+	let checks = pool.test_accept(&bad_tx, false, &header, chain.secp());
+	assert_eq!(failed_checks(&checks), vec![PoolCheck::Spends]);
+
+	assert_eq!(pool.total_size(), 1);
+	assert!(pool.stempool.is_empty());
+
+	clean_output_dir(db_root.into());
+}
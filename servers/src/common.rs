@@ -19,3 +19,4 @@ pub mod adapters;
 pub mod hooks;
 pub mod stats;
 pub mod types;
+pub mod watch_list;
@@ -453,6 +453,10 @@ where
 			.unwrap_or(None)
 	}
 
+	fn get_block_header(&self, h: &Hash) -> Option<core::BlockHeader> {
+		self.chain().get_block_header(h).ok()
+	}
+
 	/// Provides a reading view into the current txhashset state as well as
 	/// the required indexes for a consumer to rewind to a consistent state
 	/// at the provided block hash.
@@ -1222,9 +1226,12 @@ impl pool::BlockChain for PoolToChainAdapter {
 	}
 
 	fn validate_tx(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
-		self.chain()
-			.validate_tx(tx)
-			.map_err(|e| pool::PoolError::Other(format!("failed to validate tx, {}", e)))
+		self.chain().validate_tx(tx).map_err(|e| match e {
+			chain::Error::DuplicateCommitment(commit) => {
+				pool::PoolError::DuplicateCommitment(commit)
+			}
+			e => pool::PoolError::Other(format!("failed to validate tx, {}", e)),
+		})
 	}
 
 	fn validate_inputs(&self, inputs: &Inputs) -> Result<Vec<OutputIdentifier>, pool::PoolError> {
@@ -1237,7 +1244,12 @@ impl pool::BlockChain for PoolToChainAdapter {
 	fn verify_coinbase_maturity(&self, inputs: &Inputs) -> Result<(), pool::PoolError> {
 		self.chain()
 			.verify_coinbase_maturity(inputs)
-			.map_err(|_| pool::PoolError::ImmatureCoinbase)
+			.map_err(|e| match e {
+				chain::Error::ImmatureCoinbase(mature_at) => {
+					pool::PoolError::ImmatureCoinbase { mature_at }
+				}
+				e => pool::PoolError::Other(format!("failed to verify coinbase maturity, {}", e)),
+			})
 	}
 
 	fn verify_tx_lock_height(&self, tx: &Transaction) -> Result<(), pool::PoolError> {
@@ -20,6 +20,7 @@ extern crate hyper;
 extern crate hyper_rustls;
 extern crate tokio;
 
+use crate::api::WatchEvent;
 use crate::chain::BlockStatus;
 use crate::common::types::{ServerConfig, WebHooksConfig};
 use crate::core::core;
@@ -34,6 +35,7 @@ use hyper_rustls::HttpsConnector;
 use mwc_util::ToHex;
 use serde::Serialize;
 use serde_json::{json, to_string};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::{Builder, Runtime};
 
@@ -50,12 +52,29 @@ pub fn init_net_hooks(config: &ServerConfig) -> Vec<Box<dyn NetEvents + Send + S
 	list
 }
 
+/// Returns the webhook for the chain events and the watch list events, None if
+/// neither of them is configured. The watch list shares it with the chain hooks.
+pub fn init_chain_webhook(config: &ServerConfig) -> Option<Arc<WebHook>> {
+	if config.webhook_config.block_accepted_url.is_some()
+		|| config.webhook_config.watch_list_url.is_some()
+	{
+		Some(Arc::new(WebHook::from_config(&config.webhook_config)))
+	} else {
+		None
+	}
+}
+
 /// Returns the list of event hooks that will be initialized for chain events
-pub fn init_chain_hooks(config: &ServerConfig) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
+pub fn init_chain_hooks(
+	config: &ServerConfig,
+	webhook: &Option<Arc<WebHook>>,
+) -> Vec<Box<dyn ChainEvents + Send + Sync>> {
 	let mut list: Vec<Box<dyn ChainEvents + Send + Sync>> = Vec::new();
 	list.push(Box::new(EventLogger));
 	if config.webhook_config.block_accepted_url.is_some() {
-		list.push(Box::new(WebHook::from_config(&config.webhook_config)));
+		if let Some(webhook) = webhook {
+			list.push(Box::new(webhook.clone()));
+		}
 	}
 	list
 }
@@ -80,6 +99,13 @@ pub trait ChainEvents {
 	fn on_block_accepted(&self, block: &core::Block, status: BlockStatus) {}
 }
 
+/// Shared hooks, like the watch list that is used by the API as well
+impl<T: ChainEvents> ChainEvents for Arc<T> {
+	fn on_block_accepted(&self, block: &core::Block, status: BlockStatus) {
+		self.as_ref().on_block_accepted(block, status)
+	}
+}
+
 /// Basic Logger
 struct EventLogger;
 
@@ -189,7 +215,7 @@ fn parse_url(value: &Option<String>) -> Option<hyper::Uri> {
 }
 
 /// A struct that holds the hyper/tokio runtime.
-struct WebHook {
+pub struct WebHook {
 	/// url to POST transaction data when a new transaction arrives from a peer
 	tx_received_url: Option<hyper::Uri>,
 	/// url to POST header data when a new header arrives from a peer
@@ -198,6 +224,8 @@ struct WebHook {
 	block_received_url: Option<hyper::Uri>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	block_accepted_url: Option<hyper::Uri>,
+	/// url to POST watch list events
+	watch_list_url: Option<hyper::Uri>,
 	/// The hyper client to be used for all requests
 	client: Client<HttpsConnector<HttpConnector>>,
 	/// The tokio event loop
@@ -211,6 +239,7 @@ impl WebHook {
 		header_received_url: Option<hyper::Uri>,
 		block_received_url: Option<hyper::Uri>,
 		block_accepted_url: Option<hyper::Uri>,
+		watch_list_url: Option<hyper::Uri>,
 		nthreads: u16,
 		timeout: u16,
 	) -> WebHook {
@@ -231,6 +260,7 @@ impl WebHook {
 			block_received_url,
 			header_received_url,
 			block_accepted_url,
+			watch_list_url,
 			client,
 			runtime: Builder::new()
 				.threaded_scheduler()
@@ -248,6 +278,7 @@ impl WebHook {
 			parse_url(&config.header_received_url),
 			parse_url(&config.block_received_url),
 			parse_url(&config.block_accepted_url),
+			parse_url(&config.watch_list_url),
 			config.nthreads,
 			config.timeout,
 		)
@@ -282,6 +313,18 @@ impl WebHook {
 	}
 }
 
+impl WebHook {
+	/// POST the watch list event
+	pub(crate) fn on_watch_event(&self, event: &WatchEvent) {
+		if !self.make_request(event, &self.watch_list_url) {
+			error!(
+				"Failed to serialize watch list event {} for {}",
+				event.seq, event.commit
+			);
+		}
+	}
+}
+
 impl ChainEvents for WebHook {
 	fn on_block_accepted(&self, block: &core::Block, status: BlockStatus) {
 		let status_str = match status {
@@ -116,6 +116,8 @@ pub struct WorkerStats {
 	pub num_stale: u64,
 	/// number of valid blocks found
 	pub num_blocks_found: u64,
+	/// graphs per second, reported by the internal test miner
+	pub hashrate: f64,
 }
 
 /// Struct to return relevant information about the stratum server
@@ -283,6 +285,11 @@ impl PeerStats {
 			p2p::types::Direction::InboundTor => "Inbound  (TOR)",
 			p2p::types::Direction::OutboundTor => "Outbound (TOR)",
 		};
+		let direction = if peer.info.is_block_relay_only() {
+			format!("{} (block relay)", direction)
+		} else {
+			direction.to_string()
+		};
 		PeerStats {
 			state: state.to_string(),
 			addr: addr,
@@ -290,7 +297,7 @@ impl PeerStats {
 			user_agent: peer.info.user_agent.clone(),
 			total_difficulty: peer.info.total_difficulty().to_num(),
 			height: peer.info.height(),
-			direction: direction.to_string(),
+			direction,
 			last_seen: peer.info.last_seen(),
 			sent_bytes_per_sec: peer.tracker().sent_bytes.read().bytes_per_min() / 60,
 			received_bytes_per_sec: peer.tracker().received_bytes.read().bytes_per_min() / 60,
@@ -311,6 +318,7 @@ impl Default for WorkerStats {
 			num_rejected: 0,
 			num_stale: 0,
 			num_blocks_found: 0,
+			hashrate: 0.0,
 		}
 	}
 }
@@ -162,6 +162,17 @@ pub struct TorConfig {
 	pub tor_external: bool,
 	/// Onion address to use, only applicable with external tor
 	pub onion_address: Option<String>,
+	/// Control port address of the running tor daemon. If set, the node onion service
+	/// is published with the tor control protocol instead of starting a tor process.
+	pub control_addr: Option<String>,
+	/// Password for the tor control port (HashedControlPassword auth)
+	pub control_password: Option<String>,
+	/// Cookie file for the tor control port (CookieAuthentication). By default the
+	/// location reported by the tor daemon is used.
+	pub control_cookie_file: Option<String>,
+	/// x25519 public keys (base32) of the clients that are authorized to connect to the
+	/// onion service. If there are no authorized clients, the onion service is public.
+	pub authorized_clients: Option<Vec<String>>,
 }
 
 impl Default for TorConfig {
@@ -171,6 +182,10 @@ impl Default for TorConfig {
 			socks_port: 51234,
 			tor_external: false,
 			onion_address: Some("".to_string()),
+			control_addr: None,
+			control_password: None,
+			control_cookie_file: None,
+			authorized_clients: None,
 		}
 	}
 }
@@ -223,6 +238,11 @@ pub struct ServerConfig {
 	/// (Default: none)
 	pub invalid_block_hashes: Option<Vec<String>>,
 
+	/// Additional header checkpoints in "<height>:<hash>" format.
+	/// Header chains that conflict with them are rejected.
+	/// (Default: none)
+	pub checkpoints: Option<Vec<String>>,
+
 	/// Whether to run the TUI
 	/// if enabled, this will disable logging to stdout
 	pub run_tui: Option<bool>,
@@ -233,6 +253,10 @@ pub struct ServerConfig {
 	/// Test miner wallet URL
 	pub test_miner_wallet_url: Option<String>,
 
+	/// Number of solver threads for the test miner
+	/// (Default: 1)
+	pub test_miner_threads: Option<usize>,
+
 	/// Enable libp2p server. It can run only with TOR. Needed for wallets to send messages to each other.
 	/// Default value: enabled
 	pub libp2p_enabled: Option<bool>,
@@ -244,6 +268,36 @@ pub struct ServerConfig {
 	/// libp2p connection port (will be activated with Tor)
 	pub libp2p_port: Option<u16>,
 
+	/// Received libp2p messages are stored at the node db for that time, in seconds.
+	/// Default: 86400 (24 hours)
+	pub libp2p_messages_max_age_sec: Option<u64>,
+
+	/// Max number of the stored libp2p messages per topic.
+	/// Default: 10000
+	pub libp2p_messages_max_per_topic: Option<usize>,
+
+	/// Messages per second that a single libp2p peer can publish. The messages are counted
+	/// by the author, not by the peer that forwarded them.
+	/// Default: 0.5
+	pub libp2p_peer_msg_rate: Option<f64>,
+
+	/// Max burst of the libp2p messages published by a single peer.
+	/// Default: 20
+	pub libp2p_peer_msg_burst: Option<u32>,
+
+	/// Messages per second for a single libp2p topic.
+	/// Default: 10
+	pub libp2p_topic_msg_rate: Option<f64>,
+
+	/// Max burst of the libp2p messages for a single topic.
+	/// Default: 200
+	pub libp2p_topic_msg_burst: Option<u32>,
+
+	/// Penalty score of the libp2p peer for the flood and the invalid messages when the peer
+	/// is banned. Every message over the rate limit adds 1, every invalid message adds 10.
+	/// Default: 50
+	pub libp2p_peer_ban_score: Option<f64>,
+
 	/// Configuration for the peer-to-peer server
 	pub p2p_config: p2p::P2PConfig,
 
@@ -286,14 +340,23 @@ impl Default for ServerConfig {
 			pool_config: pool::PoolConfig::default(),
 			skip_sync_wait: Some(false),
 			invalid_block_hashes: Some(vec![]),
+			checkpoints: Some(vec![]),
 			duration_sync_short: Some(30),
 			duration_sync_long: Some(50),
 			run_tui: Some(true),
 			run_test_miner: Some(false),
 			test_miner_wallet_url: None,
+			test_miner_threads: Some(1),
 			libp2p_enabled: Some(true),
 			libp2p_port: Some(3417),
 			libp2p_topics: None,
+			libp2p_messages_max_age_sec: Some(86400),
+			libp2p_messages_max_per_topic: Some(10_000),
+			libp2p_peer_msg_rate: Some(0.5),
+			libp2p_peer_msg_burst: Some(20),
+			libp2p_topic_msg_rate: Some(10.0),
+			libp2p_topic_msg_burst: Some(200),
+			libp2p_peer_ban_score: Some(50.0),
 			webhook_config: WebHooksConfig::default(),
 			tor_config: TorConfig::default(),
 		}
@@ -418,6 +481,9 @@ pub struct WebHooksConfig {
 	pub block_received_url: Option<String>,
 	/// url to POST block data when a new block is accepted by our node (might be a reorg or a fork)
 	pub block_accepted_url: Option<String>,
+	/// url to POST watch list events (output or kernel confirmation, spend or reorg)
+	#[serde(default)]
+	pub watch_list_url: Option<String>,
 	/// number of worker threads in the tokio runtime
 	#[serde(default = "default_nthreads")]
 	pub nthreads: u16,
@@ -441,6 +507,7 @@ impl Default for WebHooksConfig {
 			header_received_url: None,
 			block_received_url: None,
 			block_accepted_url: None,
+			watch_list_url: None,
 			nthreads: default_nthreads(),
 			timeout: default_timeout(),
 		}
@@ -488,7 +555,12 @@ impl DandelionEpoch {
 	/// Choose a new outbound stem relay peer.
 	pub fn next_epoch(&mut self, peers: &Arc<p2p::Peers>) {
 		self.start_time = Some(Utc::now().timestamp());
-		self.relay_peer = peers.iter().outbound().connected().choose_random();
+		self.relay_peer = peers
+			.iter()
+			.outbound()
+			.full_relay()
+			.connected()
+			.choose_random();
 
 		// If stem_probability == 90 then we stem 90% of the time.
 		let stem_probability = self.config.stem_probability;
@@ -529,7 +601,12 @@ impl DandelionEpoch {
 		}
 
 		if update_relay {
-			self.relay_peer = peers.iter().outbound().connected().choose_random();
+			self.relay_peer = peers
+				.iter()
+				.outbound()
+				.full_relay()
+				.connected()
+				.choose_random();
 			info!(
 				"DandelionEpoch: relay_peer: new peer chosen: {:?}",
 				self.relay_peer.clone().map(|p| p.info.addr.clone())
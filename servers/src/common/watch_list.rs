@@ -0,0 +1,784 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watch list of the outputs and kernels. Wallets and exchanges register the
+//! commitments they are interested in, the node tracks the chain and reports
+//! confirmation, spend and reorg events for them.
+
+use crate::api::{self, WatchEvent, WatchEventType, WatchEvents, WatchItem, WatchItemType};
+use crate::chain::{self, BlockStatus};
+use crate::common::hooks::{ChainEvents, WebHook};
+use crate::core::core::hash::{Hash, Hashed};
+use crate::core::core::{Block, BlockHeader};
+use crate::core::ser::{self, DeserializationMode, Readable, Reader, Writeable, Writer};
+use crate::store::{self, to_key, u64_to_key};
+use crate::util::secp::pedersen::Commitment;
+use crate::util::{self, Mutex, OneTime, RwLock, ToHex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const DB_NAME: &str = "watch";
+const STORE_SUBPATH: &str = "watch_list";
+
+const ITEM_PREFIX: u8 = b'I';
+const EVENT_PREFIX: u8 = b'E';
+
+/// Number of the latest events that the node keeps
+const MAX_EVENTS: usize = 10_000;
+/// Max number of the watched items
+const MAX_ITEMS: usize = 1_000_000;
+/// Reorgs deeper than that are not tracked, events for such reorgs are not generated
+const MAX_REORG_DEPTH: u64 = 10_000;
+
+fn item_type_to_u8(t: WatchItemType) -> u8 {
+	match t {
+		WatchItemType::Output => 0,
+		WatchItemType::Kernel => 1,
+	}
+}
+
+fn item_type_from_u8(t: u8) -> Result<WatchItemType, ser::Error> {
+	match t {
+		0 => Ok(WatchItemType::Output),
+		1 => Ok(WatchItemType::Kernel),
+		_ => Err(ser::Error::CorruptedData(format!(
+			"Invalid watch item type {}",
+			t
+		))),
+	}
+}
+
+fn event_type_to_u8(t: WatchEventType) -> u8 {
+	match t {
+		WatchEventType::Confirmed => 0,
+		WatchEventType::Spent => 1,
+		WatchEventType::Unconfirmed => 2,
+		WatchEventType::SpendReverted => 3,
+	}
+}
+
+fn event_type_from_u8(t: u8) -> Result<WatchEventType, ser::Error> {
+	match t {
+		0 => Ok(WatchEventType::Confirmed),
+		1 => Ok(WatchEventType::Spent),
+		2 => Ok(WatchEventType::Unconfirmed),
+		3 => Ok(WatchEventType::SpendReverted),
+		_ => Err(ser::Error::CorruptedData(format!(
+			"Invalid watch event type {}",
+			t
+		))),
+	}
+}
+
+fn write_label<W: Writer>(writer: &mut W, label: &Option<String>) -> Result<(), ser::Error> {
+	match label {
+		Some(label) => {
+			writer.write_u8(1)?;
+			writer.write_bytes(label)
+		}
+		None => writer.write_u8(0),
+	}
+}
+
+fn read_label<R: Reader>(reader: &mut R) -> Result<Option<String>, ser::Error> {
+	if reader.read_u8()? == 0 {
+		return Ok(None);
+	}
+	let label = reader.read_bytes_len_prefix()?;
+	let label = String::from_utf8(label)
+		.map_err(|e| ser::Error::CorruptedData(format!("Fail to read watch label, {}", e)))?;
+	Ok(Some(label))
+}
+
+/// Watched item as it is stored in the db
+struct StoredItem {
+	item_type: WatchItemType,
+	commit: Commitment,
+	label: Option<String>,
+}
+
+impl Writeable for StoredItem {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u8(item_type_to_u8(self.item_type))?;
+		self.commit.write(writer)?;
+		write_label(writer, &self.label)
+	}
+}
+
+impl Readable for StoredItem {
+	fn read<R: Reader>(reader: &mut R) -> Result<StoredItem, ser::Error> {
+		let item_type = item_type_from_u8(reader.read_u8()?)?;
+		let commit = Commitment::read(reader)?;
+		let label = read_label(reader)?;
+		Ok(StoredItem {
+			item_type,
+			commit,
+			label,
+		})
+	}
+}
+
+/// Event as it is stored in the db
+struct StoredEvent {
+	seq: u64,
+	event_type: WatchEventType,
+	item: StoredItem,
+	height: u64,
+	block_hash: Hash,
+}
+
+impl Writeable for StoredEvent {
+	fn write<W: Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_u64(self.seq)?;
+		writer.write_u8(event_type_to_u8(self.event_type))?;
+		self.item.write(writer)?;
+		writer.write_u64(self.height)?;
+		self.block_hash.write(writer)
+	}
+}
+
+impl Readable for StoredEvent {
+	fn read<R: Reader>(reader: &mut R) -> Result<StoredEvent, ser::Error> {
+		let seq = reader.read_u64()?;
+		let event_type = event_type_from_u8(reader.read_u8()?)?;
+		let item = StoredItem::read(reader)?;
+		let height = reader.read_u64()?;
+		let block_hash = Hash::read(reader)?;
+		Ok(StoredEvent {
+			seq,
+			event_type,
+			item,
+			height,
+			block_hash,
+		})
+	}
+}
+
+impl StoredEvent {
+	fn to_api(&self) -> WatchEvent {
+		WatchEvent {
+			seq: self.seq,
+			event_type: self.event_type,
+			item_type: self.item.item_type,
+			commit: self.item.commit.0.to_hex(),
+			label: self.item.label.clone(),
+			height: self.height,
+			block_hash: self.block_hash.to_hex(),
+		}
+	}
+}
+
+fn item_key(item_type: WatchItemType, commit: &Commitment) -> Vec<u8> {
+	let mut k = vec![item_type_to_u8(item_type)];
+	k.extend_from_slice(&commit.0);
+	to_key(ITEM_PREFIX, k)
+}
+
+fn parse_item(item: &WatchItem) -> Result<(WatchItemType, Commitment), String> {
+	let bytes = util::from_hex(&item.commit)
+		.map_err(|e| format!("Invalid commitment {}, {}", item.commit, e))?;
+	if bytes.len() != util::secp::constants::PEDERSEN_COMMITMENT_SIZE {
+		return Err(format!("Invalid commitment length {}", item.commit));
+	}
+	Ok((item.item_type, Commitment::from_vec(bytes)))
+}
+
+struct EventsState {
+	next_seq: u64,
+	events: VecDeque<StoredEvent>,
+}
+
+/// Watch list of the outputs and kernels. Hooked into the chain events, the items and
+/// the latest events are stored in the node db.
+pub struct WatchList {
+	db: store::Store,
+	chain: OneTime<Weak<chain::Chain>>,
+	items: RwLock<HashMap<(WatchItemType, Commitment), Option<String>>>,
+	// Last block that was processed by the watch list, used to find the reorged blocks
+	last_processed: Mutex<Option<BlockHeader>>,
+	events: std::sync::Mutex<EventsState>,
+	events_cond: Condvar,
+	// Last event sequence number, for the asynchronous wait of the API handler
+	last_seq: watch::Sender<u64>,
+	last_seq_rx: watch::Receiver<u64>,
+	webhook: Option<Arc<WebHook>>,
+}
+
+impl WatchList {
+	/// Open the watch list at the node db. Events are posted with the `webhook` shared with
+	/// the chain hooks, if its watch list url is set.
+	pub fn new(db_root: &str, webhook: Option<Arc<WebHook>>) -> Result<WatchList, store::Error> {
+		let db = store::Store::new(db_root, Some(DB_NAME), Some(STORE_SUBPATH), None)?;
+
+		let mut items = HashMap::new();
+		let protocol_version = db.protocol_version();
+		for item in db.iter(&[ITEM_PREFIX], move |_, mut v| {
+			ser::deserialize::<StoredItem, _>(
+				&mut v,
+				protocol_version,
+				DeserializationMode::default(),
+			)
+			.map_err(From::from)
+		})? {
+			items.insert((item.item_type, item.commit), item.label);
+		}
+
+		// Keys are big endian sequence numbers, so the events are ordered
+		let events: VecDeque<StoredEvent> = db
+			.iter(&[EVENT_PREFIX], move |_, mut v| {
+				ser::deserialize::<StoredEvent, _>(
+					&mut v,
+					protocol_version,
+					DeserializationMode::default(),
+				)
+				.map_err(From::from)
+			})?
+			.collect();
+		let next_seq = events.back().map(|e| e.seq + 1).unwrap_or(1);
+
+		info!(
+			"Watch list is loaded with {} items and {} events",
+			items.len(),
+			events.len()
+		);
+
+		let (last_seq, last_seq_rx) = watch::channel(next_seq - 1);
+		Ok(WatchList {
+			db,
+			chain: OneTime::new(),
+			items: RwLock::new(items),
+			last_processed: Mutex::new(None),
+			events: std::sync::Mutex::new(EventsState { next_seq, events }),
+			events_cond: Condvar::new(),
+			last_seq,
+			last_seq_rx,
+			webhook,
+		})
+	}
+
+	/// Set the chain, should be called once when the chain is created
+	pub fn init(&self, chain: Arc<chain::Chain>) {
+		if let Ok(head) = chain.head_header() {
+			*self.last_processed.lock() = Some(head);
+		}
+		self.chain.init(Arc::downgrade(&chain));
+	}
+
+	fn chain(&self) -> Option<Arc<chain::Chain>> {
+		if !self.chain.is_init() {
+			return None;
+		}
+		self.chain.borrow().upgrade()
+	}
+
+	fn process_block(&self, block: &Block) {
+		let chain = match self.chain() {
+			Some(chain) => chain,
+			None => return,
+		};
+
+		let mut last_processed = self.last_processed.lock();
+		let (rewound, applied) = match last_processed.as_ref() {
+			Some(last) if last.hash() == block.header.prev_hash => (vec![], vec![block.hash()]),
+			Some(last) => match Self::find_fork(&chain, last, &block.header) {
+				Ok(r) => r,
+				Err(e) => {
+					warn!(
+						"Watch list is unable to find the fork point for block {} at {}, {}",
+						block.hash(),
+						block.header.height,
+						e
+					);
+					(vec![], vec![block.hash()])
+				}
+			},
+			None => (vec![], vec![block.hash()]),
+		};
+		*last_processed = Some(block.header.clone());
+
+		if self.items.read().is_empty() {
+			return;
+		}
+
+		let mut new_events = vec![];
+		// Reverting the events of the blocks that are not on the chain any more
+		for hash in rewound {
+			match chain.get_block(&hash) {
+				Ok(b) => self.block_events(
+					&b,
+					WatchEventType::Unconfirmed,
+					WatchEventType::SpendReverted,
+					&mut new_events,
+				),
+				Err(e) => warn!("Watch list is unable to read reorged block {}, {}", hash, e),
+			}
+		}
+		for hash in applied {
+			if hash == block.hash() {
+				self.block_events(
+					block,
+					WatchEventType::Confirmed,
+					WatchEventType::Spent,
+					&mut new_events,
+				);
+				continue;
+			}
+			match chain.get_block(&hash) {
+				Ok(b) => self.block_events(
+					&b,
+					WatchEventType::Confirmed,
+					WatchEventType::Spent,
+					&mut new_events,
+				),
+				Err(e) => warn!("Watch list is unable to read block {}, {}", hash, e),
+			}
+		}
+
+		if !new_events.is_empty() {
+			if let Err(e) = self.add_events(new_events) {
+				error!("Unable to save watch list events, {}", e);
+			}
+		}
+	}
+
+	/// Blocks to rewind (from the top) and blocks to apply (from the fork point) to move
+	/// from `from` to `to`.
+	fn find_fork(
+		chain: &chain::Chain,
+		from: &BlockHeader,
+		to: &BlockHeader,
+	) -> Result<(Vec<Hash>, Vec<Hash>), chain::Error> {
+		let mut rewound = vec![];
+		let mut applied = vec![];
+		let mut old = from.clone();
+		let mut new = to.clone();
+		while old.hash() != new.hash() {
+			if (rewound.len() + applied.len()) as u64 > MAX_REORG_DEPTH * 2 {
+				return Err(chain::Error::Other(format!(
+					"Reorg is deeper than {} blocks",
+					MAX_REORG_DEPTH
+				)));
+			}
+			if new.height > old.height {
+				applied.push(new.hash());
+				new = chain.get_previous_header(&new)?;
+			} else {
+				rewound.push(old.hash());
+				old = chain.get_previous_header(&old)?;
+			}
+		}
+		applied.reverse();
+		Ok((rewound, applied))
+	}
+
+	fn block_events(
+		&self,
+		block: &Block,
+		added: WatchEventType,
+		spent: WatchEventType,
+		events: &mut Vec<(WatchEventType, StoredItem, u64, Hash)>,
+	) {
+		let items = self.items.read();
+		let mut check = |item_type: WatchItemType, commit: Commitment, event_type| {
+			if let Some(label) = items.get(&(item_type, commit)) {
+				events.push((
+					event_type,
+					StoredItem {
+						item_type,
+						commit,
+						label: label.clone(),
+					},
+					block.header.height,
+					block.hash(),
+				));
+			}
+		};
+
+		let inputs: Vec<_> = block.inputs().into();
+		for input in inputs {
+			check(WatchItemType::Output, input.commitment(), spent);
+		}
+		for output in block.outputs() {
+			check(WatchItemType::Output, output.commitment(), added);
+		}
+		for kernel in block.kernels() {
+			check(WatchItemType::Kernel, kernel.excess, added);
+		}
+	}
+
+	fn add_events(
+		&self,
+		new_events: Vec<(WatchEventType, StoredItem, u64, Hash)>,
+	) -> Result<(), store::Error> {
+		let mut state = self.events.lock().expect("watch list events lock");
+		let batch = self.db.batch_write()?;
+		let mut api_events = vec![];
+		for (event_type, item, height, block_hash) in new_events {
+			let event = StoredEvent {
+				seq: state.next_seq,
+				event_type,
+				item,
+				height,
+				block_hash,
+			};
+			state.next_seq += 1;
+			batch.put_ser(&u64_to_key(EVENT_PREFIX, event.seq), &event)?;
+			api_events.push(event.to_api());
+			state.events.push_back(event);
+		}
+		while state.events.len() > MAX_EVENTS {
+			if let Some(e) = state.events.pop_front() {
+				batch.delete(&u64_to_key(EVENT_PREFIX, e.seq))?;
+			}
+		}
+		batch.commit()?;
+		let last_seq = state.next_seq - 1;
+		drop(state);
+		self.events_cond.notify_all();
+		// The watch list keeps a receiver, so the broadcast never fails
+		let _ = self.last_seq.broadcast(last_seq);
+
+		if let Some(webhook) = &self.webhook {
+			for event in &api_events {
+				webhook.on_watch_event(event);
+			}
+		}
+		for event in api_events {
+			debug!(
+				"Watch list event {:?} for {:?} {} at {}",
+				event.event_type, event.item_type, event.commit, event.height
+			);
+		}
+		Ok(())
+	}
+
+	fn events_after(state: &EventsState, after_seq: u64) -> Vec<WatchEvent> {
+		state
+			.events
+			.iter()
+			.filter(|e| e.seq > after_seq)
+			.map(|e| e.to_api())
+			.collect()
+	}
+}
+
+impl ChainEvents for WatchList {
+	fn on_block_accepted(&self, block: &Block, status: BlockStatus) {
+		// Fork blocks are not on the chain yet, they will be processed with the reorg
+		if status.is_next() || status.is_reorg() {
+			self.process_block(block);
+		}
+	}
+}
+
+impl api::WatchList for WatchList {
+	fn add_items(&self, items: Vec<WatchItem>) -> Result<(), String> {
+		let parsed = items
+			.iter()
+			.map(parse_item)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let mut watched = self.items.write();
+		if watched.len() + parsed.len() > MAX_ITEMS {
+			return Err(format!("Watch list is limited to {} items", MAX_ITEMS));
+		}
+		let batch = self.db.batch_write().map_err(|e| e.to_string())?;
+		for ((item_type, commit), item) in parsed.iter().zip(items.iter()) {
+			let stored = StoredItem {
+				item_type: *item_type,
+				commit: *commit,
+				label: item.label.clone(),
+			};
+			batch
+				.put_ser(&item_key(*item_type, commit), &stored)
+				.map_err(|e| e.to_string())?;
+		}
+		batch.commit().map_err(|e| e.to_string())?;
+		for ((item_type, commit), item) in parsed.into_iter().zip(items.into_iter()) {
+			watched.insert((item_type, commit), item.label);
+		}
+		Ok(())
+	}
+
+	fn remove_items(&self, items: Vec<WatchItem>) -> Result<(), String> {
+		let parsed = items
+			.iter()
+			.map(parse_item)
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let mut watched = self.items.write();
+		let batch = self.db.batch_write().map_err(|e| e.to_string())?;
+		for (item_type, commit) in &parsed {
+			if watched.contains_key(&(*item_type, *commit)) {
+				batch
+					.delete(&item_key(*item_type, commit))
+					.map_err(|e| e.to_string())?;
+			}
+		}
+		batch.commit().map_err(|e| e.to_string())?;
+		for key in parsed {
+			watched.remove(&key);
+		}
+		Ok(())
+	}
+
+	fn get_items(&self) -> Result<Vec<WatchItem>, String> {
+		Ok(self
+			.items
+			.read()
+			.iter()
+			.map(|((item_type, commit), label)| WatchItem {
+				item_type: *item_type,
+				commit: commit.0.to_hex(),
+				label: label.clone(),
+			})
+			.collect())
+	}
+
+	fn subscribe(&self) -> watch::Receiver<u64> {
+		self.last_seq_rx.clone()
+	}
+
+	fn get_events(&self, after_seq: u64, timeout: Duration) -> Result<WatchEvents, String> {
+		let deadline = Instant::now() + timeout;
+		let mut state = self
+			.events
+			.lock()
+			.map_err(|e| format!("Watch list events lock is poisoned, {}", e))?;
+		loop {
+			let events = Self::events_after(&state, after_seq);
+			let now = Instant::now();
+			if !events.is_empty() || now >= deadline {
+				return Ok(WatchEvents {
+					last_seq: state.next_seq - 1,
+					events,
+				});
+			}
+			state = self
+				.events_cond
+				.wait_timeout(state, deadline - now)
+				.map_err(|e| format!("Watch list events lock is poisoned, {}", e))?
+				.0;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::api::WatchList as _;
+	use crate::chain::types::{ChainAdapter, Options};
+	use crate::core::core::{Output, TxKernel};
+	use crate::core::global;
+	use crate::core::libtx::{self, ProofBuilder};
+	use crate::core::pow::{self, Difficulty};
+	use crate::core::ser::ProtocolVersion;
+	use crate::keychain::{ExtKeychain, ExtKeychainPath, Keychain};
+	use chrono::Duration as ChronoDuration;
+	use std::fs;
+
+	// Forwards the accepted blocks to the watch list, like the server chain hooks
+	struct WatchAdapter(Arc<WatchList>);
+
+	impl ChainAdapter for WatchAdapter {
+		fn block_accepted(&self, b: &Block, status: BlockStatus, _opts: Options) {
+			self.0.on_block_accepted(b, status);
+		}
+	}
+
+	fn reward(kc: &ExtKeychain, n: u32, height: u64) -> (Output, TxKernel) {
+		let key_id = ExtKeychainPath::new(1, n, 0, 0, 0).to_identifier();
+		libtx::reward::output(
+			kc,
+			&ProofBuilder::new(kc),
+			&key_id,
+			0,
+			false,
+			height,
+			kc.secp(),
+		)
+		.unwrap()
+	}
+
+	// Block with a random proof, valid for processing with SKIP_POW only
+	fn build_block(
+		chain: &chain::Chain,
+		prev: &BlockHeader,
+		reward: (Output, TxKernel),
+		kc: &ExtKeychain,
+	) -> Block {
+		let diff = Difficulty::from_num(10);
+		let mut b = Block::new(prev, &[], diff, reward, kc.secp()).unwrap();
+		b.header.timestamp = prev.timestamp + ChronoDuration::seconds(60);
+		b.header.pow.total_difficulty = prev.total_difficulty() + diff;
+		b.header.pow.proof = pow::Proof::random(global::proofsize());
+		chain.set_txhashset_roots(&mut b).unwrap();
+		b
+	}
+
+	#[test]
+	fn watch_events_on_block_and_reorg() {
+		let db_root = ".mwc_watch_list_events";
+		let _ = fs::remove_dir_all(db_root);
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		let kc = ExtKeychain::from_random_seed(false).unwrap();
+
+		let watch_list = Arc::new(WatchList::new(db_root, None).unwrap());
+		let chain = Arc::new(
+			chain::Chain::init(
+				format!("{}/chain", db_root),
+				Arc::new(WatchAdapter(watch_list.clone())),
+				pow::mine_genesis_block().unwrap(),
+				pow::verify_size,
+				false,
+			)
+			.unwrap(),
+		);
+		watch_list.init(chain.clone());
+		let genesis = chain.head_header().unwrap();
+		let mut last_seq = watch_list.subscribe();
+		assert_eq!(*last_seq.borrow(), 0);
+
+		// Watched output and kernel are confirmed at the block 1
+		let reward_1 = reward(&kc, 1, 1);
+		watch_list
+			.add_items(vec![
+				WatchItem {
+					item_type: WatchItemType::Output,
+					commit: reward_1.0.commitment().0.to_hex(),
+					label: Some("coinbase".to_string()),
+				},
+				WatchItem {
+					item_type: WatchItemType::Kernel,
+					commit: reward_1.1.excess.0.to_hex(),
+					label: None,
+				},
+			])
+			.unwrap();
+		let block_1 = build_block(&chain, &genesis, reward_1.clone(), &kc);
+		chain
+			.process_block(block_1.clone(), Options::SKIP_POW)
+			.unwrap();
+
+		let res = watch_list.get_events(0, Duration::from_secs(0)).unwrap();
+		assert_eq!(res.last_seq, 2);
+		let types: Vec<_> = res
+			.events
+			.iter()
+			.map(|e| (e.event_type, e.item_type))
+			.collect();
+		assert_eq!(
+			types,
+			vec![
+				(WatchEventType::Confirmed, WatchItemType::Output),
+				(WatchEventType::Confirmed, WatchItemType::Kernel)
+			]
+		);
+		assert!(res
+			.events
+			.iter()
+			.all(|e| e.height == 1 && e.block_hash == block_1.hash().to_hex()));
+		assert_eq!(res.events[0].label, Some("coinbase".to_string()));
+		assert_eq!(*last_seq.borrow(), 2);
+
+		// Fork block with the same work is not on the chain, no events
+		let fork_1 = build_block(&chain, &genesis, reward(&kc, 2, 1), &kc);
+		chain
+			.process_block(fork_1.clone(), Options::SKIP_POW)
+			.unwrap();
+		let res = watch_list.get_events(2, Duration::from_millis(10)).unwrap();
+		assert!(res.events.is_empty());
+		assert_eq!(res.last_seq, 2);
+
+		// Reorg to the fork, the block 1 items are unconfirmed
+		let fork_2 = build_block(&chain, &fork_1.header, reward(&kc, 3, 2), &kc);
+		chain
+			.process_block(fork_2.clone(), Options::SKIP_POW)
+			.unwrap();
+		assert_eq!(chain.head().unwrap().last_block_h, fork_2.hash());
+
+		let res = watch_list.get_events(2, Duration::from_secs(0)).unwrap();
+		assert_eq!(res.last_seq, 4);
+		let types: Vec<_> = res
+			.events
+			.iter()
+			.map(|e| (e.event_type, e.item_type))
+			.collect();
+		assert_eq!(
+			types,
+			vec![
+				(WatchEventType::Unconfirmed, WatchItemType::Output),
+				(WatchEventType::Unconfirmed, WatchItemType::Kernel)
+			]
+		);
+		assert!(res
+			.events
+			.iter()
+			.all(|e| e.height == 1 && e.block_hash == block_1.hash().to_hex()));
+		assert_eq!(*last_seq.borrow(), 4);
+
+		// Asynchronous wait returns at once for the existing events
+		let mut rt = tokio::runtime::Runtime::new().unwrap();
+		rt.block_on(async {
+			let seq = tokio::time::timeout(Duration::from_secs(1), last_seq.recv())
+				.await
+				.unwrap();
+			assert_eq!(seq, Some(4));
+		});
+
+		drop(chain);
+		let _ = fs::remove_dir_all(db_root);
+	}
+
+	#[test]
+	fn stored_event_ser() {
+		let event = StoredEvent {
+			seq: 7,
+			event_type: WatchEventType::SpendReverted,
+			item: StoredItem {
+				item_type: WatchItemType::Kernel,
+				commit: Commitment::from_vec(vec![9; 33]),
+				label: Some("deposit".to_string()),
+			},
+			height: 100,
+			block_hash: Hash::from_vec(&[3; 32]),
+		};
+		let vec = ser::ser_vec(&event, ProtocolVersion::local()).unwrap();
+		let res: StoredEvent = ser::deserialize(
+			&mut &vec[..],
+			ProtocolVersion::local(),
+			DeserializationMode::default(),
+		)
+		.unwrap();
+		assert_eq!(res.to_api(), event.to_api());
+
+		let item = StoredItem {
+			item_type: WatchItemType::Output,
+			commit: Commitment::from_vec(vec![1; 33]),
+			label: None,
+		};
+		let vec = ser::ser_vec(&item, ProtocolVersion::local()).unwrap();
+		let res: StoredItem = ser::deserialize(
+			&mut &vec[..],
+			ProtocolVersion::local(),
+			DeserializationMode::default(),
+		)
+		.unwrap();
+		assert_eq!(res.item_type, WatchItemType::Output);
+		assert_eq!(res.commit, item.commit);
+		assert_eq!(res.label, None);
+	}
+}
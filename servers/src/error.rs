@@ -29,6 +29,10 @@ pub enum Error {
 	#[error("Tor Process Error: {0}")]
 	TorProcess(String),
 
+	/// Tor control port error
+	#[error("Tor Control Error: {0}")]
+	TorControl(String),
+
 	/// Onion V3 Address Error
 	#[error("Onion V3 Address Error")]
 	OnionV3Address(OnionV3AddressError),
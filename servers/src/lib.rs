@@ -47,4 +47,5 @@ mod tor;
 pub use crate::common::stats::{DiffBlock, PeerStats, ServerStats, StratumStats, WorkerStats};
 pub use crate::common::types::{ServerConfig, StratumServerConfig};
 pub use crate::core::global::{FLOONET_DNS_SEEDS, MAINNET_DNS_SEEDS};
+pub use crate::mining::block_generator::TestBlockGenerator;
 pub use crate::mwc::server::{Server, ServerTxPool};
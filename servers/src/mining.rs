@@ -15,6 +15,7 @@
 
 //! Mining + Mining server
 
+pub mod block_generator;
 mod mine_block;
 mod stratum_data;
 pub mod stratumserver;
@@ -0,0 +1,229 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! On demand blocks generation for the testing networks. Used by the owner API
+//! to drive the private networks from the integration tests.
+
+use crate::api;
+use crate::chain;
+use crate::common::adapters::{PoolToChainAdapter, PoolToNetAdapter};
+use crate::core::core::hash::{Hash, Hashed};
+use crate::core::global;
+use crate::core::pow::{self, Difficulty};
+use crate::mining::mine_block;
+use crate::pool;
+use crate::util::{Mutex, RwLock};
+use crate::ServerTxPool;
+use rand::{thread_rng, Rng};
+use std::sync::{Arc, Weak};
+
+/// Maximum number of blocks that can be generated with a single call
+const MAX_BLOCKS_PER_CALL: u64 = 1000;
+
+/// Builds, mines and processes blocks immediately on top of the chain head.
+pub struct TestBlockGenerator {
+	chain: Weak<chain::Chain>,
+	tx_pool: Weak<RwLock<pool::TransactionPool<PoolToChainAdapter, PoolToNetAdapter>>>,
+	// Only one generation at a time, otherwise generated blocks will compete for the same height
+	lock: Mutex<()>,
+}
+
+impl TestBlockGenerator {
+	/// Creates a new blocks generator
+	pub fn new(chain: &Arc<chain::Chain>, tx_pool: &ServerTxPool) -> Self {
+		TestBlockGenerator {
+			chain: Arc::downgrade(chain),
+			tx_pool: Arc::downgrade(tx_pool),
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn generate_block(
+		&self,
+		chain: &Arc<chain::Chain>,
+		tx_pool: &ServerTxPool,
+		coinbase_destination: &Option<String>,
+	) -> Result<Hash, String> {
+		let head = chain
+			.head_header()
+			.map_err(|e| format!("Unable to get chain head, {}", e))?;
+		let (mut b, _block_fees) =
+			mine_block::build_block(chain, tx_pool, None, coinbase_destination.clone())
+				.map_err(|e| format!("Unable to build a block, {}", e))?;
+
+		// Mock difficulty applies to the generated blocks only, the consensus difficulty
+		// is not affected. Such blocks don't pass the difficulty validation, so the
+		// difficulty check is skipped for them, the proof of work is found right here.
+		// The peers would reject them as well, so they are processed like the synced
+		// blocks, without the broadcast.
+		let opts = match global::get_mock_difficulty() {
+			Some(difficulty) => {
+				b.header.pow.total_difficulty =
+					head.total_difficulty() + Difficulty::from_num(difficulty);
+				chain::Options::SYNC | chain::Options::SKIP_POW
+			}
+			None => chain::Options::MINE,
+		};
+
+		b.header.pow.nonce = thread_rng().gen();
+		let target_difficulty = b.header.total_difficulty() - head.total_difficulty();
+		pow::pow_size(
+			&mut b.header,
+			target_difficulty,
+			global::proofsize(),
+			global::min_edge_bits(),
+		)
+		.map_err(|e| format!("Unable to find proof of work, {}", e))?;
+
+		let hash = b.hash();
+		chain
+			.process_block(b, opts)
+			.map_err(|e| format!("Generated block {} is rejected, {}", hash, e))?;
+		Ok(hash)
+	}
+}
+
+impl api::BlockGenerator for TestBlockGenerator {
+	fn generate_blocks(
+		&self,
+		count: u64,
+		coinbase_destination: Option<String>,
+	) -> Result<Vec<Hash>, String> {
+		if global::is_production_mode() {
+			return Err("Blocks generation is available for the testing networks only".into());
+		}
+		if count == 0 || count > MAX_BLOCKS_PER_CALL {
+			return Err(format!(
+				"Blocks count must be in range 1..{}, get {}",
+				MAX_BLOCKS_PER_CALL, count
+			));
+		}
+		let chain = self.chain.upgrade().ok_or("Chain is not available")?;
+		let tx_pool = self
+			.tx_pool
+			.upgrade()
+			.ok_or("Transaction pool is not available")?;
+
+		let _guard = self.lock.lock();
+		let mut hashes = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			let hash = self.generate_block(&chain, &tx_pool, &coinbase_destination)?;
+			debug!("Generated block {} on demand", hash);
+			hashes.push(hash);
+		}
+		Ok(hashes)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::api::{Owner, PoolDryRun};
+	use crate::chain::{BlockStatus, ChainAdapter, Options};
+	use crate::core::core::Block;
+	use crate::util::ToHex;
+	use chrono::Utc;
+	use std::fs;
+
+	// Records the processing options of the accepted blocks, the MINE blocks are broadcasted
+	#[derive(Default)]
+	struct OptionsAdapter(Mutex<Vec<Options>>);
+
+	impl ChainAdapter for OptionsAdapter {
+		fn block_accepted(&self, _b: &Block, _status: BlockStatus, opts: Options) {
+			self.0.lock().push(opts);
+		}
+	}
+
+	#[test]
+	fn owner_generate_blocks() {
+		let db_root = ".mwc_block_generator";
+		let _ = fs::remove_dir_all(db_root);
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+
+		let adapter = Arc::new(OptionsAdapter::default());
+		let chain = Arc::new(
+			chain::Chain::init(
+				db_root.to_string(),
+				adapter.clone(),
+				pow::mine_genesis_block().unwrap(),
+				pow::verify_size,
+				false,
+			)
+			.unwrap(),
+		);
+		let pool_adapter = Arc::new(PoolToChainAdapter::new());
+		pool_adapter.set_chain(chain.clone());
+		let tx_pool: ServerTxPool = Arc::new(RwLock::new(pool::TransactionPool::new(
+			pool::PoolConfig::default(),
+			pool_adapter,
+			Arc::new(PoolToNetAdapter::new(pool::DandelionConfig::default())),
+		)));
+		let pool_dry_run: Weak<dyn PoolDryRun> = Arc::downgrade(&tx_pool);
+		let owner = Owner::new(
+			Arc::downgrade(&chain),
+			Weak::new(),
+			Weak::new(),
+			pool_dry_run,
+			Some(Arc::new(TestBlockGenerator::new(&chain, &tx_pool))),
+			None,
+			None,
+		);
+
+		// Consensus difficulty, the blocks are broadcasted
+		let headers = owner.generate_blocks(2, None).unwrap();
+		assert_eq!(
+			headers.iter().map(|h| h.height).collect::<Vec<_>>(),
+			vec![1, 2]
+		);
+		assert_eq!(headers[1].hash, chain.head().unwrap().last_block_h.to_hex());
+		assert_eq!(*adapter.0.lock(), vec![Options::MINE; 2]);
+		assert!(owner.generate_blocks(0, None).is_err());
+		assert!(owner
+			.generate_blocks(MAX_BLOCKS_PER_CALL + 1, None)
+			.is_err());
+
+		// Mock time and difficulty, the blocks are processed without the broadcast
+		let mock_time = Utc::now().timestamp() + 100;
+		let prev = chain.head_header().unwrap();
+		owner.set_mock_time(Some(mock_time)).unwrap();
+		owner.set_difficulty(Some(100)).unwrap();
+		assert!(owner.set_difficulty(Some(0)).is_err());
+		assert!(owner.set_mock_time(Some(-1)).is_err());
+		let headers = owner.generate_blocks(1, None).unwrap();
+		let head = chain.head_header().unwrap();
+		assert_eq!(headers[0].hash, head.hash().to_hex());
+		assert_eq!(head.timestamp.timestamp(), mock_time);
+		assert_eq!(
+			head.total_difficulty(),
+			prev.total_difficulty() + Difficulty::from_num(100)
+		);
+		assert_eq!(
+			adapter.0.lock().last(),
+			Some(&(Options::SYNC | Options::SKIP_POW))
+		);
+		owner.set_mock_time(None).unwrap();
+		owner.set_difficulty(None).unwrap();
+
+		// Production networks refuse all of them
+		global::set_local_chain_type(global::ChainTypes::Mainnet);
+		assert!(owner.generate_blocks(1, None).is_err());
+		assert!(owner.set_mock_time(Some(mock_time)).is_err());
+		assert!(owner.set_difficulty(Some(1000)).is_err());
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		assert_eq!(chain.head().unwrap().height, 3);
+
+		let _ = fs::remove_dir_all(db_root);
+	}
+}
@@ -118,7 +118,7 @@ pub fn get_block(
 
 /// Builds a new block with the chain head as previous and eligible
 /// transactions from the pool.
-fn build_block(
+pub fn build_block(
 	chain: &Arc<chain::Chain>,
 	tx_pool: &ServerTxPool,
 	key_id: Option<Identifier>,
@@ -127,7 +127,11 @@ fn build_block(
 	let head = chain.head_header()?;
 
 	// prepare the block header timestamp
-	let mut now_sec = Utc::now().timestamp();
+	// Mock time in the future is limited, the peers refuse blocks that are too far ahead.
+	let clock_sec = Utc::now().timestamp();
+	let mut now_sec = global::get_mock_time()
+		.map(|t| t.min(clock_sec + consensus::FUTURE_TIME_LIMIT_SEC - 1))
+		.unwrap_or(clock_sec);
 	let head_sec = head.timestamp.timestamp();
 	if now_sec <= head_sec {
 		now_sec = head_sec + 1;
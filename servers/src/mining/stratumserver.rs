@@ -24,7 +24,7 @@ use tokio_util::codec::{Framed, LinesCodec};
 
 use crate::util::RwLock;
 use chrono::prelude::Utc;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
@@ -32,6 +32,7 @@ use std::time::Duration;
 use std::{cmp, thread};
 
 use super::stratum_data::WorkersList;
+use crate::api;
 use crate::chain::{self, SyncState};
 use crate::common::stats::StratumStats;
 use crate::common::types::StratumServerConfig;
@@ -77,53 +78,63 @@ struct RpcResponse {
 	error: Option<Value>,
 }
 
+/// Stratum error. Codes are stable, the miners rely on them:
+/// -32000 node is syncing, -32501 low difficulty share, -32502 solution is not valid,
+/// -32503 solution is too late, -32600 invalid request, -32601 method not found,
+/// 32603 internal error.
 #[derive(Serialize, Deserialize, Debug)]
 struct RpcError {
 	code: i32,
 	message: String,
+	/// Structured details of the error, if any
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	data: Option<Value>,
 }
 
 impl RpcError {
-	pub fn internal_error() -> Self {
+	fn new(code: i32, message: &str) -> Self {
 		RpcError {
-			code: 32603,
-			message: "Internal error".to_owned(),
+			code,
+			message: message.to_owned(),
+			data: None,
 		}
 	}
+	pub fn internal_error() -> Self {
+		RpcError::new(32603, "Internal error")
+	}
 	pub fn node_is_syncing() -> Self {
-		RpcError {
-			code: -32000,
-			message: "Node is syncing - Please wait".to_owned(),
-		}
+		RpcError::new(-32000, "Node is syncing - Please wait")
 	}
 	pub fn method_not_found() -> Self {
-		RpcError {
-			code: -32601,
-			message: "Method not found".to_owned(),
-		}
+		RpcError::new(-32601, "Method not found")
 	}
 	pub fn too_late() -> Self {
-		RpcError {
-			code: -32503,
-			message: "Solution submitted too late".to_string(),
-		}
+		RpcError::new(-32503, "Solution submitted too late")
 	}
 	pub fn cannot_validate() -> Self {
-		RpcError {
-			code: -32502,
-			message: "Failed to validate solution".to_string(),
-		}
+		RpcError::new(-32502, "Failed to validate solution")
 	}
 	pub fn too_low_difficulty() -> Self {
+		RpcError::new(-32501, "Share rejected due to low difficulty")
+	}
+	pub fn invalid_request() -> Self {
+		RpcError::new(-32600, "Invalid Request")
+	}
+	/// Share difficulty is lower than the minimum share difficulty
+	pub fn share_difficulty(difficulty: u64, minimum_difficulty: u64) -> Self {
 		RpcError {
-			code: -32501,
-			message: "Share rejected due to low difficulty".to_string(),
+			data: Some(json!({
+				"difficulty": difficulty,
+				"minimum_difficulty": minimum_difficulty,
+			})),
+			..RpcError::too_low_difficulty()
 		}
 	}
-	pub fn invalid_request() -> Self {
+	/// Solution is rejected by the chain, the data is the API error with the chain error code
+	pub fn block_rejected(e: chain::Error) -> Self {
 		RpcError {
-			code: -32600,
-			message: "Invalid Request".to_string(),
+			data: serde_json::to_value(api::Error::from(e)).ok(),
+			..RpcError::cannot_validate()
 		}
 	}
 }
@@ -455,7 +466,10 @@ impl Handler {
 			);
 			self.workers
 				.update_stats(worker_id, |worker_stats| worker_stats.num_rejected += 1);
-			return Err(RpcError::too_low_difficulty());
+			return Err(RpcError::share_difficulty(
+				unscaled_share_difficulty,
+				minimum_share_difficulty,
+			));
 		}
 
 		// If the difficulty is high enough, submit it (which also validates it)
@@ -476,7 +490,7 @@ impl Handler {
 				);
 				self.workers
 					.update_stats(worker_id, |worker_stats| worker_stats.num_rejected += 1);
-				return Err(RpcError::cannot_validate());
+				return Err(RpcError::block_rejected(e));
 			}
 			share_is_block = true;
 			self.workers
@@ -1122,4 +1136,28 @@ mod tests {
 
 		assert_eq!(expected_deserialized, actual_deserialized);
 	}
+
+	#[test]
+	fn test_error_serialize() {
+		let err: Value = RpcError::method_not_found().into();
+		assert_eq!(err, json!({"code": -32601, "message": "Method not found"}));
+
+		let err: Value = RpcError::share_difficulty(1, 4).into();
+		assert_eq!(err["code"], json!(-32501));
+		assert_eq!(
+			err["data"],
+			json!({"difficulty": 1, "minimum_difficulty": 4})
+		);
+
+		let err: Value = RpcError::block_rejected(chain::Error::ImmatureCoinbase(10)).into();
+		assert_eq!(err["code"], json!(-32502));
+		assert_eq!(
+			err["data"]["code"],
+			json!(api::error_codes::CHAIN_IMMATURE_COINBASE)
+		);
+		assert_eq!(
+			err["data"]["data"],
+			json!({"ImmatureCoinbase": {"mature_at": 10}})
+		);
+	}
 }
@@ -19,15 +19,20 @@
 //! network.
 
 use chrono::prelude::Utc;
+use std::cmp;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use crate::chain;
+use crate::common::stats::StratumStats;
 use crate::common::types::StratumServerConfig;
 use crate::core::core::hash::{Hash, Hashed};
 use crate::core::core::{Block, BlockHeader};
 use crate::core::global;
+use crate::core::pow::{Difficulty, ProofOfWork};
 use crate::mining::mine_block;
-use crate::util::StopState;
+use crate::util::{Mutex, StopState};
 use crate::ServerTxPool;
 use mwc_chain::SyncState;
 use std::thread;
@@ -39,6 +44,9 @@ pub struct Miner {
 	tx_pool: ServerTxPool,
 	stop_state: Arc<StopState>,
 	sync_state: Arc<SyncState>,
+	stratum_stats: Arc<StratumStats>,
+	// Number of solver threads
+	threads: usize,
 	// Just to hold the port we're on, so this miner can be identified
 	// while watching debug output
 	debug_output_id: String,
@@ -53,6 +61,8 @@ impl Miner {
 		tx_pool: ServerTxPool,
 		stop_state: Arc<StopState>,
 		sync_state: Arc<SyncState>,
+		stratum_stats: Arc<StratumStats>,
+		threads: usize,
 	) -> Miner {
 		Miner {
 			config,
@@ -61,6 +71,8 @@ impl Miner {
 			debug_output_id: String::from("none"),
 			stop_state,
 			sync_state,
+			stratum_stats,
+			threads: cmp::max(1, threads),
 		}
 	}
 
@@ -71,57 +83,139 @@ impl Miner {
 	}
 
 	/// The inner part of mining loop for the internal miner
-	/// kept around mostly for automated testing purposes
+	/// kept around mostly for automated testing purposes.
+	/// Solver threads take the nonces from the shared counter, so every nonce is tried once.
 	fn inner_mining_loop(
 		&self,
 		b: &mut Block,
 		head: &BlockHeader,
 		attempt_time_per_block: u32,
 		latest_hash: &mut Hash,
+		worker_id: usize,
 	) -> bool {
 		// look for a pow for at most 2 sec on the same block (to give a chance to new
 		// transactions) and as long as the head hasn't changed
 		let deadline = Utc::now().timestamp() + attempt_time_per_block as i64;
 
 		debug!(
-			"(Server ID: {}) Mining Cuckoo{} for max {}s on {} @ {} [{}].",
+			"(Server ID: {}) Mining Cuckoo{} with {} threads for max {}s on {} @ {} [{}].",
 			self.debug_output_id,
 			global::min_edge_bits(),
+			self.threads,
 			attempt_time_per_block,
 			b.header.total_difficulty(),
 			b.header.height,
 			latest_hash
 		);
-		let mut iter_count = 0;
-
-		while head.hash() == *latest_hash && Utc::now().timestamp() < deadline {
-			let mut ctx = global::create_pow_context::<u32>(
-				head.height,
-				global::min_edge_bits(),
-				global::proofsize(),
-				10,
-			)
-			.unwrap();
-			ctx.set_header_nonce(b.header.pre_pow().unwrap(), None, true)
-				.unwrap();
-			if let Ok(proofs) = ctx.find_cycles() {
-				b.header.pow.proof = proofs[0].clone();
-				let proof_diff = b.header.pow.to_difficulty(b.header.height);
-				if proof_diff >= (b.header.total_difficulty() - head.total_difficulty()) {
-					return true;
-				}
+
+		// Solver threads don't see the thread local settings, we need to pass them.
+		let chain_type = global::get_chain_type();
+		let target_difficulty = b.header.total_difficulty() - head.total_difficulty();
+		let head_hash = head.hash();
+		let start_nonce = b.header.pow.nonce;
+		let start_time = Utc::now();
+
+		let solution: Mutex<Option<ProofOfWork>> = Mutex::new(None);
+		let done = AtomicBool::new(false);
+		// Offset of the next nonce to try, it is the number of tried nonces as well
+		let next_nonce = AtomicU64::new(0);
+
+		thread::scope(|s| {
+			for _ in 0..self.threads {
+				let mut header = b.header.clone();
+				let chain = &self.chain;
+				let solution = &solution;
+				let done = &done;
+				let next_nonce = &next_nonce;
+				let debug_output_id = &self.debug_output_id;
+				s.spawn(move || {
+					global::set_local_chain_type(chain_type);
+
+					while !done.load(Ordering::Relaxed) && Utc::now().timestamp() < deadline {
+						header.pow.nonce =
+							start_nonce.wrapping_add(next_nonce.fetch_add(1, Ordering::Relaxed));
+						match Self::try_nonce(&mut header, target_difficulty) {
+							Ok(true) => {
+								*solution.lock() = Some(header.pow.clone());
+								done.store(true, Ordering::Relaxed);
+								break;
+							}
+							Ok(false) => {}
+							Err(e) => {
+								error!("(Server ID: {}) Solver failed, {}", debug_output_id, e);
+								done.store(true, Ordering::Relaxed);
+								break;
+							}
+						}
+
+						match chain.head() {
+							Ok(tip) if tip.last_block_h == head_hash => {}
+							Ok(_) => done.store(true, Ordering::Relaxed),
+							Err(e) => {
+								error!(
+									"(Server ID: {}) Unable to read chain head, {}",
+									debug_output_id, e
+								);
+								done.store(true, Ordering::Relaxed);
+							}
+						}
+					}
+				});
 			}
+		});
+
+		*latest_hash = self.chain.head().unwrap().last_block_h;
+		let iter_count = next_nonce.load(Ordering::Relaxed);
 
-			b.header.pow.nonce += 1;
-			*latest_hash = self.chain.head().unwrap().last_block_h;
-			iter_count += 1;
+		// graphs per second for all solver threads
+		let elapsed_ms = (Utc::now() - start_time).num_milliseconds();
+		if elapsed_ms > 0 {
+			let hashrate = iter_count as f64 * 1000.0 / elapsed_ms as f64;
+			self.stratum_stats.update_stats(worker_id, |ws| {
+				ws.hashrate = hashrate;
+				ws.last_seen = SystemTime::now();
+			});
 		}
 
-		debug!(
-			"(Server ID: {}) No solution found after {} iterations, continuing...",
-			self.debug_output_id, iter_count
-		);
-		false
+		match solution.into_inner() {
+			Some(pow) => {
+				b.header.pow = pow;
+				true
+			}
+			None => {
+				debug!(
+					"(Server ID: {}) No solution found after {} iterations, continuing...",
+					self.debug_output_id, iter_count
+				);
+				// continue from the next not yet tried nonce
+				b.header.pow.nonce = start_nonce.wrapping_add(iter_count);
+				false
+			}
+		}
+	}
+
+	/// Look for the solution with the header nonce. Returns true if the solution
+	/// meets the target difficulty, the proof is set into the header.
+	fn try_nonce(header: &mut BlockHeader, target_difficulty: Difficulty) -> Result<bool, String> {
+		let mut ctx = global::create_pow_context::<u32>(
+			header.height,
+			global::min_edge_bits(),
+			global::proofsize(),
+			10,
+		)
+		.map_err(|e| format!("Unable to create PoW context, {}", e))?;
+		let pre_pow = header
+			.pre_pow()
+			.map_err(|e| format!("Unable to serialize header, {}", e))?;
+		ctx.set_header_nonce(pre_pow, None, true)
+			.map_err(|e| format!("Unable to set header, {}", e))?;
+		if let Ok(proofs) = ctx.find_cycles() {
+			header.pow.proof = proofs[0].clone();
+			if header.pow.to_difficulty(header.height) >= target_difficulty {
+				return Ok(true);
+			}
+		}
+		Ok(false)
 	}
 
 	/// Starts the mining loop, building a new block on top of the existing
@@ -136,6 +230,12 @@ impl Miner {
 		// nothing has changed. We only want to create a new key_id for each new block.
 		let mut key_id = None;
 
+		let worker_id = self.stratum_stats.allocate_new_worker(1);
+		self.stratum_stats.update_stats(worker_id, |ws| {
+			ws.id = format!("test_miner_{}", worker_id);
+			ws.initial_block_height = self.chain.head().map(|h| h.height).unwrap_or(0);
+		});
+
 		loop {
 			if self.stop_state.is_stopped() {
 				break;
@@ -163,6 +263,7 @@ impl Miner {
 				&head,
 				self.config.attempt_time_per_block,
 				&mut latest_hash,
+				worker_id,
 			);
 
 			// we found a solution, push our block through the chain processing pipeline
@@ -174,11 +275,19 @@ impl Miner {
 					b.header.prev_root,
 				);
 				let res = self.chain.process_block(b, chain::Options::MINE);
-				if let Err(e) = res {
-					error!(
-						"(Server ID: {}) Error validating mined block: {:?}",
-						self.debug_output_id, e
-					);
+				match res {
+					Ok(_) => self.stratum_stats.update_stats(worker_id, |ws| {
+						ws.num_accepted += 1;
+						ws.num_blocks_found += 1;
+					}),
+					Err(e) => {
+						error!(
+							"(Server ID: {}) Error validating mined block: {:?}",
+							self.debug_output_id, e
+						);
+						self.stratum_stats
+							.update_stats(worker_id, |ws| ws.num_rejected += 1);
+					}
 				}
 				trace!("resetting key_id in miner to None");
 				key_id = None;
@@ -191,6 +300,98 @@ impl Miner {
 			}
 		}
 
+		self.stratum_stats
+			.update_stats(worker_id, |ws| ws.is_connected = false);
 		info!("(Server ID: {}) test miner exit.", self.debug_output_id);
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::chain::types::NoopAdapter;
+	use crate::common::adapters::{PoolToChainAdapter, PoolToNetAdapter};
+	use crate::core::pow;
+	use crate::pool;
+	use crate::util::RwLock;
+	use std::fs;
+
+	fn new_miner(db_root: &str, threads: usize) -> Miner {
+		let _ = fs::remove_dir_all(db_root);
+		let chain = Arc::new(
+			chain::Chain::init(
+				db_root.to_string(),
+				Arc::new(NoopAdapter {}),
+				pow::mine_genesis_block().unwrap(),
+				pow::verify_size,
+				false,
+			)
+			.unwrap(),
+		);
+		let pool_adapter = Arc::new(PoolToChainAdapter::new());
+		pool_adapter.set_chain(chain.clone());
+		let tx_pool = Arc::new(RwLock::new(pool::TransactionPool::new(
+			pool::PoolConfig::default(),
+			pool_adapter,
+			Arc::new(PoolToNetAdapter::new(pool::DandelionConfig::default())),
+		)));
+		Miner::new(
+			StratumServerConfig::default(),
+			chain,
+			tx_pool,
+			Arc::new(StopState::new()),
+			Arc::new(SyncState::new()),
+			Arc::new(StratumStats::default()),
+			threads,
+		)
+	}
+
+	#[test]
+	fn mine_with_threads() {
+		let db_root = ".mwc_test_miner_threads";
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		let miner = new_miner(db_root, 4);
+		let worker_id = miner.stratum_stats.allocate_new_worker(1);
+
+		let head = miner.chain.head_header().unwrap();
+		let mut latest_hash = head.hash();
+		let (mut b, _) = mine_block::build_block(&miner.chain, &miner.tx_pool, None, None).unwrap();
+		assert!(miner.inner_mining_loop(&mut b, &head, 60, &mut latest_hash, worker_id));
+		assert!(pow::verify_size(&b.header).is_ok());
+		assert!(
+			b.header.pow.to_difficulty(b.header.height)
+				>= b.header.total_difficulty() - head.total_difficulty()
+		);
+		miner.chain.process_block(b, chain::Options::MINE).unwrap();
+		assert_eq!(miner.chain.head().unwrap().height, 1);
+
+		let _ = fs::remove_dir_all(db_root);
+	}
+
+	#[test]
+	fn hashrate_counts_all_threads() {
+		let db_root = ".mwc_test_miner_hashrate";
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		let miner = new_miner(db_root, 4);
+		let worker_id = miner.stratum_stats.allocate_new_worker(1);
+
+		// Unreachable difficulty, the threads run until the deadline
+		let head = miner.chain.head_header().unwrap();
+		let mut latest_hash = head.hash();
+		let (mut b, _) = mine_block::build_block(&miner.chain, &miner.tx_pool, None, None).unwrap();
+		b.header.pow.total_difficulty =
+			head.total_difficulty() + Difficulty::from_num(u64::MAX / 2);
+		let start_nonce = b.header.pow.nonce;
+		assert!(!miner.inner_mining_loop(&mut b, &head, 2, &mut latest_hash, worker_id));
+
+		// Every nonce is tried once, the next attempt continues after the last tried one
+		let tried = b.header.pow.nonce.wrapping_sub(start_nonce);
+		assert!(tried > 4);
+		// The attempt runs between 1 and 2 seconds, plus the last graph of every thread
+		let hashrate = miner.stratum_stats.get_stats(worker_id).unwrap().hashrate;
+		assert!(hashrate > tried as f64 / 3.0);
+		assert!(hashrate < tried as f64 * 1.1);
+
+		let _ = fs::remove_dir_all(db_root);
+	}
+}
@@ -252,8 +252,12 @@ fn monitor_peers(
 		boost_peers_capabilities,
 		config.clone(),
 	);
+	peers.update_anchors();
 
-	if !request_more_connections && peers.enough_outbound_peers() {
+	if !request_more_connections
+		&& peers.enough_outbound_peers()
+		&& peers.block_relay_peers_needed() == 0
+	{
 		return;
 	}
 
@@ -272,7 +276,7 @@ fn monitor_peers(
 			p.info.addr,
 		);
 		let _ = p.send_peer_request(
-			p2p::Capabilities::PEER_LIST | boost_peers_capabilities,
+			p2p::Capabilities::PEER_LIST | boost_peers_capabilities | config.address_capabilities(),
 			use_tor_connection,
 		);
 		connected_peers.push(p.info.addr.clone())
@@ -297,25 +301,13 @@ fn monitor_peers(
 	}
 
 	if load_peers_from_db {
-		// find some peers from our db
+		// select some peers from the address manager
 		// and queue them up for a connection attempt
 		// intentionally make too many attempts (2x) as some (most?) will fail
-		// as many nodes in our db are not publicly accessible
-		let new_peers = peers.find_peers(p2p::State::Healthy, boost_peers_capabilities);
-
-		// Only queue up connection attempts for candidate peers where we
-		// are confident we do not yet know about this peer.
-		// The call to is_known() may fail due to contention on the peers map.
-		// Do not attempt any connection where is_known() fails for any reason.
-		let mut max_addresses = 0;
-		for p in new_peers {
-			if let Ok(false) = peers.is_known(&p.addr) {
-				tx.send(p.addr.clone()).unwrap();
-				max_addresses += 1;
-				if max_addresses > 20 {
-					break;
-				}
-			}
+		// as many nodes in our db are not publicly accessible.
+		// Selected peers are not connected yet.
+		for p in peers.select_peers(boost_peers_capabilities, 21) {
+			tx.send(p.addr.clone()).unwrap();
 		}
 	}
 }
@@ -346,6 +338,14 @@ fn connect_to_seeds_and_peers(
 		}
 	}
 
+	// Anchor peers from the previous run go first, so the node is connected to
+	// the same honest peers even if the address tables were poisoned.
+	for addr in peers.get_anchors() {
+		if !peers_deny.as_slice().contains(&addr) {
+			let _ = tx.send(addr);
+		}
+	}
+
 	// check if we have some peers in db
 	// look for peers that are able to give us other peers (via PEER_LIST capability)
 	let mut found_peers = peers.find_peers(
@@ -426,6 +426,9 @@ fn listen_for_addrs(
 
 	connection_threads.retain(|h| !h.is_finished());
 
+	// Block relay only connections are opened when we have enough full outbound peers
+	let mut block_relay_needed = peers.block_relay_peers_needed();
+
 	while !listen_q_addrs.is_empty() {
 		if connection_threads.len() > PEER_MAX_INITIATE_CONNECTIONS {
 			break;
@@ -438,9 +441,15 @@ fn listen_for_addrs(
 			continue;
 		}
 
+		// Too many outbound connections to the same network group, will try later
+		if peers.is_group_limited(&addr) {
+			debug!("Skipping peer {}, network group limit is reached", addr);
+			continue;
+		}
+
 		connecting_history.insert(addr.clone(), now);
 
-		if p2p.socks_port == 0 {
+		if !p2p.can_connect_onion() {
 			match &addr {
 				Onion(_) => {
 					continue;
@@ -448,6 +457,16 @@ fn listen_for_addrs(
 				_ => {}
 			}
 		}
+		if !p2p.can_connect_i2p() {
+			if let PeerAddr::I2p(_) = &addr {
+				continue;
+			}
+		}
+
+		let block_relay_only = block_relay_needed > 0;
+		if block_relay_only {
+			block_relay_needed -= 1;
+		}
 
 		let addr_c = addr.clone();
 		let peers_c = peers.clone();
@@ -457,19 +476,29 @@ fn listen_for_addrs(
 			.spawn(move || {
 				// if we don't have a socks port, and it's onion, don't set as defunct because
 				// we don't know.
-				match p2p_c.connect(&addr_c) {
+				let res = if block_relay_only {
+					p2p_c.connect_block_relay_only(&addr_c)
+				} else {
+					p2p_c.connect(&addr_c)
+				};
+				match res {
 					Ok(p) => {
 						debug!(
-							"New peer {} is connected as outbound! Capability: {:b}",
-							p.info.addr, p.info.capabilities
+							"New peer {} is connected as outbound {:?}! Capability: {:b}",
+							p.info.addr, p.info.connection_type, p.info.capabilities
 						);
 						// If peer advertizes PEER_LIST then ask it for more peers that support PEER_LIST.
 						// We want to build a local db of possible peers to connect to.
 						// We do not necessarily care (at this point in time) what other capabilities these peers support.
-						if p.info.capabilities.contains(Capabilities::PEER_LIST) {
+						// Block relay only peers don't share the addresses.
+						if p.info.capabilities.contains(Capabilities::PEER_LIST)
+							&& !p.info.is_block_relay_only()
+						{
 							debug!("Sending peer request to {}", addr_c);
 							match p.send_peer_request(
-								Capabilities::PEER_LIST | peers_c.get_boost_peers_capabilities(),
+								Capabilities::PEER_LIST
+									| peers_c.get_boost_peers_capabilities()
+									| p2p_c.config.address_capabilities(),
 								use_tor_connection,
 							) {
 								Ok(_) => {
@@ -42,21 +42,25 @@ use crate::chain::{self, SyncState, SyncStatus};
 use crate::common::adapters::{
 	ChainToPoolAndNetAdapter, NetToChainAdapter, PoolToChainAdapter, PoolToNetAdapter,
 };
-use crate::common::hooks::{init_chain_hooks, init_net_hooks};
+use crate::common::hooks::{init_chain_hooks, init_chain_webhook, init_net_hooks};
 use crate::common::stats::{
 	ChainStats, DiffBlock, DiffStats, PeerStats, ServerStateInfo, ServerStats, TxStats,
 };
 use crate::common::types::{Error, ServerConfig, StratumServerConfig};
+use crate::common::watch_list::WatchList;
 use crate::core::core::hash::{Hashed, ZERO_HASH};
 use crate::core::ser::ProtocolVersion;
 use crate::core::stratum::connections;
 use crate::core::{consensus, genesis, global, pow};
+use crate::mining::block_generator::TestBlockGenerator;
 use crate::mining::stratumserver;
 use crate::mining::test_miner::Miner;
 use crate::mwc::{dandelion_monitor, seed, sync};
 use crate::p2p;
 use crate::p2p::types::PeerAddr;
 use crate::pool;
+use crate::tor::client_auth as tor_client_auth;
+use crate::tor::control as tor_control;
 use crate::tor::process as tor_process;
 use crate::util::file::get_first_line;
 use crate::util::{RwLock, StopState};
@@ -68,6 +72,8 @@ use std::sync::atomic::Ordering;
 
 use crate::mwc::sync::sync_manager::SyncManager;
 use crate::p2p::libp2p_connection;
+use crate::p2p::libp2p_message_store::Libp2pMessageStore;
+use crate::p2p::libp2p_rate_limit::GossipRateLimits;
 use chrono::Utc;
 use mwc_core::consensus::HeaderDifficultyInfo;
 use mwc_core::core::TxKernel;
@@ -126,6 +132,14 @@ impl Server {
 
 		mwc_chain::pipe::init_invalid_lock_hashes(&config.invalid_block_hashes)?;
 
+		if let Some(checkpoints) = config.checkpoints.as_ref() {
+			if checkpoints.len() > 0 {
+				info!("config.checkpoints = {:?}", checkpoints);
+			}
+		}
+
+		mwc_chain::pipe::init_checkpoints(&config.checkpoints)?;
+
 		let mining_config = config.stratum_mining_config.clone();
 		let enable_test_miner = config.run_test_miner;
 		let test_miner_wallet_url = config.test_miner_wallet_url.clone();
@@ -211,6 +225,26 @@ impl Server {
 		stratum_ip_pool: Arc<connections::StratumIpPool>,
 		stop_state: Option<Arc<StopState>>,
 		api_chan: &'static mut (oneshot::Sender<()>, oneshot::Receiver<()>),
+	) -> Result<Server, Error> {
+		Server::new_with_transport(
+			config,
+			allow_to_stop,
+			stratum_ip_pool,
+			stop_state,
+			api_chan,
+			None,
+		)
+	}
+
+	/// Instantiates a new server with a custom transport for the peer connections.
+	/// If transport is None, TCP is used. Other transports are used by the network simulation tests.
+	pub fn new_with_transport(
+		config: ServerConfig,
+		allow_to_stop: bool,
+		stratum_ip_pool: Arc<connections::StratumIpPool>,
+		stop_state: Option<Arc<StopState>>,
+		api_chan: &'static mut (oneshot::Sender<()>, oneshot::Receiver<()>),
+		transport: Option<Arc<dyn p2p::Transport>>,
 	) -> Result<Server, Error> {
 		//let duration_sync_long = config.duration_sync_long.unwrap_or(150);
 		//let duration_sync_short = config.duration_sync_short.unwrap_or(100);
@@ -247,10 +281,12 @@ impl Server {
 
 		let sync_state = Arc::new(SyncState::new());
 
-		let chain_adapter = Arc::new(ChainToPoolAndNetAdapter::new(
-			tx_pool.clone(),
-			init_chain_hooks(&config),
-		));
+		let chain_webhook = init_chain_webhook(&config);
+		let watch_list = Arc::new(WatchList::new(&config.db_root, chain_webhook.clone())?);
+		let mut chain_hooks = init_chain_hooks(&config, &chain_webhook);
+		chain_hooks.push(Box::new(watch_list.clone()));
+
+		let chain_adapter = Arc::new(ChainToPoolAndNetAdapter::new(tx_pool.clone(), chain_hooks));
 
 		let genesis = match config.chain_type {
 			global::ChainTypes::AutomatedTesting => pow::mine_genesis_block().unwrap(),
@@ -270,6 +306,7 @@ impl Server {
 		)?);
 
 		pool_adapter.set_chain(shared_chain.clone());
+		watch_list.init(shared_chain.clone());
 
 		let sync_manager: Arc<SyncManager> = Arc::new(SyncManager::new(
 			shared_chain.clone(),
@@ -288,6 +325,8 @@ impl Server {
 
 		api::reset_server_onion_address();
 
+		// Onion service that is published with the tor control port
+		let mut onion_service = None;
 		let (onion_address, tor_secret) = if config.tor_config.tor_enabled {
 			if !config.p2p_config.host.is_loopback() {
 				error!("If Tor is enabled, host must be '127.0.0.1'.");
@@ -296,7 +335,22 @@ impl Server {
 				));
 			}
 
-			if !config.tor_config.tor_external {
+			if let Some(control_addr) = &config.tor_config.control_addr {
+				println!("Publishing onion service with tor control port, please wait...");
+				let (secret, service) = Server::init_tor_control(
+					control_addr,
+					&config,
+					stop_state.clone(),
+					shared_chain.secp(),
+				)?;
+				onion_service = Some(service);
+				let onion_address = OnionV3Address::from_private(&secret.0)
+					.map_err(|e| Error::General(format!("Unable to build onion address, {}", e)))?;
+				(
+					Some(format!("{}.onion", onion_address)),
+					Some(to_hex(&secret.0)),
+				)
+			} else if !config.tor_config.tor_external {
 				let stop_state_clone = stop_state.clone();
 				let cloned_config = config.clone();
 
@@ -318,6 +372,11 @@ impl Server {
 							cloned_config.libp2p_port.unwrap_or(3417),
 							Some(&cloned_config.db_root),
 							cloned_config.tor_config.socks_port,
+							&cloned_config
+								.tor_config
+								.authorized_clients
+								.clone()
+								.unwrap_or(vec![]),
 							&tor_secp,
 						);
 
@@ -384,6 +443,24 @@ impl Server {
 			(None, None)
 		};
 
+		// Client keys are stored at the onion service directory, so they can be managed
+		// only if the onion service is published by this node
+		let onion_client_auth: Option<Arc<dyn api::OnionClientAuth>> =
+			match (&onion_address, config.tor_config.tor_external) {
+				(Some(onion_address), false) => {
+					let onion_address = onion_address.trim_end_matches(".onion").to_string();
+					Some(Arc::new(tor_client_auth::OnionClientAuthDir::new(
+						tor_config::onion_service_dir(
+							&Server::tor_listener_dir(Some(&config.db_root)),
+							&onion_address,
+						),
+						onion_address,
+						onion_service,
+					)))
+				}
+				_ => None,
+			};
+
 		let socks_port = if config.tor_config.tor_enabled {
 			config.tor_config.socks_port
 		} else {
@@ -406,8 +483,22 @@ impl Server {
 			let libp2p_port = config.libp2p_port;
 			let tor_socks_port = config.tor_config.socks_port;
 			let fee_base = config.pool_config.accept_fee_base;
+			let rate_limits = GossipRateLimits {
+				peer_rate: config.libp2p_peer_msg_rate.unwrap_or(0.5),
+				peer_burst: config.libp2p_peer_msg_burst.unwrap_or(20) as f64,
+				topic_rate: config.libp2p_topic_msg_rate.unwrap_or(10.0),
+				topic_burst: config.libp2p_topic_msg_burst.unwrap_or(200) as f64,
+				peer_ban_score: config.libp2p_peer_ban_score.unwrap_or(50.0),
+			};
 			api::set_server_onion_address(&onion_address);
 
+			let messages_store = Libp2pMessageStore::new(
+				&config.db_root,
+				config.libp2p_messages_max_age_sec.unwrap_or(86400),
+				config.libp2p_messages_max_per_topic.unwrap_or(10_000),
+			)?;
+			libp2p_connection::set_messages_store(Arc::new(messages_store));
+
 			let clone_shared_chain = shared_chain.clone();
 			let libp2p_topics = config
 				.libp2p_topics
@@ -471,16 +562,18 @@ impl Server {
 
 					let libp2p_stopper = Arc::new(std::sync::Mutex::new(1));
 
-					loop {
-						for t in &libp2p_topics {
-							libp2p_connection::add_topic(t, 1);
-						}
+					// Topics are registered once, the owner API can change them at runtime.
+					for t in &libp2p_topics {
+						libp2p_connection::add_topic(t, 1);
+					}
 
+					loop {
 						let libp2p_node_runner = libp2p_connection::run_libp2p_node(
 							tor_socks_port,
 							&secret,
 							libp2p_port.unwrap_or(3417),
 							fee_base,
+							rate_limits.clone(),
 							validation_fn.clone(),
 							libp2p_stopper.clone(), // passing new obj, because we never will stop the libp2p process
 						);
@@ -508,12 +601,13 @@ impl Server {
 		// Currently either "default" or with optional "archive_mode" (block history) support enabled.
 		let capabilities = Capabilities::new(
 			onion_address.is_some(),
+			config.p2p_config.i2p_config.is_some(),
 			config.archive_mode.unwrap_or(false),
 		);
 		debug!("Capabilities: {:?}", capabilities);
 		let use_tor = onion_address.is_some();
 
-		let p2p_server = Arc::new(p2p::Server::new(
+		let mut p2p_server = p2p::Server::new(
 			&config.db_root,
 			capabilities,
 			config.p2p_config.clone(),
@@ -523,7 +617,26 @@ impl Server {
 			stop_state.clone(),
 			socks_port,
 			onion_address,
-		)?);
+		)?;
+		if let Some(transport) = transport {
+			p2p_server = p2p_server.with_transport(transport);
+		}
+		let i2p_inbound = match &config.p2p_config.i2p_config {
+			Some(i2p_config) => {
+				let session = p2p::i2p::I2pSession::new(
+					&i2p_config.sam_addr(),
+					&Path::new(&config.db_root).join("i2p"),
+				)?;
+				info!(
+					"I2P session is created, node address: {}",
+					session.address()
+				);
+				p2p_server = p2p_server.with_i2p(Arc::new(session));
+				i2p_config.inbound()
+			}
+			None => false,
+		};
+		let p2p_server = Arc::new(p2p_server);
 
 		// Initialize various adapters with our dynamic set of connected peers.
 		chain_adapter.init(p2p_server.peers.clone());
@@ -581,6 +694,17 @@ impl Server {
 				}
 			})?;
 
+		if i2p_inbound {
+			let p2p_inner = p2p_server.clone();
+			let _ = thread::Builder::new()
+				.name("p2p-i2p-server".to_string())
+				.spawn(move || {
+					if let Err(e) = p2p_inner.listen_i2p() {
+						error!("P2P I2P server failed with error: {:?}", e);
+					}
+				})?;
+		}
+
 		info!("Starting rest apis at: {}", &config.api_http_addr);
 		let api_secret = get_first_line(config.api_secret_path.clone());
 		let foreign_api_secret = get_first_line(config.foreign_api_secret_path.clone());
@@ -598,6 +722,14 @@ impl Server {
 			}
 		};
 
+		// Blocks generation on demand is available for the testing networks only
+		let block_generator: Option<Arc<dyn api::BlockGenerator>> = if global::is_production_mode()
+		{
+			None
+		} else {
+			Some(Arc::new(TestBlockGenerator::new(&shared_chain, &tx_pool)))
+		};
+
 		// TODO fix API shutdown and join this thread
 		api::node_apis(
 			&config.api_http_addr,
@@ -610,6 +742,9 @@ impl Server {
 			tls_conf,
 			allow_to_stop,
 			stratum_ip_pool,
+			block_generator,
+			Some(watch_list as Arc<dyn api::WatchList>),
+			onion_client_auth,
 			api_chan,
 			stop_state.clone(),
 		)?;
@@ -655,6 +790,72 @@ impl Server {
 			p
 		}
 	}
+	/// Publish the onion service with the control port of the running tor daemon
+	/// Return (<secret for tor address>, <published onion service>)
+	fn init_tor_control(
+		control_addr: &str,
+		config: &ServerConfig,
+		stop_state: Arc<StopState>,
+		secp: &Secp256k1,
+	) -> Result<(SecretKey, Arc<tor_control::OnionService>), Error> {
+		let tor_dir = Server::tor_listener_dir(Some(&config.db_root));
+
+		let secret = tor_config::load_or_create_onion_service_key(&tor_dir, secp)
+			.map_err(|e| Error::General(format!("Unable to read tor secret, {}", e)))?;
+		let onion_address = OnionV3Address::from_private(&secret.0)
+			.map_err(|e| Error::General(format!("Unable to build onion address, {}", e)))?;
+		let service_dir = tor_config::onion_service_dir(&tor_dir, &onion_address.to_string());
+		tor_client_auth::sync_config_clients(
+			&service_dir,
+			&config
+				.tor_config
+				.authorized_clients
+				.clone()
+				.unwrap_or(vec![]),
+		)
+		.map_err(|e| Error::Configuration(format!("Invalid tor authorized clients, {}", e)))?;
+
+		let ports = vec![
+			(
+				80,
+				format!("{}:{}", config.p2p_config.host, config.p2p_config.port),
+			),
+			(8080, config.api_http_addr.clone()),
+			(
+				global::get_tor_libp2p_port(),
+				format!("127.0.0.1:{}", config.libp2p_port.unwrap_or(3417)),
+			),
+		];
+		let auth = tor_control::TorControlAuth {
+			password: config.tor_config.control_password.clone(),
+			cookie_file: config.tor_config.control_cookie_file.clone(),
+		};
+		let service = tor_control::start_onion_service(
+			control_addr,
+			auth,
+			secret.clone(),
+			ports,
+			service_dir,
+			stop_state,
+		)
+		.map_err(|e| {
+			Error::Configuration(format!(
+				"Unable to publish onion service with tor control port {}, {}",
+				control_addr, e
+			))
+		})?;
+		Ok((secret, service))
+	}
+
+	/// Directory of the tor listener config, `tor_base` is the node data directory
+	fn tor_listener_dir(tor_base: Option<&str>) -> String {
+		let tor_dir = format!("{}/tor/listener", tor_base.unwrap_or("~/.mwc/main"));
+		let home_dir = dirs::home_dir()
+			.map(|p| p.to_str().unwrap().to_string())
+			.unwrap_or("~".to_string());
+		tor_dir.replace("~", &home_dir)
+	}
+
 	/// Start the Tor listener for inbound connections
 	/// Return (<tor_process>, <onion_address>, <secret for tor address>)
 	pub fn init_tor_listener(
@@ -663,19 +864,11 @@ impl Server {
 		libp2p_port: u16,
 		tor_base: Option<&str>,
 		socks_port: u16,
+		authorized_clients: &[String],
 		secp: &Secp256k1,
 	) -> Result<(tor_process::TorProcess, String, SecretKey), Error> {
 		let mut process = tor_process::TorProcess::new();
-		let tor_dir = if tor_base.is_some() {
-			format!("{}/tor/listener", tor_base.unwrap())
-		} else {
-			format!("{}/tor/listener", "~/.mwc/main")
-		};
-
-		let home_dir = dirs::home_dir()
-			.map(|p| p.to_str().unwrap().to_string())
-			.unwrap_or("~".to_string());
-		let tor_dir = tor_dir.replace("~", &home_dir);
+		let tor_dir = Server::tor_listener_dir(tor_base);
 
 		// remove all other onion addresses that were previously used.
 
@@ -757,6 +950,13 @@ impl Server {
 		.map_err(|e| crate::Error::TorConfig(format!("Failed to configure tor, {}", e).into()))
 		.unwrap();
 
+		// tor reads the client keys from the onion service directory at the start
+		tor_client_auth::sync_config_clients(
+			&tor_config::onion_service_dir(&tor_dir, &onion_address),
+			authorized_clients,
+		)
+		.map_err(|e| Error::Configuration(format!("Invalid tor authorized clients, {}", e)))?;
+
 		info!(
 			"Starting Tor inbound listener at address {}.onion, binding to {}",
 			onion_address, addr
@@ -874,6 +1074,8 @@ impl Server {
 			self.tx_pool.clone(),
 			stop_state,
 			sync_state,
+			self.state_info.stratum_stats.clone(),
+			self.config.test_miner_threads.unwrap_or(1),
 		);
 		miner.set_debug_output_id(format!("Port {}", self.config.p2p_config.port));
 		let _ = thread::Builder::new()
@@ -395,6 +395,18 @@ impl HeaderSync {
 			return Ok(());
 		}
 
+		if let Some(err) = bhs
+			.iter()
+			.find_map(|h| chain::pipe::validate_header_checkpoint(h).err())
+		{
+			debug!(
+				"headers_received: headers conflict with checkpoint, all data is rejected. Peer: {}",
+				peer
+			);
+			sync_peers.report_error_response(peer, format!("headers_received: {}", err));
+			return Ok(());
+		}
+
 		// That is needed for sync tracking
 		info!(
 			"Received {} block headers from {}, height {}",
@@ -0,0 +1,306 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client authorization for the node onion service. Only the clients with the x25519
+//! keys listed at the `authorized_clients` directory of the onion service can connect.
+//! Keys from the config are stored as `config_<n>.auth`, keys that are generated with
+//! the owner API are stored as `<name>.auth`.
+
+use crate::api::{self, OnionClientKey};
+use crate::tor::config::AUTH_CLIENTS_DIR;
+use crate::tor::control::OnionService;
+use crate::Error;
+use data_encoding::BASE32_NOPAD;
+use rand::RngCore;
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::sync::Arc;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const AUTH_FILE_EXT: &str = "auth";
+const DESCRIPTOR_PREFIX: &str = "descriptor:x25519:";
+/// Prefix of the client keys that are coming from the config
+const CONFIG_CLIENT_PREFIX: &str = "config_";
+
+/// Parse the x25519 client public key. Accepted formats are the base32 key and
+/// the tor auth file line `descriptor:x25519:<base32 key>`. Returns the base32 key.
+pub fn parse_client_pubkey(key: &str) -> Result<String, Error> {
+	let key = key.trim();
+	let key = key.strip_prefix(DESCRIPTOR_PREFIX).unwrap_or(key);
+	let key = key.to_uppercase();
+	let bytes = BASE32_NOPAD
+		.decode(key.as_bytes())
+		.map_err(|e| Error::TorConfig(format!("Invalid client key {}, {}", key, e)))?;
+	if bytes.len() != 32 {
+		return Err(Error::TorConfig(format!(
+			"Invalid client key {}, expected 32 bytes",
+			key
+		)));
+	}
+	Ok(key)
+}
+
+/// Generate a new client key pair. Returns (private key, public key), base32 encoded.
+pub fn generate_client_keys() -> (String, String) {
+	let mut secret = [0u8; 32];
+	rand::thread_rng().fill_bytes(&mut secret);
+	let secret = StaticSecret::from(secret);
+	let public = PublicKey::from(&secret);
+	(
+		BASE32_NOPAD.encode(&secret.to_bytes()),
+		BASE32_NOPAD.encode(public.as_bytes()),
+	)
+}
+
+fn validate_client_name(name: &str) -> Result<(), Error> {
+	if name.is_empty()
+		|| name.len() > 64
+		|| name.starts_with(CONFIG_CLIENT_PREFIX)
+		|| !name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+	{
+		return Err(Error::TorConfig(format!(
+			"Invalid client name '{}', expected up to 64 letters, digits, '-' or '_', not starting with '{}'",
+			name, CONFIG_CLIENT_PREFIX
+		)));
+	}
+	Ok(())
+}
+
+fn auth_dir(service_dir: &str) -> String {
+	format!("{}{}{}", service_dir, MAIN_SEPARATOR, AUTH_CLIENTS_DIR)
+}
+
+fn auth_file(service_dir: &str, name: &str) -> PathBuf {
+	Path::new(&auth_dir(service_dir)).join(format!("{}.{}", name, AUTH_FILE_EXT))
+}
+
+fn write_auth_file(service_dir: &str, name: &str, pubkey: &str) -> Result<(), Error> {
+	let file_path = auth_file(service_dir, name);
+	fs::write(&file_path, format!("{}{}\n", DESCRIPTOR_PREFIX, pubkey)).map_err(|e| {
+		Error::IO(format!(
+			"Unable to write client key {}, {}",
+			file_path.display(),
+			e
+		))
+	})
+}
+
+/// Client keys that are authorized for the onion service, pairs of name and base32 public key
+pub fn list_clients(service_dir: &str) -> Result<Vec<(String, String)>, Error> {
+	let dir = auth_dir(service_dir);
+	if !Path::new(&dir).exists() {
+		return Ok(vec![]);
+	}
+	let mut res = vec![];
+	for entry in
+		fs::read_dir(&dir).map_err(|e| Error::IO(format!("Unable to read dir {}, {}", dir, e)))?
+	{
+		let path = entry
+			.map_err(|e| Error::IO(format!("Unable to read dir {}, {}", dir, e)))?
+			.path();
+		if path.extension().and_then(|e| e.to_str()) != Some(AUTH_FILE_EXT) {
+			continue;
+		}
+		let name = match path.file_stem().and_then(|s| s.to_str()) {
+			Some(name) => name.to_string(),
+			None => continue,
+		};
+		let data = fs::read_to_string(&path).map_err(|e| {
+			Error::IO(format!(
+				"Unable to read client key {}, {}",
+				path.display(),
+				e
+			))
+		})?;
+		match parse_client_pubkey(&data) {
+			Ok(key) => res.push((name, key)),
+			Err(e) => warn!("Skipping client key {}, {}", path.display(), e),
+		}
+	}
+	res.sort();
+	Ok(res)
+}
+
+/// Replace the client keys that are coming from the config with `config_keys`.
+/// Must be called before the onion service is published.
+pub fn sync_config_clients(service_dir: &str, config_keys: &[String]) -> Result<(), Error> {
+	let config_keys = config_keys
+		.iter()
+		.map(|k| parse_client_pubkey(k))
+		.collect::<Result<Vec<String>, Error>>()?;
+
+	let dir = auth_dir(service_dir);
+	fs::create_dir_all(&dir)
+		.map_err(|e| Error::IO(format!("Unable to create dir {}, {}", dir, e)))?;
+
+	for (name, _) in list_clients(service_dir)? {
+		if name.starts_with(CONFIG_CLIENT_PREFIX) {
+			let file_path = auth_file(service_dir, &name);
+			fs::remove_file(&file_path).map_err(|e| {
+				Error::IO(format!(
+					"Unable to delete client key {}, {}",
+					file_path.display(),
+					e
+				))
+			})?;
+		}
+	}
+	for (i, key) in config_keys.iter().enumerate() {
+		write_auth_file(service_dir, &format!("{}{}", CONFIG_CLIENT_PREFIX, i), key)?;
+	}
+	Ok(())
+}
+
+/// Client authorization of the node onion service, keys are stored at the onion service directory
+pub struct OnionClientAuthDir {
+	service_dir: String,
+	onion_address: String,
+	onion_service: Option<Arc<OnionService>>,
+}
+
+impl OnionClientAuthDir {
+	/// Create for the onion service directory and the onion address (without .onion).
+	/// `onion_service` is the service published with the tor control port, it is published
+	/// again when the clients are changed.
+	pub fn new(
+		service_dir: String,
+		onion_address: String,
+		onion_service: Option<Arc<OnionService>>,
+	) -> OnionClientAuthDir {
+		OnionClientAuthDir {
+			service_dir,
+			onion_address,
+			onion_service,
+		}
+	}
+
+	// Apply the changed client keys to the onion service published with the control port
+	fn republish(&self) -> Result<(), String> {
+		match &self.onion_service {
+			Some(service) => service.republish().map_err(|e| {
+				format!(
+					"Client keys are updated, but the onion service is not published, {}",
+					e
+				)
+			}),
+			None => Ok(()),
+		}
+	}
+}
+
+impl api::OnionClientAuth for OnionClientAuthDir {
+	fn add_client(&self, name: String) -> Result<OnionClientKey, String> {
+		validate_client_name(&name).map_err(|e| e.to_string())?;
+		if auth_file(&self.service_dir, &name).exists() {
+			return Err(format!("Client {} already exists", name));
+		}
+		let (private_key, public_key) = generate_client_keys();
+		write_auth_file(&self.service_dir, &name, &public_key).map_err(|e| e.to_string())?;
+		info!("Onion service client {} is authorized", name);
+		self.republish()?;
+		Ok(OnionClientKey {
+			name,
+			public_key,
+			client_auth: Some(format!(
+				"{}:{}{}",
+				self.onion_address, DESCRIPTOR_PREFIX, private_key
+			)),
+		})
+	}
+
+	fn revoke_client(&self, name: &str) -> Result<bool, String> {
+		if name.starts_with(CONFIG_CLIENT_PREFIX) {
+			return Err(format!(
+				"Client {} is from the config, please update the config instead",
+				name
+			));
+		}
+		validate_client_name(name).map_err(|e| e.to_string())?;
+		let file_path = auth_file(&self.service_dir, name);
+		if !file_path.exists() {
+			return Ok(false);
+		}
+		fs::remove_file(&file_path)
+			.map_err(|e| format!("Unable to delete client key {}, {}", file_path.display(), e))?;
+		info!("Onion service client {} is revoked", name);
+		self.republish()?;
+		Ok(true)
+	}
+
+	fn get_clients(&self) -> Result<Vec<OnionClientKey>, String> {
+		Ok(list_clients(&self.service_dir)
+			.map_err(|e| e.to_string())?
+			.into_iter()
+			.map(|(name, public_key)| OnionClientKey {
+				name,
+				public_key,
+				client_auth: None,
+			})
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::api::OnionClientAuth;
+
+	#[test]
+	fn test_onion_client_auth() {
+		let service_dir = "target/test_output/onion_client_auth";
+		let _ = fs::remove_dir_all(service_dir);
+
+		let (_, config_key) = generate_client_keys();
+		sync_config_clients(
+			service_dir,
+			&[format!(
+				"{}{}",
+				DESCRIPTOR_PREFIX,
+				config_key.to_lowercase()
+			)],
+		)
+		.unwrap();
+		assert!(sync_config_clients(service_dir, &["abc".to_string()]).is_err());
+
+		let auth = OnionClientAuthDir::new(service_dir.to_string(), "onion".to_string(), None);
+		let key = auth.add_client("wallet-1".to_string()).unwrap();
+		assert!(key
+			.client_auth
+			.as_ref()
+			.unwrap()
+			.starts_with("onion:descriptor:x25519:"));
+		assert!(auth.add_client("wallet-1".to_string()).is_err());
+		assert!(auth.add_client("config_1".to_string()).is_err());
+		assert!(auth.add_client("../wallet".to_string()).is_err());
+
+		let clients = auth.get_clients().unwrap();
+		assert_eq!(clients.len(), 2);
+		assert_eq!(clients[0].name, "config_0");
+		assert_eq!(clients[0].public_key, config_key);
+		assert_eq!(clients[1].public_key, key.public_key);
+
+		// Config keys are replaced, generated keys are kept
+		sync_config_clients(service_dir, &[]).unwrap();
+		assert!(auth.revoke_client("config_0").is_err());
+		assert!(auth.revoke_client("../onion_client_auth/wallet-1").is_err());
+		assert!(auth.revoke_client("").is_err());
+		assert_eq!(auth.revoke_client("wallet-1"), Ok(true));
+		assert_eq!(auth.revoke_client("wallet-1"), Ok(false));
+		assert!(auth.get_clients().unwrap().is_empty());
+
+		let _ = fs::remove_dir_all(service_dir);
+	}
+}
@@ -36,7 +36,7 @@ const PUB_KEY_FILE: &str = "hs_ed25519_public_key";
 const HOSTNAME_FILE: &str = "hostname";
 const TORRC_FILE: &str = "torrc";
 const TOR_DATA_DIR: &str = "data";
-const AUTH_CLIENTS_DIR: &str = "authorized_clients";
+pub(crate) const AUTH_CLIENTS_DIR: &str = "authorized_clients";
 const HIDDEN_SERVICES_DIR: &str = "onion_service_addresses";
 
 #[cfg(unix)]
@@ -203,6 +203,14 @@ pub fn create_onion_auth_clients_dir(os_directory: &str) -> Result<(), Error> {
 		.map_err(|e| Error::IO(format!("Unable to create dir {}, {}", auth_dir_path, e)))?;
 	Ok(())
 }
+/// Directory of the onion service with the onion address (without .onion)
+pub fn onion_service_dir(tor_config_directory: &str, address: &str) -> String {
+	format!(
+		"{}{}{}{}{}",
+		tor_config_directory, MAIN_SEPARATOR, HIDDEN_SERVICES_DIR, MAIN_SEPARATOR, address
+	)
+}
+
 /// output an onion service config for the secret key, and return the address
 pub fn output_onion_service_config(
 	tor_config_directory: &str,
@@ -211,10 +219,7 @@ pub fn output_onion_service_config(
 	let d_sec_key = DalekSecretKey::from_bytes(&sec_key.0)
 		.map_err(|e| Error::ED25519Key(format!("Unable to parse private key, {}", e)))?;
 	let address = OnionV3Address::from_private(&sec_key.0)?;
-	let hs_dir_file_path = format!(
-		"{}{}{}{}{}",
-		tor_config_directory, MAIN_SEPARATOR, HIDDEN_SERVICES_DIR, MAIN_SEPARATOR, address
-	);
+	let hs_dir_file_path = onion_service_dir(tor_config_directory, &address.to_string());
 
 	// If file already exists, don't overwrite it, just return address
 	if Path::new(&hs_dir_file_path).exists() {
@@ -236,6 +241,40 @@ pub fn output_onion_service_config(
 	Ok(address)
 }
 
+/// Read the secret key of the onion service that was used before, or create a new one.
+/// The key is stored in the same way as for the tor process, so the onion address
+/// is the same with tor process and with the tor control port.
+pub fn load_or_create_onion_service_key(
+	tor_config_directory: &str,
+	secp: &Secp256k1,
+) -> Result<SecretKey, Error> {
+	let hs_dir = format!(
+		"{}{}{}",
+		tor_config_directory, MAIN_SEPARATOR, HIDDEN_SERVICES_DIR
+	);
+	if Path::new(&hs_dir).exists() {
+		let entries = fs::read_dir(&hs_dir)
+			.map_err(|e| Error::IO(format!("Unable to read dir {}, {}", hs_dir, e)))?;
+		for entry in entries {
+			let entry =
+				entry.map_err(|e| Error::IO(format!("Unable to read dir {}, {}", hs_dir, e)))?;
+			let service_dir = entry.path();
+			if !service_dir.join(SEC_KEY_FILE_COPY).exists() {
+				continue;
+			}
+			let sec_key = read_sec_key_file(&service_dir.to_string_lossy(), secp)?;
+			let address = OnionV3Address::from_private(&sec_key.0)?;
+			if entry.file_name().to_string_lossy() == address.to_string() {
+				return Ok(sec_key);
+			}
+		}
+	}
+
+	let sec_key = SecretKey::new(secp, &mut rand::thread_rng());
+	output_onion_service_config(tor_config_directory, &sec_key)?;
+	Ok(sec_key)
+}
+
 /// output torrc file given a list of hidden service directories
 pub fn output_torrc(
 	tor_config_directory: &str,
@@ -0,0 +1,779 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tor control protocol client. Connects to the tor daemon that is already running,
+//! publishes the node onion service with ADD_ONION and keeps it published.
+//! Protocol spec: https://spec.torproject.org/control-spec
+
+use crate::tor::client_auth;
+use crate::util::secp::key::SecretKey;
+use crate::util::{self, Mutex, OnionV3Address, StopState, ToHex};
+use crate::Error;
+use ed25519_dalek::ExpandedSecretKey;
+use ed25519_dalek::SecretKey as DalekSecretKey;
+use hmac::{Hmac, Mac, NewMac};
+use rand::{thread_rng, Rng};
+use sha2::Sha256;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Timeout for connect and for the control port replies
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Interval of the onion service health check
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Length of the SAFECOOKIE client nonce
+const SAFECOOKIE_NONCE_LEN: usize = 32;
+/// HMAC keys of the SAFECOOKIE authentication, defined by the control spec
+const SAFECOOKIE_SERVER_KEY: &[u8] = b"Tor safe cookie authentication server-to-controller hash";
+const SAFECOOKIE_CLIENT_KEY: &[u8] = b"Tor safe cookie authentication controller-to-server hash";
+
+/// Authentication data for the control port
+#[derive(Debug, Clone, Default)]
+pub struct TorControlAuth {
+	/// Password, for HashedControlPassword
+	pub password: Option<String>,
+	/// Cookie file, for CookieAuthentication. By default the file reported by tor is used.
+	pub cookie_file: Option<String>,
+}
+
+/// Reply to PROTOCOLINFO
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolInfo {
+	/// Supported auth methods
+	pub auth_methods: Vec<String>,
+	/// Location of the cookie file
+	pub cookie_file: Option<String>,
+}
+
+/// Connection to the tor control port
+pub struct TorControl {
+	reader: BufReader<TcpStream>,
+	writer: TcpStream,
+}
+
+impl TorControl {
+	/// Connect to the control port
+	pub fn connect(addr: &str) -> Result<TorControl, Error> {
+		let sock_addr = addr
+			.to_socket_addrs()
+			.map_err(|e| Error::TorControl(format!("Invalid control address {}, {}", addr, e)))?
+			.next()
+			.ok_or(Error::TorControl(format!(
+				"Invalid control address {}",
+				addr
+			)))?;
+		let stream = TcpStream::connect_timeout(&sock_addr, CONTROL_TIMEOUT).map_err(|e| {
+			Error::TorControl(format!("Unable to connect to control port {}, {}", addr, e))
+		})?;
+		stream
+			.set_read_timeout(Some(CONTROL_TIMEOUT))
+			.map_err(|e| Error::IO(format!("Unable to set read timeout, {}", e)))?;
+		let writer = stream
+			.try_clone()
+			.map_err(|e| Error::IO(format!("Unable to clone control stream, {}", e)))?;
+		Ok(TorControl {
+			reader: BufReader::new(stream),
+			writer,
+		})
+	}
+
+	/// Send the command and read the reply. Returns the reply lines without the status codes,
+	/// the data of the multiline replies is joined with '\n'. The whole reply is read even if
+	/// the command failed, so the next command doesn't get the rest of the error reply.
+	fn command(&mut self, cmd: &str) -> Result<Vec<String>, Error> {
+		self.writer
+			.write_all(format!("{}\r\n", cmd).as_bytes())
+			.map_err(|e| Error::TorControl(format!("Unable to send the command, {}", e)))?;
+
+		let (code, lines) = self.read_reply()?;
+		if !code.starts_with('2') {
+			return Err(Error::TorControl(format!(
+				"Command failed, {} {}",
+				code,
+				lines.join("; ")
+			)));
+		}
+		Ok(lines)
+	}
+
+	/// Read the reply lines up to the final 'NNN ' line. Returns the status code and the
+	/// reply lines.
+	fn read_reply(&mut self) -> Result<(String, Vec<String>), Error> {
+		let mut reply_code: Option<String> = None;
+		let mut lines = vec![];
+		loop {
+			let line = self.read_line()?;
+			if line.len() < 4 || !line.as_bytes()[..4].is_ascii() {
+				return Err(Error::TorControl(format!("Invalid reply line: {}", line)));
+			}
+			let (code, sep, data) = (&line[0..3], &line[3..4], &line[4..]);
+			// All lines of the reply have the same status code
+			if reply_code.get_or_insert_with(|| code.to_string()).as_str() != code {
+				return Err(Error::TorControl(format!(
+					"Unexpected status code at reply line: {}",
+					line
+				)));
+			}
+			match sep {
+				"-" => lines.push(data.to_string()),
+				"+" => {
+					let mut data = data.to_string();
+					loop {
+						let l = self.read_line()?;
+						if l == "." {
+							break;
+						}
+						data.push('\n');
+						// Leading dot is escaped
+						data.push_str(l.strip_prefix('.').unwrap_or(&l));
+					}
+					lines.push(data);
+				}
+				" " => {
+					lines.push(data.to_string());
+					return Ok((code.to_string(), lines));
+				}
+				_ => return Err(Error::TorControl(format!("Invalid reply line: {}", line))),
+			}
+		}
+	}
+
+	fn read_line(&mut self) -> Result<String, Error> {
+		let mut line = String::new();
+		let sz = self
+			.reader
+			.read_line(&mut line)
+			.map_err(|e| Error::TorControl(format!("Unable to read the reply, {}", e)))?;
+		if sz == 0 {
+			return Err(Error::TorControl("Control connection is closed".into()));
+		}
+		Ok(line
+			.trim_end_matches(|c| c == '\r' || c == '\n')
+			.to_string())
+	}
+
+	/// Request the supported auth methods
+	pub fn protocol_info(&mut self) -> Result<ProtocolInfo, Error> {
+		let lines = self.command("PROTOCOLINFO 1")?;
+		let mut info = ProtocolInfo {
+			auth_methods: vec![],
+			cookie_file: None,
+		};
+		for line in lines {
+			if let Some(auth) = line.strip_prefix("AUTH ") {
+				if let Some(methods) = auth.strip_prefix("METHODS=") {
+					let methods = methods.split(' ').next().unwrap_or("");
+					info.auth_methods = methods.split(',').map(|m| m.to_string()).collect();
+				}
+				if let Some(pos) = auth.find("COOKIEFILE=") {
+					info.cookie_file = Some(unquote(&auth[pos + "COOKIEFILE=".len()..])?);
+				}
+			}
+		}
+		Ok(info)
+	}
+
+	/// Authenticate with the password or with the cookie, the method is selected from
+	/// the methods supported by the tor daemon. SAFECOOKIE is preferred to COOKIE.
+	pub fn authenticate(&mut self, auth: &TorControlAuth) -> Result<(), Error> {
+		let info = self.protocol_info()?;
+		let has_method = |m: &str| info.auth_methods.iter().any(|am| am == m);
+
+		let cmd = if let Some(password) = &auth.password {
+			if !has_method("HASHEDPASSWORD") {
+				return Err(Error::TorControl(
+					"Tor doesn't support password authentication".into(),
+				));
+			}
+			format!("AUTHENTICATE {}", quote(password))
+		} else if has_method("NULL") {
+			"AUTHENTICATE".to_string()
+		} else if has_method("SAFECOOKIE") || has_method("COOKIE") {
+			let cookie_file = auth
+				.cookie_file
+				.clone()
+				.or(info.cookie_file.clone())
+				.ok_or(Error::TorControl("Tor cookie file is unknown".into()))?;
+			let cookie = fs::read(&cookie_file).map_err(|e| {
+				Error::TorControl(format!(
+					"Unable to read tor cookie file {}, {}",
+					cookie_file, e
+				))
+			})?;
+			if has_method("SAFECOOKIE") {
+				let mut client_nonce = [0u8; SAFECOOKIE_NONCE_LEN];
+				thread_rng().fill(&mut client_nonce);
+				let client_hash = self.auth_challenge(&cookie, &client_nonce)?;
+				format!("AUTHENTICATE {}", client_hash.to_hex())
+			} else {
+				format!("AUTHENTICATE {}", cookie.to_hex())
+			}
+		} else {
+			return Err(Error::TorControl(format!(
+				"Tor auth methods {:?} are not supported, please configure the password or the cookie auth",
+				info.auth_methods
+			)));
+		};
+		self.command(&cmd)?;
+		Ok(())
+	}
+
+	/// SAFECOOKIE challenge. Checks that tor knows the cookie too and returns the client hash
+	/// for AUTHENTICATE, so the cookie itself is never sent to the control port.
+	fn auth_challenge(&mut self, cookie: &[u8], client_nonce: &[u8]) -> Result<Vec<u8>, Error> {
+		let lines = self.command(&format!(
+			"AUTHCHALLENGE SAFECOOKIE {}",
+			client_nonce.to_hex()
+		))?;
+		let reply = lines
+			.iter()
+			.find_map(|l| l.strip_prefix("AUTHCHALLENGE "))
+			.ok_or(Error::TorControl(
+				"AUTHCHALLENGE is not found at reply".into(),
+			))?;
+		let reply_value = |key: &str| -> Result<Vec<u8>, Error> {
+			let prefix = format!("{}=", key);
+			let value = reply
+				.split(' ')
+				.find_map(|kv| kv.strip_prefix(&prefix))
+				.ok_or(Error::TorControl(format!(
+					"{} is not found at AUTHCHALLENGE reply",
+					key
+				)))?;
+			util::from_hex(value)
+				.map_err(|e| Error::TorControl(format!("Invalid {} hex, {}", key, e)))
+		};
+		let server_hash = reply_value("SERVERHASH")?;
+		let server_nonce = reply_value("SERVERNONCE")?;
+
+		let mut message = cookie.to_vec();
+		message.extend_from_slice(client_nonce);
+		message.extend_from_slice(&server_nonce);
+		safe_cookie_hmac(SAFECOOKIE_SERVER_KEY, &message)
+			.verify(&server_hash)
+			.map_err(|_| {
+				Error::TorControl(
+					"Tor SAFECOOKIE server hash doesn't match, the cookie file is wrong".into(),
+				)
+			})?;
+		Ok(safe_cookie_hmac(SAFECOOKIE_CLIENT_KEY, &message)
+			.finalize()
+			.into_bytes()
+			.to_vec())
+	}
+
+	/// Publish the onion service for the key. `ports` are pairs of the onion service
+	/// port and the local target address. `client_keys` are x25519 public keys (base32) of the
+	/// authorized clients, if empty the service is public. Returns the onion service id
+	/// (address without .onion). The service is removed when the control connection is closed.
+	pub fn add_onion(
+		&mut self,
+		sec_key: &SecretKey,
+		ports: &[(u16, String)],
+		client_keys: &[String],
+	) -> Result<String, Error> {
+		let d_sec_key = DalekSecretKey::from_bytes(&sec_key.0)
+			.map_err(|e| Error::ED25519Key(format!("Unable to parse private key, {}", e)))?;
+		let expanded_skey = ExpandedSecretKey::from(&d_sec_key);
+		let mut cmd = format!(
+			"ADD_ONION ED25519-V3:{} Flags=DiscardPK{}",
+			util::to_base64(&expanded_skey.to_bytes()[..]),
+			if client_keys.is_empty() {
+				""
+			} else {
+				",V3Auth"
+			}
+		);
+		for (port, target) in ports {
+			cmd.push_str(&format!(" Port={},{}", port, target));
+		}
+		for key in client_keys {
+			cmd.push_str(&format!(" ClientAuthV3={}", key));
+		}
+		let lines = self.command(&cmd)?;
+		let service_id = lines
+			.iter()
+			.find_map(|l| l.strip_prefix("ServiceID="))
+			.ok_or(Error::TorControl("ServiceID is not found at reply".into()))?
+			.to_string();
+
+		let expected = OnionV3Address::from_private(&sec_key.0)?.to_string();
+		if service_id != expected {
+			return Err(Error::TorControl(format!(
+				"Tor published unexpected onion service {}, expected {}",
+				service_id, expected
+			)));
+		}
+		Ok(service_id)
+	}
+
+	/// Remove the onion service that was published with this connection
+	pub fn del_onion(&mut self, service_id: &str) -> Result<(), Error> {
+		self.command(&format!("DEL_ONION {}", service_id))?;
+		Ok(())
+	}
+
+	/// Request the info value from tor
+	pub fn get_info(&mut self, key: &str) -> Result<String, Error> {
+		let prefix = format!("{}=", key);
+		let lines = self.command(&format!("GETINFO {}", key))?;
+		lines
+			.iter()
+			.find_map(|l| l.strip_prefix(&prefix))
+			.map(|v| v.trim_start_matches('\n').to_string())
+			.ok_or(Error::TorControl(format!("{} is not found at reply", key)))
+	}
+
+	/// Check if the onion service, published with this connection, is still alive
+	pub fn is_onion_published(&mut self, service_id: &str) -> Result<bool, Error> {
+		let onions = self.get_info("onions/current")?;
+		Ok(onions.lines().any(|l| l.trim() == service_id))
+	}
+}
+
+// HMAC-SHA256 of the SAFECOOKIE message (cookie | client nonce | server nonce)
+fn safe_cookie_hmac(key: &[u8], message: &[u8]) -> Hmac<Sha256> {
+	let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+	mac.update(message);
+	mac
+}
+
+fn quote(s: &str) -> String {
+	format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn unquote(s: &str) -> Result<String, Error> {
+	let mut chars = s.chars();
+	if chars.next() != Some('"') {
+		return Err(Error::TorControl(format!("Expected quoted string: {}", s)));
+	}
+	let mut res = String::new();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => match chars.next() {
+				Some(c) => res.push(c),
+				None => break,
+			},
+			'"' => return Ok(res),
+			c => res.push(c),
+		}
+	}
+	Err(Error::TorControl(format!(
+		"Unterminated quoted string: {}",
+		s
+	)))
+}
+
+fn publish(
+	control_addr: &str,
+	auth: &TorControlAuth,
+	sec_key: &SecretKey,
+	ports: &[(u16, String)],
+	service_dir: &str,
+) -> Result<(TorControl, String), Error> {
+	// Client keys are read at every publish, so the owner API changes are applied
+	let client_keys: Vec<String> = client_auth::list_clients(service_dir)?
+		.into_iter()
+		.map(|(_, key)| key)
+		.collect();
+	let mut control = TorControl::connect(control_addr)?;
+	control.authenticate(auth)?;
+	let service_id = control.add_onion(sec_key, ports, &client_keys)?;
+	Ok((control, service_id))
+}
+
+/// Onion service, published with the tor control port
+pub struct OnionService {
+	control_addr: String,
+	auth: TorControlAuth,
+	sec_key: SecretKey,
+	ports: Vec<(u16, String)>,
+	service_dir: String,
+	service_id: String,
+	control: Mutex<Option<TorControl>>,
+}
+
+impl OnionService {
+	/// Onion service id (address without .onion)
+	pub fn service_id(&self) -> &str {
+		&self.service_id
+	}
+
+	/// Publish the onion service again, authorized client keys are reloaded from
+	/// the onion service directory.
+	pub fn republish(&self) -> Result<(), Error> {
+		let mut control = self.control.lock();
+		if let Some(mut c) = control.take() {
+			// tor rejects the same key while the service is published. The service is removed
+			// with the connection, DEL_ONION is needed to be sure that it is gone.
+			if let Err(e) = c.del_onion(&self.service_id) {
+				debug!("Unable to remove onion service {}, {}", self.service_id, e);
+			}
+		}
+		let (c, _) = publish(
+			&self.control_addr,
+			&self.auth,
+			&self.sec_key,
+			&self.ports,
+			&self.service_dir,
+		)?;
+		*control = Some(c);
+		Ok(())
+	}
+
+	// Check if the service is still published
+	fn is_healthy(&self) -> bool {
+		match self.control.lock().as_mut() {
+			Some(c) => match c.is_onion_published(&self.service_id) {
+				Ok(published) => published,
+				Err(e) => {
+					warn!("Tor control port health check failed, {}", e);
+					false
+				}
+			},
+			None => false,
+		}
+	}
+}
+
+/// Publish the onion service with the tor control port and start the thread that
+/// checks the service health. If tor was restarted or the service is gone, the service
+/// is published again. Authorized client keys are read from the onion service directory
+/// `service_dir`.
+pub fn start_onion_service(
+	control_addr: &str,
+	auth: TorControlAuth,
+	sec_key: SecretKey,
+	ports: Vec<(u16, String)>,
+	service_dir: String,
+	stop_state: Arc<StopState>,
+) -> Result<Arc<OnionService>, Error> {
+	let (control, service_id) = publish(control_addr, &auth, &sec_key, &ports, &service_dir)?;
+	info!(
+		"Onion service {}.onion is published with tor control port {}",
+		service_id, control_addr
+	);
+
+	let service = Arc::new(OnionService {
+		control_addr: control_addr.to_string(),
+		auth,
+		sec_key,
+		ports,
+		service_dir,
+		service_id,
+		control: Mutex::new(Some(control)),
+	});
+	let health_service = service.clone();
+	thread::Builder::new()
+		.name("tor_control".to_string())
+		.spawn(move || {
+			let service = health_service;
+			let mut last_check = Instant::now();
+			while !stop_state.is_stopped() {
+				thread::sleep(Duration::from_millis(500));
+				if last_check.elapsed() < HEALTH_CHECK_INTERVAL {
+					continue;
+				}
+				last_check = Instant::now();
+
+				if service.is_healthy() {
+					continue;
+				}
+
+				warn!(
+					"Onion service {}.onion is not published, publishing it again",
+					service.service_id
+				);
+				match service.republish() {
+					Ok(()) => info!(
+						"Onion service {}.onion is published again",
+						service.service_id
+					),
+					Err(e) => error!(
+						"Unable to publish onion service {}, {}",
+						service.service_id, e
+					),
+				}
+			}
+		})
+		.map_err(|e| Error::TorControl(format!("Unable to start tor control thread, {}", e)))?;
+
+	Ok(service)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::util::secp::{ContextFlag, Secp256k1};
+	use rand::rngs::mock::StepRng;
+	use std::net::TcpListener;
+
+	// Mock of the tor control port. Replies with the scripted answers and
+	// checks the received commands.
+	fn mock_control_port(script: Vec<(&'static str, String)>) -> String {
+		mock_control_sessions(vec![script])
+	}
+
+	// Mock of the tor control port that accepts a connection for every script
+	fn mock_control_sessions(sessions: Vec<Vec<(&'static str, String)>>) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+		thread::spawn(move || {
+			for script in sessions {
+				let (stream, _) = listener.accept().unwrap();
+				let mut reader = BufReader::new(stream.try_clone().unwrap());
+				let mut writer = stream;
+				for (expected_cmd, reply) in script {
+					let mut line = String::new();
+					reader.read_line(&mut line).unwrap();
+					let line = line.trim_end();
+					let reply = if line.starts_with(expected_cmd) {
+						reply
+					} else {
+						format!("510 Unexpected command {}\r\n", line)
+					};
+					writer.write_all(reply.as_bytes()).unwrap();
+				}
+			}
+		});
+		addr
+	}
+
+	#[test]
+	fn test_tor_control() {
+		let test_dir = "target/test_output/tor_control";
+		let _ = fs::remove_dir_all(test_dir);
+		fs::create_dir_all(test_dir).unwrap();
+		let cookie_file = format!("{}/control_auth_cookie", test_dir);
+		fs::write(&cookie_file, &[1u8, 2, 0xab]).unwrap();
+
+		let secp = Secp256k1::with_caps(ContextFlag::None);
+		let mut test_rng = StepRng::new(1_234_567_890_u64, 1);
+		let sec_key = SecretKey::new(&secp, &mut test_rng);
+		let onion = OnionV3Address::from_private(&sec_key.0)
+			.unwrap()
+			.to_string();
+
+		let addr = mock_control_port(vec![
+			(
+				"PROTOCOLINFO 1",
+				format!(
+					"250-PROTOCOLINFO 1\r\n250-AUTH METHODS=COOKIE COOKIEFILE=\"{}\"\r\n250-VERSION Tor=\"0.4.8.9\"\r\n250 OK\r\n",
+					cookie_file
+				),
+			),
+			("AUTHENTICATE 0102ab", "250 OK\r\n".to_string()),
+			(
+				"ADD_ONION ED25519-V3:",
+				format!("250-ServiceID={}\r\n250 OK\r\n", onion),
+			),
+			(
+				"GETINFO onions/current",
+				format!(
+					"250+onions/current=\r\nabc\r\n{}\r\n.\r\n250 OK\r\n",
+					onion
+				),
+			),
+			(
+				"GETINFO onions/current",
+				"250-onions/current=abc\r\n250 OK\r\n".to_string(),
+			),
+		]);
+
+		let mut control = TorControl::connect(&addr).unwrap();
+		control.authenticate(&TorControlAuth::default()).unwrap();
+		let service_id = control
+			.add_onion(&sec_key, &[(80, "127.0.0.1:3414".to_string())], &[])
+			.unwrap();
+		assert_eq!(service_id, onion);
+		assert!(control.is_onion_published(&onion).unwrap());
+		assert!(!control.is_onion_published(&onion).unwrap());
+
+		let _ = fs::remove_dir_all(test_dir);
+	}
+
+	#[test]
+	fn test_tor_control_password() {
+		let addr = mock_control_port(vec![
+			(
+				"PROTOCOLINFO 1",
+				"250-PROTOCOLINFO 1\r\n250-AUTH METHODS=HASHEDPASSWORD\r\n250 OK\r\n".to_string(),
+			),
+			(
+				"AUTHENTICATE \"pa\\\"ss\"",
+				"515 Authentication failed\r\n".to_string(),
+			),
+		]);
+		let mut control = TorControl::connect(&addr).unwrap();
+		let auth = TorControlAuth {
+			password: Some("pa\"ss".to_string()),
+			cookie_file: None,
+		};
+		match control.authenticate(&auth) {
+			Err(Error::TorControl(msg)) => assert!(msg.contains("515")),
+			res => panic!("Unexpected result {:?}", res),
+		}
+	}
+
+	#[test]
+	fn test_tor_control_error_reply() {
+		let addr = mock_control_port(vec![
+			(
+				"GETINFO unknown/key",
+				"552-Unrecognized key \"unknown/key\"\r\n552 Unrecognized key\r\n".to_string(),
+			),
+			(
+				"GETINFO version",
+				"250-version=0.4.8.9\r\n250 OK\r\n".to_string(),
+			),
+		]);
+		let mut control = TorControl::connect(&addr).unwrap();
+		match control.get_info("unknown/key") {
+			Err(Error::TorControl(msg)) => {
+				assert!(msg.contains("552"));
+				assert!(msg.contains("Unrecognized key \"unknown/key\""));
+			}
+			res => panic!("Unexpected result {:?}", res),
+		}
+		// The whole error reply is read, the next command gets its own reply
+		assert_eq!(control.get_info("version").unwrap(), "0.4.8.9");
+	}
+
+	#[test]
+	fn test_tor_control_safe_cookie() {
+		let test_dir = "target/test_output/tor_control_safe_cookie";
+		let _ = fs::remove_dir_all(test_dir);
+		fs::create_dir_all(test_dir).unwrap();
+		let cookie_file = format!("{}/control_auth_cookie", test_dir);
+		let cookie = [0x11u8; 32];
+		fs::write(&cookie_file, &cookie).unwrap();
+
+		// Server hash for the cookie 0x11.., client nonce 0x22.. and server nonce 0x33..
+		let server_hash = "FC0B2C069DB253F5CE08D7867C520D1E2B64F18D81FCF888802A89EC5F097874";
+		let server_nonce = "3333333333333333333333333333333333333333333333333333333333333333";
+		let addr = mock_control_port(vec![
+			(
+				"AUTHCHALLENGE SAFECOOKIE 2222222222222222222222222222222222222222222222222222222222222222",
+				format!(
+					"250 AUTHCHALLENGE SERVERHASH={} SERVERNONCE={}\r\n",
+					server_hash, server_nonce
+				),
+			),
+			(
+				"AUTHCHALLENGE SAFECOOKIE 2222222222222222222222222222222222222222222222222222222222222222",
+				format!(
+					"250 AUTHCHALLENGE SERVERHASH={} SERVERNONCE={}\r\n",
+					server_hash, server_nonce
+				),
+			),
+			(
+				"PROTOCOLINFO 1",
+				format!(
+					"250-PROTOCOLINFO 1\r\n250-AUTH METHODS=COOKIE,SAFECOOKIE COOKIEFILE=\"{}\"\r\n250 OK\r\n",
+					cookie_file
+				),
+			),
+			(
+				"AUTHCHALLENGE SAFECOOKIE ",
+				format!(
+					"250 AUTHCHALLENGE SERVERHASH={} SERVERNONCE={}\r\n",
+					server_hash, server_nonce
+				),
+			),
+		]);
+
+		let mut control = TorControl::connect(&addr).unwrap();
+		let client_hash = control.auth_challenge(&cookie, &[0x22u8; 32]).unwrap();
+		assert_eq!(
+			client_hash.to_hex(),
+			"e20ab915df5b06ca94b1f465770a1f2d3d3171e5a035f406761b24b74adb0368"
+		);
+		// Tor must prove that it knows the cookie
+		assert!(control
+			.auth_challenge(&[0x12u8; 32], &[0x22u8; 32])
+			.is_err());
+		// The client nonce is random, the scripted server hash doesn't match it.
+		// The cookie is never sent with AUTHENTICATE.
+		match control.authenticate(&TorControlAuth::default()) {
+			Err(Error::TorControl(msg)) => assert!(msg.contains("server hash doesn't match")),
+			res => panic!("Unexpected result {:?}", res),
+		}
+
+		let _ = fs::remove_dir_all(test_dir);
+	}
+
+	#[test]
+	fn test_onion_service_republish() {
+		let test_dir = "target/test_output/tor_control_republish";
+		let _ = fs::remove_dir_all(test_dir);
+
+		let secp = Secp256k1::with_caps(ContextFlag::None);
+		let mut test_rng = StepRng::new(987_654_321_u64, 1);
+		let sec_key = SecretKey::new(&secp, &mut test_rng);
+		let onion = OnionV3Address::from_private(&sec_key.0)
+			.unwrap()
+			.to_string();
+		let service_dir = format!("{}/{}", test_dir, onion);
+		client_auth::sync_config_clients(&service_dir, &[]).unwrap();
+
+		let auth_script = || {
+			vec![
+				(
+					"PROTOCOLINFO 1",
+					"250-PROTOCOLINFO 1\r\n250-AUTH METHODS=HASHEDPASSWORD\r\n250 OK\r\n"
+						.to_string(),
+				),
+				("AUTHENTICATE \"pass\"", "250 OK\r\n".to_string()),
+			]
+		};
+		let mut first = auth_script();
+		first.push((
+			"ADD_ONION ED25519-V3:",
+			format!("250-ServiceID={}\r\n250 OK\r\n", onion),
+		));
+		first.push(("DEL_ONION", "250 OK\r\n".to_string()));
+		// Client keys are read again at republish
+		let mut second = auth_script();
+		second.push((
+			"ADD_ONION ED25519-V3:",
+			format!("250-ServiceID={}\r\n250 OK\r\n", onion),
+		));
+		let addr = mock_control_sessions(vec![first, second]);
+
+		let stop_state = Arc::new(StopState::new());
+		let service = start_onion_service(
+			&addr,
+			TorControlAuth {
+				password: Some("pass".to_string()),
+				cookie_file: None,
+			},
+			sec_key,
+			vec![(80, "127.0.0.1:3414".to_string())],
+			service_dir,
+			stop_state.clone(),
+		)
+		.unwrap();
+		assert_eq!(service.service_id(), onion);
+		service.republish().unwrap();
+		// Mock is done, tor is not reachable
+		assert!(service.republish().is_err());
+		stop_state.stop();
+
+		let _ = fs::remove_dir_all(test_dir);
+	}
+}
@@ -13,5 +13,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod client_auth;
 pub mod config;
+pub mod control;
 pub mod process;
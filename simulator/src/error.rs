@@ -0,0 +1,58 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simulation errors
+
+use crate::chain;
+use crate::core::libtx;
+use crate::keychain;
+use crate::servers;
+
+/// Simulation error
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// Server error
+	#[error("Server error, {0}")]
+	Server(#[from] servers::common::types::Error),
+	/// Chain error
+	#[error("Chain error, {0}")]
+	Chain(#[from] chain::Error),
+	/// Keychain error
+	#[error("Keychain error, {0}")]
+	Keychain(#[from] keychain::Error),
+	/// Transaction building error
+	#[error("Transaction error, {0}")]
+	Transaction(#[from] libtx::Error),
+	/// IO error
+	#[error("IO error, {0}")]
+	IO(#[from] std::io::Error),
+	/// Node failed to execute the request
+	#[error("Node {0} error, {1}")]
+	Node(usize, String),
+	/// Node is not running
+	#[error("Node {0} is stopped")]
+	NodeStopped(usize),
+	/// Node index is out of range
+	#[error("Unknown node {0}")]
+	UnknownNode(usize),
+	/// Transaction index is out of range
+	#[error("Unknown transaction {0}")]
+	UnknownTx(usize),
+	/// Wait step is timed out
+	#[error("Timeout, {0}")]
+	Timeout(String),
+	/// Scenario assertion failed
+	#[error("Assertion failed, {0}")]
+	Assertion(String),
+}
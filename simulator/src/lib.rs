@@ -0,0 +1,55 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-process network simulator. Runs several complete nodes in a single
+//! process, connected through the in-memory network with configurable
+//! latency, partitions and misbehaving peers.
+//!
+//! Everything that the simulation feeds into the nodes is derived from the seed:
+//! blocks with their timestamps and proof of work, transactions with their
+//! signatures and the data corruption. The network runs on a virtual clock, the data
+//! in flight is delivered in order of its delivery time when the nodes are done with
+//! the data they already have, so the delivery order doesn't depend on the host speed.
+//! The same seed and scenario are producing the same chains.
+//!
+//! Timers of the nodes (sync, ping, dandelion) are still running on the real clock.
+//! Wait steps are waiting for the node threads without a timeout, a single wall-clock
+//! limit only fails the hung runs.
+
+#![deny(non_upper_case_globals)]
+#![deny(non_camel_case_types)]
+#![deny(non_snake_case)]
+#![deny(unused_mut)]
+#![warn(missing_docs)]
+
+#[macro_use]
+extern crate log;
+
+use mwc_chain as chain;
+use mwc_core as core;
+use mwc_keychain as keychain;
+use mwc_p2p as p2p;
+use mwc_pool as pool;
+use mwc_servers as servers;
+use mwc_util as util;
+
+mod error;
+pub mod network;
+pub mod node;
+pub mod scenario;
+
+pub use crate::error::Error;
+pub use crate::network::{Behaviour, SimNetwork};
+pub use crate::node::{SimNode, SimWallet, TxState};
+pub use crate::scenario::{Scenario, Simulation, Step};
@@ -0,0 +1,889 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-memory network. Every node has its own IP address, connections are pairs of
+//! byte pipes. The network controls the latency of every link, partitions and the
+//! behaviour of the malicious nodes.
+//!
+//! Latency is measured by the virtual clock of the network. The data is delivered when
+//! the clock reaches its delivery time, the simulation moves the clock forward only when
+//! the nodes are done with the data that is already delivered.
+
+use crate::p2p::msg::{MsgHeader, Type};
+use crate::p2p::{PeerListener, PeerStream, Transport};
+use crate::util::{Mutex, RwLock};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// First port that is used for the outbound connections
+const FIRST_EPHEMERAL_PORT: u16 = 40000;
+/// Nodes are considered idle if they didn't read or write anything during this time
+const SETTLE_QUIET: Duration = Duration::from_millis(50);
+/// Nodes are running their own timers, they might never be idle
+const SETTLE_LIMIT: Duration = Duration::from_secs(1);
+
+/// How the node is treating the data that it sends to the network.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Behaviour {
+	/// Data is delivered as it is
+	Honest,
+	/// All outgoing data is dropped, the connections stay open
+	Silent,
+	/// Every outgoing chunk gets a random byte flipped with `probability` (0.0 - 1.0)
+	Corrupt { probability: f64 },
+	/// Outgoing data is delivered with extra delay on top of the link latency
+	Slow { delay: Duration },
+}
+
+/// Virtual time of the network
+struct Clock {
+	now_us: AtomicU64,
+	// Incremented on every read and write, nodes are busy while it is changing
+	activity: AtomicU64,
+}
+
+impl Clock {
+	fn now(&self) -> Duration {
+		Duration::from_micros(self.now_us.load(Ordering::SeqCst))
+	}
+
+	fn touch(&self) {
+		self.activity.fetch_add(1, Ordering::SeqCst);
+	}
+
+	fn activity(&self) -> u64 {
+		self.activity.load(Ordering::SeqCst)
+	}
+}
+
+/// Splits the byte stream of the connection into the p2p messages
+#[derive(Default)]
+struct MsgTracer {
+	header: Vec<u8>,
+	// Bytes of the current message body or attachment that are still expected
+	remaining: u64,
+	// TxHashSetArchive is followed by the attachment, its size is the last field of the body
+	archive: Option<Vec<u8>>,
+}
+
+impl MsgTracer {
+	fn feed<F>(&mut self, mut data: &[u8], mut on_message: F)
+	where
+		F: FnMut(u8),
+	{
+		while !data.is_empty() {
+			if self.remaining > 0 {
+				let n = std::cmp::min(self.remaining, data.len() as u64) as usize;
+				if let Some(body) = self.archive.as_mut() {
+					body.extend_from_slice(&data[..n]);
+				}
+				self.remaining -= n as u64;
+				data = &data[n..];
+				if self.remaining == 0 {
+					if let Some(body) = self.archive.take() {
+						if body.len() >= 8 {
+							let size = &body[body.len() - 8..];
+							self.remaining = u64::from_be_bytes(size.try_into().unwrap());
+						}
+					}
+				}
+				continue;
+			}
+			let n = std::cmp::min(MsgHeader::LEN - self.header.len(), data.len());
+			self.header.extend_from_slice(&data[..n]);
+			data = &data[n..];
+			if self.header.len() == MsgHeader::LEN {
+				let msg_type = self.header[2];
+				self.remaining = u64::from_be_bytes(self.header[3..].try_into().unwrap());
+				if msg_type == Type::TxHashSetArchive as u8 {
+					self.archive = Some(vec![]);
+				}
+				self.header.clear();
+				on_message(msg_type);
+			}
+		}
+	}
+}
+
+/// One direction of the connection
+struct Pipe {
+	from: IpAddr,
+	to: IpAddr,
+	clock: Arc<Clock>,
+	// std Mutex because Condvar is waiting on it
+	state: std::sync::Mutex<PipeState>,
+	cond: Condvar,
+	// Data corruption of the link, seeded by the network seed and the link
+	rng: Mutex<StdRng>,
+	tracer: Mutex<MsgTracer>,
+}
+
+struct PipeState {
+	// (delivery time, data, read offset)
+	chunks: VecDeque<(Duration, Vec<u8>, usize)>,
+	closed: bool,
+}
+
+impl Pipe {
+	fn new(from: IpAddr, to: IpAddr, clock: Arc<Clock>, seed: u64) -> Arc<Pipe> {
+		Arc::new(Pipe {
+			from,
+			to,
+			clock,
+			state: std::sync::Mutex::new(PipeState {
+				chunks: VecDeque::new(),
+				closed: false,
+			}),
+			cond: Condvar::new(),
+			rng: Mutex::new(StdRng::seed_from_u64(seed)),
+			tracer: Mutex::new(MsgTracer::default()),
+		})
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, PipeState> {
+		// Pipe state is always consistent, poisoning can be ignored
+		self.state.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn push(&self, deliver_at: Duration, data: Vec<u8>) -> io::Result<()> {
+		let mut state = self.lock();
+		if state.closed {
+			return Err(io::Error::new(
+				io::ErrorKind::BrokenPipe,
+				"connection is closed",
+			));
+		}
+		state.chunks.push_back((deliver_at, data, 0));
+		self.clock.touch();
+		self.cond.notify_all();
+		Ok(())
+	}
+
+	fn close(&self) {
+		self.lock().closed = true;
+		self.cond.notify_all();
+	}
+
+	fn is_closed(&self) -> bool {
+		self.lock().closed
+	}
+
+	/// Wake up the reader, the clock is moved
+	fn wake(&self) {
+		let _state = self.lock();
+		self.cond.notify_all();
+	}
+
+	/// Delivery time of the data that is waiting for the reader
+	fn next_delivery(&self) -> Option<Duration> {
+		let state = self.lock();
+		if state.closed {
+			// Nobody is expected to read the rest
+			return None;
+		}
+		state.chunks.front().map(|(t, _, _)| *t)
+	}
+
+	fn read(&self, buf: &mut [u8], timeout: Option<Duration>) -> io::Result<usize> {
+		let deadline = timeout.map(|t| Instant::now() + t);
+		let mut state = self.lock();
+		loop {
+			let now = self.clock.now();
+			match state.chunks.front().map(|(t, _, _)| *t) {
+				Some(deliver_at) if deliver_at <= now => {
+					let (_, data, offset) = state.chunks.front_mut().unwrap();
+					let n = std::cmp::min(buf.len(), data.len() - *offset);
+					buf[..n].copy_from_slice(&data[*offset..*offset + n]);
+					*offset += n;
+					if *offset == data.len() {
+						state.chunks.pop_front();
+					}
+					self.clock.touch();
+					return Ok(n);
+				}
+				None if state.closed => {
+					// EOF, the same as for the closed socket
+					return Ok(0);
+				}
+				_ => {}
+			}
+
+			// Data in flight is delivered when the clock is moved, the pipe is woken up then
+			state = match deadline {
+				Some(dl) => {
+					let now = Instant::now();
+					if dl <= now {
+						return Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"));
+					}
+					self.cond
+						.wait_timeout(state, dl - now)
+						.unwrap_or_else(|e| e.into_inner())
+						.0
+				}
+				None => self.cond.wait(state).unwrap_or_else(|e| e.into_inner()),
+			};
+		}
+	}
+}
+
+/// Established connections, needed to cut them on partition
+struct Link {
+	a: IpAddr,
+	b: IpAddr,
+	a_to_b: Weak<Pipe>,
+	b_to_a: Weak<Pipe>,
+}
+
+impl Link {
+	fn pipes(&self) -> Vec<Arc<Pipe>> {
+		self.a_to_b
+			.upgrade()
+			.into_iter()
+			.chain(self.b_to_a.upgrade())
+			.collect()
+	}
+}
+
+struct NetworkState {
+	listeners: HashMap<SocketAddr, Sender<(SimStream, SocketAddr)>>,
+	next_port: HashMap<IpAddr, u16>,
+	default_latency: Duration,
+	latency: HashMap<(IpAddr, IpAddr), Duration>,
+	// Node group in the partition, nodes from different groups can't talk to each other
+	partition: HashMap<IpAddr, usize>,
+	behaviour: HashMap<IpAddr, Behaviour>,
+	links: Vec<Link>,
+	// Number of connections between the nodes, part of the link seed
+	connections: HashMap<(IpAddr, IpAddr), u64>,
+}
+
+/// Shared in-memory network. Clones are referring to the same network.
+#[derive(Clone)]
+pub struct SimNetwork {
+	state: Arc<RwLock<NetworkState>>,
+	clock: Arc<Clock>,
+	seed: u64,
+	// Number of messages by (sender, receiver, message type)
+	trace: Arc<Mutex<HashMap<(IpAddr, IpAddr, u8), usize>>>,
+}
+
+impl SimNetwork {
+	/// Create a new network. `seed` is used for all randomness in the network (data corruption).
+	pub fn new(default_latency: Duration, seed: u64) -> SimNetwork {
+		SimNetwork {
+			state: Arc::new(RwLock::new(NetworkState {
+				listeners: HashMap::new(),
+				next_port: HashMap::new(),
+				default_latency,
+				latency: HashMap::new(),
+				partition: HashMap::new(),
+				behaviour: HashMap::new(),
+				links: vec![],
+				connections: HashMap::new(),
+			})),
+			clock: Arc::new(Clock {
+				now_us: AtomicU64::new(0),
+				activity: AtomicU64::new(0),
+			}),
+			seed,
+			trace: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	/// Transport for the node with address `ip`
+	pub fn transport(&self, ip: IpAddr) -> Arc<dyn Transport> {
+		Arc::new(SimTransport {
+			network: self.clone(),
+			ip,
+		})
+	}
+
+	/// Set the latency between two nodes, both directions.
+	pub fn set_latency(&self, a: IpAddr, b: IpAddr, latency: Duration) {
+		let mut state = self.state.write();
+		state.latency.insert((a, b), latency);
+		state.latency.insert((b, a), latency);
+	}
+
+	/// Split the network into groups. Nodes that are not listed are isolated from everybody.
+	/// Existing connections between the groups are closed.
+	pub fn partition(&self, groups: &[Vec<IpAddr>]) {
+		let mut state = self.state.write();
+		state.partition.clear();
+		for (idx, group) in groups.iter().enumerate() {
+			for ip in group {
+				state.partition.insert(*ip, idx);
+			}
+		}
+		let mut links = std::mem::replace(&mut state.links, vec![]);
+		links.retain(|link| {
+			let pipes = link.pipes();
+			if pipes.len() < 2 {
+				return false;
+			}
+			if Self::is_reachable(&state, &link.a, &link.b) {
+				true
+			} else {
+				pipes.iter().for_each(|p| p.close());
+				false
+			}
+		});
+		state.links = links;
+	}
+
+	/// Remove the partition, all nodes can talk to each other again.
+	pub fn heal(&self) {
+		self.state.write().partition.clear();
+	}
+
+	/// Set the behaviour for the node
+	pub fn set_behaviour(&self, ip: IpAddr, behaviour: Behaviour) {
+		self.state.write().behaviour.insert(ip, behaviour);
+	}
+
+	/// Number of the open connections in the network
+	pub fn connections_count(&self) -> usize {
+		self.state
+			.read()
+			.links
+			.iter()
+			.filter(|l| match l.a_to_b.upgrade() {
+				Some(p) => !p.is_closed(),
+				None => false,
+			})
+			.count()
+	}
+
+	/// Current time of the network clock
+	pub fn now(&self) -> Duration {
+		self.clock.now()
+	}
+
+	/// Number of the messages of `msg_type` that node `from` has sent to node `to`.
+	/// Messages are counted as the node writes them, before the behaviour of the node
+	/// is applied.
+	pub fn sent_messages(&self, from: IpAddr, to: IpAddr, msg_type: Type) -> usize {
+		self.trace
+			.lock()
+			.get(&(from, to, msg_type as u8))
+			.cloned()
+			.unwrap_or(0)
+	}
+
+	/// Forget all messages that were sent so far
+	pub fn reset_trace(&self) {
+		self.trace.lock().clear();
+	}
+
+	/// Wait until the nodes have read all the data that is due and stopped reading and
+	/// writing. Nodes are running their own timers, so the wait is limited.
+	pub fn settle(&self) {
+		let deadline = Instant::now() + SETTLE_LIMIT;
+		loop {
+			let activity = self.clock.activity();
+			thread::sleep(SETTLE_QUIET);
+			let now = self.clock.now();
+			let due = self
+				.pipes()
+				.iter()
+				.any(|p| p.next_delivery().map(|t| t <= now).unwrap_or(false));
+			if (!due && activity == self.clock.activity()) || Instant::now() > deadline {
+				return;
+			}
+		}
+	}
+
+	/// Earliest delivery time of the data in flight, if any
+	pub fn next_delivery(&self) -> Option<Duration> {
+		let now = self.clock.now();
+		self.pipes()
+			.iter()
+			.filter_map(|p| p.next_delivery())
+			.filter(|t| *t > now)
+			.min()
+	}
+
+	/// Deliver the next portion of the data in flight. Returns false if there is nothing
+	/// in flight, then the nodes are waiting for their own timers.
+	pub fn step(&self) -> bool {
+		self.settle();
+		match self.next_delivery() {
+			Some(t) => {
+				self.set_time(t);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Move the clock forward by `duration`, the data in flight is delivered in order of
+	/// its delivery time.
+	pub fn advance(&self, duration: Duration) {
+		let target = self.clock.now() + duration;
+		loop {
+			self.settle();
+			match self.next_delivery() {
+				Some(t) if t <= target => self.set_time(t),
+				_ => break,
+			}
+		}
+		self.set_time(target);
+		self.settle();
+	}
+
+	fn set_time(&self, time: Duration) {
+		if time <= self.clock.now() {
+			return;
+		}
+		self.clock
+			.now_us
+			.store(time.as_micros() as u64, Ordering::SeqCst);
+		for pipe in self.pipes() {
+			pipe.wake();
+		}
+	}
+
+	fn pipes(&self) -> Vec<Arc<Pipe>> {
+		self.state
+			.read()
+			.links
+			.iter()
+			.flat_map(|l| l.pipes())
+			.collect()
+	}
+
+	fn is_reachable(state: &NetworkState, a: &IpAddr, b: &IpAddr) -> bool {
+		let group_a = state.partition.get(a);
+		let group_b = state.partition.get(b);
+		if state.partition.is_empty() {
+			return true;
+		}
+		match (group_a, group_b) {
+			(Some(ga), Some(gb)) => ga == gb,
+			_ => false,
+		}
+	}
+
+	/// Seed for the data corruption in one direction of the connection. Doesn't depend
+	/// on the order in which the other nodes are connecting.
+	fn link_seed(&self, from: IpAddr, to: IpAddr, connection: u64) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		(self.seed, from, to, connection).hash(&mut hasher);
+		hasher.finish()
+	}
+
+	fn bind(&self, addr: SocketAddr) -> io::Result<SimListener> {
+		let mut state = self.state.write();
+		if state.listeners.contains_key(&addr) {
+			return Err(io::Error::new(
+				io::ErrorKind::AddrInUse,
+				format!("address {} is in use", addr),
+			));
+		}
+		let (tx, rx) = mpsc::channel();
+		state.listeners.insert(addr, tx);
+		Ok(SimListener {
+			network: self.clone(),
+			addr,
+			incoming: Mutex::new(rx),
+		})
+	}
+
+	fn unbind(&self, addr: &SocketAddr) {
+		self.state.write().listeners.remove(addr);
+	}
+
+	fn connect(&self, from: IpAddr, to: &SocketAddr) -> io::Result<SimStream> {
+		let mut state = self.state.write();
+		if !Self::is_reachable(&state, &from, &to.ip()) {
+			return Err(io::Error::new(
+				io::ErrorKind::TimedOut,
+				format!("{} is not reachable from {}", to, from),
+			));
+		}
+		let listener = match state.listeners.get(to) {
+			Some(l) => l.clone(),
+			None => {
+				return Err(io::Error::new(
+					io::ErrorKind::ConnectionRefused,
+					format!("{} is not listening", to),
+				))
+			}
+		};
+
+		let port = {
+			let port = state.next_port.entry(from).or_insert(FIRST_EPHEMERAL_PORT);
+			let res = *port;
+			*port = port.wrapping_add(1).max(FIRST_EPHEMERAL_PORT);
+			res
+		};
+		let local = SocketAddr::new(from, port);
+
+		let connection = {
+			let count = state.connections.entry((from, to.ip())).or_insert(0);
+			*count += 1;
+			*count
+		};
+		let out_pipe = Pipe::new(
+			from,
+			to.ip(),
+			self.clock.clone(),
+			self.link_seed(from, to.ip(), connection),
+		);
+		let in_pipe = Pipe::new(
+			to.ip(),
+			from,
+			self.clock.clone(),
+			self.link_seed(to.ip(), from, connection),
+		);
+		state.links.retain(|l| l.a_to_b.strong_count() > 0);
+		state.links.push(Link {
+			a: from,
+			b: to.ip(),
+			a_to_b: Arc::downgrade(&out_pipe),
+			b_to_a: Arc::downgrade(&in_pipe),
+		});
+		drop(state);
+
+		let client = SimStream::new(self.clone(), local, *to, in_pipe.clone(), out_pipe.clone());
+		let server = SimStream::new(self.clone(), *to, local, out_pipe, in_pipe);
+		listener.send((server, local)).map_err(|_| {
+			io::Error::new(
+				io::ErrorKind::ConnectionRefused,
+				format!("{} is not listening", to),
+			)
+		})?;
+		Ok(client)
+	}
+
+	/// Trace the data and put it into the pipe as the network will deliver it.
+	fn transmit(&self, pipe: &Pipe, buf: &[u8]) -> io::Result<()> {
+		pipe.tracer.lock().feed(buf, |msg_type| {
+			*self
+				.trace
+				.lock()
+				.entry((pipe.from, pipe.to, msg_type))
+				.or_insert(0) += 1;
+		});
+
+		let state = self.state.read();
+		let mut latency = state
+			.latency
+			.get(&(pipe.from, pipe.to))
+			.cloned()
+			.unwrap_or(state.default_latency);
+		let mut data = buf.to_vec();
+		match state.behaviour.get(&pipe.from) {
+			None | Some(Behaviour::Honest) => {}
+			Some(Behaviour::Silent) => return Ok(()),
+			Some(Behaviour::Corrupt { probability }) => {
+				let mut rng = pipe.rng.lock();
+				if !data.is_empty() && rng.gen::<f64>() < *probability {
+					let idx = rng.gen_range(0, data.len());
+					data[idx] ^= 0xFF;
+				}
+			}
+			Some(Behaviour::Slow { delay }) => latency += *delay,
+		}
+		drop(state);
+		pipe.push(self.clock.now() + latency, data)
+	}
+}
+
+/// One side of the in-memory connection
+pub struct SimStream {
+	network: SimNetwork,
+	local: SocketAddr,
+	peer: SocketAddr,
+	incoming: Arc<Pipe>,
+	outgoing: Arc<Pipe>,
+	read_timeout: Arc<Mutex<Option<Duration>>>,
+}
+
+impl SimStream {
+	fn new(
+		network: SimNetwork,
+		local: SocketAddr,
+		peer: SocketAddr,
+		incoming: Arc<Pipe>,
+		outgoing: Arc<Pipe>,
+	) -> SimStream {
+		SimStream {
+			network,
+			local,
+			peer,
+			incoming,
+			outgoing,
+			read_timeout: Arc::new(Mutex::new(None)),
+		}
+	}
+}
+
+impl Read for SimStream {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if buf.is_empty() {
+			return Ok(0);
+		}
+		let timeout = *self.read_timeout.lock();
+		self.incoming.read(buf, timeout)
+	}
+}
+
+impl Write for SimStream {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if self.outgoing.is_closed() {
+			return Err(io::Error::new(
+				io::ErrorKind::BrokenPipe,
+				"connection is closed",
+			));
+		}
+		self.network.transmit(&self.outgoing, buf)?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl PeerStream for SimStream {
+	fn try_clone(&self) -> io::Result<Box<dyn PeerStream>> {
+		Ok(Box::new(SimStream {
+			network: self.network.clone(),
+			local: self.local,
+			peer: self.peer,
+			incoming: self.incoming.clone(),
+			outgoing: self.outgoing.clone(),
+			read_timeout: self.read_timeout.clone(),
+		}))
+	}
+
+	fn peer_addr(&self) -> io::Result<SocketAddr> {
+		Ok(self.peer)
+	}
+
+	fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+		*self.read_timeout.lock() = dur;
+		Ok(())
+	}
+
+	fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+		// Writes never block, pipes are unbounded
+		Ok(())
+	}
+
+	fn shutdown(&self, _how: Shutdown) -> io::Result<()> {
+		self.incoming.close();
+		self.outgoing.close();
+		Ok(())
+	}
+}
+
+/// Listener for the incoming connections of the node
+pub struct SimListener {
+	network: SimNetwork,
+	addr: SocketAddr,
+	incoming: Mutex<Receiver<(SimStream, SocketAddr)>>,
+}
+
+impl PeerListener for SimListener {
+	fn accept(&self) -> io::Result<(Box<dyn PeerStream>, SocketAddr)> {
+		match self.incoming.lock().try_recv() {
+			Ok((stream, peer_addr)) => Ok((Box::new(stream), peer_addr)),
+			Err(TryRecvError::Empty) => Err(io::Error::new(
+				io::ErrorKind::WouldBlock,
+				"no pending connections",
+			)),
+			Err(TryRecvError::Disconnected) => Err(io::Error::new(
+				io::ErrorKind::NotConnected,
+				"listener is closed",
+			)),
+		}
+	}
+}
+
+impl Drop for SimListener {
+	fn drop(&mut self) {
+		self.network.unbind(&self.addr);
+	}
+}
+
+/// Transport of the single node in the in-memory network
+pub struct SimTransport {
+	network: SimNetwork,
+	ip: IpAddr,
+}
+
+impl Transport for SimTransport {
+	fn bind(&self, addr: SocketAddr) -> io::Result<Box<dyn PeerListener>> {
+		// node is listening on its own address regardless of the host in config
+		let addr = SocketAddr::new(self.ip, addr.port());
+		Ok(Box::new(self.network.bind(addr)?))
+	}
+
+	fn connect(&self, addr: &SocketAddr, _timeout: Duration) -> io::Result<Box<dyn PeerStream>> {
+		Ok(Box::new(self.network.connect(self.ip, addr)?))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn ip(n: u8) -> IpAddr {
+		IpAddr::from([10, 0, 0, n])
+	}
+
+	#[test]
+	fn stream_delivers_with_latency() {
+		let network = SimNetwork::new(Duration::from_millis(50), 1);
+		let listener = network
+			.transport(ip(1))
+			.bind("0.0.0.0:3414".parse().unwrap())
+			.unwrap();
+		let target = SocketAddr::new(ip(1), 3414);
+		let mut client = network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.unwrap();
+		let (mut server, peer_addr) = listener.accept().unwrap();
+		assert_eq!(peer_addr.ip(), ip(2));
+		assert_eq!(client.peer_addr().unwrap(), target);
+
+		client.write_all(b"hello").unwrap();
+		let mut buf = [0u8; 5];
+		// Data is in flight until the clock reaches the delivery time
+		server
+			.set_read_timeout(Some(Duration::from_millis(10)))
+			.unwrap();
+		assert_eq!(
+			server.read(&mut buf).unwrap_err().kind(),
+			io::ErrorKind::TimedOut
+		);
+		assert_eq!(network.next_delivery(), Some(Duration::from_millis(50)));
+		network.advance(Duration::from_millis(49));
+		assert!(server.read(&mut buf).is_err());
+		network.advance(Duration::from_millis(1));
+		server.read_exact(&mut buf).unwrap();
+		assert_eq!(&buf, b"hello");
+		assert_eq!(network.now(), Duration::from_millis(50));
+		assert_eq!(network.next_delivery(), None);
+
+		// nothing else to read
+		assert_eq!(
+			server.read(&mut buf).unwrap_err().kind(),
+			io::ErrorKind::TimedOut
+		);
+	}
+
+	#[test]
+	fn messages_are_traced() {
+		let network = SimNetwork::new(Duration::from_millis(0), 1);
+		let _listener = network
+			.transport(ip(1))
+			.bind("0.0.0.0:3414".parse().unwrap())
+			.unwrap();
+		let target = SocketAddr::new(ip(1), 3414);
+		let mut client = network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.unwrap();
+
+		let message = |msg_type: Type, body: &[u8]| -> Vec<u8> {
+			let mut data = vec![0u8, 0u8, msg_type as u8];
+			data.extend_from_slice(&(body.len() as u64).to_be_bytes());
+			data.extend_from_slice(body);
+			data
+		};
+		let mut data = message(Type::Ping, &[1, 2, 3]);
+		data.extend(message(Type::GetHeaders, &[]));
+		data.extend(message(Type::Ping, &[4; 20]));
+		// Message header and body are split between the writes
+		client.write_all(&data[..5]).unwrap();
+		client.write_all(&data[5..20]).unwrap();
+		client.write_all(&data[20..]).unwrap();
+
+		assert_eq!(network.sent_messages(ip(2), ip(1), Type::Ping), 2);
+		assert_eq!(network.sent_messages(ip(2), ip(1), Type::GetHeaders), 1);
+		assert_eq!(network.sent_messages(ip(1), ip(2), Type::Ping), 0);
+		network.reset_trace();
+		assert_eq!(network.sent_messages(ip(2), ip(1), Type::Ping), 0);
+	}
+
+	#[test]
+	fn partition_closes_connections() {
+		let network = SimNetwork::new(Duration::from_millis(0), 1);
+		let listener = network
+			.transport(ip(1))
+			.bind("0.0.0.0:3414".parse().unwrap())
+			.unwrap();
+		let target = SocketAddr::new(ip(1), 3414);
+		let mut client = network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.unwrap();
+		let (mut server, _) = listener.accept().unwrap();
+		assert_eq!(network.connections_count(), 1);
+
+		network.partition(&[vec![ip(1)], vec![ip(2)]]);
+		assert_eq!(network.connections_count(), 0);
+		assert!(client.write_all(b"data").is_err());
+		let mut buf = [0u8; 4];
+		assert_eq!(server.read(&mut buf).unwrap(), 0);
+		assert!(network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.is_err());
+
+		network.heal();
+		assert!(network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.is_ok());
+	}
+
+	#[test]
+	fn silent_node_drops_data() {
+		let network = SimNetwork::new(Duration::from_millis(0), 1);
+		let listener = network
+			.transport(ip(1))
+			.bind("0.0.0.0:3414".parse().unwrap())
+			.unwrap();
+		let target = SocketAddr::new(ip(1), 3414);
+		let mut client = network
+			.transport(ip(2))
+			.connect(&target, Duration::from_secs(1))
+			.unwrap();
+		let (mut server, _) = listener.accept().unwrap();
+		network.set_behaviour(ip(2), Behaviour::Silent);
+
+		client.write_all(b"data").unwrap();
+		server
+			.set_read_timeout(Some(Duration::from_millis(20)))
+			.unwrap();
+		let mut buf = [0u8; 4];
+		assert!(server.read(&mut buf).is_err());
+	}
+}
@@ -0,0 +1,457 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simulated node: a complete `Server` that is talking to its peers through
+//! the in-memory network. Blocks and transactions of the node are built from its
+//! seeded wallet, so the same scenario is producing the same chain.
+
+use crate::chain::{self, Tip};
+use crate::core::consensus;
+use crate::core::core::hash::{Hash, Hashed};
+use crate::core::core::{Block, CompactBlock, KernelFeatures, Output, Transaction, TxKernel};
+use crate::core::global::{self, ChainTypes};
+use crate::core::libtx::{self, aggsig, build, reward, tx_fee, ProofBuilder};
+use crate::core::pow;
+use crate::core::stratum::connections::StratumIpPool;
+use crate::error::Error;
+use crate::keychain::{BlindingFactor, ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
+use crate::network::SimNetwork;
+use crate::p2p::{self, PeerAddr};
+use crate::pool::{DandelionConfig, TxSource};
+use crate::servers::{Server, ServerConfig, StratumServerConfig};
+use crate::util::Mutex;
+use futures::channel::oneshot;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+/// P2P port for all simulated nodes, nodes are different by IP address
+pub const SIM_P2P_PORT: u16 = 3414;
+
+/// Interval between the simulated blocks
+const BLOCK_INTERVAL_SEC: i64 = 60;
+
+// Key derivation paths of the wallet, the last part is the block height
+const COINBASE_KEY: u32 = 0;
+const OUTPUT_KEY: u32 = 1;
+const EXCESS_KEY: u32 = 2;
+const NONCE_KEY: u32 = 3;
+
+/// Where the node has the transaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxState {
+	/// Node doesn't know the transaction
+	Unknown,
+	/// Transaction is in the stempool, it wasn't fluffed yet
+	Stem,
+	/// Transaction is in the txpool
+	Pool,
+	/// Transaction is in the chain
+	Mined,
+}
+
+/// Keys of the simulated node. The coinbase of the block at height `h` and the
+/// transaction that spends it are derived from `h`, so they are the same for the
+/// same seed. Survives the node restart.
+#[derive(Clone)]
+pub struct SimWallet {
+	keychain: ExtKeychain,
+	// Coinbase values of the blocks that the node has mined, by height
+	coinbases: Arc<Mutex<BTreeMap<u64, u64>>>,
+	// Coinbases that are spent by the pushed transactions
+	spent: Arc<Mutex<HashSet<u64>>>,
+}
+
+impl SimWallet {
+	/// Wallet of the node `id` in the simulation with `seed`
+	pub fn new(seed: u64, id: usize) -> Result<SimWallet, Error> {
+		let mut master_seed = [0u8; 32];
+		master_seed[..8].copy_from_slice(&seed.to_be_bytes());
+		master_seed[8..16].copy_from_slice(&(id as u64).to_be_bytes());
+		Ok(SimWallet {
+			keychain: ExtKeychain::from_seed(&master_seed, false)?,
+			coinbases: Arc::new(Mutex::new(BTreeMap::new())),
+			spent: Arc::new(Mutex::new(HashSet::new())),
+		})
+	}
+
+	fn key_id(kind: u32, height: u64) -> Identifier {
+		ExtKeychain::derive_key_id(2, kind, height as u32, 0, 0)
+	}
+
+	/// Reward output of the block, the kernel is signed with the fixed nonce
+	fn coinbase(&self, fees: u64, height: u64) -> Result<(Output, TxKernel), libtx::Error> {
+		reward::output(
+			&self.keychain,
+			&ProofBuilder::new(&self.keychain),
+			&Self::key_id(COINBASE_KEY, height),
+			fees,
+			true,
+			height,
+			self.keychain.secp(),
+		)
+	}
+
+	/// Transaction that spends the coinbase of the block at `height`. Kernel excess and
+	/// the signature nonce are derived from the keychain instead of the random ones.
+	fn spend_coinbase(&self, height: u64, value: u64) -> Result<Transaction, libtx::Error> {
+		let keychain = &self.keychain;
+		let secp = keychain.secp();
+		let fee = tx_fee(1, 1, 1);
+		let mut kernel = TxKernel::with_features(KernelFeatures::Plain { fee: fee.into() });
+		let msg = kernel.msg_to_sign()?;
+		let skey = keychain.derive_key(
+			0,
+			&Self::key_id(EXCESS_KEY, height),
+			SwitchCommitmentType::None,
+		)?;
+		let nonce = keychain.derive_key(
+			0,
+			&Self::key_id(NONCE_KEY, height),
+			SwitchCommitmentType::None,
+		)?;
+		kernel.excess = secp.commit(0, skey.clone())?;
+		let pubkey = kernel.excess.to_pubkey(secp)?;
+		kernel.excess_sig = aggsig::sign_single(secp, &msg, &skey, Some(&nonce), Some(&pubkey))?;
+		build::transaction_with_kernel(
+			&[
+				build::coinbase_input(value, Self::key_id(COINBASE_KEY, height)),
+				build::output(value - fee, Self::key_id(OUTPUT_KEY, height)),
+			],
+			kernel,
+			BlindingFactor::from_secret_key(skey),
+			keychain,
+			&ProofBuilder::new(keychain),
+		)
+	}
+}
+
+/// Node of the simulated network
+pub struct SimNode {
+	/// Index of the node in the simulation
+	pub id: usize,
+	/// P2P address of the node in the in-memory network
+	pub addr: SocketAddr,
+	/// Keys of the node
+	pub wallet: SimWallet,
+	server: Option<Server>,
+}
+
+impl SimNode {
+	/// IP address of the node with index `id`
+	pub fn ip(id: usize) -> IpAddr {
+		IpAddr::from([10, 0, (id / 250) as u8, (id % 250 + 1) as u8])
+	}
+
+	/// Start a new node. The node will connect to the `seeds` by itself.
+	pub fn start(
+		id: usize,
+		network: &SimNetwork,
+		db_root: String,
+		seeds: Vec<SocketAddr>,
+		wallet: SimWallet,
+	) -> Result<SimNode, Error> {
+		let ip = SimNode::ip(id);
+		let addr = SocketAddr::new(ip, SIM_P2P_PORT);
+
+		let mut config = ServerConfig::default();
+		config.db_root = db_root;
+		config.chain_type = ChainTypes::AutomatedTesting;
+		// Real tcp port for the API, the rest of communication is in memory
+		config.api_http_addr = format!("127.0.0.1:{}", free_local_port()?);
+		config.api_secret_path = None;
+		config.foreign_api_secret_path = None;
+		config.skip_sync_wait = Some(true);
+		config.run_tui = Some(false);
+		config.run_test_miner = Some(false);
+		config.stratum_mining_config = None;
+		config.libp2p_enabled = Some(false);
+		config.p2p_config.host = ip;
+		config.p2p_config.port = SIM_P2P_PORT;
+		config.p2p_config.seeding_type = p2p::Seeding::List;
+		config.p2p_config.seeds = Some(p2p::msg::PeerAddrs {
+			peers: seeds.into_iter().map(PeerAddr::Ip).collect(),
+		});
+		// Own transactions are always stemmed and the relays never stem, they fluff at
+		// the next dandelion monitor run. The stem path is a single hop.
+		config.dandelion_config = DandelionConfig {
+			epoch_secs: 60,
+			embargo_secs: 30,
+			aggregation_secs: 1,
+			stem_probability: 0,
+			always_stem_our_txs: true,
+		};
+
+		let stratum_config = StratumServerConfig::default();
+		let stratum_ip_pool = Arc::new(StratumIpPool::new(
+			stratum_config.ban_action_limit,
+			stratum_config.shares_weight,
+			stratum_config.connection_pace_ms,
+		));
+		// The API server needs the static reference, same as the node binary the channel
+		// is leaked, one per started node.
+		let api_chan: &'static mut (oneshot::Sender<()>, oneshot::Receiver<()>) =
+			Box::leak(Box::new(oneshot::channel::<()>()));
+
+		let server = Server::new_with_transport(
+			config,
+			false,
+			stratum_ip_pool,
+			None,
+			api_chan,
+			Some(network.transport(ip)),
+		)?;
+
+		info!("Simulated node {} is started at {}", id, addr);
+		Ok(SimNode {
+			id,
+			addr,
+			wallet,
+			server: Some(server),
+		})
+	}
+
+	/// Server of the running node
+	pub fn server(&self) -> Result<&Server, Error> {
+		self.server.as_ref().ok_or(Error::NodeStopped(self.id))
+	}
+
+	/// Mine `count` blocks on top of the node chain with the transactions from its pool.
+	/// Blocks are `BLOCK_INTERVAL_SEC` apart and the proof of work search starts from
+	/// nonce 0, so the blocks are the same for the same chain and pool. With `broadcast`
+	/// false the blocks are not announced, peers can get them with sync or `send_block`.
+	pub fn mine(&self, count: u64, broadcast: bool) -> Result<Vec<Hash>, Error> {
+		let server = self.server()?;
+		let opts = if broadcast {
+			chain::Options::MINE
+		} else {
+			chain::Options::SYNC
+		};
+		(0..count).map(|_| self.mine_block(server, opts)).collect()
+	}
+
+	fn mine_block(&self, server: &Server, opts: chain::Options) -> Result<Hash, Error> {
+		let chain = &server.chain;
+		let secp = chain.secp();
+		let head = chain.head_header()?;
+		let height = head.height + 1;
+		let difficulty =
+			consensus::next_difficulty(height, chain.difficulty_iter()?, &mut VecDeque::new());
+
+		let txs = server
+			.tx_pool
+			.read()
+			.prepare_mineable_transactions(secp)
+			.map_err(|e| self.error(format!("Unable to get transactions to mine, {}", e)))?;
+		let fees = txs.iter().map(|tx| tx.fee(head.height)).sum();
+		let (output, kernel) = self.wallet.coinbase(fees, height)?;
+		let mut b = Block::from_reward(&head, &txs, output, kernel, difficulty.difficulty, secp)
+			.map_err(|e| self.error(format!("Unable to build a block, {}", e)))?;
+		b.header.timestamp = head.timestamp + chrono::Duration::seconds(BLOCK_INTERVAL_SEC);
+		b.header.pow.secondary_scaling = difficulty.secondary_scaling;
+		chain.set_txhashset_roots(&mut b)?;
+
+		b.header.pow.nonce = 0;
+		pow::pow_size(
+			&mut b.header,
+			difficulty.difficulty,
+			global::proofsize(),
+			global::min_edge_bits(),
+		)
+		.map_err(|e| self.error(format!("Unable to find proof of work, {}", e)))?;
+
+		let hash = b.hash();
+		chain.process_block(b, opts)?;
+		self.wallet
+			.coinbases
+			.lock()
+			.insert(height, consensus::reward(fees, height));
+		debug!("Node {} mined block {} at {}", self.id, hash, height);
+		Ok(hash)
+	}
+
+	/// Push to the node pool a transaction that spends the oldest mature coinbase
+	/// of the node. With `stem` the transaction goes through dandelion.
+	pub fn push_tx(&self, stem: bool) -> Result<Transaction, Error> {
+		let server = self.server()?;
+		let chain = &server.chain;
+		let header = chain.head_header()?;
+		let mut coinbase = None;
+		{
+			let coinbases = self.wallet.coinbases.lock();
+			let spent = self.wallet.spent.lock();
+			for (height, value) in coinbases.iter() {
+				if height + global::coinbase_maturity() > header.height {
+					break;
+				}
+				if spent.contains(height) {
+					continue;
+				}
+				// Block might be reorged out
+				let commit = self.wallet.keychain.commit(
+					*value,
+					&SimWallet::key_id(COINBASE_KEY, *height),
+					SwitchCommitmentType::Regular,
+				)?;
+				if chain.get_unspent(commit)?.is_some() {
+					coinbase = Some((*height, *value));
+					break;
+				}
+			}
+		}
+		let (height, value) =
+			coinbase.ok_or_else(|| self.error("No mature coinbase to spend".into()))?;
+
+		let tx = self.wallet.spend_coinbase(height, value)?;
+		server
+			.tx_pool
+			.write()
+			.add_to_pool(TxSource::PushApi, tx.clone(), stem, &header, chain.secp())
+			.map_err(|e| self.error(format!("Transaction is rejected, {}", e)))?;
+		self.wallet.spent.lock().insert(height);
+		info!(
+			"Node {} pushed transaction {} spending coinbase at {}",
+			self.id,
+			tx.hash(),
+			height
+		);
+		Ok(tx)
+	}
+
+	/// State of the transaction that was built by `push_tx` on this node
+	pub fn tx_state(&self, tx: &Transaction) -> Result<TxState, Error> {
+		let server = self.server()?;
+		// Output of the pushed transaction is never spent
+		if server
+			.chain
+			.get_unspent(tx.outputs()[0].commitment())?
+			.is_some()
+		{
+			return Ok(TxState::Mined);
+		}
+		let kernel = tx.kernels()[0].hash();
+		let tx_pool = server.tx_pool.read();
+		if tx_pool.txpool.retrieve_tx_by_kernel_hash(kernel).is_some() {
+			Ok(TxState::Pool)
+		} else if tx_pool
+			.stempool
+			.retrieve_tx_by_kernel_hash(kernel)
+			.is_some()
+		{
+			Ok(TxState::Stem)
+		} else {
+			Ok(TxState::Unknown)
+		}
+	}
+
+	/// Send the block at `height` of the node chain to the connected peer `to`, the
+	/// same way as the node announces the blocks it has mined.
+	pub fn send_block(&self, height: u64, to: IpAddr) -> Result<Hash, Error> {
+		let server = self.server()?;
+		let header = server.chain.get_header_by_height(height)?;
+		let block = server.chain.get_block(&header.hash())?;
+		let peer = server
+			.p2p
+			.peers
+			.iter()
+			.connected()
+			.into_iter()
+			.find(|p| match &p.info.addr {
+				PeerAddr::Ip(addr) => addr.ip() == to,
+				_ => false,
+			})
+			.ok_or_else(|| self.error(format!("Peer {} is not connected", to)))?;
+		let cb: CompactBlock = block.into();
+		peer.send_compact_block(&cb)
+			.map_err(|e| self.error(format!("Unable to send block to {}, {}", to, e)))?;
+		Ok(header.hash())
+	}
+
+	/// Does the node have the full block at `height` of its header chain. Nodes that
+	/// were synced with the state sync have only the headers of the old blocks.
+	pub fn has_block(&self, height: u64) -> Result<bool, Error> {
+		let chain = &self.server()?.chain;
+		let header = chain.get_header_by_height(height)?;
+		Ok(chain.block_exists(&header.hash())?)
+	}
+
+	/// Chain head of the node
+	pub fn head(&self) -> Result<Tip, Error> {
+		Ok(self.server()?.head()?)
+	}
+
+	/// Header chain head of the node
+	pub fn header_head(&self) -> Result<Tip, Error> {
+		Ok(self.server()?.header_head()?)
+	}
+
+	/// Start connecting to another node. The handshake is going through the simulated
+	/// network, the result arrives when the network clock is moving.
+	pub fn connect(&self, addr: SocketAddr) -> Result<Receiver<Result<(), Error>>, Error> {
+		let p2p = self.server()?.p2p.clone();
+		let id = self.id;
+		let (tx, rx) = mpsc::channel();
+		thread::Builder::new()
+			.name(format!("sim-connect-{}", id))
+			.spawn(move || {
+				let res = p2p
+					.connect(&PeerAddr::Ip(addr))
+					.map(|_| ())
+					.map_err(|e| Error::Node(id, format!("Unable to connect to {}, {}", addr, e)));
+				let _ = tx.send(res);
+			})?;
+		Ok(rx)
+	}
+
+	/// Number of connected peers
+	pub fn peer_count(&self) -> Result<u32, Error> {
+		Ok(self.server()?.peer_count())
+	}
+
+	/// Is the peer banned by this node
+	pub fn is_banned(&self, addr: SocketAddr) -> Result<bool, Error> {
+		Ok(self.server()?.p2p.peers.is_banned(&PeerAddr::Ip(addr)))
+	}
+
+	/// Is node running
+	pub fn is_running(&self) -> bool {
+		self.server.is_some()
+	}
+
+	fn error(&self, msg: String) -> Error {
+		Error::Node(self.id, msg)
+	}
+
+	/// Stop the node. Node data stays on the disk.
+	pub fn stop(&mut self) {
+		if let Some(server) = self.server.take() {
+			info!("Stopping simulated node {}", self.id);
+			server.stop();
+		}
+	}
+}
+
+impl Drop for SimNode {
+	fn drop(&mut self) {
+		self.stop();
+	}
+}
+
+/// Free port on the loopback interface
+fn free_local_port() -> Result<u16, Error> {
+	let listener = TcpListener::bind("127.0.0.1:0")?;
+	Ok(listener.local_addr()?.port())
+}
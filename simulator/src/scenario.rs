@@ -0,0 +1,525 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scriptable scenarios for the simulated network.
+
+use crate::core::core::Transaction;
+use crate::core::global;
+use crate::error::Error;
+use crate::network::{Behaviour, SimNetwork};
+use crate::node::{SimNode, SimWallet, TxState};
+use crate::p2p::msg::Type;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long wait steps are sleeping when there is no data in flight
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Default wall-clock limit for the wait steps
+const DEFAULT_WAIT_LIMIT: Duration = Duration::from_secs(300);
+
+/// Single scenario step
+#[derive(Debug, Clone)]
+pub enum Step {
+	/// Mine blocks on the node and announce them to the peers
+	Mine { node: usize, blocks: u64 },
+	/// Mine blocks on the node without announcing them
+	MineQuiet { node: usize, blocks: u64 },
+	/// Send the block at `height` of node `from` to the connected node `to`
+	SendBlock { from: usize, to: usize, height: u64 },
+	/// Push a transaction that spends a mature coinbase of the node, `stem` sends it
+	/// through dandelion. Transactions are numbered from 0 in the order they are pushed.
+	PushTx { node: usize, stem: bool },
+	/// Connect node `from` to node `to`
+	Connect { from: usize, to: usize },
+	/// Set latency between two nodes
+	SetLatency {
+		a: usize,
+		b: usize,
+		latency: Duration,
+	},
+	/// Split network into groups of nodes
+	Partition(Vec<Vec<usize>>),
+	/// Remove the partition
+	Heal,
+	/// Change how the node is sending its data
+	SetBehaviour { node: usize, behaviour: Behaviour },
+	/// Stop the node, its data stays
+	StopNode(usize),
+	/// Start previously stopped node
+	StartNode(usize),
+	/// Move the network clock forward, the data in flight is delivered
+	Sleep(Duration),
+	/// Forget the messages that nodes have sent so far
+	ResetTrace,
+	/// Wait until all nodes have the same chain head
+	WaitForSync { nodes: Vec<usize> },
+	/// Wait until the node reaches the height
+	WaitForHeight { node: usize, height: u64 },
+	/// Wait until `node` has banned `peer`
+	WaitForBan { node: usize, peer: usize },
+	/// Wait until the transaction reaches the state on the node
+	WaitForTx {
+		node: usize,
+		tx: usize,
+		state: TxState,
+	},
+	/// Check the node height
+	AssertHeight { node: usize, height: u64 },
+	/// Check that nodes have different chain heads
+	AssertDiverged { a: usize, b: usize },
+	/// Check the state of the transaction on the node
+	AssertTx {
+		node: usize,
+		tx: usize,
+		state: TxState,
+	},
+	/// Check if the node has the full block at `height`
+	AssertBlock {
+		node: usize,
+		height: u64,
+		exists: bool,
+	},
+	/// Check if node `from` has sent a message of `msg` type to node `to`
+	AssertSent {
+		from: usize,
+		to: usize,
+		msg: Type,
+		sent: bool,
+	},
+}
+
+/// Named list of steps. Built with the chained calls:
+/// `Scenario::new("sync").mine(0, 10).wait_for_sync(&[0, 1])`
+#[derive(Debug, Clone)]
+pub struct Scenario {
+	/// Scenario name, used for the logs
+	pub name: String,
+	/// Steps to run
+	pub steps: Vec<Step>,
+}
+
+impl Scenario {
+	/// New empty scenario
+	pub fn new(name: &str) -> Scenario {
+		Scenario {
+			name: name.to_string(),
+			steps: vec![],
+		}
+	}
+
+	/// Add a step
+	pub fn step(mut self, step: Step) -> Scenario {
+		self.steps.push(step);
+		self
+	}
+
+	/// Mine blocks on the node and announce them
+	pub fn mine(self, node: usize, blocks: u64) -> Scenario {
+		self.step(Step::Mine { node, blocks })
+	}
+
+	/// Mine blocks on the node without announcing them
+	pub fn mine_quiet(self, node: usize, blocks: u64) -> Scenario {
+		self.step(Step::MineQuiet { node, blocks })
+	}
+
+	/// Send the block at `height` of node `from` to node `to`
+	pub fn send_block(self, from: usize, to: usize, height: u64) -> Scenario {
+		self.step(Step::SendBlock { from, to, height })
+	}
+
+	/// Push a transaction to the node pool
+	pub fn push_tx(self, node: usize, stem: bool) -> Scenario {
+		self.step(Step::PushTx { node, stem })
+	}
+
+	/// Connect node `from` to node `to`
+	pub fn connect(self, from: usize, to: usize) -> Scenario {
+		self.step(Step::Connect { from, to })
+	}
+
+	/// Set latency between two nodes
+	pub fn latency(self, a: usize, b: usize, latency_ms: u64) -> Scenario {
+		self.step(Step::SetLatency {
+			a,
+			b,
+			latency: Duration::from_millis(latency_ms),
+		})
+	}
+
+	/// Split network into groups of nodes
+	pub fn partition(self, groups: Vec<Vec<usize>>) -> Scenario {
+		self.step(Step::Partition(groups))
+	}
+
+	/// Remove the partition
+	pub fn heal(self) -> Scenario {
+		self.step(Step::Heal)
+	}
+
+	/// Change how the node is sending its data
+	pub fn behaviour(self, node: usize, behaviour: Behaviour) -> Scenario {
+		self.step(Step::SetBehaviour { node, behaviour })
+	}
+
+	/// Move the network clock forward
+	pub fn sleep(self, ms: u64) -> Scenario {
+		self.step(Step::Sleep(Duration::from_millis(ms)))
+	}
+
+	/// Forget the messages that nodes have sent so far
+	pub fn reset_trace(self) -> Scenario {
+		self.step(Step::ResetTrace)
+	}
+
+	/// Wait until all nodes have the same chain head
+	pub fn wait_for_sync(self, nodes: &[usize]) -> Scenario {
+		self.step(Step::WaitForSync {
+			nodes: nodes.to_vec(),
+		})
+	}
+
+	/// Wait until the node reaches the height
+	pub fn wait_for_height(self, node: usize, height: u64) -> Scenario {
+		self.step(Step::WaitForHeight { node, height })
+	}
+
+	/// Wait until `node` has banned `peer`
+	pub fn wait_for_ban(self, node: usize, peer: usize) -> Scenario {
+		self.step(Step::WaitForBan { node, peer })
+	}
+
+	/// Wait until the transaction reaches the state on the node
+	pub fn wait_for_tx(self, node: usize, tx: usize, state: TxState) -> Scenario {
+		self.step(Step::WaitForTx { node, tx, state })
+	}
+
+	/// Check the node height
+	pub fn assert_height(self, node: usize, height: u64) -> Scenario {
+		self.step(Step::AssertHeight { node, height })
+	}
+
+	/// Check that nodes have different chain heads
+	pub fn assert_diverged(self, a: usize, b: usize) -> Scenario {
+		self.step(Step::AssertDiverged { a, b })
+	}
+
+	/// Check the state of the transaction on the node
+	pub fn assert_tx(self, node: usize, tx: usize, state: TxState) -> Scenario {
+		self.step(Step::AssertTx { node, tx, state })
+	}
+
+	/// Check that the node has the full block at `height`
+	pub fn assert_block(self, node: usize, height: u64) -> Scenario {
+		self.step(Step::AssertBlock {
+			node,
+			height,
+			exists: true,
+		})
+	}
+
+	/// Check that the node has only the header at `height`
+	pub fn assert_no_block(self, node: usize, height: u64) -> Scenario {
+		self.step(Step::AssertBlock {
+			node,
+			height,
+			exists: false,
+		})
+	}
+
+	/// Check that node `from` has sent a message of `msg` type to node `to`
+	pub fn assert_sent(self, from: usize, to: usize, msg: Type) -> Scenario {
+		self.step(Step::AssertSent {
+			from,
+			to,
+			msg,
+			sent: true,
+		})
+	}
+
+	/// Check that node `from` hasn't sent any message of `msg` type to node `to`
+	pub fn assert_not_sent(self, from: usize, to: usize, msg: Type) -> Scenario {
+		self.step(Step::AssertSent {
+			from,
+			to,
+			msg,
+			sent: false,
+		})
+	}
+}
+
+/// Network of the simulated nodes
+pub struct Simulation {
+	/// In-memory network that nodes are using
+	pub network: SimNetwork,
+	/// Simulated nodes
+	pub nodes: Vec<SimNode>,
+	/// Pushed transactions
+	pub txs: Vec<Transaction>,
+	dir: String,
+	wait_limit: Duration,
+}
+
+impl Simulation {
+	/// Start `node_count` nodes in directory `dir`. Nodes are not connected, use
+	/// `Step::Connect` to build the topology. Keys of the nodes and randomness of the
+	/// network are defined by `seed`.
+	pub fn new(dir: &str, node_count: usize, seed: u64) -> Result<Simulation, Error> {
+		global::set_global_chain_type(global::ChainTypes::AutomatedTesting);
+		global::set_local_chain_type(global::ChainTypes::AutomatedTesting);
+		let _ = fs::remove_dir_all(dir);
+
+		let network = SimNetwork::new(Duration::from_millis(0), seed);
+		let mut sim = Simulation {
+			network,
+			nodes: vec![],
+			txs: vec![],
+			dir: dir.to_string(),
+			wait_limit: DEFAULT_WAIT_LIMIT,
+		};
+		for id in 0..node_count {
+			let wallet = SimWallet::new(seed, id)?;
+			let node = SimNode::start(id, &sim.network, sim.node_dir(id), vec![], wallet)?;
+			sim.nodes.push(node);
+		}
+		Ok(sim)
+	}
+
+	/// Wall-clock limit for a single wait step. It is not a part of the scenario, it
+	/// only stops the runs where the nodes are stuck.
+	pub fn set_wait_limit(&mut self, limit: Duration) {
+		self.wait_limit = limit;
+	}
+
+	fn node_dir(&self, id: usize) -> String {
+		format!("{}/node_{}", self.dir, id)
+	}
+
+	/// Access to the node
+	pub fn node(&self, id: usize) -> Result<&SimNode, Error> {
+		self.nodes.get(id).ok_or(Error::UnknownNode(id))
+	}
+
+	/// Transaction pushed by the step `PushTx`
+	pub fn tx(&self, idx: usize) -> Result<&Transaction, Error> {
+		self.txs.get(idx).ok_or(Error::UnknownTx(idx))
+	}
+
+	/// Run all scenario steps one by one. Stops on the first failed step.
+	pub fn run(&mut self, scenario: &Scenario) -> Result<(), Error> {
+		info!("Running scenario {}", scenario.name);
+		for (idx, step) in scenario.steps.iter().enumerate() {
+			debug!(
+				"Scenario {}, step {} at {:?}: {:?}",
+				scenario.name,
+				idx,
+				self.network.now(),
+				step
+			);
+			self.run_step(step).map_err(|e| {
+				error!(
+					"Scenario {} failed at step {} {:?}, {}",
+					scenario.name, idx, step, e
+				);
+				e
+			})?;
+		}
+		info!("Scenario {} is finished", scenario.name);
+		Ok(())
+	}
+
+	fn run_step(&mut self, step: &Step) -> Result<(), Error> {
+		match step {
+			Step::Mine { node, blocks } => {
+				self.node(*node)?.mine(*blocks, true)?;
+			}
+			Step::MineQuiet { node, blocks } => {
+				self.node(*node)?.mine(*blocks, false)?;
+			}
+			Step::SendBlock { from, to, height } => {
+				self.node(*to)?;
+				self.node(*from)?.send_block(*height, SimNode::ip(*to))?;
+			}
+			Step::PushTx { node, stem } => {
+				let tx = self.node(*node)?.push_tx(*stem)?;
+				self.txs.push(tx);
+			}
+			Step::Connect { from, to } => {
+				let addr = self.node(*to)?.addr;
+				let result = self.node(*from)?.connect(addr)?;
+				let mut connected = None;
+				self.wait_for(&format!("connection {} -> {}", from, to), |_| {
+					if let Ok(res) = result.try_recv() {
+						connected = Some(res);
+					}
+					Ok(connected.is_some())
+				})?;
+				if let Some(res) = connected {
+					res?;
+				}
+			}
+			Step::SetLatency { a, b, latency } => {
+				self.network
+					.set_latency(SimNode::ip(*a), SimNode::ip(*b), *latency);
+			}
+			Step::Partition(groups) => {
+				let groups: Vec<_> = groups
+					.iter()
+					.map(|g| g.iter().map(|id| SimNode::ip(*id)).collect())
+					.collect();
+				self.network.partition(&groups);
+			}
+			Step::Heal => self.network.heal(),
+			Step::SetBehaviour { node, behaviour } => {
+				self.network
+					.set_behaviour(SimNode::ip(*node), behaviour.clone());
+			}
+			Step::StopNode(node) => {
+				self.nodes
+					.get_mut(*node)
+					.ok_or(Error::UnknownNode(*node))?
+					.stop();
+			}
+			Step::StartNode(node) => {
+				let wallet = {
+					let n = self.node(*node)?;
+					if n.is_running() {
+						return Err(Error::Assertion(format!("node {} is running", node)));
+					}
+					n.wallet.clone()
+				};
+				self.nodes[*node] =
+					SimNode::start(*node, &self.network, self.node_dir(*node), vec![], wallet)?;
+			}
+			Step::Sleep(duration) => self.network.advance(*duration),
+			Step::ResetTrace => self.network.reset_trace(),
+			Step::WaitForSync { nodes } => {
+				self.wait_for("nodes sync", |sim| {
+					let mut heads = vec![];
+					for n in nodes {
+						heads.push(sim.node(*n)?.head()?.last_block_h);
+					}
+					Ok(heads.windows(2).all(|w| w[0] == w[1]))
+				})?;
+			}
+			Step::WaitForHeight { node, height } => {
+				self.wait_for(&format!("height {}", height), |sim| {
+					Ok(sim.node(*node)?.head()?.height >= *height)
+				})?;
+			}
+			Step::WaitForBan { node, peer } => {
+				let addr = self.node(*peer)?.addr;
+				self.wait_for(&format!("ban of {}", addr), |sim| {
+					sim.node(*node)?.is_banned(addr)
+				})?;
+			}
+			Step::WaitForTx { node, tx, state } => {
+				self.wait_for(&format!("transaction {} {:?}", tx, state), |sim| {
+					Ok(sim.node(*node)?.tx_state(sim.tx(*tx)?)? == *state)
+				})?;
+			}
+			Step::AssertHeight { node, height } => {
+				let head = self.node(*node)?.head()?;
+				if head.height != *height {
+					return Err(Error::Assertion(format!(
+						"node {} height {}, expected {}",
+						node, head.height, height
+					)));
+				}
+			}
+			Step::AssertDiverged { a, b } => {
+				let head_a = self.node(*a)?.head()?;
+				let head_b = self.node(*b)?.head()?;
+				if head_a.last_block_h == head_b.last_block_h {
+					return Err(Error::Assertion(format!(
+						"nodes {} and {} have the same head {}",
+						a, b, head_a.last_block_h
+					)));
+				}
+			}
+			Step::AssertTx { node, tx, state } => {
+				let tx_state = self.node(*node)?.tx_state(self.tx(*tx)?)?;
+				if tx_state != *state {
+					return Err(Error::Assertion(format!(
+						"transaction {} is {:?} on node {}, expected {:?}",
+						tx, tx_state, node, state
+					)));
+				}
+			}
+			Step::AssertBlock {
+				node,
+				height,
+				exists,
+			} => {
+				if self.node(*node)?.has_block(*height)? != *exists {
+					return Err(Error::Assertion(format!(
+						"node {} block at {} exists: {}, expected {}",
+						node, height, !exists, exists
+					)));
+				}
+			}
+			Step::AssertSent {
+				from,
+				to,
+				msg,
+				sent,
+			} => {
+				let count = self
+					.network
+					.sent_messages(SimNode::ip(*from), SimNode::ip(*to), *msg);
+				if (count > 0) != *sent {
+					return Err(Error::Assertion(format!(
+						"node {} sent {} {:?} messages to node {}, expected sent: {}",
+						from, count, msg, to, sent
+					)));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Keep delivering the data in flight until the condition is true. If nothing is in
+	/// flight the nodes are waiting for their timers.
+	fn wait_for<F>(&self, what: &str, mut check: F) -> Result<(), Error>
+	where
+		F: FnMut(&Simulation) -> Result<bool, Error>,
+	{
+		let deadline = Instant::now() + self.wait_limit;
+		loop {
+			if check(self)? {
+				return Ok(());
+			}
+			if Instant::now() > deadline {
+				return Err(Error::Timeout(format!(
+					"{} is not reached in {:?}, network time {:?}",
+					what,
+					self.wait_limit,
+					self.network.now()
+				)));
+			}
+			if !self.network.step() {
+				thread::sleep(POLL_INTERVAL);
+			}
+		}
+	}
+
+	/// Stop all nodes and remove their data
+	pub fn shutdown(mut self) {
+		for node in &mut self.nodes {
+			node.stop();
+		}
+		let _ = fs::remove_dir_all(&self.dir);
+	}
+}
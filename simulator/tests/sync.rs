@@ -0,0 +1,208 @@
+// Copyright 2024 The MWC Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use mwc_core::core::hash::Hash;
+use mwc_p2p::msg::Type;
+use mwc_simulator::{Behaviour, Scenario, Simulation, TxState};
+use mwc_util as util;
+
+const SEED: u64 = 42;
+
+#[test]
+fn sim_basic_sync() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_basic_sync", 3, SEED).unwrap();
+
+	let scenario = Scenario::new("basic_sync")
+		.latency(0, 1, 50)
+		.latency(1, 2, 100)
+		.connect(1, 0)
+		.connect(2, 1)
+		.mine(0, 10)
+		.wait_for_height(2, 10)
+		.wait_for_sync(&[0, 1, 2])
+		.assert_height(1, 10);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_partition_fork_converges() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_partition", 4, SEED).unwrap();
+
+	let scenario = Scenario::new("partition")
+		.connect(1, 0)
+		.connect(2, 0)
+		.connect(3, 2)
+		.mine(0, 5)
+		.wait_for_sync(&[0, 1, 2, 3])
+		.partition(vec![vec![0, 1], vec![2, 3]])
+		// Both sides are mining, the right side is mining more work
+		.mine(0, 3)
+		.mine(2, 6)
+		.wait_for_sync(&[0, 1])
+		.wait_for_sync(&[2, 3])
+		.assert_diverged(1, 3)
+		.heal()
+		.connect(1, 3)
+		.wait_for_sync(&[0, 1, 2, 3])
+		.assert_height(0, 11);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_silent_peer() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_silent", 3, SEED).unwrap();
+
+	// Silent peer has a short fork. It neither stalls the sync of node 1 nor gets
+	// the blocks, it can't request them.
+	let scenario = Scenario::new("silent")
+		.mine(2, 4)
+		.connect(1, 0)
+		.connect(1, 2)
+		.behaviour(2, Behaviour::Silent)
+		.mine(0, 8)
+		.wait_for_sync(&[0, 1])
+		.assert_height(1, 8)
+		.sleep(5000)
+		.assert_height(2, 4);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_corrupt_peer_is_banned() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_corrupt", 2, SEED).unwrap();
+
+	// Corrupt peer has the longer fork, its headers fail the validation.
+	// It starts corrupting the data after the handshake.
+	let scenario = Scenario::new("corrupt")
+		.mine(0, 8)
+		.mine(1, 16)
+		.connect(1, 0)
+		.behaviour(1, Behaviour::Corrupt { probability: 0.2 })
+		.wait_for_ban(0, 1);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_orphan_block() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_orphan", 2, SEED).unwrap();
+
+	let scenario = Scenario::new("orphan")
+		.connect(1, 0)
+		.mine(0, 5)
+		.wait_for_sync(&[0, 1])
+		// Node 1 gets the block 8 first, its parents were never announced
+		.mine_quiet(0, 3)
+		.latency(0, 1, 2000)
+		.reset_trace()
+		.send_block(0, 1, 8)
+		// The block is delivered, the parents can't arrive before the round trip
+		.sleep(2000)
+		.assert_height(1, 5)
+		.assert_sent(1, 0, Type::GetHeaders)
+		.wait_for_sync(&[0, 1])
+		.assert_height(1, 8);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_header_hash_and_pibd_sync() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_pibd", 2, SEED).unwrap();
+
+	// Chain is long enough for the archive height 80, the new node syncs the headers
+	// hashes, headers, the state at 80 and the blocks after it.
+	let scenario = Scenario::new("pibd")
+		.mine(0, 100)
+		.connect(1, 0)
+		.wait_for_sync(&[0, 1])
+		.assert_sent(1, 0, Type::StartHeadersHashRequest)
+		.assert_sent(1, 0, Type::GetHeadersHashesSegment)
+		.assert_sent(0, 1, Type::OutputHeadersHashesSegment)
+		.assert_sent(1, 0, Type::StartPibdSyncRequest)
+		.assert_sent(1, 0, Type::GetOutputSegment)
+		.assert_sent(1, 0, Type::GetKernelSegment)
+		.assert_not_sent(1, 0, Type::TxHashSetRequest)
+		// Old blocks are not downloaded, the outputs came with the state
+		.assert_no_block(1, 10)
+		.assert_block(1, 100);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+#[test]
+fn sim_dandelion_stem_and_fluff() {
+	util::init_test_logger();
+	let mut sim = Simulation::new(".mwc_sim_dandelion", 3, SEED).unwrap();
+
+	// Node 0 stems its transaction to its only outbound peer, node 1 is in the fluff
+	// epoch and broadcasts it.
+	let scenario = Scenario::new("dandelion")
+		.connect(0, 1)
+		.connect(1, 2)
+		.mine(0, 5)
+		.wait_for_sync(&[0, 1, 2])
+		.reset_trace()
+		.push_tx(0, true)
+		.assert_tx(0, 0, TxState::Stem)
+		.wait_for_tx(2, 0, TxState::Pool)
+		.assert_sent(0, 1, Type::StemTransaction)
+		.assert_not_sent(1, 2, Type::StemTransaction)
+		.assert_sent(1, 2, Type::TransactionKernel)
+		.mine(2, 1)
+		.wait_for_sync(&[0, 1, 2])
+		.assert_tx(0, 0, TxState::Mined)
+		.assert_tx(1, 0, TxState::Mined);
+	sim.run(&scenario).unwrap();
+	sim.shutdown();
+}
+
+fn run_seeded(dir: &str, seed: u64) -> Vec<Hash> {
+	let mut sim = Simulation::new(dir, 2, seed).unwrap();
+	let scenario = Scenario::new("seeded")
+		.latency(0, 1, 100)
+		.connect(1, 0)
+		.mine(0, 6)
+		.wait_for_sync(&[0, 1])
+		.push_tx(0, false)
+		.wait_for_tx(1, 0, TxState::Pool)
+		.mine(1, 2)
+		.wait_for_sync(&[0, 1])
+		.assert_tx(0, 0, TxState::Mined);
+	sim.run(&scenario).unwrap();
+	let heads = (0..2)
+		.map(|n| sim.node(n).unwrap().head().unwrap().last_block_h)
+		.collect();
+	sim.shutdown();
+	heads
+}
+
+#[test]
+fn sim_same_seed_same_chain() {
+	util::init_test_logger();
+	let first = run_seeded(".mwc_sim_seeded_1", SEED);
+	let second = run_seeded(".mwc_sim_seeded_2", SEED);
+	assert_eq!(first, second);
+	let other = run_seeded(".mwc_sim_seeded_3", SEED + 1);
+	assert_ne!(first, other);
+}
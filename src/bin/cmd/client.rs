@@ -13,16 +13,22 @@
 // limitations under the License.
 
 /// Mwc client commands processing
+use std::fs;
 use std::net::SocketAddr;
 
 use clap::ArgMatches;
 
 use crate::api::client;
 use crate::api::json_rpc::*;
-use crate::api::types::Status;
+use crate::api::types::{OnionClientKey, Status, TxPoolVerdict};
 use crate::config::GlobalConfig;
+use crate::core::core::hash::Hashed;
+use crate::core::core::{Inputs, Transaction, Weighting};
+use crate::core::ser::{self, DeserializationMode, ProtocolVersion};
 use crate::p2p::types::PeerInfoDisplay;
 use crate::util::file::get_first_line;
+use crate::util::secp::{ContextFlag, Secp256k1};
+use crate::util::{self, ToHex};
 use serde_json::json;
 
 const ENDPOINT: &str = "/v2/owner";
@@ -179,6 +185,75 @@ impl HTTPNodeClient {
 		e.reset().unwrap();
 	}
 
+	pub fn inspect_tx(&self, file: &str) {
+		let mut e = term::stdout().unwrap();
+		match read_transaction(file) {
+			Ok(tx) => {
+				let height = self.chain_height().unwrap_or(0);
+				print_transaction(&mut e, &tx, height);
+			}
+			Err(err) => writeln!(e, "{}", err).unwrap(),
+		}
+		e.reset().unwrap();
+	}
+
+	pub fn validate_tx(&self, file: &str) {
+		let mut e = term::stdout().unwrap();
+		let tx = match read_transaction(file) {
+			Ok(tx) => tx,
+			Err(err) => {
+				writeln!(e, "{}", err).unwrap();
+				e.reset().unwrap();
+				return;
+			}
+		};
+		let height = match self.chain_height() {
+			Some(height) => height,
+			None => {
+				writeln!(
+					e,
+					"WARNING: Unable to get the chain height, validating at height 0"
+				)
+				.unwrap();
+				0
+			}
+		};
+		print_transaction(&mut e, &tx, height);
+		println!();
+
+		let secp = Secp256k1::with_caps(ContextFlag::Commit);
+		match tx.validate(Weighting::AsTransaction, height, &secp) {
+			Ok(_) => writeln!(e, "Offline validation: OK").unwrap(),
+			Err(err) => writeln!(e, "Offline validation: FAILED, {}", err).unwrap(),
+		}
+
+		let params = json!([tx]);
+		match self.send_json_request::<TxPoolVerdict>("dry_run_transaction", &params) {
+			Ok(verdict) => {
+				writeln!(e, "Node pool checks:").unwrap();
+				for check in &verdict.checks {
+					match &check.error {
+						None => writeln!(e, "  {:?}: OK", check.check).unwrap(),
+						Some(err) => writeln!(e, "  {:?}: FAILED, {}", check.check, err).unwrap(),
+					}
+				}
+				if verdict.accepted {
+					writeln!(e, "Transaction would be accepted by the pool").unwrap();
+				} else {
+					writeln!(e, "Transaction would be rejected by the pool").unwrap();
+				}
+			}
+			Err(err) => writeln!(e, "Failed to run the node pool checks: {}", err).unwrap(),
+		}
+		e.reset().unwrap();
+	}
+
+	fn chain_height(&self) -> Option<u64> {
+		self.send_json_request::<Status>("get_status", &serde_json::Value::Null)
+			.ok()
+			.map(|status| status.tip.height)
+	}
+
 	pub fn ban_peer(&self, peer_addr: &SocketAddr) {
 		let mut e = term::stdout().unwrap();
 		let params = json!([peer_addr]);
@@ -198,6 +273,131 @@ impl HTTPNodeClient {
 		};
 		e.reset().unwrap();
 	}
+
+	pub fn add_onion_client(&self, name: &str) {
+		let mut e = term::stdout().unwrap();
+		let params = json!([name]);
+		match self.send_json_request::<OnionClientKey>("add_onion_client", &params) {
+			Ok(key) => {
+				writeln!(e, "Client {} is authorized", key.name).unwrap();
+				writeln!(e, "Public key: {}", key.public_key).unwrap();
+				if let Some(client_auth) = &key.client_auth {
+					writeln!(
+						e,
+						"Client auth file line, keep it secret, the node doesn't store it:"
+					)
+					.unwrap();
+					writeln!(e, "{}", client_auth).unwrap();
+				}
+				writeln!(e, "The key is applied at the node restart").unwrap();
+			}
+			Err(err) => writeln!(e, "Failed to add onion client {}, {}", name, err).unwrap(),
+		};
+		e.reset().unwrap();
+	}
+
+	pub fn revoke_onion_client(&self, name: &str) {
+		let mut e = term::stdout().unwrap();
+		let params = json!([name]);
+		match self.send_json_request::<bool>("revoke_onion_client", &params) {
+			Ok(true) => writeln!(
+				e,
+				"Client {} is revoked, the change is applied at the node restart",
+				name
+			)
+			.unwrap(),
+			Ok(false) => writeln!(e, "Client {} is not found", name).unwrap(),
+			Err(err) => writeln!(e, "Failed to revoke onion client {}, {}", name, err).unwrap(),
+		};
+		e.reset().unwrap();
+	}
+
+	pub fn list_onion_clients(&self) {
+		let mut e = term::stdout().unwrap();
+		match self
+			.send_json_request::<Vec<OnionClientKey>>("get_onion_clients", &serde_json::Value::Null)
+		{
+			Ok(clients) => {
+				if clients.is_empty() {
+					writeln!(e, "No authorized clients, the onion service is public").unwrap();
+				}
+				for client in &clients {
+					writeln!(e, "{} {}", client.name, client.public_key).unwrap();
+				}
+			}
+			Err(err) => writeln!(e, "Failed to get onion clients, {}", err).unwrap(),
+		};
+		e.reset().unwrap();
+	}
+}
+
+/// Read the transaction from the file. Supported formats are the json transaction,
+/// the json wrapper `{"tx_hex": "..."}`, the hex encoded binary and the binary transaction.
+fn read_transaction(file: &str) -> Result<Transaction, Error> {
+	let data =
+		fs::read(file).map_err(|e| Error::TxFile(format!("Unable to read {}, {}", file, e)))?;
+
+	if let Ok(text) = std::str::from_utf8(&data) {
+		let text = text.trim();
+		if let Ok(tx) = serde_json::from_str::<Transaction>(text) {
+			return Ok(tx);
+		}
+		let hex = match serde_json::from_str::<serde_json::Value>(text) {
+			Ok(val) => val["tx_hex"].as_str().map(|s| s.to_string()),
+			Err(_) => Some(text.to_string()),
+		};
+		if let Some(bin) = hex.and_then(|hex| util::from_hex(&hex).ok()) {
+			return deserialize_transaction(&bin);
+		}
+	}
+	deserialize_transaction(&data)
+}
+
+fn deserialize_transaction(bin: &[u8]) -> Result<Transaction, Error> {
+	// Wallets are using the protocol version 1, the local version is the fallback
+	for version in &[ProtocolVersion(1), ProtocolVersion::local()] {
+		if let Ok(tx) = ser::deserialize(&mut &bin[..], *version, DeserializationMode::default()) {
+			return Ok(tx);
+		}
+	}
+	Err(Error::TxFile(
+		"Unable to decode the transaction".to_string(),
+	))
+}
+
+fn print_transaction(e: &mut Box<term::StdoutTerminal>, tx: &Transaction, height: u64) {
+	writeln!(e, "Transaction: {}", tx.hash()).unwrap();
+	match tx.inputs() {
+		Inputs::CommitOnly(inputs) => {
+			writeln!(e, "Inputs: {}", inputs.len()).unwrap();
+			for input in inputs {
+				writeln!(e, "  {}", input.commitment().to_hex()).unwrap();
+			}
+		}
+		Inputs::FeaturesAndCommit(inputs) => {
+			writeln!(e, "Inputs: {}", inputs.len()).unwrap();
+			for input in inputs {
+				writeln!(e, "  {} {:?}", input.commitment().to_hex(), input.features).unwrap();
+			}
+		}
+	}
+	writeln!(e, "Outputs: {}", tx.outputs().len()).unwrap();
+	for output in tx.outputs() {
+		writeln!(
+			e,
+			"  {} {:?}",
+			output.commitment().to_hex(),
+			output.features()
+		)
+		.unwrap();
+	}
+	writeln!(e, "Kernels: {}", tx.kernels().len()).unwrap();
+	for kernel in tx.kernels() {
+		writeln!(e, "  {} {:?}", kernel.excess.to_hex(), kernel.features).unwrap();
+	}
+	writeln!(e, "Fee: {}", tx.fee(height)).unwrap();
+	writeln!(e, "Weight: {}", tx.weight_size()).unwrap();
+	writeln!(e, "Lock height: {}", tx.lock_height()).unwrap();
 }
 
 pub fn client_command(client_args: &ArgMatches<'_>, global_config: GlobalConfig) -> i32 {
@@ -225,6 +425,29 @@ pub fn client_command(client_args: &ArgMatches<'_>, global_config: GlobalConfig)
 			let assume_valid_rangeproofs_kernels = args.is_present("fast");
 			node_client.verify_chain(assume_valid_rangeproofs_kernels);
 		}
+		("tx", Some(tx_args)) => match tx_args.subcommand() {
+			("inspect", Some(args)) => {
+				node_client.inspect_tx(args.value_of("file").unwrap());
+			}
+			("validate", Some(args)) => {
+				node_client.validate_tx(args.value_of("file").unwrap());
+			}
+			_ => panic!("Unknown client tx command, use 'mwc help client tx' for details"),
+		},
+		("onion-auth", Some(auth_args)) => match auth_args.subcommand() {
+			("add", Some(args)) => {
+				node_client.add_onion_client(args.value_of("name").unwrap());
+			}
+			("revoke", Some(args)) => {
+				node_client.revoke_onion_client(args.value_of("name").unwrap());
+			}
+			("list", Some(_)) => {
+				node_client.list_onion_clients();
+			}
+			_ => panic!(
+				"Unknown client onion-auth command, use 'mwc help client onion-auth' for details"
+			),
+		},
 		("ban", Some(peer_args)) => {
 			let peer = peer_args.value_of("peer").unwrap();
 
@@ -253,4 +476,7 @@ enum Error {
 	/// RPC Error
 	#[error("RPC error: {0}")]
 	RPCError(String),
+	/// Transaction file error
+	#[error("Transaction file error: {0}")]
+	TxFile(String),
 }
@@ -47,6 +47,7 @@ enum StratumWorkerColumn {
 	NumRejected,
 	NumStale,
 	NumBlocksFound,
+	Hashrate,
 }
 
 impl StratumWorkerColumn {
@@ -60,6 +61,7 @@ impl StratumWorkerColumn {
 			StratumWorkerColumn::NumRejected => "Num Rejected",
 			StratumWorkerColumn::NumStale => "Num Stale",
 			StratumWorkerColumn::NumBlocksFound => "Blocks Found",
+			StratumWorkerColumn::Hashrate => "Graphs/s",
 		}
 	}
 }
@@ -85,6 +87,7 @@ impl TableViewItem<StratumWorkerColumn> for WorkerStats {
 			StratumWorkerColumn::NumRejected => self.num_rejected.to_string(),
 			StratumWorkerColumn::NumStale => self.num_stale.to_string(),
 			StratumWorkerColumn::NumBlocksFound => self.num_blocks_found.to_string(),
+			StratumWorkerColumn::Hashrate => format!("{:.2}", self.hashrate),
 		}
 	}
 
@@ -103,6 +106,10 @@ impl TableViewItem<StratumWorkerColumn> for WorkerStats {
 			StratumWorkerColumn::NumBlocksFound => {
 				self.num_blocks_found.cmp(&other.num_blocks_found)
 			}
+			StratumWorkerColumn::Hashrate => self
+				.hashrate
+				.partial_cmp(&other.hashrate)
+				.unwrap_or(Ordering::Equal),
 		}
 	}
 }
@@ -199,7 +206,10 @@ impl TUIMiningView {
 				c.width_percent(5)
 			})
 			.column(StratumWorkerColumn::NumBlocksFound, "Blocks Found", |c| {
-				c.width_percent(35)
+				c.width_percent(25)
+			})
+			.column(StratumWorkerColumn::Hashrate, "Graphs/s", |c| {
+				c.width_percent(10)
 			})
 			.default_column(StratumWorkerColumn::IsConnected);
 		table_view.sort_by(StratumWorkerColumn::IsConnected, Ordering::Greater);
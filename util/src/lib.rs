@@ -119,9 +119,9 @@ where
 	}
 }
 
-/// Encode an utf8 string to a base64 string
-pub fn to_base64(s: &str) -> String {
-	base64::encode(s)
+/// Encode an utf8 string or binary data to a base64 string
+pub fn to_base64<T: AsRef<[u8]>>(data: T) -> String {
+	base64::encode(data)
 }
 
 /// Global stopped/paused state shared across various subcomponents of Mwc.